@@ -1,5 +1,17 @@
 use std::{env, fs, path::Path};
 
+/// FNV-1a (64-bit) over the migration file with `\r` stripped, so a CRLF
+/// checkout hashes the same as LF. Only used to detect edits to migrations
+/// that have already been applied, not for security.
+fn checksum(data: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in data.iter().filter(|b| **b != b'\r') {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
 fn main() {
     println!("🛠️ build.rs started");
 
@@ -16,7 +28,7 @@ fn main() {
     println!("📁 Reading migrations from: {:?}", migrations_dir);
 
     let mut contents = String::new();
-    contents.push_str("pub const MIGRATIONS: &[(&str, &str)] = &[\n");
+    contents.push_str("pub const MIGRATIONS: &[EmbeddedMigration] = &[\n");
 
     if migrations_dir.exists() {
         let mut entries: Vec<_> = fs::read_dir(migrations_dir)
@@ -37,8 +49,10 @@ fn main() {
                     .expect("Failed to read migration file");
 
                 contents.push_str(&format!(
-                    "    (\"{}\", r#\"{}\"#),\n",
-                    name, sql
+                    "    EmbeddedMigration {{ name: \"{}\", checksum: \"{}\", sql: r#\"{}\"# }},\n",
+                    name,
+                    checksum(sql.as_bytes()),
+                    sql
                 ));
             }
         }
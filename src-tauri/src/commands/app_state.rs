@@ -3,16 +3,18 @@ use tauri::AppHandle;
 use tauri::Manager;
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
 use tauri::{WebviewUrl, WebviewWindowBuilder};
-use crate::db::migrate;
+use crate::db;
 use crate::db::models::app_state_repo;
 #[cfg(desktop)]
 use crate::WsState;
 use local_ip_address::local_ip;
+use crate::error::{AppError, AppResult};
+use crate::money;
 
 #[tauri::command]
-pub fn get_app_state(app: AppHandle) -> Result<crate::db::models::app_state::AppState, String> {
-    let conn = migrate::connection(&app);
-    app_state_repo::get_app_state(&conn).map_err(|e| e.to_string())
+pub fn get_app_state(app: AppHandle) -> AppResult<crate::db::models::app_state::AppState> {
+    let conn = db::reader(&app)?;
+    app_state_repo::get_app_state(&conn)
 }
 
 #[tauri::command]
@@ -20,14 +22,12 @@ pub fn set_tenant(
     app: AppHandle,
     domain: String,
     token: String,
-) -> Result<(), String> {
-    let conn = migrate::connection(&app);
+) -> AppResult<()> {
+    let conn = db::writer(&app)?;
 
-    app_state_repo::update_app_state(&conn, "tenant_domain", &domain)
-        .map_err(|e| e.to_string())?;
+    app_state_repo::update_app_state(&conn, "tenant_domain", &domain)?;
 
-    app_state_repo::update_app_state(&conn, "access_token", &token)
-        .map_err(|e| e.to_string())?;
+    app_state_repo::update_app_state(&conn, "access_token", &token)?;
 
     Ok(())
 }
@@ -38,26 +38,22 @@ pub fn set_location(
     location_id: String,
     brand_id: String,
     location_name: String
-) -> Result<(), String> {
-    let conn = migrate::connection(&app);
+) -> AppResult<()> {
+    let conn = db::writer(&app)?;
 
-    app_state_repo::update_app_state(&conn, "selected_location_id", &location_id)
-        .map_err(|e| e.to_string())?;
+    app_state_repo::update_app_state(&conn, "selected_location_id", &location_id)?;
 
-    app_state_repo::update_app_state(&conn, "brand_id", &brand_id)
-        .map_err(|e| e.to_string())?;
+    app_state_repo::update_app_state(&conn, "brand_id", &brand_id)?;
 
-    app_state_repo::update_app_state(&conn, "selected_location_name", &location_name)
-        .map_err(|e| e.to_string())?;
+    app_state_repo::update_app_state(&conn, "selected_location_name", &location_name)?;
 
     Ok(())
 }
 
 #[tauri::command]
-pub fn set_device_role(app: AppHandle, role: String) -> Result<(), String> {
-    let conn = migrate::connection(&app);
-    app_state_repo::update_app_state(&conn, "device_role", &role)
-        .map_err(|e| e.to_string())?;
+pub fn set_device_role(app: AppHandle, role: String) -> AppResult<()> {
+    let conn = db::writer(&app)?;
+    app_state_repo::update_app_state(&conn, "device_role", &role)?;
 
     // Start WebSocket server on desktop when role is POS
     #[cfg(desktop)]
@@ -82,11 +78,11 @@ pub fn set_device_role(app: AppHandle, role: String) -> Result<(), String> {
 pub fn set_setup_code(
     app: AppHandle,
     setup_code: String,
-) -> Result<(), String> {
-    let conn = migrate::connection(&app);
+) -> AppResult<()> {
+    let conn = db::writer(&app)?;
 
-    app_state_repo::update_app_state(&conn, "setup_code", &setup_code)
-        .map_err(|e| e.to_string())?;
+    app_state_repo::update_app_state(&conn, "setup_code", &setup_code)?;
+    money::currency::load_store_currency(&conn)?;
 
     Ok(())
 }
@@ -98,121 +94,141 @@ pub fn set_order_modes(
     order_mode_names: Vec<String>,
     default_mode_id: String,
     default_mode_name: String,
-) -> Result<(), String> {
-    let conn = migrate::connection(&app);
+) -> AppResult<()> {
+    let conn = db::writer(&app)?;
 
-    let ids_json = serde_json::to_string(&order_mode_ids)
-        .map_err(|e| e.to_string())?;
+    let ids_json = serde_json::to_string(&order_mode_ids)?;
 
-    let names_json = serde_json::to_string(&order_mode_names)
-        .map_err(|e| e.to_string())?;
+    let names_json = serde_json::to_string(&order_mode_names)?;
 
-    app_state_repo::update_app_state(&conn, "order_mode_ids", &ids_json)
-        .map_err(|e| e.to_string())?;
+    app_state_repo::update_app_state(&conn, "order_mode_ids", &ids_json)?;
 
-    app_state_repo::update_app_state(&conn, "order_mode_names", &names_json)
-        .map_err(|e| e.to_string())?;
+    app_state_repo::update_app_state(&conn, "order_mode_names", &names_json)?;
 
-    app_state_repo::update_app_state(&conn, "selected_order_mode_id", &default_mode_id)
-        .map_err(|e| e.to_string())?;
+    app_state_repo::update_app_state(&conn, "selected_order_mode_id", &default_mode_id)?;
 
-    app_state_repo::update_app_state(&conn, "selected_order_mode_name", &default_mode_name)
-        .map_err(|e| e.to_string())?;
+    app_state_repo::update_app_state(&conn, "selected_order_mode_name", &default_mode_name)?;
 
     Ok(())
 }
 
 #[tauri::command]
-pub fn set_theme(app: AppHandle, theme: String) -> Result<(), String> {
-    let conn = migrate::connection(&app);
-    app_state_repo::update_app_state(&conn, "theme", &theme)
-        .map_err(|e| e.to_string())?;
+pub fn set_theme(app: AppHandle, theme: String) -> AppResult<()> {
+    let conn = db::writer(&app)?;
+    app_state_repo::update_app_state(&conn, "theme", &theme)?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn set_language(app: AppHandle, language: String) -> Result<(), String> {
-    let conn = migrate::connection(&app);
-    app_state_repo::update_app_state(&conn, "language", &language)
-        .map_err(|e| e.to_string())?;
+pub fn set_language(app: AppHandle, language: String) -> AppResult<()> {
+    let conn = db::writer(&app)?;
+    app_state_repo::update_app_state(&conn, "language", &language)?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn get_kds_settings(app: AppHandle) -> Result<String, String> {
-    let conn = migrate::connection(&app);
-    let state = app_state_repo::get_app_state(&conn).map_err(|e| e.to_string())?;
+pub fn get_kds_settings(app: AppHandle) -> AppResult<String> {
+    let conn = db::reader(&app)?;
+    let state = app_state_repo::get_app_state(&conn)?;
     Ok(state.kds_settings.unwrap_or_else(|| "{}".to_string()))
 }
 
 #[tauri::command]
-pub fn set_kds_settings(app: AppHandle, settings: String) -> Result<(), String> {
-    let conn = migrate::connection(&app);
-    app_state_repo::update_app_state(&conn, "kds_settings", &settings)
-        .map_err(|e| e.to_string())?;
+pub fn set_kds_settings(app: AppHandle, settings: String) -> AppResult<()> {
+    let conn = db::writer(&app)?;
+    app_state_repo::update_app_state(&conn, "kds_settings", &settings)?;
     Ok(())
 }
 #[tauri::command]
-pub fn set_logo_url(app: AppHandle, logo_url: String) -> Result<(), String> {
-    let conn = migrate::connection(&app);
-    app_state_repo::update_app_state(&conn, "logo_url", &logo_url)
-        .map_err(|e| e.to_string())?;
+pub fn set_logo_url(app: AppHandle, logo_url: String) -> AppResult<()> {
+    let conn = db::writer(&app)?;
+    app_state_repo::update_app_state(&conn, "logo_url", &logo_url)?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn get_kds_view_mode(app: AppHandle) -> Result<String, String> {
-    let conn = migrate::connection(&app);
-    let state = app_state_repo::get_app_state(&conn).map_err(|e| e.to_string())?;
+pub fn get_kds_view_mode(app: AppHandle) -> AppResult<String> {
+    let conn = db::reader(&app)?;
+    let state = app_state_repo::get_app_state(&conn)?;
     Ok(state.kds_view_mode.unwrap_or_else(|| "grid".to_string()))
 }
 
 #[tauri::command]
-pub fn set_kds_view_mode(app: AppHandle, mode: String) -> Result<(), String> {
-    let conn = migrate::connection(&app);
-    app_state_repo::update_app_state(&conn, "kds_view_mode", &mode)
-        .map_err(|e| e.to_string())?;
+pub fn set_kds_view_mode(app: AppHandle, mode: String) -> AppResult<()> {
+    let conn = db::writer(&app)?;
+    app_state_repo::update_app_state(&conn, "kds_view_mode", &mode)?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn get_ws_settings(app: AppHandle) -> Result<(bool, String), String> {
-    let conn = migrate::connection(&app);
-    let state = app_state_repo::get_app_state(&conn).map_err(|e| e.to_string())?;
+pub fn get_ws_settings(app: AppHandle) -> AppResult<(bool, String, bool)> {
+    let conn = db::reader(&app)?;
+    let state = app_state_repo::get_app_state(&conn)?;
 
     let server_mode = state.ws_server_mode.unwrap_or(0) == 1;
     let server_url = state.ws_server_url.unwrap_or_else(|| "".to_string());
+    let tls_enabled = state.ws_tls_enabled.unwrap_or(0) == 1;
 
-    Ok((server_mode, server_url))
+    Ok((server_mode, server_url, tls_enabled))
 }
 
 #[tauri::command]
-pub fn set_ws_server_mode(app: AppHandle, enabled: bool) -> Result<(), String> {
-    let conn = migrate::connection(&app);
+pub fn set_ws_server_mode(app: AppHandle, enabled: bool) -> AppResult<()> {
+    let conn = db::writer(&app)?;
     let value = if enabled { "1" } else { "0" };
-    app_state_repo::update_app_state(&conn, "ws_server_mode", value)
-        .map_err(|e| e.to_string())?;
+    app_state_repo::update_app_state(&conn, "ws_server_mode", value)?;
     Ok(())
 }
 
+/// Serve `wss://` from the POS hub; applied the next time the server starts
 #[tauri::command]
-pub fn set_ws_server_url(app: AppHandle, url: String) -> Result<(), String> {
-    let conn = migrate::connection(&app);
-    app_state_repo::update_app_state(&conn, "ws_server_url", &url)
-        .map_err(|e| e.to_string())?;
+pub fn set_ws_tls_enabled(app: AppHandle, enabled: bool) -> AppResult<()> {
+    let conn = db::writer(&app)?;
+    let value = if enabled { "1" } else { "0" };
+    app_state_repo::update_app_state(&conn, "ws_tls_enabled", value)?;
+    Ok(())
+}
+
+/// Mirror the hub saved in `ws_server_url` and take over if it goes
+/// silent, instead of serving one; applied the next time the app starts.
+/// The hub must serve wss:// and be pinned at pairing.
+#[tauri::command]
+pub fn set_ws_hub_standby(app: AppHandle, enabled: bool) -> AppResult<()> {
+    let conn = db::writer(&app)?;
+    if enabled && app_state_repo::get_app_state(&conn)?.ws_tls_fingerprint.is_none() {
+        return Err(AppError::InvalidInput("a standby needs a wss:// hub, pair with one first".into()));
+    }
+    let value = if enabled { "1" } else { "0" };
+    app_state_repo::update_app_state(&conn, "ws_hub_standby", value)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_ws_server_url(app: AppHandle, url: String) -> AppResult<()> {
+    let conn = db::writer(&app)?;
+    app_state_repo::update_app_state(&conn, "ws_server_url", &url)?;
     Ok(())
 }
 
+/// Store the device id and token the POS issued when this device was paired
 #[tauri::command]
-pub fn get_local_ip() -> Result<String, String> {
+pub fn set_ws_credentials(app: AppHandle, device_id: String, token: String) -> AppResult<()> {
+    let conn = db::writer(&app)?;
+    app_state_repo::update_app_state(&conn, "ws_device_id", &device_id)?;
+    app_state_repo::update_app_state(&conn, "ws_device_token", &token)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_local_ip() -> AppResult<String> {
     local_ip()
         .map(|ip| ip.to_string())
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppError::Network(e.to_string()))
 }
 
 #[tauri::command]
-pub fn clear_app_state(app: AppHandle) -> Result<(), String> {
-    let conn = migrate::connection(&app);
+pub fn clear_app_state(app: AppHandle) -> AppResult<()> {
+    let conn = db::writer(&app)?;
 
     conn.execute(
         r#"
@@ -229,24 +245,27 @@ pub fn clear_app_state(app: AppHandle) -> Result<(), String> {
           device_role = NULL,
           sync_status = 'IDLE',
           kds_view_mode = 'grid',
-          kds_settings = '{}'
+          kds_settings = '{}',
+          ws_device_id = NULL,
+          ws_device_token = NULL,
+          ws_tls_fingerprint = NULL,
+          ws_hub_standby = 0,
+          ws_routes = NULL
         WHERE id = 1
         "#,
         [],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     Ok(())
 }
 
 #[tauri::command]
-pub fn clear_all_data(app: AppHandle) -> Result<(), String> {
-    let conn = migrate::connection(&app);
+pub fn clear_all_data(app: AppHandle) -> AppResult<()> {
+    let conn = db::writer(&app)?;
     log::info!("🗑️  Starting complete database clear...");
 
     // Disable foreign key checks temporarily
-    conn.execute("PRAGMA foreign_keys = OFF", [])
-        .map_err(|e| e.to_string())?;
+    conn.execute("PRAGMA foreign_keys = OFF", [])?;
 
     // Clear all tables (order doesn't matter now with FK disabled)
         let tables_to_clear = vec![
@@ -276,8 +295,7 @@ pub fn clear_all_data(app: AppHandle) -> Result<(), String> {
     }
 
     // Re-enable foreign key checks
-    conn.execute("PRAGMA foreign_keys = ON", [])
-        .map_err(|e| e.to_string())?;
+    conn.execute("PRAGMA foreign_keys = ON", [])?;
 
     // Reset app_state
     conn.execute(
@@ -297,12 +315,17 @@ pub fn clear_all_data(app: AppHandle) -> Result<(), String> {
           kds_view_mode = 'grid',
           kds_settings = '{}',
           ws_server_mode = 0,
-          ws_server_url = 'ws://localhost:9001'
+          ws_server_url = 'ws://localhost:9001',
+          ws_device_id = NULL,
+          ws_device_token = NULL,
+          ws_tls_enabled = 0,
+          ws_tls_fingerprint = NULL,
+          ws_hub_standby = 0,
+          ws_routes = NULL
         WHERE id = 1
         "#,
         [],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     log::info!("✅ All data cleared successfully");
     Ok(())
@@ -316,7 +339,7 @@ pub fn clear_all_data(app: AppHandle) -> Result<(), String> {
 pub async fn open_role_window(
     app: AppHandle,
     role: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     let timestamp = chrono::Utc::now().timestamp();
     let window_label = format!("{}-{}", role.to_lowercase(), timestamp);
 
@@ -375,7 +398,7 @@ pub async fn open_role_window(
         }
         Err(e) => {
             log::error!("❌ Failed to create window: {}", e);
-            Err(format!("Failed to create window: {}", e))
+            Err(AppError::Internal(format!("Failed to create window: {}", e)))
         }
     }
 }
@@ -386,25 +409,25 @@ pub async fn open_role_window(
 pub async fn open_role_window(
     _app: AppHandle,
     role: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     log::warn!("🚫 open_role_window called on mobile - multi-window not supported for role: {}", role);
-    Err("Multi-window is not supported on mobile devices".to_string())
+    Err(AppError::Unsupported("multi-window on mobile devices".to_string()))
 }
 
 /// Get all configured device roles from the database
 /// Returns a list of roles that have been set up
 #[tauri::command]
-pub fn get_configured_roles(app: AppHandle) -> Result<Vec<String>, String> {
-    let conn = migrate::connection(&app);
+pub fn get_configured_roles(app: AppHandle) -> AppResult<Vec<String>> {
+    let conn = db::reader(&app)?;
     
     log::info!("📋 Fetching configured roles from device_profiles table");
     
     // Get all configured roles from device_profiles table
-    let mut stmt = conn.prepare(
-        "SELECT DISTINCT role FROM device_profiles WHERE role IS NOT NULL"
+    let mut stmt = conn.prepare_cached(
+        "SELECT DISTINCT role FROM device_profiles WHERE role IS NOT NULL AND paired_at IS NULL"
     ).map_err(|e| {
         log::error!("❌ Failed to prepare SQL statement: {}", e);
-        e.to_string()
+        AppError::from(e)
     })?;
     
     let roles = stmt.query_map([], |row| {
@@ -413,12 +436,12 @@ pub fn get_configured_roles(app: AppHandle) -> Result<Vec<String>, String> {
     })
     .map_err(|e| {
         log::error!("❌ Failed to query roles: {}", e);
-        e.to_string()
+        AppError::from(e)
     })?
     .collect::<Result<Vec<String>, _>>()
     .map_err(|e| {
         log::error!("❌ Failed to collect roles: {}", e);
-        e.to_string()
+        AppError::from(e)
     })?;
     
     log::info!("✅ Found {} configured roles: {:?}", roles.len(), roles);
@@ -1,24 +1,23 @@
 
 use tauri::AppHandle;
-use crate::db::migrate;
+use crate::db;
 use crate::db::models::category::Category;
 use crate::db::models::category_repo;
+use crate::error::AppResult;
 
 #[tauri::command]
 pub fn save_categories(
     app: AppHandle,
     items: Vec<Category>,
-) -> Result<(), String> {
-    let mut conn = migrate::connection(&app);
+) -> AppResult<()> {
+    let mut conn = db::writer(&app)?;
     category_repo::save_categories(&mut conn, &items)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub fn get_categories(
     app: AppHandle,
-) -> Result<Vec<Category>, String> {
-    let conn = migrate::connection(&app);
+) -> AppResult<Vec<Category>> {
+    let conn = db::reader(&app)?;
     category_repo::get_categories(&conn)
-        .map_err(|e| e.to_string())
 }
@@ -1,43 +1,47 @@
 use tauri::AppHandle;
-use crate::db::migrate;
+use crate::db;
 use crate::db::models::charges::{Charge, ChargeMapping};
 use crate::db::models::charges_repo;
+use crate::error::AppResult;
+use crate::pricing::{self, PricingLine, PricingResult};
 
 #[tauri::command]
-pub fn save_charges(app: AppHandle, items: Vec<Charge>) -> Result<(), String> {
+pub fn save_charges(app: AppHandle, items: Vec<Charge>) -> AppResult<()> {
     println!("🦀 Rust Command: save_charges called with {} items", items.len());
 
-    let mut conn = migrate::connection(&app);
+    let mut conn = db::writer(&app)?;
     charges_repo::save_charges(&mut conn, &items)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn save_charge_mappings(app: AppHandle, items: Vec<ChargeMapping>) -> Result<(), String> {
+pub fn save_charge_mappings(app: AppHandle, items: Vec<ChargeMapping>) -> AppResult<()> {
     println!("🦀 Rust Command: save_charge_mappings called with {} items", items.len());
 
-    let mut conn = migrate::connection(&app);
+    let mut conn = db::writer(&app)?;
     charges_repo::save_charge_mappings(&mut conn, &items)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn get_charges(app: AppHandle) -> Result<Vec<Charge>, String> {
-    let conn = migrate::connection(&app);
+pub fn get_charges(app: AppHandle) -> AppResult<Vec<Charge>> {
+    let conn = db::reader(&app)?;
     charges_repo::get_charges(&conn)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn get_charge_mappings(app: AppHandle) -> Result<Vec<ChargeMapping>, String> {
-    let conn = migrate::connection(&app);
+pub fn get_charge_mappings(app: AppHandle) -> AppResult<Vec<ChargeMapping>> {
+    let conn = db::reader(&app)?;
     charges_repo::get_charge_mappings(&conn)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn clear_charges_cache(app: AppHandle) -> Result<(), String> {
-    let mut conn = migrate::connection(&app);
+pub fn clear_charges_cache(app: AppHandle) -> AppResult<()> {
+    let mut conn = db::writer(&app)?;
     charges_repo::clear_all(&mut conn)
-        .map_err(|e| e.to_string())
+}
+
+/// Line and ticket charges for a cart, from the stored charges and mappings
+#[tauri::command]
+pub fn calculate_charges(app: AppHandle, items: Vec<PricingLine>) -> AppResult<PricingResult> {
+    let conn = db::reader(&app)?;
+    pricing::price_cart(&conn, &items)
 }
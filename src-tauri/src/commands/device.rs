@@ -1,26 +1,33 @@
 use tauri::AppHandle;
-use crate::db::migrate;
+use crate::db;
 use crate::db::models::device::DeviceProfile;
 use crate::db::models::device_repo;
+use crate::error::AppResult;
 
 #[tauri::command]
-pub fn get_devices(app: AppHandle) -> Result<Vec<DeviceProfile>, String> {
-    let conn = migrate::connection(&app);
-    device_repo::get_devices(&conn).map_err(|e| e.to_string())
+pub fn get_devices(app: AppHandle) -> AppResult<Vec<DeviceProfile>> {
+    let conn = db::reader(&app)?;
+    device_repo::get_devices(&conn)
 }
 
 #[tauri::command]
-pub fn save_device(app: AppHandle, device: DeviceProfile) -> Result<(), String> {
-    let mut conn = migrate::connection(&app);
-    device_repo::save_device(&mut conn, &device).map_err(|e| e.to_string())
+pub fn save_device(app: AppHandle, device: DeviceProfile) -> AppResult<()> {
+    let mut conn = db::writer(&app)?;
+    device_repo::save_device(&mut conn, &device)
 }
 
 #[tauri::command]
-pub fn get_device(app: AppHandle) -> Result<Option<DeviceProfile>, String> {
-    let conn = migrate::connection(&app);
+pub fn get_device(app: AppHandle) -> AppResult<Option<DeviceProfile>> {
+    let conn = db::reader(&app)?;
 
     let mut devices =
-        device_repo::get_devices(&conn).map_err(|e| e.to_string())?;
+        device_repo::get_devices(&conn)?;
 
     Ok(devices.pop()) 
-}
\ No newline at end of file
+}
+/// KDS/QUEUE devices paired with this POS, revoked ones included
+#[tauri::command]
+pub fn get_paired_devices(app: AppHandle) -> AppResult<Vec<DeviceProfile>> {
+    let conn = db::reader(&app)?;
+    device_repo::get_paired_devices(&conn)
+}
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use tauri::Error;
+use crate::error::{AppError, AppResult};
 
 #[derive(Debug, Deserialize)]
 pub struct GiftCardConfig {
@@ -53,7 +53,7 @@ pub struct GiftCardTokenResult {
 #[tauri::command]
 pub async fn giftcard_get_token(
     config: GiftCardConfig,
-) -> Result<GiftCardTokenResult, Error> {
+) -> AppResult<GiftCardTokenResult> {
     let url =
         "https://uatapi.rbsgiftserver.com/api/partner/v1/oauth/token";
 
@@ -68,34 +68,32 @@ pub async fn giftcard_get_token(
             &config.client_secret,
         )
         .send()
-        .await
-        .map_err(|e| Error::Anyhow(anyhow::anyhow!(e)))?;
+        .await?;
 
     // Optional but safe: handle non-200 HTTP
-    if !response.status().is_success() {
+    let status = response.status();
+    if !status.is_success() {
         let text = response.text().await.unwrap_or_default();
-        return Err(Error::Anyhow(anyhow::anyhow!(
-            "Token API HTTP error: {}",
-            text
-        )));
+        return Err(AppError::upstream(
+            Some(status.as_u16()),
+            format!("Token API HTTP error: {}", text),
+        ));
     }
 
     // Parse wrapped RBS response
     let body: TokenApiResponse = response
         .json()
-        .await
-        .map_err(|e| Error::Anyhow(anyhow::anyhow!(e)))?;
+        .await?;
 
     if !body.success {
-        return Err(Error::Anyhow(anyhow::anyhow!(
-            body.message.unwrap_or("Token API failed".to_string())
-        )));
+        return Err(AppError::upstream(
+            None,
+            body.message.unwrap_or("Token API failed".to_string()),
+        ));
     }
 
     let data = body.data.ok_or_else(|| {
-        Error::Anyhow(anyhow::anyhow!(
-            "Token API response missing Data"
-        ))
+        AppError::upstream(None, "Token API response missing Data")
     })?;
 
     Ok(GiftCardTokenResult {
@@ -0,0 +1,107 @@
+use tauri::{AppHandle, State};
+
+use crate::db;
+use crate::db::models::device_repo;
+use crate::db::models::kitchen_station::{KitchenSetup, KitchenStation, TARGET_CATEGORY, TARGET_PRODUCT};
+use crate::db::models::kitchen_station_repo;
+use crate::error::{AppError, AppResult};
+use crate::WsState;
+
+/// Stations and the products/categories mapped to them
+#[tauri::command]
+pub fn get_kitchen_setup(app: AppHandle) -> AppResult<KitchenSetup> {
+    let conn = db::reader(&app)?;
+    kitchen_station_repo::get_setup(&conn)
+}
+
+/// Create a station (empty `id`) or rename / reorder one
+#[tauri::command]
+pub async fn save_kitchen_station(
+    app: AppHandle,
+    ws_state: State<'_, WsState>,
+    station: KitchenStation,
+) -> AppResult<KitchenStation> {
+    println!("🦀 Rust Command: save_kitchen_station ({})", station.name);
+    if station.name.trim().is_empty() {
+        return Err(AppError::InvalidInput("Station name is required".into()));
+    }
+
+    let saved = {
+        let conn = db::writer(&app)?;
+        kitchen_station_repo::save_station(&conn, &station)?
+    };
+    ws_state.server.kitchen_changed().await;
+    Ok(saved)
+}
+
+#[tauri::command]
+pub async fn delete_kitchen_station(
+    app: AppHandle,
+    ws_state: State<'_, WsState>,
+    station_id: String,
+) -> AppResult<()> {
+    println!("🦀 Rust Command: delete_kitchen_station ({})", station_id);
+    let devices: Vec<String> = {
+        let mut conn = db::writer(&app)?;
+        let devices = device_repo::get_paired_devices(&conn)?
+            .into_iter()
+            .filter(|d| d.station_id.as_deref() == Some(station_id.as_str()))
+            .map(|d| d.id)
+            .collect();
+        kitchen_station_repo::delete_station(&mut conn, &station_id)?;
+        devices
+    };
+
+    ws_state.server.kitchen_changed().await;
+    for device_id in devices {
+        ws_state.server.device_changed(&device_id).await;
+    }
+    Ok(())
+}
+
+/// Send a product's (`PRODUCT`) or category's (`CATEGORY`) items to a prep
+/// station, or stop mapping them when `station_id` is None
+#[tauri::command]
+pub async fn set_station_mapping(
+    app: AppHandle,
+    ws_state: State<'_, WsState>,
+    target_type: String,
+    target_id: String,
+    station_id: Option<String>,
+) -> AppResult<()> {
+    if target_type != TARGET_PRODUCT && target_type != TARGET_CATEGORY {
+        return Err(AppError::InvalidInput(format!("Unknown mapping target {}", target_type)));
+    }
+
+    {
+        let conn = db::writer(&app)?;
+        if let Some(station_id) = &station_id {
+            if kitchen_station_repo::get_station(&conn, station_id)?.is_expo {
+                return Err(AppError::InvalidInput("Items can't be mapped to an expo station".into()));
+            }
+        }
+        kitchen_station_repo::set_mapping(&conn, &target_type, &target_id, station_id.as_deref())?;
+    }
+    ws_state.server.kitchen_changed().await;
+    Ok(())
+}
+
+/// Choose the station a paired KDS shows; None shows whole orders
+#[tauri::command]
+pub async fn set_device_station(
+    app: AppHandle,
+    ws_state: State<'_, WsState>,
+    device_id: String,
+    station_id: Option<String>,
+) -> AppResult<()> {
+    println!("🦀 Rust Command: set_device_station ({} → {:?})", device_id, station_id);
+    {
+        let conn = db::writer(&app)?;
+        if let Some(station_id) = &station_id {
+            kitchen_station_repo::get_station(&conn, station_id)?;
+        }
+        device_repo::set_device_station(&conn, &device_id, station_id.as_deref())?;
+    }
+    ws_state.server.device_changed(&device_id).await;
+    Ok(())
+}
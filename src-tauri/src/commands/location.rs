@@ -1,20 +1,21 @@
 
 use tauri::AppHandle;
-use crate::db::{migrate, models::location::*};
+use crate::db::{self, models::location::*};
+use crate::error::AppResult;
 #[tauri::command]
-pub fn save_locations(app: AppHandle, locations: Vec<Location>) -> Result<(), String> {
-    let mut conn = migrate::connection(&app);
-    upsert(&mut conn, &locations).map_err(|e| e.to_string())
+pub fn save_locations(app: AppHandle, locations: Vec<Location>) -> AppResult<()> {
+    let mut conn = db::writer(&app)?;
+    upsert(&mut conn, &locations)
 }
 
 #[tauri::command]
-pub fn select_location(app: AppHandle, server_id: String) -> Result<(), String> {
-    let mut conn = migrate::connection(&app);
-    select(&mut conn, &server_id).map_err(|e| e.to_string())
+pub fn select_location(app: AppHandle, server_id: String) -> AppResult<()> {
+    let mut conn = db::writer(&app)?;
+    select(&mut conn, &server_id)
 }
 
 #[tauri::command]
-pub fn get_locations(app: AppHandle) -> Result<Vec<Location>, String> {
-    let conn = migrate::connection(&app);
-    list_active(&conn).map_err(|e| e.to_string())
+pub fn get_locations(app: AppHandle) -> AppResult<Vec<Location>> {
+    let conn = db::reader(&app)?;
+    list_active(&conn)
 }
@@ -18,11 +18,15 @@ pub mod payment_method;
 pub mod transaction_type;
 pub mod ticket;
 pub mod kds_ticket;
+#[cfg(any(desktop, target_os = "android"))]
+pub mod kitchen_station;
 pub mod printer;
+pub mod print_template;
 pub mod resync;
 pub mod gift_card;
 pub mod terminal;
 #[cfg(any(desktop, target_os = "android"))]
 pub mod websocket;
 pub mod queue_token;
-pub mod setup;
\ No newline at end of file
+pub mod setup;
+pub mod sync;
\ No newline at end of file
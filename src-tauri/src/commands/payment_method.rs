@@ -1,27 +1,25 @@
 use tauri::AppHandle;
-use crate::db::migrate;
+use crate::db;
 use crate::db::models::payment_method::PaymentMethod;
 use crate::db::models::payment_method_repo;
+use crate::error::AppResult;
 
 #[tauri::command]
-pub fn save_payment_methods(app: AppHandle, items: Vec<PaymentMethod>) -> Result<(), String> {
+pub fn save_payment_methods(app: AppHandle, items: Vec<PaymentMethod>) -> AppResult<()> {
     println!("🦀 Rust Command: save_payment_methods called with {} items", items.len());
 
-    let mut conn = migrate::connection(&app);
+    let mut conn = db::writer(&app)?;
     payment_method_repo::save_payment_methods(&mut conn, &items)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn get_payment_methods(app: AppHandle) -> Result<Vec<PaymentMethod>, String> {
-    let conn = migrate::connection(&app);
+pub fn get_payment_methods(app: AppHandle) -> AppResult<Vec<PaymentMethod>> {
+    let conn = db::reader(&app)?;
     payment_method_repo::get_payment_methods(&conn)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn clear_payment_methods_cache(app: AppHandle) -> Result<(), String> {
-    let mut conn = migrate::connection(&app);
+pub fn clear_payment_methods_cache(app: AppHandle) -> AppResult<()> {
+    let mut conn = db::writer(&app)?;
     payment_method_repo::clear_all(&mut conn)
-        .map_err(|e| e.to_string())
 }
@@ -0,0 +1,83 @@
+use tauri::{command, AppHandle};
+
+use super::printer::template_logo;
+use crate::db;
+use crate::db::models::app_state_repo;
+use crate::db::models::print_template::{PrintTemplate, TemplateKind};
+use crate::db::models::print_template_repo;
+use crate::error::{AppError, AppResult};
+use crate::printer::document::Document;
+use crate::printer::preview::{self, PreviewFormat};
+
+/// Every saved template; kinds without one print with the defaults
+#[command]
+pub fn get_print_templates(app: AppHandle) -> AppResult<Vec<PrintTemplate>> {
+    let conn = db::reader(&app)?;
+    print_template_repo::get_templates(&conn)
+}
+
+/// The template `kind` prints with at `location_id` (the setup-wide or
+/// built-in one when the location has none)
+#[command]
+pub fn get_print_template(app: AppHandle, location_id: Option<String>, kind: TemplateKind) -> AppResult<PrintTemplate> {
+    let conn = db::reader(&app)?;
+    print_template_repo::resolve(&conn, location_id.as_deref(), kind)
+}
+
+/// Create or update the template of its kind for its location
+#[command]
+pub fn save_print_template(app: AppHandle, mut template: PrintTemplate) -> AppResult<PrintTemplate> {
+    println!("🦀 Rust Command: save_print_template ({} @ {:?})", template.kind.as_str(), template.location_id);
+
+    let allowed = template.kind.fields();
+    if let Some(field) = template.fields.iter().find(|f| !allowed.contains(f)) {
+        return Err(AppError::InvalidInput(format!(
+            "{:?} can't be shown on a {} template",
+            field,
+            template.kind.as_str()
+        )));
+    }
+    // Kept in the kind's order so the UI shows them consistently
+    template.fields = allowed.iter().copied().filter(|f| template.fields.contains(f)).collect();
+
+    let conn = db::writer(&app)?;
+    print_template_repo::save_template(&conn, &template)
+}
+
+#[command]
+pub fn delete_print_template(app: AppHandle, id: String) -> AppResult<()> {
+    println!("🦀 Rust Command: delete_print_template ({})", id);
+    let conn = db::writer(&app)?;
+    print_template_repo::delete_template(&conn, &id)
+}
+
+/// `template` rendered as plain text or as a base64 PNG, saved or not.
+/// Prints `source_id`'s document when given (see `print_document`), sample
+/// data otherwise.
+#[command]
+pub async fn preview_print_template(
+    app: AppHandle,
+    template: PrintTemplate,
+    format: PreviewFormat,
+    source_id: Option<String>,
+) -> AppResult<String> {
+    let (document, logo_url) = {
+        let conn = db::reader(&app)?;
+        let document = match &source_id {
+            Some(id) => Document::load(&conn, template.kind, id)?,
+            None => Document::sample(template.kind),
+        };
+        (document, app_state_repo::get_app_state(&conn)?.logo_url)
+    };
+
+    let logo = template_logo(&app, &template, logo_url).await;
+    let bytes = document.render(&template, logo.as_ref());
+
+    match format {
+        PreviewFormat::Text => Ok(preview::to_text(&bytes, template.paper_width)),
+        PreviewFormat::Png => {
+            use base64::{Engine as _, engine::general_purpose::STANDARD};
+            Ok(STANDARD.encode(preview::to_png(&bytes, template.paper_width)?))
+        }
+    }
+}
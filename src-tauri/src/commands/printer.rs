@@ -1,140 +1,326 @@
-use crate::db::migrate;
+use crate::db;
+use crate::db::models::app_state_repo;
+use crate::db::models::print_job::PrintJob;
+use crate::db::models::print_job_repo;
+use crate::db::models::print_template::{PrintTemplate, TemplateKind};
+use crate::db::models::print_template_repo;
 use crate::db::models::printer::Printer;
 use crate::db::models::printer_repo::PrinterRepo;
-use crate::printer::{PrinterConfig, PrinterService};
-use tauri::{command, AppHandle};
+use crate::printer::document::Document;
+use crate::printer::raster::Raster;
+use crate::printer::monitor;
+use crate::printer::spooler::PrintSpooler;
+use crate::printer::status::PrinterHealth;
+use crate::printer::transport::{self, PrinterDevice};
+use crate::printer::{logo, PrinterConfig, PrinterService};
+use tauri::{command, AppHandle, Manager, State};
+use crate::error::{AppError, AppResult};
 
 #[command]
-pub fn get_printers(app: AppHandle) -> Result<Vec<Printer>, String> {
-    let conn = migrate::connection(&app);
-    PrinterRepo::get_all(&conn).map_err(|e| format!("Failed to get printers: {}", e))
+pub fn get_printers(app: AppHandle) -> AppResult<Vec<Printer>> {
+    let conn = db::reader(&app)?;
+    PrinterRepo::get_all(&conn)
 }
 
 #[command]
-pub fn get_active_printers(app: AppHandle) -> Result<Vec<Printer>, String> {
-    let conn = migrate::connection(&app);
-    PrinterRepo::get_active(&conn).map_err(|e| format!("Failed to get active printers: {}", e))
+pub fn get_active_printers(app: AppHandle) -> AppResult<Vec<Printer>> {
+    let conn = db::reader(&app)?;
+    PrinterRepo::get_active(&conn)
 }
 
 #[command]
-pub fn get_printer(app: AppHandle, id: String) -> Result<Option<Printer>, String> {
-    let conn = migrate::connection(&app);
-    PrinterRepo::get_by_id(&conn, &id).map_err(|e| format!("Failed to get printer: {}", e))
+pub fn get_printer(app: AppHandle, id: String) -> AppResult<Option<Printer>> {
+    let conn = db::reader(&app)?;
+    PrinterRepo::get_by_id(&conn, &id)
 }
 
 #[command]
-pub fn save_printer(app: AppHandle, printer: Printer) -> Result<(), String> {
-    let conn = migrate::connection(&app);
-    PrinterRepo::save(&conn, &printer).map_err(|e| format!("Failed to save printer: {}", e))
+pub fn save_printer(app: AppHandle, printer: Printer) -> AppResult<()> {
+    if printer.backup_printer_id.as_deref() == Some(printer.id.as_str()) {
+        return Err(AppError::InvalidInput(format!("Printer {} can't be its own backup", printer.name)));
+    }
+
+    let conn = db::writer(&app)?;
+    PrinterRepo::save(&conn, &printer)
 }
 
 #[command]
-pub fn delete_printer(app: AppHandle, id: String) -> Result<(), String> {
-    let conn = migrate::connection(&app);
-    PrinterRepo::delete(&conn, &id).map_err(|e| format!("Failed to delete printer: {}", e))
+pub fn delete_printer(app: AppHandle, id: String) -> AppResult<()> {
+    let conn = db::writer(&app)?;
+    PrinterRepo::delete(&conn, &id)
 }
 
 #[command]
-pub fn set_printer_active(app: AppHandle, id: String, is_active: bool) -> Result<(), String> {
-    let conn = migrate::connection(&app);
+pub fn set_printer_active(app: AppHandle, id: String, is_active: bool) -> AppResult<()> {
+    let conn = db::writer(&app)?;
     PrinterRepo::set_active(&conn, &id, is_active)
-        .map_err(|e| format!("Failed to update printer status: {}", e))
 }
 
 #[command]
-pub fn test_printer(printer: Printer) -> Result<(), String> {
-    let config = PrinterConfig {
-        id: printer.id,
-        name: printer.name,
-        printer_type: printer.printer_type,
-        ip_address: printer.ip_address,
-        port: printer.port.map(|p| p as u16),
-        is_active: printer.is_active,
-    };
+pub fn test_printer(printer: Printer) -> AppResult<()> {
+    PrinterService::test_print(&PrinterConfig::from(&printer))
+}
 
-    PrinterService::test_print(&config)
+/// USB printers and serial ports attached to this machine, to pick a
+/// `device_path` from when adding a printer
+#[command]
+pub fn list_printer_devices() -> AppResult<Vec<PrinterDevice>> {
+    transport::list_devices()
 }
 
-/// Print raw ESC/POS data to a specific printer
-/// The data should be base64 encoded ESC/POS commands from TypeScript
+/// Ask a printer for its status now rather than waiting for the next
+/// health check. Stores it like the health check does.
 #[command]
-pub fn print_raw(app: AppHandle, printer_id: String, data: String) -> Result<(), String> {
-    let conn = migrate::connection(&app);
+pub async fn check_printer_status(app: AppHandle, id: String) -> AppResult<PrinterHealth> {
+    let printer = {
+        let conn = db::reader(&app)?;
+        PrinterRepo::get_by_id(&conn, &id)?
+            .ok_or_else(|| AppError::NotFound(format!("Printer {}", id)))?
+    };
 
-    let printer = PrinterRepo::get_by_id(&conn, &printer_id)
-        .map_err(|e| format!("Failed to get printer: {}", e))?
-        .ok_or("Printer not found")?;
+    monitor::check(&app, printer).await
+}
+
+/// Queue raw ESC/POS data for a specific printer
+/// The data should be base64 encoded ESC/POS commands from TypeScript.
+/// None for builtin printers, which the frontend prints itself.
+#[command]
+pub fn print_raw(
+    app: AppHandle,
+    spooler: State<'_, PrintSpooler>,
+    printer_id: String,
+    data: String,
+) -> AppResult<Option<PrintJob>> {
+    let printer = {
+        let conn = db::reader(&app)?;
+        PrinterRepo::get_by_id(&conn, &printer_id)?
+            .ok_or_else(|| AppError::NotFound(format!("Printer {}", printer_id)))?
+    };
 
     if !printer.is_active {
-        return Err("Printer is not active".to_string());
+        return Err(AppError::InvalidInput(format!("Printer {} is not active", printer.name)));
     }
 
     // Skip builtin printers (handled by frontend)
     if printer.printer_type == "builtin" {
-        return Ok(());
+        return Ok(None);
     }
 
-    let config = PrinterConfig {
-        id: printer.id,
-        name: printer.name,
-        printer_type: printer.printer_type,
-        ip_address: printer.ip_address,
-        port: printer.port.map(|p| p as u16),
-        is_active: printer.is_active,
-    };
-
     // Decode base64 data
     use base64::{Engine as _, engine::general_purpose::STANDARD};
-    let raw_bytes = STANDARD.decode(&data)
-        .map_err(|e| format!("Failed to decode base64 data: {}", e))?;
+    let raw_bytes = STANDARD.decode(&data)?;
 
-    PrinterService::print_raw(&config, &raw_bytes)
+    let mut jobs = enqueue(&app, &spooler, vec![printer], None, None, &raw_bytes)?;
+    Ok(jobs.pop())
 }
 
-/// Print raw ESC/POS data to all active network printers
+/// Queue raw ESC/POS data for all active network, USB and serial printers,
+/// one job each
 /// The data should be base64 encoded ESC/POS commands from TypeScript
 #[command]
-pub fn print_raw_to_all_active(app: AppHandle, data: String) -> Result<(), String> {
-    let conn = migrate::connection(&app);
-
-    let printers = PrinterRepo::get_active(&conn)
-        .map_err(|e| format!("Failed to get active printers: {}", e))?;
+pub fn print_raw_to_all_active(
+    app: AppHandle,
+    spooler: State<'_, PrintSpooler>,
+    data: String,
+) -> AppResult<Vec<PrintJob>> {
+    let printers = {
+        let conn = db::reader(&app)?;
+        PrinterRepo::get_active(&conn)?
+    };
 
-    // Filter only network printers (builtin handled by frontend)
-    let network_printers: Vec<_> = printers.into_iter()
-        .filter(|p| p.printer_type == "network")
+    // Only printers the spooler can reach (builtin handled by frontend)
+    let direct_printers: Vec<_> = printers.into_iter()
+        .filter(|p| transport::supports(&p.printer_type))
         .collect();
 
-    if network_printers.is_empty() {
-        // No network printers, that's ok - builtin will be handled by frontend
-        return Ok(());
-    }
-
     // Decode base64 data once
     use base64::{Engine as _, engine::general_purpose::STANDARD};
-    let raw_bytes = STANDARD.decode(&data)
-        .map_err(|e| format!("Failed to decode base64 data: {}", e))?;
-
-    let mut errors = Vec::new();
-
-    for printer in network_printers {
-        let config = PrinterConfig {
-            id: printer.id.clone(),
-            name: printer.name.clone(),
-            printer_type: printer.printer_type.clone(),
-            ip_address: printer.ip_address.clone(),
-            port: printer.port.map(|p| p as u16),
-            is_active: printer.is_active,
-        };
-
-        if let Err(e) = PrinterService::print_raw(&config, &raw_bytes) {
-            errors.push(format!("Printer '{}': {}", printer.name, e));
+    let raw_bytes = STANDARD.decode(&data)?;
+
+    enqueue(&app, &spooler, direct_printers, None, None, &raw_bytes)
+}
+
+/// Print the customer receipt for a saved ticket, laid out here rather
+/// than in the webview. Goes to `printer_id`, or to every active network,
+/// USB and serial printer when it's None.
+#[command]
+pub async fn print_receipt(
+    app: AppHandle,
+    spooler: State<'_, PrintSpooler>,
+    ticket_id: String,
+    printer_id: Option<String>,
+) -> AppResult<Vec<PrintJob>> {
+    println!("🦀 Rust Command: print_receipt {}", ticket_id);
+    print_to(&app, &spooler, TemplateKind::Receipt, &ticket_id, printer_id).await
+}
+
+/// The receipt `print_receipt` would print, as base64 ESC/POS for printers
+/// driven from the webview (the Android builtin printer)
+#[command]
+pub async fn render_receipt(app: AppHandle, ticket_id: String) -> AppResult<String> {
+    use base64::{Engine as _, engine::general_purpose::STANDARD};
+    Ok(STANDARD.encode(document_bytes(&app, TemplateKind::Receipt, &ticket_id).await?))
+}
+
+/// Print any templated document: a receipt or void slip by ticket id, a
+/// kitchen chit by KDS ticket id, a Z-report by workday id
+#[command]
+pub async fn print_document(
+    app: AppHandle,
+    spooler: State<'_, PrintSpooler>,
+    kind: TemplateKind,
+    source_id: String,
+    printer_id: Option<String>,
+) -> AppResult<Vec<PrintJob>> {
+    println!("🦀 Rust Command: print_document {} {}", kind.as_str(), source_id);
+    print_to(&app, &spooler, kind, &source_id, printer_id).await
+}
+
+/// `print_document`'s bytes as base64, for the Android builtin printer
+#[command]
+pub async fn render_document(app: AppHandle, kind: TemplateKind, source_id: String) -> AppResult<String> {
+    use base64::{Engine as _, engine::general_purpose::STANDARD};
+    Ok(STANDARD.encode(document_bytes(&app, kind, &source_id).await?))
+}
+
+async fn print_to(
+    app: &AppHandle,
+    spooler: &PrintSpooler,
+    kind: TemplateKind,
+    source_id: &str,
+    printer_id: Option<String>,
+) -> AppResult<Vec<PrintJob>> {
+    let printers = {
+        let conn = db::reader(app)?;
+        match &printer_id {
+            Some(id) => {
+                let printer = PrinterRepo::get_by_id(&conn, id)?
+                    .ok_or_else(|| AppError::NotFound(format!("Printer {}", id)))?;
+                if !printer.is_active {
+                    return Err(AppError::InvalidInput(format!("Printer {} is not active", printer.name)));
+                }
+                vec![printer]
+            }
+            None => PrinterRepo::get_active(&conn)?,
         }
+    };
+
+    // Builtin printers print through the Android bridge, from render_document
+    let printers: Vec<_> = printers
+        .into_iter()
+        .filter(|p| match printer_id {
+            Some(_) => p.printer_type != "builtin",
+            None => transport::supports(&p.printer_type),
+        })
+        .collect();
+    if printers.is_empty() {
+        return Ok(Vec::new());
     }
 
-    if errors.is_empty() {
-        Ok(())
-    } else {
-        Err(format!("Some printers failed: {}", errors.join("; ")))
+    let bytes = document_bytes(app, kind, source_id).await?;
+    enqueue(app, spooler, printers, Some(kind), Some(source_id), &bytes)
+}
+
+/// Queue `data` for each of `printers` and wake the spooler
+fn enqueue(
+    app: &AppHandle,
+    spooler: &PrintSpooler,
+    printers: Vec<Printer>,
+    kind: Option<TemplateKind>,
+    source_id: Option<&str>,
+    data: &[u8],
+) -> AppResult<Vec<PrintJob>> {
+    if printers.is_empty() {
+        return Ok(Vec::new());
     }
+
+    let jobs = {
+        let mut conn = db::writer(app)?;
+        let tx = conn.transaction()?;
+        let jobs = printers
+            .iter()
+            .map(|printer| print_job_repo::enqueue_job(&tx, &printer.id, kind, source_id, data))
+            .collect::<AppResult<Vec<_>>>()?;
+        tx.commit()?;
+        jobs
+    };
+
+    spooler.wake();
+    Ok(jobs)
+}
+
+/// `kind` rendered from `source_id` with the template of its location
+async fn document_bytes(app: &AppHandle, kind: TemplateKind, source_id: &str) -> AppResult<Vec<u8>> {
+    let (document, template, logo_url) = {
+        let conn = db::reader(app)?;
+        let document = Document::load(&conn, kind, source_id)?;
+        let template = print_template_repo::resolve(&conn, document.location_id(), kind)?;
+        (document, template, app_state_repo::get_app_state(&conn)?.logo_url)
+    };
+
+    let logo = template_logo(app, &template, logo_url).await;
+    Ok(document.render(&template, logo.as_ref()))
+}
+
+/// The store logo sized for `template`'s paper, if it shows one
+pub(crate) async fn template_logo(app: &AppHandle, template: &PrintTemplate, logo_url: Option<String>) -> Option<Raster> {
+    let url = logo_url.filter(|url| template.show_logo && !url.trim().is_empty())?;
+
+    // A document without its logo beats no document
+    let cache_dir = match app.path().app_data_dir() {
+        Ok(dir) => dir.join("printer"),
+        Err(e) => {
+            log::warn!("⚠️ Printing without the logo: {}", e);
+            return None;
+        }
+    };
+    match logo::load(&cache_dir, &url, template.paper_width.dots() * 2 / 3).await {
+        Ok(logo) => Some(logo),
+        Err(e) => {
+            log::warn!("⚠️ Printing without the logo: {}", e);
+            None
+        }
+    }
+}
+
+/// The newest `limit` print jobs (100 by default), only those in `status`
+/// when given
+#[command]
+pub fn get_print_jobs(app: AppHandle, status: Option<String>, limit: Option<i64>) -> AppResult<Vec<PrintJob>> {
+    let conn = db::reader(&app)?;
+    print_job_repo::get_jobs(&conn, status.as_deref(), limit.unwrap_or(100))
+}
+
+/// Print job `job_id` again, on `printer_id` or the printer it was sent to
+#[command]
+pub fn reprint_print_job(
+    app: AppHandle,
+    spooler: State<'_, PrintSpooler>,
+    job_id: String,
+    printer_id: Option<String>,
+) -> AppResult<PrintJob> {
+    println!("🦀 Rust Command: reprint_print_job {}", job_id);
+
+    let job = {
+        let conn = db::writer(&app)?;
+        if let Some(id) = &printer_id {
+            let printer = PrinterRepo::get_by_id(&conn, id)?
+                .ok_or_else(|| AppError::NotFound(format!("Printer {}", id)))?;
+            if printer.printer_type == "builtin" {
+                return Err(AppError::Unsupported("Builtin printer must be printed via Android bridge".to_string()));
+            }
+        }
+        print_job_repo::reprint_job(&conn, &job_id, printer_id.as_deref())?
+    };
+
+    spooler.wake();
+    Ok(job)
+}
+
+/// Stop a print job that hasn't printed yet
+#[command]
+pub fn cancel_print_job(app: AppHandle, job_id: String) -> AppResult<PrintJob> {
+    println!("🦀 Rust Command: cancel_print_job {}", job_id);
+    let conn = db::writer(&app)?;
+    print_job_repo::cancel_job(&conn, &job_id)
 }
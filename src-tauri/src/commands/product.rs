@@ -1,11 +1,12 @@
 
 use tauri::AppHandle;
-use crate::db::migrate;
+use crate::db;
 use crate::db::models::product::Product;
 use crate::db::models::product_repo;
+use crate::error::AppResult;
 
 #[tauri::command]
-pub fn save_products(app: AppHandle, items: Vec<Product>) -> Result<(), String> {
+pub fn save_products(app: AppHandle, items: Vec<Product>) -> AppResult<()> {
     println!("🦀 Rust Command: save_products called with {} items", items.len());
 
     // Debug: Check first few items for overrides
@@ -27,28 +28,24 @@ pub fn save_products(app: AppHandle, items: Vec<Product>) -> Result<(), String>
         }
     }
 
-    let mut conn = migrate::connection(&app);
+    let mut conn = db::writer(&app)?;
     product_repo::save_products(&mut conn, &items)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn get_products(app: AppHandle) -> Result<Vec<Product>, String> {
-    let conn = migrate::connection(&app);
+pub fn get_products(app: AppHandle) -> AppResult<Vec<Product>> {
+    let conn = db::reader(&app)?;
     product_repo::get_products(&conn)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn clear_products_cache(app: AppHandle) -> Result<(), String> {
-    let mut conn = migrate::connection(&app);
+pub fn clear_products_cache(app: AppHandle) -> AppResult<()> {
+    let mut conn = db::writer(&app)?;
     product_repo::clear_all(&mut conn)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn update_product_sold_out_status(app: AppHandle, product_id: String, is_sold_out: bool) -> Result<(), String> {
-    let mut conn = migrate::connection(&app);
+pub fn update_product_sold_out_status(app: AppHandle, product_id: String, is_sold_out: bool) -> AppResult<()> {
+    let mut conn = db::writer(&app)?;
     product_repo::update_sold_out_status(&mut conn, &product_id, is_sold_out)
-        .map_err(|e| e.to_string())
 }
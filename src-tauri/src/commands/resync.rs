@@ -1,9 +1,10 @@
 use tauri::AppHandle;
-use crate::db::migrate;
+use crate::db;
+use crate::error::AppResult;
 
 #[tauri::command]
-pub fn clear_resync_data(app: AppHandle) -> Result<(), String> {
-    let conn = migrate::connection(&app);
+pub fn clear_resync_data(app: AppHandle) -> AppResult<()> {
+    let conn = db::writer(&app)?;
 
     log::info!("🧹 Clearing re-sync related tables...");
 
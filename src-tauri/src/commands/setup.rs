@@ -1,24 +1,28 @@
 use tauri::AppHandle;
-use crate::db::migrate;
+use crate::db;
 use crate::db::models::setup::Setup;
 use crate::db::models::setup_repo;
+use crate::error::AppResult;
+use crate::money;
 
 #[tauri::command]
 pub fn save_setup(
     app: AppHandle,
     setup: Setup,
-) -> Result<(), String> {
-    let conn = migrate::connection(&app);
-    setup_repo::upsert_setup(&conn, &setup)
-        .map_err(|e| e.to_string())
+) -> AppResult<()> {
+    let conn = db::writer(&app)?;
+    setup_repo::upsert_setup(&conn, &setup)?;
+
+    // The saved setup may be the selected one, with a new currency
+    money::currency::load_store_currency(&conn)?;
+    Ok(())
 }
 
 #[tauri::command]
 pub fn get_setup_by_code(
     app: AppHandle,
     code: String,
-) -> Result<Option<Setup>, String> {
-    let conn = migrate::connection(&app);
+) -> AppResult<Option<Setup>> {
+    let conn = db::reader(&app)?;
     setup_repo::get_setup_by_code(&conn, &code)
-        .map_err(|e| e.to_string())
 }
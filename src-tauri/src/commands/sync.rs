@@ -0,0 +1,29 @@
+use tauri::{AppHandle, State};
+use crate::db;
+use crate::db::models::{ticket_repo, workday_repo};
+use crate::error::AppResult;
+use crate::sync::{self, SyncReport, SyncWorker};
+
+/// Run a sync pass now and return its report.
+/// Waits for a background pass that is already running.
+#[tauri::command]
+pub async fn sync_now(app: AppHandle, worker: State<'_, SyncWorker>) -> AppResult<SyncReport> {
+    log::info!("🔄 Manual sync requested");
+
+    sync::run_and_emit(&app, &worker).await
+}
+
+/// Move dead-lettered tickets and workdays back to PENDING and wake the worker.
+/// Returns (tickets, workdays) requeued.
+#[tauri::command]
+pub fn requeue_dead_letters(app: AppHandle, worker: State<'_, SyncWorker>) -> AppResult<(usize, usize)> {
+    let mut conn = db::writer(&app)?;
+    let tickets = ticket_repo::requeue_dead_tickets(&mut conn)?;
+    let workdays = workday_repo::requeue_dead_workdays(&mut conn)?;
+    drop(conn);
+
+    log::info!("🔁 Requeued {} dead tickets and {} dead workdays", tickets, workdays);
+    worker.wake();
+
+    Ok((tickets, workdays))
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use tauri::Error;
+use crate::error::{AppError, AppResult};
+use crate::money::{store_currency, Currency, Money, Rounding};
 
 /* -------------------------------------------------------------------------- */
 /*                              HTTP CLIENT (DEV)                              */
@@ -10,11 +11,10 @@ use tauri::Error;
  DO NOT use danger_accept_invalid_certs(true) in PROD.
 */
 
-fn build_http_client() -> Result<reqwest::Client, Error> {
-    reqwest::Client::builder()
+fn build_http_client() -> AppResult<reqwest::Client> {
+    Ok(reqwest::Client::builder()
         .danger_accept_invalid_certs(true)
-        .build()
-        .map_err(|e| Error::Anyhow(anyhow::anyhow!(e)))
+        .build()?)
 }
 
 /* -------------------------------------------------------------------------- */
@@ -35,13 +35,14 @@ pub struct RbsPayConfig {
 #[derive(Debug, Deserialize)]
 pub struct TerminalInitiateRequest {
     pub config: RbsPayConfig,
-    pub amount: f64,
+    /// Major units, as sent by the webview
+    pub amount: Money,
     pub currency: String, 
     pub payment_method: String,
     pub invoice_number: String,
     pub description: String,
-    pub tax_amount: f64,
-    pub tip_amount: f64,
+    pub tax_amount: Money,
+    pub tip_amount: Money,
     pub triggered_by: String,
 }
 
@@ -66,53 +67,58 @@ pub struct IqProTerminalData {
 #[tauri::command]
 pub async fn rbs_terminal_initiate(
     payload: TerminalInitiateRequest,
-) -> Result<IqProTerminalData, Error> {
+) -> AppResult<IqProTerminalData> {
     let url = format!("{}/terminal/transactions", payload.config.base_url);
     let client = build_http_client()?;
 
+    // Amounts are read in the store currency; restate them in the one the
+    // terminal is asked to charge so the decimals match
+    let currency = Currency::from_code(&payload.currency)?;
+    let amount = payload.amount.with_currency(currency, Rounding::HalfUp);
+    let tax_amount = payload.tax_amount.with_currency(currency, Rounding::HalfUp);
+    let tip_amount = payload.tip_amount.with_currency(currency, Rounding::HalfUp);
+
     let response = client
         .post(&url)
         .header("x-api-key", &payload.config.api_key)
         .header("Content-Type", "application/json")
         .json(&serde_json::json!({
     "terminal_id": payload.config.terminal_id,
-    "amount": payload.amount,
-    "currency": payload.currency,
+    "amount": amount,
+    "currency": currency,
     "payment_method": payload.payment_method,
     "invoice_number": payload.invoice_number,
     "description": payload.description,
-    "tax_amount": payload.tax_amount,
-    "tip_amount": payload.tip_amount,
+    "tax_amount": tax_amount,
+    "tip_amount": tip_amount,
     "triggered_by": payload.triggered_by,
     "metadata": {}
 }))
         .send()
-        .await
-        .map_err(|e| Error::Anyhow(anyhow::anyhow!(e)))?;
+        .await?;
 
-    if !response.status().is_success() {
+    let status = response.status();
+    if !status.is_success() {
         let text = response.text().await.unwrap_or_default();
-        return Err(Error::Anyhow(anyhow::anyhow!(
-            "RBSPay initiate HTTP error: {}",
-            text
-        )));
+        return Err(AppError::upstream(
+            Some(status.as_u16()),
+            format!("RBSPay initiate HTTP error: {}", text),
+        ));
     }
 
     let body: RbsTerminalInitiateApiResponse = response
         .json()
-        .await
-        .map_err(|e| Error::Anyhow(anyhow::anyhow!(e)))?;
+        .await?;
 
     if !body.success {
-        return Err(Error::Anyhow(anyhow::anyhow!(
-            body.message.unwrap_or("Terminal initiation failed".to_string())
-        )));
+        return Err(AppError::upstream(
+            None,
+            body.message.unwrap_or("Terminal initiation failed".to_string()),
+        ));
     }
 
     body.data.ok_or_else(|| {
-        Error::Anyhow(anyhow::anyhow!(
-            "Terminal initiation response missing data"
-        ))
+        AppError::upstream(None, "Terminal initiation response missing data")
     })
 }
 
@@ -124,7 +130,7 @@ pub async fn rbs_terminal_initiate(
 pub async fn rbs_terminal_cancel(
     config: RbsPayConfig,
     transaction_id: String,
-) -> Result<(), Error> {
+) -> AppResult<()> {
     let url = format!(
         "{}/terminal/transactions/{}",
         config.base_url, transaction_id
@@ -136,15 +142,15 @@ pub async fn rbs_terminal_cancel(
         .delete(&url)
         .header("x-api-key", &config.api_key)
         .send()
-        .await
-        .map_err(|e| Error::Anyhow(anyhow::anyhow!(e)))?;
+        .await?;
 
-    if !response.status().is_success() {
+    let status = response.status();
+    if !status.is_success() {
         let text = response.text().await.unwrap_or_default();
-        return Err(Error::Anyhow(anyhow::anyhow!(
-            "RBSPay cancel failed: {}",
-            text
-        )));
+        return Err(AppError::upstream(
+            Some(status.as_u16()),
+            format!("RBSPay cancel failed: {}", text),
+        ));
     }
 
     Ok(())
@@ -162,14 +168,14 @@ pub struct TransactionStatusResponse {
     pub processor_response_text: Option<String>,
     pub processor_response_code: Option<String>,
     pub payment_method: Option<String>,
-    pub amount: Option<f64>,
+    pub amount: Option<Money>,
     pub currency: Option<String>,
 }
 #[tauri::command]
 pub async fn rbs_get_transaction(
     config: RbsPayConfig,
     transaction_id: String,
-) -> Result<TransactionStatusResponse, Error> {
+) -> AppResult<TransactionStatusResponse> {
     let client = build_http_client()?;
 
     let url = format!(
@@ -182,23 +188,26 @@ pub async fn rbs_get_transaction(
         .header("x-api-key", &config.api_key)
         .query(&[("transaction_id", transaction_id)])
         .send()
-        .await
-        .map_err(|e| Error::Anyhow(anyhow::anyhow!(e)))?;
+        .await?;
 
-    if !response.status().is_success() {
+    let status = response.status();
+    if !status.is_success() {
         let text = response.text().await.unwrap_or_default();
-        return Err(Error::Anyhow(anyhow::anyhow!(
-            "RBSPay get transaction failed: {}",
-            text
-        )));
+        return Err(AppError::upstream(
+            Some(status.as_u16()),
+            format!("RBSPay get transaction failed: {}", text),
+        ));
     }
 
     let json: serde_json::Value = response
         .json()
-        .await
-        .map_err(|e| Error::Anyhow(anyhow::anyhow!(e)))?;
+        .await?;
 
     let data = &json["data"];
+    let currency = data["currency"]
+        .as_str()
+        .and_then(|code| Currency::from_code(code).ok())
+        .unwrap_or_else(store_currency);
 
     Ok(TransactionStatusResponse {
         transaction_id: data["transaction_id"]
@@ -216,7 +225,18 @@ pub async fn rbs_get_transaction(
             .as_str().map(String::from),
         payment_method: data["payment_method"]
             .as_str().map(String::from),
-        amount: data["remit"]["amount"].as_f64(),
+        amount: remit_amount(&data["remit"]["amount"], currency),
         currency: data["currency"].as_str().map(String::from),
     })
 }
+
+/// RBSPay reports the remitted amount as a number or a decimal string
+fn remit_amount(value: &serde_json::Value, currency: Currency) -> Option<Money> {
+    match value {
+        serde_json::Value::Number(n) => n
+            .as_f64()
+            .map(|v| Money::from_major(v, currency, Rounding::HalfUp)),
+        serde_json::Value::String(s) => Money::parse(s, currency, Rounding::HalfUp).ok(),
+        _ => None,
+    }
+}
@@ -1,84 +1,131 @@
-use crate::websocket::{broadcast_to_device_type, ConnectedDevice, DeviceMessage};
+use crate::websocket::{ConnectedDevice, DeviceMessage};
 use crate::WsState;
+use crate::kitchen;
+use crate::db;
+use crate::db::models::device::PairingCode;
+use crate::db::models::app_state_repo;
+use crate::db::models::device_repo;
+use crate::db::models::ws_outbox::DeliveryStatus;
+use crate::db::models::ws_outbox_repo;
+use crate::websocket::client::{ClientStatus, HubConfig};
+use crate::websocket::discovery::{self, DiscoveredHub};
+use crate::websocket::limits::MetricsSnapshot;
+use crate::websocket::pairing::{self, PairedHub};
+use crate::websocket::presence::DeviceInfo;
+use crate::websocket::routing::RouteTable;
+use crate::websocket::standby::HubRole;
 use std::collections::HashMap;
-use tauri::{command, State};
+use tauri::{command, AppHandle, State};
 use tokio::sync::RwLockReadGuard;
+use crate::error::{AppError, AppResult};
 
 #[command]
 pub async fn broadcast_to_kds(
     ws_state: State<'_, WsState>,
     message: DeviceMessage,
-) -> Result<(), String> {
+) -> AppResult<()> {
     let devices = ws_state.server.get_devices();
 
     let device_guard: RwLockReadGuard<'_, HashMap<String, ConnectedDevice>> = devices.read().await;
     let count = device_guard.len();
     drop(device_guard);
     if count == 0 {
-        log::warn!("⚠️ No connected KDS devices");
+        log::warn!("⚠️ No connected KDS devices, message kept for replay");
     }
 
-    broadcast_to_device_type(&devices, "KDS", &message)
-        .await
-        .map_err(|e| format!("Failed to broadcast to KDS: {}", e))
+    ws_state.server.publish("KDS", message).await?;
+    Ok(())
 }
 
 #[command]
 pub async fn broadcast_to_queue(
     ws_state: State<'_, WsState>,
     message: DeviceMessage,
-) -> Result<(), String> {
+) -> AppResult<()> {
     let devices = ws_state.server.get_devices();
 
     let device_guard: RwLockReadGuard<'_, HashMap<String, ConnectedDevice>> = devices.read().await;
     let count = device_guard.len();
     drop(device_guard);
     if count == 0 {
-        log::warn!("⚠️ No connected QUEUE devices");
+        log::warn!("⚠️ No connected QUEUE devices, message kept for replay");
     }
 
-    broadcast_to_device_type(&devices, "QUEUE", &message)
-        .await
-        .map_err(|e| format!("Failed to broadcast to QUEUE: {}", e))
+    ws_state.server.publish("QUEUE", message).await?;
+    Ok(())
 }
 
 #[command]
 pub async fn broadcast_to_pos(
     ws_state: State<'_, WsState>,
     message: DeviceMessage,
-) -> Result<(), String> {
+) -> AppResult<()> {
     let devices = ws_state.server.get_devices();
 
     let device_guard: RwLockReadGuard<'_, HashMap<String, ConnectedDevice>> = devices.read().await;
     let count = device_guard.len();
     drop(device_guard);
     if count == 0 {
-        log::warn!("⚠️ No connected POS devices");
+        log::warn!("⚠️ No connected POS devices, message kept for replay");
     }
 
-    broadcast_to_device_type(&devices, "POS", &message)
-        .await
-        .map_err(|e| format!("Failed to broadcast to POS: {}", e))
+    ws_state.server.publish("POS", message).await?;
+    Ok(())
 }
 
 #[command]
 pub async fn broadcast_order(
+    app: AppHandle,
     ws_state: State<'_, WsState>,
     order_data: serde_json::Value,
-) -> Result<(), String> {
-    let devices = ws_state.server.get_devices();
-
-    // 🧑‍🍳 KDS
-    let kds_message = DeviceMessage {
+) -> AppResult<()> {
+    // 🧑‍🍳 KDS: each station's screen gets its own items
+    let kds_message = |payload| DeviceMessage {
         message_type: "new_order".to_string(),
         device_id: None,
         device_type: "SERVER".to_string(),
-        payload: order_data.clone(), 
+        payload,
+        ..Default::default()
     };
 
-    broadcast_to_device_type(&devices, "KDS", &kds_message)
-        .await
-        .map_err(|e| format!("Failed to broadcast to KDS: {}", e))?;
+    let plan = {
+        let devices = ws_state.server.get_devices();
+        let devices = devices.read().await;
+        let conn = db::reader(&app)?;
+        let mut unpaired = Vec::new();
+        for device in devices.values().filter(|d| d.device_type == "KDS") {
+            match device_repo::get_paired_device(&conn, &device.device_id) {
+                Ok(_) => {}
+                Err(AppError::NotFound(_)) => unpaired.push(device.device_id.clone()),
+                Err(e) => return Err(e),
+            }
+        }
+        kitchen::plan_order(&conn, &order_data, &unpaired)?
+    };
+
+    // One screen failing shouldn't keep the order from the others
+    let mut failures = Vec::new();
+    let mut check = |target: &str, result: AppResult<i64>| {
+        if let Err(e) = result {
+            log::error!("❌ Order for {} not published: {}", target, e);
+            failures.push(format!("{}: {}", target, e));
+        }
+    };
+
+    match plan {
+        Some(deliveries) => {
+            for delivery in deliveries {
+                let result = ws_state
+                    .server
+                    .publish_to_device(&delivery.device_id, kds_message(delivery.payload))
+                    .await;
+                check(&delivery.device_id, result);
+            }
+        }
+        None => {
+            check("KDS", ws_state.server.publish("KDS", kds_message(order_data.clone())).await);
+        }
+    }
 
     // 📺 QUEUE
     let queue_message = DeviceMessage {
@@ -86,11 +133,224 @@ pub async fn broadcast_order(
         device_id: None,
         device_type: "SERVER".to_string(),
         payload: order_data, 
+        ..Default::default()
     };
 
-    broadcast_to_device_type(&devices, "QUEUE", &queue_message)
-        .await
-        .map_err(|e| format!("Failed to broadcast to QUEUE: {}", e))?;
+    check("QUEUE", ws_state.server.publish("QUEUE", queue_message).await);
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::Internal(format!("order not published to {}", failures.join("; "))))
+    }
+}
+
+/// Show a one-time code on the POS for a KDS/QUEUE to pair with
+#[command]
+pub fn create_pairing_code(
+    ws_state: State<'_, WsState>,
+    role: String,
+) -> AppResult<PairingCode> {
+    println!("🦀 Rust Command: create_pairing_code ({})", role);
+    ws_state.server.create_pairing_code(&role)
+}
 
+/// Revoke a paired device's token and drop it if it is connected
+#[command]
+pub async fn revoke_device(
+    app: AppHandle,
+    ws_state: State<'_, WsState>,
+    device_id: String,
+) -> AppResult<()> {
+    println!("🦀 Rust Command: revoke_device ({})", device_id);
+    {
+        let conn = db::writer(&app)?;
+        device_repo::revoke_device(&conn, &device_id)?;
+    }
+
+    ws_state.server.disconnect(&device_id).await;
+    ws_state.server.device_changed(&device_id).await;
+    log::info!("🚫 Revoked device {}", device_id);
     Ok(())
 }
+
+/// KDS/QUEUE side: pair with the hub at `url`, then keep the issued
+/// credentials and the pinned certificate in app_state
+#[command]
+pub async fn pair_with_hub(
+    app: AppHandle,
+    url: String,
+    code: String,
+    name: String,
+    role: String,
+) -> AppResult<PairedHub> {
+    println!("🦀 Rust Command: pair_with_hub ({})", url);
+    let hub = pairing::pair_with_hub(&url, &code, &name, &role).await?;
+
+    let conn = db::writer(&app)?;
+    app_state_repo::update_app_state(&conn, "ws_server_url", &url)?;
+    app_state_repo::update_app_state(&conn, "ws_device_id", &hub.device_id)?;
+    app_state_repo::update_app_state(&conn, "ws_device_token", &hub.token)?;
+    match &hub.fingerprint {
+        Some(fp) => app_state_repo::update_app_state(&conn, "ws_tls_fingerprint", fp)?,
+        None => {
+            conn.execute("UPDATE app_state SET ws_tls_fingerprint = NULL WHERE id = 1", [])?;
+        }
+    }
+
+    log::info!("🤝 Paired with {} as {}", url, hub.device_id);
+    Ok(hub)
+}
+
+/// Per device: last acknowledged message and how many are still pending
+#[command]
+pub async fn get_delivery_status(
+    app: AppHandle,
+    ws_state: State<'_, WsState>,
+) -> AppResult<Vec<DeliveryStatus>> {
+    let mut statuses = {
+        let conn = db::reader(&app)?;
+        ws_outbox_repo::get_delivery_status(&conn)?
+    };
+
+    let devices = ws_state.server.get_devices();
+    let connected = devices.read().await;
+    for status in &mut statuses {
+        status.connected = connected.contains_key(&status.device_id);
+    }
+
+    Ok(statuses)
+}
+
+/// Devices currently registered with the hub, with their last heartbeat
+#[command]
+pub async fn get_connected_devices(ws_state: State<'_, WsState>) -> AppResult<Vec<DeviceInfo>> {
+    Ok(ws_state.server.connected_devices().await)
+}
+
+/// Queue depths on the hub, and how many frames/events its limits dropped
+#[command]
+pub async fn get_ws_metrics(ws_state: State<'_, WsState>) -> AppResult<MetricsSnapshot> {
+    Ok(ws_state.server.metrics().await)
+}
+
+/// Whether this POS serves the hub, stands by, or took over
+#[command]
+pub fn get_hub_role(ws_state: State<'_, WsState>) -> HubRole {
+    *ws_state.hub_role.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The table the hub routes device messages by
+#[command]
+pub fn get_ws_routes(ws_state: State<'_, WsState>) -> RouteTable {
+    ws_state.routes.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Save and apply a route table right away; `None` goes back to the defaults
+#[command]
+pub async fn set_ws_routes(
+    app: AppHandle,
+    ws_state: State<'_, WsState>,
+    routes: Option<RouteTable>,
+) -> AppResult<RouteTable> {
+    println!("🦀 Rust Command: set_ws_routes called");
+    if let Some(routes) = &routes {
+        routes.validate()?;
+    }
+
+    {
+        let conn = db::writer(&app)?;
+        let json = routes.as_ref().map(serde_json::to_string).transpose()?;
+        conn.execute(
+            "UPDATE app_state SET ws_routes = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
+            [json],
+        )?;
+    }
+
+    let routes = routes.unwrap_or_default();
+    *ws_state.routes.write().unwrap_or_else(|e| e.into_inner()) = routes.clone();
+    ws_state.server.routes_changed().await;
+    Ok(routes)
+}
+
+/// KDS/QUEUE side: connect to the saved hub from Rust. Safe to call on every
+/// UI load; a connection with the same settings is kept as it is.
+#[command]
+pub async fn start_hub_client(
+    app: AppHandle,
+    ws_state: State<'_, WsState>,
+    role: String,
+    fallback_device_id: Option<String>,
+) -> AppResult<ClientStatus> {
+    println!("🦀 Rust Command: start_hub_client ({})", role);
+    let config = {
+        let conn = db::reader(&app)?;
+        let state = app_state_repo::get_app_state(&conn)?;
+        HubConfig::from_app_state(&state, &role, fallback_device_id)?
+    };
+
+    Ok(ws_state.client.start(config))
+}
+
+#[command]
+pub fn stop_hub_client(ws_state: State<'_, WsState>) {
+    println!("🦀 Rust Command: stop_hub_client");
+    ws_state.client.stop();
+}
+
+#[command]
+pub fn get_hub_client_status(ws_state: State<'_, WsState>) -> ClientStatus {
+    ws_state.client.status()
+}
+
+/// Send a message to the hub over the Rust client
+#[command]
+pub fn send_to_hub(ws_state: State<'_, WsState>, message: DeviceMessage) -> AppResult<()> {
+    ws_state.client.send(message)
+}
+
+/// Hub messages received after `after_seq`, for a UI catching up after a reload
+#[command]
+pub fn get_hub_messages(ws_state: State<'_, WsState>, after_seq: i64) -> Vec<DeviceMessage> {
+    ws_state.client.messages_since(after_seq)
+}
+
+/// KDS/QUEUE side: POS hubs advertised on the LAN for the selected location
+#[command]
+pub async fn discover_hubs(app: AppHandle) -> AppResult<Vec<DiscoveredHub>> {
+    println!("🦀 Rust Command: discover_hubs");
+    let location_id = {
+        let conn = db::reader(&app)?;
+        app_state_repo::get_app_state(&conn)?.selected_location_id
+    };
+
+    let hubs = discovery::discover(discovery::BROWSE_TIME).await?;
+    Ok(discovery::for_location(hubs, location_id.as_deref()))
+}
+
+/// Discover and save the hub this device should use: the one serving the
+/// pinned certificate, or else the first one for the location
+#[command]
+pub async fn auto_select_hub(app: AppHandle) -> AppResult<Option<DiscoveredHub>> {
+    println!("🦀 Rust Command: auto_select_hub");
+    let state = {
+        let conn = db::reader(&app)?;
+        app_state_repo::get_app_state(&conn)?
+    };
+
+    let hubs = discovery::discover(discovery::BROWSE_TIME).await?;
+    let Some(hub) = discovery::pick_hub(
+        &hubs,
+        state.selected_location_id.as_deref(),
+        state.ws_tls_fingerprint.as_deref(),
+    )
+    .cloned() else {
+        log::info!("🧭 No hub found for this location");
+        return Ok(None);
+    };
+
+    let conn = db::writer(&app)?;
+    app_state_repo::update_app_state(&conn, "ws_server_url", &hub.url)?;
+    log::info!("🧭 Selected hub {} at {}", hub.name, hub.url);
+    Ok(Some(hub))
+}
@@ -1,25 +1,28 @@
 
 use tauri::AppHandle;
 use rusqlite::{params, OptionalExtension};
-use crate::db::migrate;
+use crate::db;
+use crate::db::models::work_shift::{CountedAmount, WorkShift};
+use crate::db::models::work_shift_repo;
+use crate::error::AppResult;
+use crate::money::Money;
 
 #[tauri::command]
-pub fn get_work_shift_draft(app: AppHandle) -> Result<Option<String>, String> {
-    let conn = migrate::connection(&app);
+pub fn get_work_shift_draft(app: AppHandle) -> AppResult<Option<String>> {
+    let conn = db::reader(&app)?;
     let res: Option<String> = conn
         .query_row(
             "SELECT data FROM work_shift_draft WHERE id = 1",
             [],
             |row| row.get(0),
         )
-        .optional()
-        .map_err(|e| e.to_string())?;
+        .optional()?;
     Ok(res)
 }
 
 #[tauri::command]
-pub fn save_work_shift_draft(app: AppHandle, data: String) -> Result<(), String> {
-    let conn = migrate::connection(&app);
+pub fn save_work_shift_draft(app: AppHandle, data: String) -> AppResult<()> {
+    let conn = db::writer(&app)?;
     conn.execute(
         "INSERT INTO work_shift_draft (id, data, updated_at)
          VALUES (1, ?1, datetime('now'))
@@ -27,15 +30,63 @@ pub fn save_work_shift_draft(app: AppHandle, data: String) -> Result<(), String>
          SET data = excluded.data,
              updated_at = excluded.updated_at",
         params![data],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn clear_work_shift_draft(app: AppHandle) -> Result<(), String> {
-    let conn = migrate::connection(&app);
-    conn.execute("DELETE FROM work_shift_draft WHERE id = 1", [])
-        .map_err(|e| e.to_string())?;
+pub fn clear_work_shift_draft(app: AppHandle) -> AppResult<()> {
+    let conn = db::writer(&app)?;
+    conn.execute("DELETE FROM work_shift_draft WHERE id = 1", [])?;
     Ok(())
 }
+
+#[tauri::command]
+pub fn start_shift(
+    app: AppHandle,
+    workday_id: i64,
+    opened_by: String,
+    opening_float: Money,
+) -> AppResult<WorkShift> {
+    println!("🦀 Rust Command: start_shift in workday {} by {}", workday_id, opened_by);
+
+    let mut conn = db::writer(&app)?;
+    work_shift_repo::start_work_shift(&mut conn, workday_id, &opened_by, opening_float)
+}
+
+/// Close a shift with what was counted in the drawer; returns the cash-up
+#[tauri::command]
+pub fn end_shift(
+    app: AppHandle,
+    id: i64,
+    closed_by: String,
+    counted: Option<Vec<CountedAmount>>,
+) -> AppResult<WorkShift> {
+    println!("🦀 Rust Command: end_shift {} by {}", id, closed_by);
+
+    let shift = {
+        let mut conn = db::writer(&app)?;
+        work_shift_repo::end_work_shift(&mut conn, id, &closed_by, &counted.unwrap_or_default())?
+    };
+
+    log::info!(
+        "💵 Shift {} closed: expected cash {:.2}, variance {:?}",
+        id,
+        shift.expected_cash.unwrap_or_default(),
+        shift.variance
+    );
+
+    Ok(shift)
+}
+
+#[tauri::command]
+pub fn get_open_shift(app: AppHandle, location_id: String) -> AppResult<Option<WorkShift>> {
+    let conn = db::reader(&app)?;
+    work_shift_repo::get_open_work_shift(&conn, &location_id)
+}
+
+#[tauri::command]
+pub fn get_workday_shifts(app: AppHandle, workday_id: i64) -> AppResult<Vec<WorkShift>> {
+    let conn = db::reader(&app)?;
+    work_shift_repo::get_work_shifts_for_workday(&conn, workday_id)
+}
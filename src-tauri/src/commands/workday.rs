@@ -1,43 +1,42 @@
-use tauri::AppHandle;
-use crate::db::migrate;
+use tauri::{AppHandle, Manager};
+use crate::db;
+use crate::db::models::report::SalesReport;
 use crate::db::models::workday::Workday;
 use crate::db::models::workday_repo;
+use crate::error::{AppError, AppResult};
+use crate::money::{store_currency, Money, Rounding};
+use crate::sync::SyncWorker;
 
 #[tauri::command]
-pub fn save_workday(app: AppHandle, workday: Workday) -> Result<i64, String> {
+pub fn save_workday(app: AppHandle, workday: Workday) -> AppResult<i64> {
     println!("🦀 Rust Command: save_workday called for location: {}", workday.location_id);
 
-    let mut conn = migrate::connection(&app);
+    let mut conn = db::writer(&app)?;
     workday_repo::save_workday(&mut conn, &workday)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn get_all_workdays(app: AppHandle) -> Result<Vec<Workday>, String> {
-    let conn = migrate::connection(&app);
+pub fn get_all_workdays(app: AppHandle) -> AppResult<Vec<Workday>> {
+    let conn = db::reader(&app)?;
     workday_repo::get_all_workdays(&conn)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn get_pending_workdays(app: AppHandle) -> Result<Vec<Workday>, String> {
-    let conn = migrate::connection(&app);
+pub fn get_pending_workdays(app: AppHandle) -> AppResult<Vec<Workday>> {
+    let conn = db::reader(&app)?;
     workday_repo::get_pending_workdays(&conn)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn get_active_workday(app: AppHandle) -> Result<Option<Workday>, String> {
-    let conn = migrate::connection(&app);
+pub fn get_active_workday(app: AppHandle) -> AppResult<Option<Workday>> {
+    let conn = db::reader(&app)?;
     workday_repo::get_active_workday(&conn)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn get_workday_by_id(app: AppHandle, id: i64) -> Result<Option<Workday>, String> {
-    let conn = migrate::connection(&app);
+pub fn get_workday_by_id(app: AppHandle, id: i64) -> AppResult<Option<Workday>> {
+    let conn = db::reader(&app)?;
     workday_repo::get_workday_by_id(&conn, id)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -46,17 +45,16 @@ pub fn update_workday_sync_status(
     id: i64,
     status: String,
     error: Option<String>,
-) -> Result<(), String> {
+) -> AppResult<()> {
     println!("🦀 Updating workday {} status to: {}", id, status);
 
-    let mut conn = migrate::connection(&app);
+    let mut conn = db::writer(&app)?;
     workday_repo::update_workday_sync_status(
         &mut conn,
         id,
         &status,
         error.as_deref(),
     )
-    .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -64,19 +62,17 @@ pub fn set_workday_server_id(
     app: AppHandle,
     id: i64,
     workday_id: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     println!("🦀 Setting workday {} server ID to: {}", id, workday_id);
 
-    let mut conn = migrate::connection(&app);
+    let mut conn = db::writer(&app)?;
     workday_repo::set_workday_server_id(&mut conn, id, &workday_id)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn delete_workday(app: AppHandle, id: i64) -> Result<(), String> {
-    let mut conn = migrate::connection(&app);
+pub fn delete_workday(app: AppHandle, id: i64) -> AppResult<()> {
+    let mut conn = db::writer(&app)?;
     workday_repo::delete_workday(&mut conn, id)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -84,24 +80,22 @@ pub fn get_workdays_by_date_range(
     app: AppHandle,
     start_date: String,
     end_date: String,
-) -> Result<Vec<Workday>, String> {
+) -> AppResult<Vec<Workday>> {
     println!("🦀 Getting workdays between {} and {}", start_date, end_date);
 
-    let conn = migrate::connection(&app);
+    let conn = db::reader(&app)?;
     workday_repo::get_workdays_by_date_range(&conn, &start_date, &end_date)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub fn get_workdays_by_location(
     app: AppHandle,
     location_id: String,
-) -> Result<Vec<Workday>, String> {
+) -> AppResult<Vec<Workday>> {
     println!("🦀 Getting workdays for location: {}", location_id);
 
-    let conn = migrate::connection(&app);
+    let conn = db::reader(&app)?;
     workday_repo::get_workdays_by_location(&conn, &location_id)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -109,15 +103,14 @@ pub fn update_workday(
     app: AppHandle,
     id: i64,
     updates: serde_json::Value,
-) -> Result<(), String> {
+) -> AppResult<()> {
     println!("🦀 Updating workday {} with data: {:?}", id, updates);
 
-    let conn = migrate::connection(&app);
+    let mut conn = db::writer(&app)?;
 
     // Get existing workday
-    let mut workday = workday_repo::get_workday_by_id(&conn, id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Workday with id {} not found", id))?;
+    let mut workday = workday_repo::get_workday_by_id(&conn, id)?
+        .ok_or_else(|| AppError::NotFound(format!("Workday with id {}", id)))?;
 
     // Update fields from the updates object
     if let Some(end_user) = updates.get("end_user").and_then(|v| v.as_str()) {
@@ -127,10 +120,10 @@ pub fn update_workday(
         workday.end_time = Some(end_time.to_string());
     }
     if let Some(total_sales) = updates.get("total_sales").and_then(|v| v.as_f64()) {
-        workday.total_sales = Some(total_sales);
+        workday.total_sales = Some(Money::from_major(total_sales, store_currency(), Rounding::HalfUp));
     }
     if let Some(total_taxes) = updates.get("total_taxes").and_then(|v| v.as_f64()) {
-        workday.total_taxes = Some(total_taxes);
+        workday.total_taxes = Some(Money::from_major(total_taxes, store_currency(), Rounding::HalfUp));
     }
     if let Some(total_ticket_count) = updates.get("total_ticket_count").and_then(|v| v.as_i64()) {
         workday.total_ticket_count = Some(total_ticket_count as i32);
@@ -139,17 +132,54 @@ pub fn update_workday(
         workday.updated_at = Some(updated_at.to_string());
     }
 
-    let mut conn = migrate::connection(&app);
-    workday_repo::save_workday(&mut conn, &workday)
-        .map_err(|e| e.to_string())?;
+    workday_repo::save_workday(&mut conn, &workday)?;
 
     Ok(())
 }
 
+/// X-report for a workday (the active one when no id is given). Read-only.
+#[tauri::command]
+pub fn get_x_report(app: AppHandle, workday_id: Option<i64>) -> AppResult<SalesReport> {
+    let conn = db::reader(&app)?;
+
+    let id = match workday_id {
+        Some(id) => id,
+        None => workday_repo::get_active_workday(&conn)?
+            .and_then(|w| w.id)
+            .ok_or_else(|| AppError::NotFound("Active workday".to_string()))?,
+    };
+
+    println!("🦀 Rust Command: get_x_report for workday {}", id);
+    workday_repo::build_x_report(&conn, id)
+}
+
+/// Build and store the Z-report, lock the workday and queue it for upload
+#[tauri::command]
+pub fn close_workday(app: AppHandle, id: i64, end_user: String) -> AppResult<SalesReport> {
+    println!("🦀 Rust Command: close_workday {} by {}", id, end_user);
+
+    let report = {
+        let mut conn = db::writer(&app)?;
+        workday_repo::close_workday(&mut conn, id, &end_user)?
+    };
+
+    log::info!(
+        "🔒 Workday {} closed: {} tickets, {:.2} taken",
+        id,
+        report.ticket_count,
+        report.total_payments
+    );
+
+    if let Some(worker) = app.try_state::<SyncWorker>() {
+        worker.wake();
+    }
+
+    Ok(report)
+}
+
 #[tauri::command]
-pub fn clear_all_workdays(app: AppHandle) -> Result<(), String> {
+pub fn clear_all_workdays(app: AppHandle) -> AppResult<()> {
     println!("🦀 Rust Command: clear_all_workdays called");
-    let mut conn = migrate::connection(&app);
+    let mut conn = db::writer(&app)?;
     workday_repo::clear_all_workdays(&mut conn)
-        .map_err(|e| e.to_string())
 }
@@ -1,30 +1,38 @@
-use rusqlite::Connection;
-use std::{fs, path::PathBuf, sync::Once};
-use tauri::{AppHandle, Manager};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::time::Instant;
 
-include!(concat!(env!("OUT_DIR"), "/migrations_gen.rs"));
-
-static INIT: Once = Once::new();
+/// A Drizzle SQL file embedded by `build.rs`
+pub struct EmbeddedMigration {
+    pub name: &'static str,
+    pub checksum: &'static str,
+    pub sql: &'static str,
+}
 
-fn db_path(app: &AppHandle) -> PathBuf {
-    let dir = app
-        .path()
-        .app_data_dir()
-        .expect("app_data_dir not available");
+include!(concat!(env!("OUT_DIR"), "/migrations_gen.rs"));
 
-    fs::create_dir_all(&dir).ok();
-    dir.join("local.db")
-}
+/// Migrations that were applied by the old "replay everything and ignore
+/// `already exists`" runner. Databases created before the ledger existed
+/// adopt these once, in tolerant mode, and record them as applied.
+const LEGACY_BASELINE: &[&str] = &["0000_mighty_ma_gnuci.sql"];
 
-fn open_conn(app: &AppHandle) -> Connection {
-    let conn = Connection::open(db_path(app))
-        .expect("Failed to open SQLite");
+#[derive(Debug, thiserror::Error)]
+pub enum MigrationError {
+    #[error("failed to read migration ledger: {0}")]
+    Ledger(#[source] rusqlite::Error),
 
-    // Mobile-safe pragmas
-    conn.pragma_update(None, "journal_mode", "WAL").ok();
-    conn.pragma_update(None, "synchronous", "NORMAL").ok();
+    #[error("migration {name} was modified after being applied (applied checksum {applied}, embedded checksum {embedded})")]
+    ChecksumMismatch {
+        name: String,
+        applied: String,
+        embedded: String,
+    },
 
-    conn
+    #[error("migration {name} failed: {source}")]
+    Failed {
+        name: String,
+        #[source]
+        source: rusqlite::Error,
+    },
 }
 
 /// Clean Drizzle-generated SQL for SQLite compatibility
@@ -40,43 +48,315 @@ fn clean_drizzle_sql(sql: &str) -> String {
         .to_string()
 }
 
-pub fn run_migrations_once(app: &AppHandle) {
-    INIT.call_once(|| {
-        let conn = open_conn(app);
+fn ensure_ledger(conn: &Connection) -> Result<(), MigrationError> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+          name TEXT PRIMARY KEY NOT NULL,
+          checksum TEXT NOT NULL,
+          applied_at TEXT NOT NULL,
+          execution_ms INTEGER
+        );
+        "#,
+    )
+    .map_err(MigrationError::Ledger)
+}
 
-        log::info!("📜 Running embedded migrations");
+fn applied_checksum(conn: &Connection, name: &str) -> Result<Option<String>, MigrationError> {
+    conn.query_row(
+        "SELECT checksum FROM schema_migrations WHERE name = ?1",
+        params![name],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(MigrationError::Ledger)
+}
 
-        for (name, sql) in MIGRATIONS {
-            log::info!("➡ Applying migration: {}", name);
+/// True when the database was created by the pre-ledger runner:
+/// no ledger rows yet, but the baseline tables are already there.
+fn is_legacy_database(conn: &Connection) -> Result<bool, MigrationError> {
+    let recorded: i64 = conn
+        .query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| row.get(0))
+        .map_err(MigrationError::Ledger)?;
 
-            // Clean Drizzle SQL for SQLite compatibility
-            let cleaned_sql = clean_drizzle_sql(sql);
+    if recorded > 0 {
+        return Ok(false);
+    }
 
-            for stmt in cleaned_sql.split(';') {
-                let stmt = stmt.trim();
-                if stmt.is_empty() {
-                    continue;
-                }
+    let has_tables: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'app_state'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(MigrationError::Ledger)?;
 
-                if let Err(err) = conn.execute(stmt, []) {
-                    let msg = err.to_string();
+    Ok(has_tables > 0)
+}
 
-                    if msg.contains("already exists") {
-                        continue;
-                    }
+fn record(
+    conn: &Connection,
+    migration: &EmbeddedMigration,
+    started: Instant,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "INSERT INTO schema_migrations (name, checksum, applied_at, execution_ms) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            migration.name,
+            migration.checksum,
+            chrono::Utc::now().to_rfc3339(),
+            started.elapsed().as_millis() as i64,
+        ],
+    )
+}
+
+/// Replay a baseline migration statement by statement, skipping objects
+/// that already exist, then record it. Only used when adopting a legacy database.
+fn adopt_legacy(conn: &mut Connection, migration: &EmbeddedMigration) -> Result<(), MigrationError> {
+    let started = Instant::now();
+    let failed = |source| MigrationError::Failed {
+        name: migration.name.to_string(),
+        source,
+    };
 
-                    panic!(
-                        "❌ Migration failed: {}\n{}\nSQL:\n{}",
-                        name, err, stmt
-                    );
-                }
+    let tx = conn.transaction().map_err(failed)?;
+
+    for stmt in clean_drizzle_sql(migration.sql).split(';') {
+        let stmt = stmt.trim();
+        if stmt.is_empty() {
+            continue;
+        }
+
+        if let Err(err) = tx.execute(stmt, []) {
+            if err.to_string().contains("already exists") {
+                continue;
             }
+            return Err(failed(err));
         }
+    }
 
-        log::info!("✅ All migrations applied");
-    });
+    record(&tx, migration, started).map_err(failed)?;
+    tx.commit().map_err(failed)?;
+    Ok(())
 }
 
-pub fn connection(app: &AppHandle) -> Connection {
-    open_conn(app)
-}
\ No newline at end of file
+fn apply(conn: &mut Connection, migration: &EmbeddedMigration) -> Result<(), MigrationError> {
+    let started = Instant::now();
+    let failed = |source| MigrationError::Failed {
+        name: migration.name.to_string(),
+        source,
+    };
+
+    // Drizzle rebuilds a table (create __new_x, copy, drop, rename) between
+    // `PRAGMA foreign_keys=OFF/ON`. The pragma is a no-op inside a
+    // transaction, so it is applied around it instead.
+    let rebuilds_tables = migration.sql.contains("PRAGMA foreign_keys=OFF");
+    if rebuilds_tables {
+        conn.pragma_update(None, "foreign_keys", false).map_err(failed)?;
+    }
+
+    let result = apply_in_transaction(conn, migration, started);
+
+    if rebuilds_tables {
+        conn.pragma_update(None, "foreign_keys", true).map_err(failed)?;
+    }
+
+    result
+}
+
+fn apply_in_transaction(
+    conn: &mut Connection,
+    migration: &EmbeddedMigration,
+    started: Instant,
+) -> Result<(), MigrationError> {
+    let failed = |source| MigrationError::Failed {
+        name: migration.name.to_string(),
+        source,
+    };
+
+    let tx = conn.transaction().map_err(failed)?;
+    tx.execute_batch(&clean_drizzle_sql(migration.sql)).map_err(failed)?;
+
+    // Rebuilt tables keep every row, so this only reports orphans that were
+    // already there; refusing to start over them would help nobody
+    let orphans: Option<String> = tx
+        .query_row("PRAGMA foreign_key_check", [], |row| row.get(0))
+        .optional()
+        .map_err(failed)?;
+
+    if let Some(table) = orphans {
+        log::warn!("⚠️ {}: rows in {} point at missing parents", migration.name, table);
+    }
+
+    record(&tx, migration, started).map_err(failed)?;
+    tx.commit().map_err(failed)?;
+    Ok(())
+}
+
+/// Apply every embedded migration that is not yet in `schema_migrations`,
+/// in file order, each inside its own transaction. Stops at the first failure.
+pub fn run_migrations(conn: &mut Connection) -> Result<(), MigrationError> {
+    run(conn, MIGRATIONS)
+}
+
+fn run(conn: &mut Connection, migrations: &[EmbeddedMigration]) -> Result<(), MigrationError> {
+    ensure_ledger(conn)?;
+
+    let legacy = is_legacy_database(conn)?;
+    if legacy {
+        log::info!("📜 Existing database without migration ledger, adopting baseline");
+    }
+
+    for migration in migrations {
+        if let Some(applied) = applied_checksum(conn, migration.name)? {
+            if applied != migration.checksum {
+                return Err(MigrationError::ChecksumMismatch {
+                    name: migration.name.to_string(),
+                    applied,
+                    embedded: migration.checksum.to_string(),
+                });
+            }
+            continue;
+        }
+
+        if legacy && LEGACY_BASELINE.contains(&migration.name) {
+            log::info!("➡ Adopting baseline migration: {}", migration.name);
+            adopt_legacy(conn, migration)?;
+        } else {
+            log::info!("➡ Applying migration: {}", migration.name);
+            apply(conn, migration)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn migration(name: &'static str, checksum: &'static str, sql: &'static str) -> EmbeddedMigration {
+        EmbeddedMigration { name, checksum, sql }
+    }
+
+    const ITEMS: EmbeddedMigration = migration(
+        "0001_items.sql",
+        "c1",
+        "CREATE TABLE `items` (`id` text PRIMARY KEY NOT NULL);--> statement-breakpoint\nINSERT INTO items VALUES ('a');",
+    );
+    const PRICES: EmbeddedMigration = migration("0002_prices.sql", "c2", "ALTER TABLE items ADD price integer;");
+    const BASELINE: EmbeddedMigration = migration(
+        LEGACY_BASELINE[0],
+        "base",
+        "CREATE TABLE `app_state` (`id` integer);--> statement-breakpoint\nCREATE TABLE `printers` (`id` text);",
+    );
+
+    fn ledger(conn: &Connection) -> Vec<(String, String)> {
+        let mut stmt = conn.prepare("SELECT name, checksum FROM schema_migrations ORDER BY name").unwrap();
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        rows.collect::<Result<_, _>>().unwrap()
+    }
+
+    fn has_table(conn: &Connection, name: &str) -> bool {
+        conn.query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1", [name], |row| {
+            row.get::<_, i64>(0)
+        })
+        .unwrap()
+            > 0
+    }
+
+    #[test]
+    fn applies_each_migration_once() {
+        let mut conn = Connection::open_in_memory().unwrap();
+
+        run(&mut conn, &[ITEMS]).unwrap();
+        // ITEMS again would fail on the existing table
+        run(&mut conn, &[ITEMS, PRICES]).unwrap();
+        run(&mut conn, &[ITEMS, PRICES]).unwrap();
+
+        assert_eq!(
+            ledger(&conn),
+            vec![("0001_items.sql".into(), "c1".into()), ("0002_prices.sql".into(), "c2".into())]
+        );
+        let rows: i64 = conn.query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0)).unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    fn refuses_a_migration_changed_after_it_was_applied() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn, &[ITEMS]).unwrap();
+
+        let edited = migration(ITEMS.name, "c1-edited", ITEMS.sql);
+        match run(&mut conn, &[edited, PRICES]) {
+            Err(MigrationError::ChecksumMismatch { name, applied, embedded }) => {
+                assert_eq!((name.as_str(), applied.as_str(), embedded.as_str()), ("0001_items.sql", "c1", "c1-edited"));
+            }
+            other => panic!("expected a checksum mismatch, got {:?}", other),
+        }
+        // Nothing after it ran
+        assert_eq!(ledger(&conn).len(), 1);
+    }
+
+    #[test]
+    fn a_failed_migration_leaves_nothing_behind() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let broken = migration(
+            "0002_broken.sql",
+            "c2",
+            "CREATE TABLE prices (id text);--> statement-breakpoint\nINSERT INTO missing VALUES (1);",
+        );
+
+        match run(&mut conn, &[ITEMS, broken, PRICES]) {
+            Err(MigrationError::Failed { name, .. }) => assert_eq!(name, "0002_broken.sql"),
+            other => panic!("expected the migration to fail, got {:?}", other),
+        }
+        // The one before it stays applied; its own table was rolled back
+        assert_eq!(ledger(&conn), vec![("0001_items.sql".into(), "c1".into())]);
+        assert!(has_table(&conn, "items"));
+        assert!(!has_table(&conn, "prices"));
+
+        // Fixed, it applies on the next start
+        let fixed = migration("0002_broken.sql", "c2", "CREATE TABLE prices (id text);");
+        run(&mut conn, &[ITEMS, fixed, PRICES]).unwrap();
+        assert_eq!(ledger(&conn).len(), 3);
+    }
+
+    #[test]
+    fn adopts_a_database_from_before_the_ledger() {
+        // The old runner created app_state but not printers
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE app_state (id integer); INSERT INTO app_state VALUES (1);")
+            .unwrap();
+        run(&mut conn, &[BASELINE, ITEMS]).unwrap();
+
+        assert_eq!(ledger(&conn).len(), 2);
+        assert!(has_table(&conn, "printers"));
+        let kept: i64 = conn.query_row("SELECT COUNT(*) FROM app_state", [], |row| row.get(0)).unwrap();
+        assert_eq!(kept, 1);
+
+        // Only the baseline is replayed tolerantly, and only without a ledger
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE app_state (id integer); CREATE TABLE items (id text);").unwrap();
+        assert!(matches!(
+            run(&mut conn, &[BASELINE, ITEMS]),
+            Err(MigrationError::Failed { name, .. }) if name == "0001_items.sql"
+        ));
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn, &[ITEMS]).unwrap();
+        conn.execute_batch("CREATE TABLE app_state (id integer);").unwrap();
+        assert!(matches!(
+            run(&mut conn, &[BASELINE, ITEMS]),
+            Err(MigrationError::Failed { name, .. }) if name == LEGACY_BASELINE[0]
+        ));
+    }
+
+    #[test]
+    fn embedded_migrations_apply_to_an_empty_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+        run_migrations(&mut conn).unwrap();
+        assert_eq!(ledger(&conn).len(), MIGRATIONS.len());
+    }
+}
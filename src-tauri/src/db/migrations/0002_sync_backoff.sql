@@ -0,0 +1,5 @@
+ALTER TABLE `tickets` ADD `next_attempt_at` text;--> statement-breakpoint
+ALTER TABLE `workdays` ADD `sync_attempts` integer DEFAULT 0;--> statement-breakpoint
+ALTER TABLE `workdays` ADD `next_attempt_at` text;--> statement-breakpoint
+CREATE INDEX `tickets_sync_due_idx` ON `tickets` (`sync_status`,`next_attempt_at`);--> statement-breakpoint
+CREATE INDEX `workdays_sync_due_idx` ON `workdays` (`sync_status`,`next_attempt_at`);
@@ -0,0 +1,3 @@
+ALTER TABLE `tickets` ADD `idempotency_key` text;--> statement-breakpoint
+UPDATE `tickets` SET `idempotency_key` = lower(hex(randomblob(16))) WHERE `idempotency_key` IS NULL;--> statement-breakpoint
+CREATE UNIQUE INDEX `tickets_idempotency_key_unique` ON `tickets` (`idempotency_key`);
\ No newline at end of file
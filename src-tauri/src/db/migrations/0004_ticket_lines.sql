@@ -0,0 +1,148 @@
+CREATE TABLE `ticket_items` (
+	`id` integer PRIMARY KEY AUTOINCREMENT NOT NULL,
+	`ticket_id` text NOT NULL,
+	`sort_order` integer NOT NULL,
+	`product_id` text,
+	`product_name` text NOT NULL,
+	`category_id` text,
+	`product_group_id` text,
+	`quantity` real DEFAULT 1 NOT NULL,
+	`unit_price` real DEFAULT 0 NOT NULL,
+	`net_amount` real DEFAULT 0 NOT NULL,
+	`tax_amount` real DEFAULT 0,
+	`charge_amount` real DEFAULT 0,
+	`voided` integer DEFAULT 0,
+	`business_date` text,
+	`order_time` text,
+	FOREIGN KEY (`ticket_id`) REFERENCES `tickets`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+CREATE INDEX `ticket_items_ticket_idx` ON `ticket_items` (`ticket_id`);--> statement-breakpoint
+CREATE INDEX `ticket_items_business_date_idx` ON `ticket_items` (`business_date`,`product_id`);--> statement-breakpoint
+CREATE TABLE `ticket_item_modifiers` (
+	`id` integer PRIMARY KEY AUTOINCREMENT NOT NULL,
+	`ticket_item_id` integer NOT NULL,
+	`ticket_id` text NOT NULL,
+	`name` text NOT NULL,
+	`quantity` real DEFAULT 1 NOT NULL,
+	`price` real DEFAULT 0 NOT NULL,
+	FOREIGN KEY (`ticket_item_id`) REFERENCES `ticket_items`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+CREATE INDEX `ticket_item_modifiers_item_idx` ON `ticket_item_modifiers` (`ticket_item_id`);--> statement-breakpoint
+CREATE INDEX `ticket_item_modifiers_ticket_idx` ON `ticket_item_modifiers` (`ticket_id`);--> statement-breakpoint
+CREATE TABLE `ticket_payments` (
+	`id` integer PRIMARY KEY AUTOINCREMENT NOT NULL,
+	`ticket_id` text NOT NULL,
+	`payment_type_id` text,
+	`payment_type` text NOT NULL,
+	`amount` real DEFAULT 0 NOT NULL,
+	`tip_amount` real DEFAULT 0,
+	`tendered_amount` real,
+	`net_amount` real,
+	`currency` text,
+	`business_date` text,
+	`payment_date` text,
+	`payment_time` text,
+	FOREIGN KEY (`ticket_id`) REFERENCES `tickets`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+CREATE INDEX `ticket_payments_ticket_idx` ON `ticket_payments` (`ticket_id`);--> statement-breakpoint
+CREATE INDEX `ticket_payments_business_date_idx` ON `ticket_payments` (`business_date`,`payment_type_id`);--> statement-breakpoint
+CREATE TABLE `ticket_charges` (
+	`id` integer PRIMARY KEY AUTOINCREMENT NOT NULL,
+	`ticket_id` text NOT NULL,
+	`name` text NOT NULL,
+	`is_tax` integer DEFAULT 0 NOT NULL,
+	`percentage` real,
+	`amount` real,
+	`transaction_type_id` text,
+	FOREIGN KEY (`ticket_id`) REFERENCES `tickets`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+CREATE INDEX `ticket_charges_ticket_idx` ON `ticket_charges` (`ticket_id`);--> statement-breakpoint
+INSERT INTO `ticket_items` (`ticket_id`, `sort_order`, `product_id`, `product_name`, `category_id`, `product_group_id`, `quantity`, `unit_price`, `net_amount`, `tax_amount`, `charge_amount`, `voided`, `business_date`, `order_time`)
+SELECT
+	t.`id`,
+	COALESCE(json_extract(o.value, '$.sort_order'), o.key + 1),
+	json_extract(o.value, '$.extra_data.product_id'),
+	COALESCE(json_extract(o.value, '$.product_name'), ''),
+	json_extract(o.value, '$.extra_data.category_id'),
+	json_extract(o.value, '$.extra_data.product_group_id'),
+	CAST(COALESCE(json_extract(o.value, '$.quantity'), 1) AS REAL),
+	CAST(COALESCE(json_extract(o.value, '$.order_price'), 0) AS REAL),
+	CAST(COALESCE(json_extract(o.value, '$.net_amount'), 0) AS REAL),
+	CAST(COALESCE(json_extract(o.value, '$.tax_amount'), 0) AS REAL),
+	CAST(COALESCE(json_extract(o.value, '$.charge_amount'), 0) AS REAL),
+	COALESCE(json_extract(o.value, '$.order_state.void'), 0),
+	json_extract(o.value, '$.business_date'),
+	json_extract(o.value, '$.order_time')
+FROM `tickets` t, json_each(t.`ticket_data`, '$.orders') o
+WHERE json_valid(t.`ticket_data`)
+	AND COALESCE(json_extract(o.value, '$.parent_sort_order'), 0) = 0;--> statement-breakpoint
+INSERT INTO `ticket_item_modifiers` (`ticket_item_id`, `ticket_id`, `name`, `quantity`, `price`)
+SELECT
+	i.`id`,
+	t.`id`,
+	COALESCE(json_extract(o.value, '$.product_name'), ''),
+	CAST(COALESCE(json_extract(o.value, '$.quantity'), 1) AS REAL),
+	CAST(COALESCE(json_extract(o.value, '$.order_price'), 0) AS REAL)
+FROM `tickets` t, json_each(t.`ticket_data`, '$.orders') o
+JOIN `ticket_items` i
+	ON i.`ticket_id` = t.`id` AND i.`sort_order` = json_extract(o.value, '$.parent_sort_order')
+WHERE json_valid(t.`ticket_data`)
+	AND COALESCE(json_extract(o.value, '$.parent_sort_order'), 0) > 0;--> statement-breakpoint
+INSERT INTO `ticket_item_modifiers` (`ticket_item_id`, `ticket_id`, `name`, `quantity`, `price`)
+SELECT
+	i.`id`,
+	t.`id`,
+	COALESCE(json_extract(m.value, '$.name'), ''),
+	CAST(COALESCE(json_extract(m.value, '$.qty'), 1) AS REAL),
+	CAST(COALESCE(json_extract(m.value, '$.price'), 0) AS REAL)
+FROM `tickets` t, json_each(t.`ticket_data`, '$.orders') o, json_each(o.value, '$.extra_data.modifiers') m
+JOIN `ticket_items` i
+	ON i.`ticket_id` = t.`id` AND i.`sort_order` = json_extract(o.value, '$.sort_order')
+WHERE json_valid(t.`ticket_data`);--> statement-breakpoint
+INSERT INTO `ticket_payments` (`ticket_id`, `payment_type_id`, `payment_type`, `amount`, `tip_amount`, `tendered_amount`, `net_amount`, `currency`, `business_date`, `payment_date`, `payment_time`)
+SELECT
+	t.`id`,
+	json_extract(p.value, '$.payment_type_id'),
+	COALESCE(json_extract(p.value, '$.payment_type'), ''),
+	CAST(COALESCE(json_extract(p.value, '$.payment_amount'), 0) AS REAL),
+	CAST(COALESCE(json_extract(p.value, '$.tip_amount'), 0) AS REAL),
+	CAST(json_extract(p.value, '$.tendered_amount') AS REAL),
+	CAST(json_extract(p.value, '$.net_amount') AS REAL),
+	json_extract(p.value, '$.currency'),
+	json_extract(t.`ticket_data`, '$.ticket.business_date'),
+	json_extract(p.value, '$.payment_date'),
+	json_extract(p.value, '$.payment_time')
+FROM `tickets` t, json_each(t.`ticket_data`, '$.payments') p
+WHERE json_valid(t.`ticket_data`);--> statement-breakpoint
+INSERT INTO `ticket_charges` (`ticket_id`, `name`, `is_tax`, `percentage`, `amount`, `transaction_type_id`)
+SELECT
+	t.`id`,
+	COALESCE(json_extract(c.value, '$.name'), ''),
+	COALESCE(json_extract(c.value, '$.is_tax'), 0),
+	json_extract(c.value, '$.percentage'),
+	json_extract(c.value, '$.amount'),
+	json_extract(c.value, '$.transaction_type_id')
+FROM `tickets` t, json_each(t.`ticket_data`, '$.ticket.extra_data.charges') c
+WHERE json_valid(t.`ticket_data`);--> statement-breakpoint
+INSERT INTO `ticket_charges` (`ticket_id`, `name`, `is_tax`, `percentage`, `amount`)
+SELECT
+	t.`id`,
+	d.key,
+	1,
+	d.value,
+	CASE
+		WHEN (SELECT count(*) FROM json_each(t.`ticket_data`, '$.orders[0].tax_detail')) = 1
+		THEN CAST(json_extract(t.`ticket_data`, '$.ticket.extra_data.total_tax') AS REAL)
+	END
+FROM `tickets` t, json_each(t.`ticket_data`, '$.orders[0].tax_detail') d
+WHERE json_valid(t.`ticket_data`)
+	AND json_type(t.`ticket_data`, '$.ticket.extra_data.charges') IS NULL;--> statement-breakpoint
+INSERT INTO `ticket_charges` (`ticket_id`, `name`, `is_tax`, `amount`)
+SELECT t.`id`, d.key, 0, d.value
+FROM `tickets` t, json_each(t.`ticket_data`, '$.orders[0].charge_details') d
+WHERE json_valid(t.`ticket_data`)
+	AND json_type(t.`ticket_data`, '$.ticket.extra_data.charges') IS NULL;
\ No newline at end of file
@@ -0,0 +1,13 @@
+ALTER TABLE `workdays` ADD `locked_at` text;--> statement-breakpoint
+ALTER TABLE `workdays` ADD `z_report` text;--> statement-breakpoint
+ALTER TABLE `ticket_items` ADD `refunded` integer DEFAULT 0;--> statement-breakpoint
+UPDATE `ticket_items` SET `refunded` = 1
+WHERE EXISTS (
+	SELECT 1
+	FROM `tickets` t, json_each(t.`ticket_data`, '$.orders') o
+	WHERE t.`id` = `ticket_items`.`ticket_id`
+		AND json_valid(t.`ticket_data`)
+		AND COALESCE(json_extract(o.value, '$.parent_sort_order'), 0) = 0
+		AND COALESCE(json_extract(o.value, '$.sort_order'), o.key + 1) = `ticket_items`.`sort_order`
+		AND (json_extract(o.value, '$.order_state.refund') OR json_extract(o.value, '$.order_state.return'))
+);
\ No newline at end of file
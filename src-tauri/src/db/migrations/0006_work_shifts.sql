@@ -0,0 +1,22 @@
+CREATE TABLE `work_shifts` (
+	`id` integer PRIMARY KEY AUTOINCREMENT NOT NULL,
+	`workday_id` integer NOT NULL,
+	`location_id` text NOT NULL,
+	`status` text DEFAULT 'OPEN' NOT NULL,
+	`opened_by` text NOT NULL,
+	`opened_at` text NOT NULL,
+	`opening_float` real DEFAULT 0 NOT NULL,
+	`closed_by` text,
+	`closed_at` text,
+	`expected_cash` real,
+	`counted_cash` real,
+	`variance` real,
+	`cash_up` text,
+	`x_report` text,
+	`created_at` text NOT NULL,
+	`updated_at` text,
+	FOREIGN KEY (`workday_id`) REFERENCES `workdays`(`id`) ON UPDATE no action ON DELETE no action
+);
+--> statement-breakpoint
+CREATE INDEX `work_shifts_workday_idx` ON `work_shifts` (`workday_id`);--> statement-breakpoint
+CREATE UNIQUE INDEX `work_shifts_open_location_unique` ON `work_shifts` (`location_id`) WHERE `status` = 'OPEN';
\ No newline at end of file
@@ -0,0 +1,226 @@
+CREATE TABLE `__money_factor` AS SELECT COALESCE((
+	SELECT CASE
+		WHEN upper(s.`currency_code`) IN ('BIF', 'CLP', 'DJF', 'GNF', 'ISK', 'JPY', 'KMF', 'KRW', 'PYG', 'RWF', 'UGX', 'UYI', 'VND', 'VUV', 'XAF', 'XOF', 'XPF') THEN 1
+		WHEN upper(s.`currency_code`) IN ('BHD', 'IQD', 'JOD', 'KWD', 'LYD', 'OMR', 'TND') THEN 1000
+		WHEN upper(s.`currency_code`) IN ('CLF', 'UYW') THEN 10000
+		ELSE 100
+	END
+	FROM `app_state` a
+	JOIN `setups` s ON s.`code` = a.`setup_code`
+	WHERE a.`id` = 1
+	LIMIT 1
+), 100) AS `factor`;
+--> statement-breakpoint
+PRAGMA foreign_keys=OFF;
+--> statement-breakpoint
+CREATE TABLE `__new_products` (
+	`id` text PRIMARY KEY NOT NULL,
+	`name` text NOT NULL,
+	`code` text,
+	`description` text,
+	`category_id` text,
+	`price` integer NOT NULL,
+	`active` integer DEFAULT 1,
+	`sort_order` integer DEFAULT 0,
+	`is_sold_out` integer DEFAULT 0,
+	`created_at` text,
+	`updated_at` text,
+	`deleted_at` text,
+	`media` text,
+	`overrides` text,
+	`is_product_tag` integer
+);
+--> statement-breakpoint
+INSERT INTO `__new_products`(`id`, `name`, `code`, `description`, `category_id`, `price`, `active`, `sort_order`, `is_sold_out`, `created_at`, `updated_at`, `deleted_at`, `media`, `overrides`, `is_product_tag`) SELECT `id`, `name`, `code`, `description`, `category_id`, CAST(ROUND(`price` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), `active`, `sort_order`, `is_sold_out`, `created_at`, `updated_at`, `deleted_at`, `media`, `overrides`, `is_product_tag` FROM `products`;
+--> statement-breakpoint
+DROP TABLE `products`;
+--> statement-breakpoint
+ALTER TABLE `__new_products` RENAME TO `products`;
+--> statement-breakpoint
+CREATE TABLE `__new_product_tags` (
+	`id` text PRIMARY KEY NOT NULL,
+	`tag_group_id` text NOT NULL,
+	`product_id` text NOT NULL,
+	`name` text NOT NULL,
+	`price` integer DEFAULT 0,
+	`active` integer DEFAULT 1,
+	`sort_order` integer DEFAULT 0,
+	`created_at` text,
+	`updated_at` text,
+	`deleted_at` text
+);
+--> statement-breakpoint
+INSERT INTO `__new_product_tags`(`id`, `tag_group_id`, `product_id`, `name`, `price`, `active`, `sort_order`, `created_at`, `updated_at`, `deleted_at`) SELECT `id`, `tag_group_id`, `product_id`, `name`, CAST(ROUND(`price` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), `active`, `sort_order`, `created_at`, `updated_at`, `deleted_at` FROM `product_tags`;
+--> statement-breakpoint
+DROP TABLE `product_tags`;
+--> statement-breakpoint
+ALTER TABLE `__new_product_tags` RENAME TO `product_tags`;
+--> statement-breakpoint
+CREATE TABLE `__new_workdays` (
+	`id` integer PRIMARY KEY AUTOINCREMENT NOT NULL,
+	`workday_id` text,
+	`start_user` text,
+	`end_user` text,
+	`start_time` text,
+	`end_time` text,
+	`location_id` text NOT NULL,
+	`total_sales` integer,
+	`total_taxes` integer,
+	`total_ticket_count` integer,
+	`work_period_informations` text,
+	`department_ticket_informations` text,
+	`add_on` text,
+	`auto_closed` integer,
+	`external_processed` integer,
+	`work_period_day` text,
+	`business_date` text,
+	`sync_status` text DEFAULT 'PENDING',
+	`sync_error` text,
+	`created_at` text NOT NULL,
+	`updated_at` text,
+	`sync_attempts` integer DEFAULT 0,
+	`next_attempt_at` text,
+	`locked_at` text,
+	`z_report` text
+);
+--> statement-breakpoint
+INSERT INTO `__new_workdays`(`id`, `workday_id`, `start_user`, `end_user`, `start_time`, `end_time`, `location_id`, `total_sales`, `total_taxes`, `total_ticket_count`, `work_period_informations`, `department_ticket_informations`, `add_on`, `auto_closed`, `external_processed`, `work_period_day`, `business_date`, `sync_status`, `sync_error`, `created_at`, `updated_at`, `sync_attempts`, `next_attempt_at`, `locked_at`, `z_report`) SELECT `id`, `workday_id`, `start_user`, `end_user`, `start_time`, `end_time`, `location_id`, CAST(ROUND(`total_sales` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), CAST(ROUND(`total_taxes` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), `total_ticket_count`, `work_period_informations`, `department_ticket_informations`, `add_on`, `auto_closed`, `external_processed`, `work_period_day`, `business_date`, `sync_status`, `sync_error`, `created_at`, `updated_at`, `sync_attempts`, `next_attempt_at`, `locked_at`, `z_report` FROM `workdays`;
+--> statement-breakpoint
+DROP TABLE `workdays`;
+--> statement-breakpoint
+ALTER TABLE `__new_workdays` RENAME TO `workdays`;
+--> statement-breakpoint
+CREATE INDEX `workdays_sync_due_idx` ON `workdays` (`sync_status`,`next_attempt_at`);
+--> statement-breakpoint
+CREATE TABLE `__new_ticket_items` (
+	`id` integer PRIMARY KEY AUTOINCREMENT NOT NULL,
+	`ticket_id` text NOT NULL,
+	`sort_order` integer NOT NULL,
+	`product_id` text,
+	`product_name` text NOT NULL,
+	`category_id` text,
+	`product_group_id` text,
+	`quantity` real DEFAULT 1 NOT NULL,
+	`unit_price` integer DEFAULT 0 NOT NULL,
+	`net_amount` integer DEFAULT 0 NOT NULL,
+	`tax_amount` integer DEFAULT 0,
+	`charge_amount` integer DEFAULT 0,
+	`voided` integer DEFAULT 0,
+	`business_date` text,
+	`order_time` text,
+	`refunded` integer DEFAULT 0,
+	FOREIGN KEY (`ticket_id`) REFERENCES `tickets`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+INSERT INTO `__new_ticket_items`(`id`, `ticket_id`, `sort_order`, `product_id`, `product_name`, `category_id`, `product_group_id`, `quantity`, `unit_price`, `net_amount`, `tax_amount`, `charge_amount`, `voided`, `business_date`, `order_time`, `refunded`) SELECT `id`, `ticket_id`, `sort_order`, `product_id`, `product_name`, `category_id`, `product_group_id`, `quantity`, CAST(ROUND(`unit_price` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), CAST(ROUND(`net_amount` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), CAST(ROUND(`tax_amount` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), CAST(ROUND(`charge_amount` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), `voided`, `business_date`, `order_time`, `refunded` FROM `ticket_items`;
+--> statement-breakpoint
+DROP TABLE `ticket_items`;
+--> statement-breakpoint
+ALTER TABLE `__new_ticket_items` RENAME TO `ticket_items`;
+--> statement-breakpoint
+CREATE INDEX `ticket_items_ticket_idx` ON `ticket_items` (`ticket_id`);
+--> statement-breakpoint
+CREATE INDEX `ticket_items_business_date_idx` ON `ticket_items` (`business_date`,`product_id`);
+--> statement-breakpoint
+CREATE TABLE `__new_ticket_item_modifiers` (
+	`id` integer PRIMARY KEY AUTOINCREMENT NOT NULL,
+	`ticket_item_id` integer NOT NULL,
+	`ticket_id` text NOT NULL,
+	`name` text NOT NULL,
+	`quantity` real DEFAULT 1 NOT NULL,
+	`price` integer DEFAULT 0 NOT NULL,
+	FOREIGN KEY (`ticket_item_id`) REFERENCES `ticket_items`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+INSERT INTO `__new_ticket_item_modifiers`(`id`, `ticket_item_id`, `ticket_id`, `name`, `quantity`, `price`) SELECT `id`, `ticket_item_id`, `ticket_id`, `name`, `quantity`, CAST(ROUND(`price` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER) FROM `ticket_item_modifiers`;
+--> statement-breakpoint
+DROP TABLE `ticket_item_modifiers`;
+--> statement-breakpoint
+ALTER TABLE `__new_ticket_item_modifiers` RENAME TO `ticket_item_modifiers`;
+--> statement-breakpoint
+CREATE INDEX `ticket_item_modifiers_item_idx` ON `ticket_item_modifiers` (`ticket_item_id`);
+--> statement-breakpoint
+CREATE INDEX `ticket_item_modifiers_ticket_idx` ON `ticket_item_modifiers` (`ticket_id`);
+--> statement-breakpoint
+CREATE TABLE `__new_ticket_payments` (
+	`id` integer PRIMARY KEY AUTOINCREMENT NOT NULL,
+	`ticket_id` text NOT NULL,
+	`payment_type_id` text,
+	`payment_type` text NOT NULL,
+	`amount` integer DEFAULT 0 NOT NULL,
+	`tip_amount` integer DEFAULT 0,
+	`tendered_amount` integer,
+	`net_amount` integer,
+	`currency` text,
+	`business_date` text,
+	`payment_date` text,
+	`payment_time` text,
+	FOREIGN KEY (`ticket_id`) REFERENCES `tickets`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+INSERT INTO `__new_ticket_payments`(`id`, `ticket_id`, `payment_type_id`, `payment_type`, `amount`, `tip_amount`, `tendered_amount`, `net_amount`, `currency`, `business_date`, `payment_date`, `payment_time`) SELECT `id`, `ticket_id`, `payment_type_id`, `payment_type`, CAST(ROUND(`amount` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), CAST(ROUND(`tip_amount` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), CAST(ROUND(`tendered_amount` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), CAST(ROUND(`net_amount` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), `currency`, `business_date`, `payment_date`, `payment_time` FROM `ticket_payments`;
+--> statement-breakpoint
+DROP TABLE `ticket_payments`;
+--> statement-breakpoint
+ALTER TABLE `__new_ticket_payments` RENAME TO `ticket_payments`;
+--> statement-breakpoint
+CREATE INDEX `ticket_payments_ticket_idx` ON `ticket_payments` (`ticket_id`);
+--> statement-breakpoint
+CREATE INDEX `ticket_payments_business_date_idx` ON `ticket_payments` (`business_date`,`payment_type_id`);
+--> statement-breakpoint
+CREATE TABLE `__new_ticket_charges` (
+	`id` integer PRIMARY KEY AUTOINCREMENT NOT NULL,
+	`ticket_id` text NOT NULL,
+	`name` text NOT NULL,
+	`is_tax` integer DEFAULT 0 NOT NULL,
+	`percentage` real,
+	`amount` integer,
+	`transaction_type_id` text,
+	FOREIGN KEY (`ticket_id`) REFERENCES `tickets`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+INSERT INTO `__new_ticket_charges`(`id`, `ticket_id`, `name`, `is_tax`, `percentage`, `amount`, `transaction_type_id`) SELECT `id`, `ticket_id`, `name`, `is_tax`, `percentage`, CAST(ROUND(`amount` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), `transaction_type_id` FROM `ticket_charges`;
+--> statement-breakpoint
+DROP TABLE `ticket_charges`;
+--> statement-breakpoint
+ALTER TABLE `__new_ticket_charges` RENAME TO `ticket_charges`;
+--> statement-breakpoint
+CREATE INDEX `ticket_charges_ticket_idx` ON `ticket_charges` (`ticket_id`);
+--> statement-breakpoint
+CREATE TABLE `__new_work_shifts` (
+	`id` integer PRIMARY KEY AUTOINCREMENT NOT NULL,
+	`workday_id` integer NOT NULL,
+	`location_id` text NOT NULL,
+	`status` text DEFAULT 'OPEN' NOT NULL,
+	`opened_by` text NOT NULL,
+	`opened_at` text NOT NULL,
+	`opening_float` integer DEFAULT 0 NOT NULL,
+	`closed_by` text,
+	`closed_at` text,
+	`expected_cash` integer,
+	`counted_cash` integer,
+	`variance` integer,
+	`cash_up` text,
+	`x_report` text,
+	`created_at` text NOT NULL,
+	`updated_at` text,
+	FOREIGN KEY (`workday_id`) REFERENCES `workdays`(`id`) ON UPDATE no action ON DELETE no action
+);
+--> statement-breakpoint
+INSERT INTO `__new_work_shifts`(`id`, `workday_id`, `location_id`, `status`, `opened_by`, `opened_at`, `opening_float`, `closed_by`, `closed_at`, `expected_cash`, `counted_cash`, `variance`, `cash_up`, `x_report`, `created_at`, `updated_at`) SELECT `id`, `workday_id`, `location_id`, `status`, `opened_by`, `opened_at`, CAST(ROUND(`opening_float` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), `closed_by`, `closed_at`, CAST(ROUND(`expected_cash` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), CAST(ROUND(`counted_cash` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), CAST(ROUND(`variance` * (SELECT `factor` FROM `__money_factor`)) AS INTEGER), `cash_up`, `x_report`, `created_at`, `updated_at` FROM `work_shifts`;
+--> statement-breakpoint
+DROP TABLE `work_shifts`;
+--> statement-breakpoint
+ALTER TABLE `__new_work_shifts` RENAME TO `work_shifts`;
+--> statement-breakpoint
+CREATE INDEX `work_shifts_workday_idx` ON `work_shifts` (`workday_id`);
+--> statement-breakpoint
+CREATE UNIQUE INDEX `work_shifts_open_location_unique` ON `work_shifts` (`location_id`) WHERE `status` = 'OPEN';
+--> statement-breakpoint
+PRAGMA foreign_keys=ON;
+--> statement-breakpoint
+UPDATE `tickets` SET `ticket_amount` = `ticket_amount` * (SELECT `factor` FROM `__money_factor`) / 100 WHERE `ticket_amount` IS NOT NULL;
+--> statement-breakpoint
+UPDATE `kds_tickets` SET `total_amount` = `total_amount` * (SELECT `factor` FROM `__money_factor`) / 100 WHERE `total_amount` IS NOT NULL;
+--> statement-breakpoint
+DROP TABLE `__money_factor`;
\ No newline at end of file
@@ -0,0 +1,6 @@
+ALTER TABLE `device_profiles` ADD `token` text;--> statement-breakpoint
+ALTER TABLE `device_profiles` ADD `paired_at` text;--> statement-breakpoint
+ALTER TABLE `device_profiles` ADD `revoked_at` text;--> statement-breakpoint
+CREATE UNIQUE INDEX `device_profiles_token_unique` ON `device_profiles` (`token`);--> statement-breakpoint
+ALTER TABLE `app_state` ADD `ws_device_id` text;--> statement-breakpoint
+ALTER TABLE `app_state` ADD `ws_device_token` text;
\ No newline at end of file
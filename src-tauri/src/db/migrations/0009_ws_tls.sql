@@ -0,0 +1,2 @@
+ALTER TABLE `app_state` ADD `ws_tls_enabled` integer DEFAULT 0;--> statement-breakpoint
+ALTER TABLE `app_state` ADD `ws_tls_fingerprint` text;
\ No newline at end of file
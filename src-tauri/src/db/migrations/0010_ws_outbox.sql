@@ -0,0 +1,19 @@
+CREATE TABLE `ws_outbox` (
+	`seq` integer PRIMARY KEY AUTOINCREMENT NOT NULL,
+	`message_id` text NOT NULL,
+	`target_type` text NOT NULL,
+	`target_device_id` text,
+	`message` text NOT NULL,
+	`created_at` text NOT NULL
+);
+--> statement-breakpoint
+CREATE UNIQUE INDEX `ws_outbox_message_id_unique` ON `ws_outbox` (`message_id`);--> statement-breakpoint
+CREATE INDEX `ws_outbox_target_idx` ON `ws_outbox` (`target_type`,`seq`);--> statement-breakpoint
+CREATE TABLE `ws_delivery_cursors` (
+	`device_id` text PRIMARY KEY NOT NULL,
+	`device_type` text NOT NULL,
+	`last_acked_seq` integer DEFAULT 0 NOT NULL,
+	`last_acked_at` text,
+	`last_registered_at` text,
+	`created_at` text NOT NULL
+);
\ No newline at end of file
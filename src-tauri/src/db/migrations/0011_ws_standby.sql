@@ -0,0 +1 @@
+ALTER TABLE `app_state` ADD `ws_hub_standby` integer DEFAULT 0;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE `app_state` ADD `ws_routes` text;
\ No newline at end of file
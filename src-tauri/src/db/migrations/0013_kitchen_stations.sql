@@ -0,0 +1,20 @@
+CREATE TABLE `kitchen_stations` (
+	`id` text PRIMARY KEY NOT NULL,
+	`name` text NOT NULL,
+	`is_expo` integer DEFAULT 0 NOT NULL,
+	`sort_order` integer DEFAULT 0 NOT NULL,
+	`created_at` text NOT NULL,
+	`updated_at` text
+);
+--> statement-breakpoint
+CREATE TABLE `kitchen_station_mappings` (
+	`target_type` text NOT NULL,
+	`target_id` text NOT NULL,
+	`station_id` text NOT NULL,
+	PRIMARY KEY(`target_type`, `target_id`),
+	FOREIGN KEY (`station_id`) REFERENCES `kitchen_stations`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+CREATE INDEX `kitchen_station_mappings_station_idx` ON `kitchen_station_mappings` (`station_id`);--> statement-breakpoint
+ALTER TABLE `device_profiles` ADD `station_id` text;--> statement-breakpoint
+ALTER TABLE `kds_tickets` ADD `station_id` text;
\ No newline at end of file
@@ -0,0 +1,15 @@
+CREATE TABLE `print_templates` (
+	`id` text PRIMARY KEY NOT NULL,
+	`location_id` text,
+	`kind` text NOT NULL,
+	`header` text,
+	`footer` text,
+	`show_logo` integer DEFAULT 1 NOT NULL,
+	`fields` text NOT NULL,
+	`font_size` text DEFAULT 'normal' NOT NULL,
+	`paper_width` text DEFAULT 'mm58' NOT NULL,
+	`created_at` text NOT NULL,
+	`updated_at` text
+);
+--> statement-breakpoint
+CREATE UNIQUE INDEX `print_templates_location_kind_idx` ON `print_templates` (`location_id`,`kind`);
\ No newline at end of file
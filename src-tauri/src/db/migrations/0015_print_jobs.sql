@@ -0,0 +1,19 @@
+CREATE TABLE `print_jobs` (
+	`id` text PRIMARY KEY NOT NULL,
+	`printer_id` text NOT NULL,
+	`kind` text,
+	`source_id` text,
+	`data` blob NOT NULL,
+	`status` text DEFAULT 'QUEUED' NOT NULL,
+	`attempts` integer DEFAULT 0 NOT NULL,
+	`last_error` text,
+	`next_attempt_at` text,
+	`printed_by` text,
+	`reprint_of` text,
+	`created_at` text NOT NULL,
+	`updated_at` text,
+	`printed_at` text
+);
+--> statement-breakpoint
+CREATE INDEX `print_jobs_due_idx` ON `print_jobs` (`status`,`next_attempt_at`);--> statement-breakpoint
+ALTER TABLE `printers` ADD `backup_printer_id` text;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE `printers` ADD `status` text DEFAULT 'unknown' NOT NULL;--> statement-breakpoint
+ALTER TABLE `printers` ADD `status_checked_at` text;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE `printers` ADD `device_path` text;--> statement-breakpoint
+ALTER TABLE `printers` ADD `baud_rate` integer;
\ No newline at end of file
@@ -1,8 +1,51 @@
 pub mod migrate;
 pub mod models;
+pub mod pool;
+#[cfg(test)]
+pub mod test_db;
 
-use tauri::AppHandle;
+use std::fs;
+use tauri::{AppHandle, Manager};
 
-pub fn init(app: &AppHandle) {
-    migrate::run_migrations_once(app);
+use pool::{DbPool, PoolError, PooledConnection};
+
+/// Open the shared pool, apply migrations on the writer and
+/// register the pool as Tauri state
+pub fn init(app: &AppHandle) -> anyhow::Result<()> {
+    let dir = app.path().app_data_dir()?;
+    fs::create_dir_all(&dir).ok();
+
+    let pool = DbPool::open(dir.join("local.db"))?;
+
+    log::info!("📜 Running embedded migrations");
+    migrate::run_migrations(&mut pool.writer()?)?;
+    log::info!("✅ All migrations applied");
+
+    app.manage(pool);
+    Ok(())
+}
+
+/// The shared pool, for background tasks that outlive a single command
+pub fn pool(app: &AppHandle) -> Result<DbPool, PoolError> {
+    app.try_state::<DbPool>()
+        .map(|state| state.inner().clone())
+        .ok_or(PoolError::NotInitialized)
+}
+
+/// Pooled connection for writes
+pub fn writer(app: &AppHandle) -> Result<PooledConnection, PoolError> {
+    pool(app)?.writer()
+}
+
+/// Pooled connection for reads
+pub fn reader(app: &AppHandle) -> Result<PooledConnection, PoolError> {
+    pool(app)?.reader()
+}
+
+/// Checkpoint the WAL so the database file is complete on exit
+pub fn shutdown(app: &AppHandle) {
+    match pool(app).and_then(|pool| pool.checkpoint()) {
+        Ok(()) => log::info!("✅ Database checkpointed on shutdown"),
+        Err(e) => log::warn!("⚠️ Database checkpoint on shutdown failed: {}", e),
+    }
 }
@@ -19,6 +19,16 @@ pub struct AppState {
     pub kds_settings: Option<String>,
     pub ws_server_mode: Option<i32>,
     pub ws_server_url: Option<String>,
+    /// Identity issued by the POS when this device was paired
+    pub ws_device_id: Option<String>,
+    pub ws_device_token: Option<String>,
+    pub ws_tls_enabled: Option<i32>,
+    /// Hub certificate pinned when this device was paired
+    pub ws_tls_fingerprint: Option<String>,
+    /// Mirror another POS's hub and take over if it goes silent
+    pub ws_hub_standby: Option<i32>,
+    /// Hub route table as JSON (`RouteTable`); NULL for the defaults
+    pub ws_routes: Option<String>,
     pub setup_code: Option<String>,
     pub logo_url: Option<String>,
 }
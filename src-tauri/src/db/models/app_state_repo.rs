@@ -1,7 +1,8 @@
+use crate::error::AppResult;
 use rusqlite::{Connection, params};
 use super::app_state::AppState;
 
-pub fn get_app_state(conn: &Connection) -> anyhow::Result<AppState> {
+pub fn get_app_state(conn: &Connection) -> AppResult<AppState> {
     match conn.query_row(
         r#"
         SELECT tenant_domain,
@@ -22,7 +23,13 @@ pub fn get_app_state(conn: &Connection) -> anyhow::Result<AppState> {
                ws_server_mode,
                ws_server_url,
                setup_code,
-               logo_url
+               logo_url,
+               ws_device_id,
+               ws_device_token,
+               ws_tls_enabled,
+               ws_tls_fingerprint,
+               ws_hub_standby,
+               ws_routes
         FROM app_state
         WHERE id = 1
         "#,
@@ -56,6 +63,12 @@ pub fn get_app_state(conn: &Connection) -> anyhow::Result<AppState> {
                 ws_server_url: row.get(16)?,
                 setup_code:row.get(17)?,
                 logo_url: row.get(18)?,
+                ws_device_id: row.get(19)?,
+                ws_device_token: row.get(20)?,
+                ws_tls_enabled: row.get(21)?,
+                ws_tls_fingerprint: row.get(22)?,
+                ws_hub_standby: row.get(23)?,
+                ws_routes: row.get(24)?,
 
             })
         },
@@ -89,6 +102,12 @@ pub fn get_app_state(conn: &Connection) -> anyhow::Result<AppState> {
                 ws_server_url: Some("ws://localhost:9001".to_string()),
                 setup_code:Some("grid".to_string()),
                 logo_url: None,
+                ws_device_id: None,
+                ws_device_token: None,
+                ws_tls_enabled: Some(0),
+                ws_tls_fingerprint: None,
+                ws_hub_standby: Some(0),
+                ws_routes: None,
             })
         }
 
@@ -100,7 +119,7 @@ pub fn update_app_state(
     conn: &Connection,
     field: &str,
     value: &str,
-) -> anyhow::Result<()> {
+) -> AppResult<()> {
     let sql = format!(
         "UPDATE app_state SET {} = ?, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
         field
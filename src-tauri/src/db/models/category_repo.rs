@@ -1,11 +1,12 @@
 
+use crate::error::AppResult;
 use rusqlite::{params, Connection};
 use super::category::Category;
 
 pub fn save_categories(
     conn: &mut Connection,
     items: &[Category],
-) -> anyhow::Result<()> {
+) -> AppResult<()> {
     let tx = conn.transaction()?;
 
     for c in items {
@@ -52,8 +53,8 @@ pub fn save_categories(
     Ok(())
 }
 
-pub fn get_categories(conn: &Connection) -> anyhow::Result<Vec<Category>> {
-    let mut stmt = conn.prepare(
+pub fn get_categories(conn: &Connection) -> AppResult<Vec<Category>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, name, code,
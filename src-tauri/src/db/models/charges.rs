@@ -1,11 +1,13 @@
 use serde::{Deserialize, Serialize};
 
+use crate::money::Rate;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Charge {
     pub id: String,
     pub code: Option<String>,
     pub name: String,
-    pub percentage: Option<String>,
+    pub percentage: Option<Rate>,
     pub is_tax: i32,
     pub transaction_type_id: Option<String>,
     pub parent_charge_id: Option<String>,
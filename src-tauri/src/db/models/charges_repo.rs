@@ -1,7 +1,8 @@
+use crate::error::AppResult;
 use rusqlite::{params, Connection};
 use super::charges::{Charge, ChargeMapping};
 
-pub fn save_charges(conn: &mut Connection, items: &[Charge]) -> anyhow::Result<()> {
+pub fn save_charges(conn: &mut Connection, items: &[Charge]) -> AppResult<()> {
     let tx = conn.transaction()?;
 
     for c in items {
@@ -52,7 +53,7 @@ pub fn save_charges(conn: &mut Connection, items: &[Charge]) -> anyhow::Result<(
     Ok(())
 }
 
-pub fn save_charge_mappings(conn: &mut Connection, items: &[ChargeMapping]) -> anyhow::Result<()> {
+pub fn save_charge_mappings(conn: &mut Connection, items: &[ChargeMapping]) -> AppResult<()> {
     let tx = conn.transaction()?;
 
     for m in items {
@@ -99,8 +100,8 @@ pub fn save_charge_mappings(conn: &mut Connection, items: &[ChargeMapping]) -> a
     Ok(())
 }
 
-pub fn get_charges(conn: &Connection) -> anyhow::Result<Vec<Charge>> {
-    let mut stmt = conn.prepare(
+pub fn get_charges(conn: &Connection) -> AppResult<Vec<Charge>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, code, name, percentage, is_tax, transaction_type_id, parent_charge_id,
@@ -136,8 +137,8 @@ pub fn get_charges(conn: &Connection) -> anyhow::Result<Vec<Charge>> {
     Ok(rows.filter_map(Result::ok).collect())
 }
 
-pub fn get_charge_mappings(conn: &Connection) -> anyhow::Result<Vec<ChargeMapping>> {
-    let mut stmt = conn.prepare(
+pub fn get_charge_mappings(conn: &Connection) -> AppResult<Vec<ChargeMapping>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, charge_id, category_id, product_id, product_group_id,
@@ -171,7 +172,7 @@ pub fn get_charge_mappings(conn: &Connection) -> anyhow::Result<Vec<ChargeMappin
     Ok(rows.filter_map(Result::ok).collect())
 }
 
-pub fn clear_all(conn: &mut Connection) -> anyhow::Result<()> {
+pub fn clear_all(conn: &mut Connection) -> AppResult<()> {
     let tx = conn.transaction()?;
 
     tx.execute("DELETE FROM charge_mappings", [])?;
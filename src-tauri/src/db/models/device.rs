@@ -1,6 +1,7 @@
-
 use serde::{Deserialize, Serialize};
 
+/// This device's own profile, or (with a token) a KDS/QUEUE/POS device
+/// paired with this POS's WebSocket server
 #[derive(Debug,Serialize,Deserialize)]
 pub struct DeviceProfile{
     pub id: String,
@@ -8,7 +9,25 @@ pub struct DeviceProfile{
     pub role: String,
     pub config: Option<String>,
     pub sync_status: Option<String>,
+    /// Issued when the device is paired; never sent to the webview
+    #[serde(skip_serializing, default)]
+    pub token: Option<String>,
+    #[serde(default)]
+    pub paired_at: Option<String>,
+    #[serde(default)]
+    pub revoked_at: Option<String>,
+    /// Kitchen station a paired KDS shows (`kitchen_stations.id`)
+    #[serde(default)]
+    pub station_id: Option<String>,
 }
 
-
-
+/// Short-lived code shown on the POS and typed on the device being paired
+#[derive(Debug, Clone, Serialize)]
+pub struct PairingCode {
+    pub code: String,
+    /// Role the paired device will get, whatever it claims to be
+    pub role: String,
+    pub expires_at: String,
+    /// Hub certificate the device should pin, when the POS serves `wss://`
+    pub fingerprint: Option<String>,
+}
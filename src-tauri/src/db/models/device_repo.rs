@@ -1,25 +1,34 @@
-use rusqlite::{params, Connection};
+use crate::error::{AppError, AppResult};
+use rusqlite::{params, Connection, OptionalExtension};
 use super::device::DeviceProfile;
 
-pub fn get_devices(conn: &Connection) -> anyhow::Result<Vec<DeviceProfile>> {
-    let mut stmt = conn.prepare(
-        "SELECT id, name, role, config, sync_status FROM device_profiles"
-    )?;
+const SELECT_DEVICE: &str = r#"
+    SELECT id, name, role, config, sync_status, token, paired_at, revoked_at, station_id
+    FROM device_profiles
+"#;
 
-    let rows = stmt.query_map([], |row| {
-        Ok(DeviceProfile {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            role: row.get(2)?,
-            config: row.get(3).ok(),
-            sync_status: row.get(4).ok(),
-        })
-    })?;
+fn map_device(row: &rusqlite::Row) -> rusqlite::Result<DeviceProfile> {
+    Ok(DeviceProfile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        role: row.get(2)?,
+        config: row.get(3).ok(),
+        sync_status: row.get(4).ok(),
+        token: row.get(5)?,
+        paired_at: row.get(6)?,
+        revoked_at: row.get(7)?,
+        station_id: row.get(8)?,
+    })
+}
 
+/// Profiles of this device (paired remote devices excluded)
+pub fn get_devices(conn: &Connection) -> AppResult<Vec<DeviceProfile>> {
+    let mut stmt = conn.prepare_cached(&format!("{} WHERE paired_at IS NULL", SELECT_DEVICE))?;
+    let rows = stmt.query_map([], map_device)?;
     Ok(rows.filter_map(Result::ok).collect())
 }
 
-pub fn save_device(conn: &mut Connection, device: &DeviceProfile) -> anyhow::Result<()> {
+pub fn save_device(conn: &mut Connection, device: &DeviceProfile) -> AppResult<()> {
     conn.execute(
         r#"
         INSERT INTO device_profiles (id, name, role, config, sync_status)
@@ -35,3 +44,143 @@ pub fn save_device(conn: &mut Connection, device: &DeviceProfile) -> anyhow::Res
     )?;
     Ok(())
 }
+
+// ==============================
+// Paired devices (WebSocket server side)
+// ==============================
+
+/// Devices paired with this POS, revoked ones included
+pub fn get_paired_devices(conn: &Connection) -> AppResult<Vec<DeviceProfile>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "{} WHERE paired_at IS NOT NULL ORDER BY paired_at DESC",
+        SELECT_DEVICE
+    ))?;
+    let rows = stmt.query_map([], map_device)?;
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+/// One paired device, revoked or not
+pub fn get_paired_device(conn: &Connection, id: &str) -> AppResult<DeviceProfile> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "{} WHERE id = ?1 AND paired_at IS NOT NULL",
+        SELECT_DEVICE
+    ))?;
+    stmt.query_row(params![id], map_device)
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Paired device {}", id)))
+}
+
+/// Store a paired device as another hub knows it, token and revocation
+/// included (a standby POS mirroring the primary)
+pub fn upsert_paired_device(conn: &Connection, device: &DeviceProfile) -> AppResult<()> {
+    conn.execute(
+        r#"
+        INSERT INTO device_profiles (id, name, role, sync_status, token, paired_at, revoked_at, station_id)
+        VALUES (?1, ?2, ?3, 'SYNCED', ?4, ?5, ?6, ?7)
+        ON CONFLICT(id) DO UPDATE SET
+          name = excluded.name,
+          role = excluded.role,
+          token = excluded.token,
+          paired_at = excluded.paired_at,
+          revoked_at = excluded.revoked_at,
+          station_id = excluded.station_id
+        "#,
+        params![
+            device.id,
+            device.name,
+            device.role,
+            device.token,
+            device.paired_at,
+            device.revoked_at,
+            device.station_id
+        ],
+    )?;
+    Ok(())
+}
+
+/// Create the profile of a newly paired device with a fresh id and token
+pub fn pair_device(conn: &Connection, name: &str, role: &str) -> AppResult<DeviceProfile> {
+    let id = format!("{}_{}", role.to_lowercase(), random_hex(conn, 8)?);
+    let token = random_hex(conn, 32)?;
+    let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+
+    conn.execute(
+        r#"
+        INSERT INTO device_profiles (id, name, role, sync_status, token, paired_at)
+        VALUES (?1, ?2, ?3, 'SYNCED', ?4, ?5)
+        "#,
+        params![id, name, role, token, now],
+    )?;
+
+    let mut stmt = conn.prepare_cached(&format!("{} WHERE id = ?1", SELECT_DEVICE))?;
+    Ok(stmt.query_row(params![id], map_device)?)
+}
+
+/// The paired device a `register` message claims to be, if its token matches
+/// and it hasn't been revoked
+pub fn authenticate_device(conn: &Connection, id: &str, token: &str) -> AppResult<DeviceProfile> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "{} WHERE id = ?1 AND paired_at IS NOT NULL",
+        SELECT_DEVICE
+    ))?;
+
+    let device = stmt
+        .query_row(params![id], map_device)
+        .optional()?
+        .ok_or_else(|| AppError::Unauthorized(format!("unknown device {}", id)))?;
+
+    let matches = device
+        .token
+        .as_deref()
+        .is_some_and(|stored| constant_time_eq(stored.as_bytes(), token.as_bytes()));
+
+    if !matches {
+        return Err(AppError::Unauthorized(format!("bad token for device {}", id)));
+    }
+
+    if device.revoked_at.is_some() {
+        return Err(AppError::Unauthorized(format!("device {} was revoked", id)));
+    }
+
+    Ok(device)
+}
+
+/// Revoke a paired device: its token stops working immediately
+pub fn revoke_device(conn: &Connection, id: &str) -> AppResult<()> {
+    let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+
+    let updated = conn.execute(
+        r#"
+        UPDATE device_profiles SET revoked_at = COALESCE(revoked_at, ?1)
+        WHERE id = ?2 AND paired_at IS NOT NULL
+        "#,
+        params![now, id],
+    )?;
+
+    if updated == 0 {
+        return Err(AppError::NotFound(format!("Paired device {}", id)));
+    }
+    Ok(())
+}
+
+/// Show a kitchen station's items on a paired KDS (all items when None)
+pub fn set_device_station(conn: &Connection, id: &str, station_id: Option<&str>) -> AppResult<()> {
+    let updated = conn.execute(
+        "UPDATE device_profiles SET station_id = ?1 WHERE id = ?2 AND paired_at IS NOT NULL",
+        params![station_id, id],
+    )?;
+
+    if updated == 0 {
+        return Err(AppError::NotFound(format!("Paired device {}", id)));
+    }
+    Ok(())
+}
+
+/// `bytes` random bytes as hex, from SQLite's CSPRNG
+pub fn random_hex(conn: &Connection, bytes: usize) -> AppResult<String> {
+    Ok(conn.query_row("SELECT lower(hex(randomblob(?1)))", params![bytes as i64], |row| row.get(0))?)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
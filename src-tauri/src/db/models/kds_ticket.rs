@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::money::Money;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct KdsTicket {
     pub id: String,
@@ -9,8 +11,11 @@ pub struct KdsTicket {
     pub order_mode_name: Option<String>,
     pub status: String, // PENDING, IN_PROGRESS, READY
     pub items: String,  // JSON stringified array
-    pub total_amount: Option<i32>,
+    pub total_amount: Option<Money>,
     pub token_number: Option<i32>,
     pub created_at: String,
     pub updated_at: String,
+    /// Kitchen station this ticket holds the items of (whole order if None)
+    #[serde(default)]
+    pub station_id: Option<String>,
 }
@@ -1,15 +1,16 @@
-use rusqlite::{params, Connection};
+use crate::error::{AppError, AppResult};
+use rusqlite::{params, Connection, OptionalExtension};
 use super::kds_ticket::KdsTicket;
 
-pub fn save_kds_ticket(conn: &mut Connection, ticket: &KdsTicket) -> anyhow::Result<()> {
+pub fn save_kds_ticket(conn: &mut Connection, ticket: &KdsTicket) -> AppResult<()> {
     conn.execute(
         r#"
         INSERT INTO kds_tickets (
           id, ticket_number, order_id, location_id, order_mode_name,
           status, items, total_amount, token_number,
-          created_at, updated_at
+          created_at, updated_at, station_id
         )
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
         ON CONFLICT(id) DO UPDATE SET
           status = excluded.status,
           items = excluded.items,
@@ -27,18 +28,19 @@ pub fn save_kds_ticket(conn: &mut Connection, ticket: &KdsTicket) -> anyhow::Res
             ticket.token_number,
             ticket.created_at,
             ticket.updated_at,
+            ticket.station_id,
         ],
     )?;
     Ok(())
 }
 
-pub fn get_all_kds_tickets(conn: &Connection) -> anyhow::Result<Vec<KdsTicket>> {
-    let mut stmt = conn.prepare(
+pub fn get_all_kds_tickets(conn: &Connection) -> AppResult<Vec<KdsTicket>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, ticket_number, order_id, location_id, order_mode_name,
           status, items, total_amount, token_number,
-          created_at, updated_at
+          created_at, updated_at, station_id
         FROM kds_tickets
         ORDER BY created_at ASC
         "#
@@ -57,19 +59,52 @@ pub fn get_all_kds_tickets(conn: &Connection) -> anyhow::Result<Vec<KdsTicket>>
             token_number: row.get(8)?,
             created_at: row.get(9)?,
             updated_at: row.get(10)?,
+            station_id: row.get(11)?,
         })
     })?;
 
     Ok(rows.filter_map(Result::ok).collect())
 }
 
-pub fn get_active_kds_tickets(conn: &Connection) -> anyhow::Result<Vec<KdsTicket>> {
-    let mut stmt = conn.prepare(
+pub fn get_kds_ticket(conn: &Connection, ticket_id: &str) -> AppResult<KdsTicket> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, ticket_number, order_id, location_id, order_mode_name,
           status, items, total_amount, token_number,
-          created_at, updated_at
+          created_at, updated_at, station_id
+        FROM kds_tickets
+        WHERE id = ?1
+        "#
+    )?;
+
+    stmt.query_row(params![ticket_id], |row| {
+        Ok(KdsTicket {
+            id: row.get(0)?,
+            ticket_number: row.get(1)?,
+            order_id: row.get(2)?,
+            location_id: row.get(3)?,
+            order_mode_name: row.get(4)?,
+            status: row.get(5)?,
+            items: row.get(6)?,
+            total_amount: row.get(7)?,
+            token_number: row.get(8)?,
+            created_at: row.get(9)?,
+            updated_at: row.get(10)?,
+            station_id: row.get(11)?,
+        })
+    })
+    .optional()?
+    .ok_or_else(|| AppError::NotFound(format!("KDS ticket {}", ticket_id)))
+}
+
+pub fn get_active_kds_tickets(conn: &Connection) -> AppResult<Vec<KdsTicket>> {
+    let mut stmt = conn.prepare_cached(
+        r#"
+        SELECT
+          id, ticket_number, order_id, location_id, order_mode_name,
+          status, items, total_amount, token_number,
+          created_at, updated_at, station_id
         FROM kds_tickets
         WHERE status != 'READY'
         ORDER BY created_at ASC
@@ -89,6 +124,7 @@ pub fn get_active_kds_tickets(conn: &Connection) -> anyhow::Result<Vec<KdsTicket
             token_number: row.get(8)?,
             created_at: row.get(9)?,
             updated_at: row.get(10)?,
+            station_id: row.get(11)?,
         })
     })?;
 
@@ -98,13 +134,13 @@ pub fn get_active_kds_tickets(conn: &Connection) -> anyhow::Result<Vec<KdsTicket
 pub fn get_kds_tickets_by_status(
     conn: &Connection,
     status: &str,
-) -> anyhow::Result<Vec<KdsTicket>> {
-    let mut stmt = conn.prepare(
+) -> AppResult<Vec<KdsTicket>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, ticket_number, order_id, location_id, order_mode_name,
           status, items, total_amount, token_number,
-          created_at, updated_at
+          created_at, updated_at, station_id
         FROM kds_tickets
         WHERE status = ?1
         ORDER BY created_at ASC
@@ -124,6 +160,7 @@ pub fn get_kds_tickets_by_status(
             token_number: row.get(8)?,
             created_at: row.get(9)?,
             updated_at: row.get(10)?,
+            station_id: row.get(11)?,
         })
     })?;
 
@@ -134,7 +171,7 @@ pub fn update_kds_ticket_status(
     conn: &mut Connection,
     ticket_id: &str,
     status: &str,
-) -> anyhow::Result<()> {
+) -> AppResult<()> {
     let now = chrono::Utc::now().to_rfc3339();
 
     conn.execute(
@@ -148,7 +185,7 @@ pub fn update_kds_ticket_status(
     Ok(())
 }
 
-pub fn delete_kds_ticket(conn: &mut Connection, ticket_id: &str) -> anyhow::Result<()> {
+pub fn delete_kds_ticket(conn: &mut Connection, ticket_id: &str) -> AppResult<()> {
     conn.execute("DELETE FROM kds_tickets WHERE id = ?1", params![ticket_id])?;
     Ok(())
 }
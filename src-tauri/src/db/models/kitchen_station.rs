@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+/// A screen's share of the kitchen (grill, fry, bar…), or an expo that
+/// sees every station's items
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KitchenStation {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub is_expo: bool,
+    #[serde(default)]
+    pub sort_order: i32,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+/// Items of a product, or of every product in a category, go to a station.
+/// A product mapping wins over its category's.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StationMapping {
+    pub target_type: String, // PRODUCT | CATEGORY
+    pub target_id: String,
+    pub station_id: String,
+}
+
+pub const TARGET_PRODUCT: &str = "PRODUCT";
+pub const TARGET_CATEGORY: &str = "CATEGORY";
+
+/// Stations and mappings together, as the hub splits orders by them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KitchenSetup {
+    pub stations: Vec<KitchenStation>,
+    pub mappings: Vec<StationMapping>,
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use super::kitchen_station::{KitchenSetup, KitchenStation, StationMapping};
+use crate::error::{AppError, AppResult};
+
+const SELECT_STATION: &str = r#"
+    SELECT id, name, is_expo, sort_order, created_at, updated_at
+    FROM kitchen_stations
+"#;
+
+fn map_station(row: &Row) -> rusqlite::Result<KitchenStation> {
+    Ok(KitchenStation {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        is_expo: row.get(2)?,
+        sort_order: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+pub fn get_stations(conn: &Connection) -> AppResult<Vec<KitchenStation>> {
+    let mut stmt = conn.prepare_cached(&format!("{} ORDER BY sort_order, name", SELECT_STATION))?;
+    let rows = stmt.query_map([], map_station)?;
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+pub fn get_station(conn: &Connection, id: &str) -> AppResult<KitchenStation> {
+    let mut stmt = conn.prepare_cached(&format!("{} WHERE id = ?1", SELECT_STATION))?;
+    stmt.query_row(params![id], map_station)
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Kitchen station {}", id)))
+}
+
+/// Create the station (a new id when `id` is empty) or update it
+pub fn save_station(conn: &Connection, station: &KitchenStation) -> AppResult<KitchenStation> {
+    let id = if station.id.is_empty() {
+        format!("station_{}", super::device_repo::random_hex(conn, 4)?)
+    } else {
+        station.id.clone()
+    };
+    let now = now();
+
+    conn.execute(
+        r#"
+        INSERT INTO kitchen_stations (id, name, is_expo, sort_order, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        ON CONFLICT(id) DO UPDATE SET
+          name = excluded.name,
+          is_expo = excluded.is_expo,
+          sort_order = excluded.sort_order,
+          updated_at = ?5
+        "#,
+        params![id, station.name, station.is_expo, station.sort_order, now],
+    )?;
+
+    get_station(conn, &id)
+}
+
+/// Remove a station with its mappings; its screens go back to showing
+/// whole orders
+pub fn delete_station(conn: &mut Connection, id: &str) -> AppResult<()> {
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM kitchen_station_mappings WHERE station_id = ?1", params![id])?;
+    tx.execute("UPDATE device_profiles SET station_id = NULL WHERE station_id = ?1", params![id])?;
+    let deleted = tx.execute("DELETE FROM kitchen_stations WHERE id = ?1", params![id])?;
+    if deleted == 0 {
+        return Err(AppError::NotFound(format!("Kitchen station {}", id)));
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn get_mappings(conn: &Connection) -> AppResult<Vec<StationMapping>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT target_type, target_id, station_id FROM kitchen_station_mappings ORDER BY target_type, target_id",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(StationMapping {
+            target_type: row.get(0)?,
+            target_id: row.get(1)?,
+            station_id: row.get(2)?,
+        })
+    })?;
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+/// Send a product's or category's items to `station_id`, or stop mapping
+/// them when None
+pub fn set_mapping(
+    conn: &Connection,
+    target_type: &str,
+    target_id: &str,
+    station_id: Option<&str>,
+) -> AppResult<()> {
+    match station_id {
+        Some(station_id) => conn.execute(
+            r#"
+            INSERT INTO kitchen_station_mappings (target_type, target_id, station_id)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(target_type, target_id) DO UPDATE SET station_id = excluded.station_id
+            "#,
+            params![target_type, target_id, station_id],
+        )?,
+        None => conn.execute(
+            "DELETE FROM kitchen_station_mappings WHERE target_type = ?1 AND target_id = ?2",
+            params![target_type, target_id],
+        )?,
+    };
+    Ok(())
+}
+
+pub fn get_setup(conn: &Connection) -> AppResult<KitchenSetup> {
+    Ok(KitchenSetup {
+        stations: get_stations(conn)?,
+        mappings: get_mappings(conn)?,
+    })
+}
+
+/// Replace every station and mapping (a standby copying the primary)
+pub fn replace_setup(conn: &Connection, setup: &KitchenSetup) -> AppResult<()> {
+    conn.execute("DELETE FROM kitchen_station_mappings", [])?;
+    conn.execute("DELETE FROM kitchen_stations", [])?;
+
+    for s in &setup.stations {
+        conn.execute(
+            r#"
+            INSERT INTO kitchen_stations (id, name, is_expo, sort_order, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            params![s.id, s.name, s.is_expo, s.sort_order, s.created_at, s.updated_at],
+        )?;
+    }
+    for m in &setup.mappings {
+        set_mapping(conn, &m.target_type, &m.target_id, Some(&m.station_id))?;
+    }
+    Ok(())
+}
+
+/// `category_id` of each of `product_ids` found locally
+pub fn product_categories(
+    conn: &Connection,
+    product_ids: &[&str],
+) -> AppResult<HashMap<String, String>> {
+    let mut stmt = conn.prepare_cached("SELECT category_id FROM products WHERE id = ?1")?;
+    let mut categories = HashMap::new();
+
+    for id in product_ids {
+        let category: Option<Option<String>> = stmt.query_row(params![id], |row| row.get(0)).optional()?;
+        if let Some(Some(category)) = category {
+            categories.insert(id.to_string(), category);
+        }
+    }
+    Ok(categories)
+}
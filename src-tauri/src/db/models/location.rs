@@ -1,4 +1,5 @@
-use rusqlite::{params, Connection};
+use crate::error::AppResult;
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -13,7 +14,7 @@ pub struct Location {
    WRITE OPERATIONS → &mut
 ========================= */
 
-pub fn upsert(conn: &mut Connection, items: &[Location]) -> anyhow::Result<()> {
+pub fn upsert(conn: &mut Connection, items: &[Location]) -> AppResult<()> {
     let tx = conn.transaction()?;
 
     for l in items {
@@ -38,7 +39,7 @@ pub fn upsert(conn: &mut Connection, items: &[Location]) -> anyhow::Result<()> {
     Ok(())
 }
 
-pub fn select(conn: &mut Connection, server_id: &str) -> anyhow::Result<()> {
+pub fn select(conn: &mut Connection, server_id: &str) -> AppResult<()> {
     conn.execute("UPDATE location SET selected = 0", [])?;
     conn.execute(
         "UPDATE location SET selected = 1 WHERE server_id = ?1",
@@ -51,8 +52,8 @@ pub fn select(conn: &mut Connection, server_id: &str) -> anyhow::Result<()> {
    READ OPERATIONS → &
 ========================= */
 
-pub fn list_active(conn: &Connection) -> anyhow::Result<Vec<Location>> {
-    let mut stmt = conn.prepare(
+pub fn list_active(conn: &Connection) -> AppResult<Vec<Location>> {
+    let mut stmt = conn.prepare_cached(
         "SELECT server_id, name, active, selected FROM location WHERE active = 1",
     )?;
 
@@ -67,3 +68,8 @@ pub fn list_active(conn: &Connection) -> anyhow::Result<Vec<Location>> {
 
     Ok(rows.filter_map(Result::ok).collect())
 }
+
+pub fn get_name(conn: &Connection, server_id: &str) -> AppResult<Option<String>> {
+    let mut stmt = conn.prepare_cached("SELECT name FROM location WHERE server_id = ?1")?;
+    Ok(stmt.query_row(params![server_id], |row| row.get(0)).optional()?)
+}
@@ -25,15 +25,29 @@ pub mod transaction_type;
 pub mod transaction_type_repo;
 pub mod ticket;
 pub mod ticket_repo;
+pub mod ticket_detail;
+pub mod ticket_detail_repo;
 pub mod workday;
 pub mod workday_repo;
+pub mod work_shift;
+pub mod work_shift_repo;
+pub mod report;
+pub mod report_repo;
 pub mod kds_ticket;
 pub mod kds_ticket_repo;
+pub mod kitchen_station;
+pub mod kitchen_station_repo;
 pub mod printer;
 pub mod printer_repo;
+pub mod print_job;
+pub mod print_job_repo;
+pub mod print_template;
+pub mod print_template_repo;
 pub mod queue_token;
 pub mod queue_token_repo;
 pub mod setup;
 pub mod setup_repo;
 pub mod product_tag_group_mapping;
-pub mod product_tag_group_mapping_repo;
\ No newline at end of file
+pub mod product_tag_group_mapping_repo;
+pub mod ws_outbox;
+pub mod ws_outbox_repo;
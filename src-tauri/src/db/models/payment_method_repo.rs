@@ -1,7 +1,8 @@
+use crate::error::AppResult;
 use rusqlite::{params, Connection};
 use super::payment_method::PaymentMethod;
 
-pub fn save_payment_methods(conn: &mut Connection, items: &[PaymentMethod]) -> anyhow::Result<()> {
+pub fn save_payment_methods(conn: &mut Connection, items: &[PaymentMethod]) -> AppResult<()> {
     let tx = conn.transaction()?;
 
     for pm in items {
@@ -46,8 +47,8 @@ pub fn save_payment_methods(conn: &mut Connection, items: &[PaymentMethod]) -> a
     Ok(())
 }
 
-pub fn get_payment_methods(conn: &Connection) -> anyhow::Result<Vec<PaymentMethod>> {
-    let mut stmt = conn.prepare(
+pub fn get_payment_methods(conn: &Connection) -> AppResult<Vec<PaymentMethod>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, code, name, processor,
@@ -80,7 +81,7 @@ pub fn get_payment_methods(conn: &Connection) -> anyhow::Result<Vec<PaymentMetho
     Ok(rows.filter_map(Result::ok).collect())
 }
 
-pub fn clear_all(conn: &mut Connection) -> anyhow::Result<()> {
+pub fn clear_all(conn: &mut Connection) -> AppResult<()> {
     let tx = conn.transaction()?;
     tx.execute("DELETE FROM payment_methods", [])?;
     tx.commit()?;
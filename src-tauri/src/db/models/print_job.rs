@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+use super::print_template::TemplateKind;
+
+/// Something sent to a printer, kept until it prints so a printer that is
+/// out of paper or offline doesn't lose it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrintJob {
+    pub id: String,
+    pub printer_id: String,
+    /// None for ESC/POS laid out by the webview
+    pub kind: Option<TemplateKind>,
+    /// What `kind` was rendered from (see `print_document`)
+    pub source_id: Option<String>,
+    pub status: String, // QUEUED | PRINTING | PRINTED | FAILED | DEAD | CANCELLED
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<String>,
+    /// The printer that printed it: `printer_id` or its backup
+    pub printed_by: Option<String>,
+    /// The job this one reprints
+    pub reprint_of: Option<String>,
+    pub created_at: String,
+    pub updated_at: Option<String>,
+    pub printed_at: Option<String>,
+}
@@ -0,0 +1,286 @@
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use super::print_job::PrintJob;
+use super::print_template::TemplateKind;
+use crate::error::{AppError, AppResult};
+
+const SELECT_JOB: &str = r#"
+    SELECT id, printer_id, kind, source_id, status, attempts, last_error,
+           next_attempt_at, printed_by, reprint_of, created_at, updated_at, printed_at
+    FROM print_jobs
+"#;
+
+fn map_job(row: &Row) -> rusqlite::Result<PrintJob> {
+    let kind: Option<String> = row.get(2)?;
+
+    Ok(PrintJob {
+        id: row.get(0)?,
+        printer_id: row.get(1)?,
+        kind: kind
+            .map(|kind| {
+                TemplateKind::parse(&kind).ok_or_else(|| {
+                    rusqlite::Error::FromSqlConversionFailure(
+                        2,
+                        Type::Text,
+                        format!("unknown kind '{}'", kind).into(),
+                    )
+                })
+            })
+            .transpose()?,
+        source_id: row.get(3)?,
+        status: row.get(4)?,
+        attempts: row.get(5)?,
+        last_error: row.get(6)?,
+        next_attempt_at: row.get(7)?,
+        printed_by: row.get(8)?,
+        reprint_of: row.get(9)?,
+        created_at: row.get(10)?,
+        updated_at: row.get(11)?,
+        printed_at: row.get(12)?,
+    })
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Queue `data` for `printer_id`
+pub fn enqueue_job(
+    conn: &Connection,
+    printer_id: &str,
+    kind: Option<TemplateKind>,
+    source_id: Option<&str>,
+    data: &[u8],
+) -> AppResult<PrintJob> {
+    let id = format!("job_{}", super::device_repo::random_hex(conn, 8)?);
+
+    conn.execute(
+        r#"
+        INSERT INTO print_jobs (id, printer_id, kind, source_id, data, status, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, 'QUEUED', ?6)
+        "#,
+        params![id, printer_id, kind.map(TemplateKind::as_str), source_id, data, now()],
+    )?;
+
+    get_job(conn, &id)
+}
+
+/// Queue another copy of job `id`, on `printer_id` or the printer it was
+/// sent to. Works whatever state the original is in.
+pub fn reprint_job(conn: &Connection, id: &str, printer_id: Option<&str>) -> AppResult<PrintJob> {
+    let new_id = format!("job_{}", super::device_repo::random_hex(conn, 8)?);
+
+    let inserted = conn.execute(
+        r#"
+        INSERT INTO print_jobs (id, printer_id, kind, source_id, data, status, reprint_of, created_at)
+        SELECT ?1, COALESCE(?2, printer_id), kind, source_id, data, 'QUEUED', id, ?3
+        FROM print_jobs WHERE id = ?4
+        "#,
+        params![new_id, printer_id, now(), id],
+    )?;
+    if inserted == 0 {
+        return Err(AppError::NotFound(format!("Print job {}", id)));
+    }
+
+    get_job(conn, &new_id)
+}
+
+pub fn get_job(conn: &Connection, id: &str) -> AppResult<PrintJob> {
+    let mut stmt = conn.prepare_cached(&format!("{} WHERE id = ?1", SELECT_JOB))?;
+    stmt.query_row(params![id], map_job)
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Print job {}", id)))
+}
+
+/// The newest `limit` jobs, only those in `status` when given
+pub fn get_jobs(conn: &Connection, status: Option<&str>, limit: i64) -> AppResult<Vec<PrintJob>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "{} WHERE ?1 IS NULL OR status = ?1 ORDER BY created_at DESC, rowid DESC LIMIT ?2",
+        SELECT_JOB
+    ))?;
+    let rows = stmt.query_map(params![status, limit], map_job)?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+// ==============================
+// Print spooler
+// ==============================
+
+/// Jobs the spooler may send now, with their data: QUEUED or FAILED, under
+/// the attempt cap and past their backoff time. Oldest first, and none for
+/// a printer while an older job of its is still waiting out a backoff.
+pub fn get_due_jobs(
+    conn: &Connection,
+    now: &str,
+    max_attempts: i32,
+    limit: i64,
+) -> AppResult<Vec<(PrintJob, Vec<u8>)>> {
+    let mut stmt = conn.prepare_cached(
+        r#"
+        SELECT id, printer_id, kind, source_id, status, attempts, last_error,
+               next_attempt_at, printed_by, reprint_of, created_at, updated_at, printed_at,
+               data
+        FROM print_jobs j
+        WHERE status IN ('QUEUED', 'FAILED')
+          AND attempts < ?2
+          AND (next_attempt_at IS NULL OR next_attempt_at <= ?1)
+          AND NOT EXISTS (
+            SELECT 1 FROM print_jobs older
+            WHERE older.printer_id = j.printer_id
+              AND older.status IN ('QUEUED', 'FAILED')
+              AND older.attempts < ?2
+              AND older.next_attempt_at > ?1
+              AND (older.created_at < j.created_at
+                   OR (older.created_at = j.created_at AND older.rowid < j.rowid))
+          )
+        ORDER BY created_at ASC, rowid ASC
+        LIMIT ?3
+        "#,
+    )?;
+
+    let rows = stmt.query_map(params![now, max_attempts, limit], |row| {
+        Ok((map_job(row)?, row.get(13)?))
+    })?;
+
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+pub fn mark_job_printing(conn: &Connection, id: &str) -> AppResult<()> {
+    conn.execute(
+        "UPDATE print_jobs SET status = 'PRINTING', updated_at = ?1 WHERE id = ?2",
+        params![now(), id],
+    )?;
+    Ok(())
+}
+
+pub fn mark_job_printed(conn: &Connection, id: &str, printed_by: &str) -> AppResult<()> {
+    conn.execute(
+        r#"
+        UPDATE print_jobs
+        SET status = 'PRINTED', last_error = NULL, next_attempt_at = NULL, printed_by = ?1,
+            attempts = attempts + 1, updated_at = ?2, printed_at = ?2
+        WHERE id = ?3
+        "#,
+        params![printed_by, now(), id],
+    )?;
+    Ok(())
+}
+
+/// Count a failed attempt. `status` is FAILED (retried after
+/// `next_attempt_at`) or DEAD (left for a reprint).
+pub fn record_job_failure(
+    conn: &Connection,
+    id: &str,
+    status: &str,
+    error: &str,
+    next_attempt_at: Option<&str>,
+) -> AppResult<()> {
+    conn.execute(
+        r#"
+        UPDATE print_jobs
+        SET status = ?1, last_error = ?2, next_attempt_at = ?3,
+            attempts = attempts + 1, updated_at = ?4
+        WHERE id = ?5
+        "#,
+        params![status, error, next_attempt_at, now(), id],
+    )?;
+    Ok(())
+}
+
+/// Hold `printer_id`'s queued jobs until `until`, behind a job of its that
+/// failed, so they don't print ahead of it
+pub fn defer_printer_jobs(conn: &Connection, printer_id: &str, until: &str) -> AppResult<usize> {
+    let count = conn.execute(
+        r#"
+        UPDATE print_jobs SET next_attempt_at = ?1
+        WHERE printer_id = ?2 AND status = 'QUEUED'
+          AND (next_attempt_at IS NULL OR next_attempt_at < ?1)
+        "#,
+        params![until, printer_id],
+    )?;
+    Ok(count)
+}
+
+/// Make `printer_id`'s waiting jobs due now, once it can print again
+pub fn release_printer_jobs(conn: &Connection, printer_id: &str) -> AppResult<usize> {
+    let count = conn.execute(
+        r#"
+        UPDATE print_jobs SET next_attempt_at = NULL
+        WHERE printer_id = ?1 AND status IN ('QUEUED', 'FAILED')
+        "#,
+        params![printer_id],
+    )?;
+    Ok(count)
+}
+
+/// Stop a job that hasn't printed. Returns it as it is now.
+pub fn cancel_job(conn: &Connection, id: &str) -> AppResult<PrintJob> {
+    let cancelled = conn.execute(
+        r#"
+        UPDATE print_jobs
+        SET status = 'CANCELLED', next_attempt_at = NULL, updated_at = ?1
+        WHERE id = ?2 AND status IN ('QUEUED', 'FAILED', 'DEAD')
+        "#,
+        params![now(), id],
+    )?;
+
+    let job = get_job(conn, id)?;
+    if cancelled == 0 && job.status != "CANCELLED" {
+        return Err(AppError::Conflict(format!("Print job {} is {}", id, job.status)));
+    }
+    Ok(job)
+}
+
+/// Put jobs left in PRINTING by a crash or shutdown back in the queue.
+/// Some may have printed already; a second copy beats a lost chit.
+pub fn reset_interrupted_jobs(conn: &Connection) -> AppResult<usize> {
+    let count = conn.execute(
+        "UPDATE print_jobs SET status = 'QUEUED' WHERE status = 'PRINTING'",
+        [],
+    )?;
+    Ok(count)
+}
+
+/// Delete printed and cancelled jobs created before `before`
+pub fn prune_jobs(conn: &Connection, before: &str) -> AppResult<usize> {
+    let count = conn.execute(
+        "DELETE FROM print_jobs WHERE status IN ('PRINTED', 'CANCELLED') AND created_at < ?1",
+        params![before],
+    )?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db::TestDb;
+
+    fn due(conn: &Connection, now: &str) -> Vec<String> {
+        get_due_jobs(conn, now, 5, 50).unwrap().into_iter().map(|(job, _)| job.id).collect()
+    }
+
+    #[test]
+    fn later_jobs_wait_behind_one_on_backoff() {
+        let db = TestDb::new("print-jobs");
+        let conn = db.pool.writer().unwrap();
+
+        let first = enqueue_job(&conn, "kitchen", None, None, b"first").unwrap();
+        record_job_failure(&conn, &first.id, "FAILED", "offline", Some("2100-01-01T00:00:00.000Z"))
+            .unwrap();
+        // Queued after the failure, so nothing deferred it
+        let second = enqueue_job(&conn, "kitchen", None, None, b"second").unwrap();
+        let other = enqueue_job(&conn, "bar", None, None, b"other").unwrap();
+
+        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        assert_eq!(due(&conn, &now), vec![other.id.clone()]);
+
+        assert_eq!(
+            due(&conn, "2100-01-01T00:00:00.001Z"),
+            vec![first.id.clone(), second.id.clone(), other.id.clone()]
+        );
+
+        release_printer_jobs(&conn, "kitchen").unwrap();
+        assert_eq!(due(&conn, &now), vec![first.id, second.id, other.id]);
+    }
+}
@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+
+use crate::printer::escpos::PaperWidth;
+
+/// The documents the POS prints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateKind {
+    Receipt,
+    KitchenChit,
+    VoidSlip,
+    ZReport,
+}
+
+impl TemplateKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TemplateKind::Receipt => "receipt",
+            TemplateKind::KitchenChit => "kitchen_chit",
+            TemplateKind::VoidSlip => "void_slip",
+            TemplateKind::ZReport => "z_report",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        [
+            TemplateKind::Receipt,
+            TemplateKind::KitchenChit,
+            TemplateKind::VoidSlip,
+            TemplateKind::ZReport,
+        ]
+        .into_iter()
+        .find(|kind| kind.as_str() == s)
+    }
+
+    /// The optional parts a template of this kind can show or hide
+    pub fn fields(self) -> &'static [TemplateField] {
+        use TemplateField::*;
+        match self {
+            TemplateKind::Receipt => &[
+                TicketNumber,
+                QueueNumber,
+                OrderMode,
+                DateTime,
+                Modifiers,
+                Charges,
+                Payments,
+                Change,
+                TicketBarcode,
+            ],
+            TemplateKind::KitchenChit => &[TicketNumber, QueueNumber, OrderMode, DateTime, Station, Modifiers, Notes],
+            TemplateKind::VoidSlip => &[TicketNumber, OrderMode, DateTime, Modifiers, Prices],
+            TemplateKind::ZReport => &[DateTime, Payments, OrderModes, Categories, Charges, Voids, Refunds, Tips],
+        }
+    }
+}
+
+/// An optional part of a printed document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateField {
+    TicketNumber,
+    QueueNumber,
+    OrderMode,
+    DateTime,
+    Modifiers,
+    Notes,
+    Station,
+    Prices,
+    Charges,
+    Payments,
+    /// Tendered and change lines
+    Change,
+    /// The ticket number as a barcode, for looking the ticket up at a till
+    TicketBarcode,
+    OrderModes,
+    Categories,
+    Voids,
+    Refunds,
+    Tips,
+}
+
+/// Body text size; headings keep their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FontSize {
+    /// Font B, more characters per line
+    Small,
+    #[default]
+    Normal,
+    /// Double height, same characters per line
+    Large,
+}
+
+impl FontSize {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FontSize::Small => "small",
+            FontSize::Normal => "normal",
+            FontSize::Large => "large",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "small" => Some(FontSize::Small),
+            "normal" => Some(FontSize::Normal),
+            "large" => Some(FontSize::Large),
+            _ => None,
+        }
+    }
+}
+
+/// ==============================
+/// Print template
+/// ==============================
+///
+/// How one kind of document is laid out for a location, or for the whole
+/// setup when `location_id` is None. Kinds without a saved template print
+/// with `PrintTemplate::default_for`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrintTemplate {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub location_id: Option<String>,
+    pub kind: TemplateKind,
+    /// Lines under the location name, centered
+    #[serde(default)]
+    pub header: Option<String>,
+    /// Lines at the bottom, centered
+    #[serde(default)]
+    pub footer: Option<String>,
+    /// Print `app_state.logo_url` at the top
+    #[serde(default)]
+    pub show_logo: bool,
+    pub fields: Vec<TemplateField>,
+    #[serde(default)]
+    pub font_size: FontSize,
+    #[serde(default)]
+    pub paper_width: PaperWidth,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+impl PrintTemplate {
+    /// The layout printed before anyone set one up
+    pub fn default_for(kind: TemplateKind) -> Self {
+        let footer = match kind {
+            TemplateKind::Receipt => Some("Thank You!\nPlease Come Again".to_string()),
+            _ => None,
+        };
+        Self {
+            id: String::new(),
+            location_id: None,
+            kind,
+            header: None,
+            footer,
+            show_logo: kind == TemplateKind::Receipt,
+            fields: kind.fields().to_vec(),
+            font_size: FontSize::Normal,
+            paper_width: PaperWidth::default(),
+            created_at: String::new(),
+            updated_at: None,
+        }
+    }
+
+    pub fn shows(&self, field: TemplateField) -> bool {
+        self.fields.contains(&field)
+    }
+}
@@ -0,0 +1,147 @@
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use super::print_template::{FontSize, PrintTemplate, TemplateKind};
+use crate::error::{AppError, AppResult};
+use crate::printer::escpos::PaperWidth;
+
+const SELECT_TEMPLATE: &str = r#"
+    SELECT id, location_id, kind, header, footer, show_logo, fields,
+           font_size, paper_width, created_at, updated_at
+    FROM print_templates
+"#;
+
+fn map_template(row: &Row) -> rusqlite::Result<PrintTemplate> {
+    let kind: String = row.get(2)?;
+    let fields: String = row.get(6)?;
+    let font_size: String = row.get(7)?;
+    let paper_width: String = row.get(8)?;
+
+    Ok(PrintTemplate {
+        id: row.get(0)?,
+        location_id: row.get(1)?,
+        kind: TemplateKind::parse(&kind).ok_or_else(|| bad_value(2, "kind", &kind))?,
+        header: row.get(3)?,
+        footer: row.get(4)?,
+        show_logo: row.get(5)?,
+        fields: serde_json::from_str(&fields)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(6, Type::Text, Box::new(e)))?,
+        font_size: FontSize::parse(&font_size).ok_or_else(|| bad_value(7, "font_size", &font_size))?,
+        paper_width: PaperWidth::parse(&paper_width).ok_or_else(|| bad_value(8, "paper_width", &paper_width))?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+fn bad_value(column: usize, name: &str, value: &str) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(
+        column,
+        Type::Text,
+        format!("unknown {} '{}'", name, value).into(),
+    )
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Every saved template, setup-wide ones first
+pub fn get_templates(conn: &Connection) -> AppResult<Vec<PrintTemplate>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "{} ORDER BY location_id IS NOT NULL, location_id, kind",
+        SELECT_TEMPLATE
+    ))?;
+    let rows = stmt.query_map([], map_template)?;
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+pub fn get_template(conn: &Connection, id: &str) -> AppResult<PrintTemplate> {
+    let mut stmt = conn.prepare_cached(&format!("{} WHERE id = ?1", SELECT_TEMPLATE))?;
+    stmt.query_row(params![id], map_template)
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Print template {}", id)))
+}
+
+/// The template a document of `kind` prints with at `location_id`: the
+/// location's own, else the setup-wide one, else the built-in default
+pub fn resolve(conn: &Connection, location_id: Option<&str>, kind: TemplateKind) -> AppResult<PrintTemplate> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "{} WHERE kind = ?1 AND (location_id = ?2 OR location_id IS NULL)
+         ORDER BY location_id IS NULL LIMIT 1",
+        SELECT_TEMPLATE
+    ))?;
+    let template = stmt
+        .query_row(params![kind.as_str(), location_id], map_template)
+        .optional()?;
+    Ok(template.unwrap_or_else(|| PrintTemplate::default_for(kind)))
+}
+
+/// Create or update the template of its kind for its location. There is
+/// one per location and kind, so saving without an id replaces that one.
+pub fn save_template(conn: &Connection, template: &PrintTemplate) -> AppResult<PrintTemplate> {
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT id FROM print_templates WHERE location_id IS ?1 AND kind = ?2",
+            params![template.location_id, template.kind.as_str()],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    let id = match (template.id.is_empty(), existing) {
+        (true, Some(existing)) => existing,
+        (true, None) => format!("tpl_{}", super::device_repo::random_hex(conn, 4)?),
+        (false, Some(existing)) if existing != template.id => {
+            return Err(AppError::Conflict(format!(
+                "{} already has a {} template ({})",
+                template.location_id.as_deref().unwrap_or("The setup"),
+                template.kind.as_str(),
+                existing
+            )));
+        }
+        (false, _) => template.id.clone(),
+    };
+    let now = now();
+
+    conn.execute(
+        r#"
+        INSERT INTO print_templates (
+          id, location_id, kind, header, footer, show_logo, fields,
+          font_size, paper_width, created_at
+        )
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+        ON CONFLICT(id) DO UPDATE SET
+          location_id = excluded.location_id,
+          kind = excluded.kind,
+          header = excluded.header,
+          footer = excluded.footer,
+          show_logo = excluded.show_logo,
+          fields = excluded.fields,
+          font_size = excluded.font_size,
+          paper_width = excluded.paper_width,
+          updated_at = ?10
+        "#,
+        params![
+            id,
+            template.location_id,
+            template.kind.as_str(),
+            template.header,
+            template.footer,
+            template.show_logo,
+            serde_json::to_string(&template.fields)?,
+            template.font_size.as_str(),
+            template.paper_width.as_str(),
+            now,
+        ],
+    )?;
+
+    get_template(conn, &id)
+}
+
+/// Remove a template; its documents go back to the next one in line
+pub fn delete_template(conn: &Connection, id: &str) -> AppResult<()> {
+    let deleted = conn.execute("DELETE FROM print_templates WHERE id = ?1", params![id])?;
+    if deleted == 0 {
+        return Err(AppError::NotFound(format!("Print template {}", id)));
+    }
+    Ok(())
+}
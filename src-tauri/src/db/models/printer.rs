@@ -1,13 +1,29 @@
 use serde::{Deserialize, Serialize};
 
+use crate::printer::status::PrinterHealth;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Printer {
     pub id: String,
     pub name: String,
-    pub printer_type: String, // "network", "usb", "bluetooth"
+    pub printer_type: String, // "network", "usb", "serial", "bluetooth"
     pub ip_address: Option<String>,
     pub port: Option<i32>,
+    /// USB and serial printers: `/dev/usb/lp0`, `/dev/ttyUSB0`, `COM3`
+    #[serde(default)]
+    pub device_path: Option<String>,
+    /// Serial printers only
+    #[serde(default)]
+    pub baud_rate: Option<i32>,
     pub is_active: bool,
+    /// Takes this printer's jobs while it can't print
+    #[serde(default)]
+    pub backup_printer_id: Option<String>,
+    /// Last health check; saving the printer resets it to unknown
+    #[serde(default)]
+    pub status: PrinterHealth,
+    #[serde(default)]
+    pub status_checked_at: Option<String>,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
 }
@@ -1,14 +1,16 @@
-use rusqlite::{params, Connection, Result};
+use crate::error::AppResult;
+use rusqlite::{params, Connection, OptionalExtension};
 use super::printer::Printer;
+use crate::printer::status::PrinterHealth;
 
 pub struct PrinterRepo;
 
 impl PrinterRepo {
-    pub fn save(conn: &Connection, printer: &Printer) -> Result<()> {
+    pub fn save(conn: &Connection, printer: &Printer) -> AppResult<()> {
         conn.execute(
             "INSERT OR REPLACE INTO printers
-             (id, name, printer_type, ip_address, port, is_active, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, CURRENT_TIMESTAMP)",
+             (id, name, printer_type, ip_address, port, is_active, backup_printer_id, device_path, baud_rate, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, CURRENT_TIMESTAMP)",
             params![
                 printer.id,
                 printer.name,
@@ -16,14 +18,18 @@ impl PrinterRepo {
                 printer.ip_address,
                 printer.port,
                 printer.is_active as i32,
+                printer.backup_printer_id,
+                printer.device_path,
+                printer.baud_rate,
             ],
         )?;
         Ok(())
     }
 
-    pub fn get_all(conn: &Connection) -> Result<Vec<Printer>> {
-        let mut stmt = conn.prepare(
-            "SELECT id, name, printer_type, ip_address, port, is_active, created_at, updated_at
+    pub fn get_all(conn: &Connection) -> AppResult<Vec<Printer>> {
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, name, printer_type, ip_address, port, is_active, created_at, updated_at, backup_printer_id,
+                    status, status_checked_at, device_path, baud_rate
              FROM printers
              ORDER BY created_at DESC"
         )?;
@@ -38,15 +44,21 @@ impl PrinterRepo {
                 is_active: row.get::<_, i32>(5)? != 0,
                 created_at: row.get(6)?,
                 updated_at: row.get(7)?,
+                backup_printer_id: row.get(8)?,
+                status: health(row.get(9)?),
+                status_checked_at: row.get(10)?,
+                device_path: row.get(11)?,
+                baud_rate: row.get(12)?,
             })
         })?;
 
-        printers.collect()
+        Ok(printers.collect::<Result<_, _>>()?)
     }
 
-    pub fn get_by_id(conn: &Connection, id: &str) -> Result<Option<Printer>> {
-        let mut stmt = conn.prepare(
-            "SELECT id, name, printer_type, ip_address, port, is_active, created_at, updated_at
+    pub fn get_by_id(conn: &Connection, id: &str) -> AppResult<Option<Printer>> {
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, name, printer_type, ip_address, port, is_active, created_at, updated_at, backup_printer_id,
+                    status, status_checked_at, device_path, baud_rate
              FROM printers
              WHERE id = ?1"
         )?;
@@ -63,15 +75,21 @@ impl PrinterRepo {
                 is_active: row.get::<_, i32>(5)? != 0,
                 created_at: row.get(6)?,
                 updated_at: row.get(7)?,
+                backup_printer_id: row.get(8)?,
+                status: health(row.get(9)?),
+                status_checked_at: row.get(10)?,
+                device_path: row.get(11)?,
+                baud_rate: row.get(12)?,
             }))
         } else {
             Ok(None)
         }
     }
 
-    pub fn get_active(conn: &Connection) -> Result<Vec<Printer>> {
-        let mut stmt = conn.prepare(
-            "SELECT id, name, printer_type, ip_address, port, is_active, created_at, updated_at
+    pub fn get_active(conn: &Connection) -> AppResult<Vec<Printer>> {
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, name, printer_type, ip_address, port, is_active, created_at, updated_at, backup_printer_id,
+                    status, status_checked_at, device_path, baud_rate
              FROM printers
              WHERE is_active = 1
              ORDER BY created_at DESC"
@@ -87,22 +105,49 @@ impl PrinterRepo {
                 is_active: row.get::<_, i32>(5)? != 0,
                 created_at: row.get(6)?,
                 updated_at: row.get(7)?,
+                backup_printer_id: row.get(8)?,
+                status: health(row.get(9)?),
+                status_checked_at: row.get(10)?,
+                device_path: row.get(11)?,
+                baud_rate: row.get(12)?,
             })
         })?;
 
-        printers.collect()
+        Ok(printers.collect::<Result<_, _>>()?)
     }
 
-    pub fn delete(conn: &Connection, id: &str) -> Result<()> {
+    pub fn delete(conn: &Connection, id: &str) -> AppResult<()> {
         conn.execute("DELETE FROM printers WHERE id = ?1", params![id])?;
+        conn.execute(
+            "UPDATE printers SET backup_printer_id = NULL WHERE backup_printer_id = ?1",
+            params![id],
+        )?;
         Ok(())
     }
 
-    pub fn set_active(conn: &Connection, id: &str, is_active: bool) -> Result<()> {
+    pub fn set_active(conn: &Connection, id: &str, is_active: bool) -> AppResult<()> {
         conn.execute(
             "UPDATE printers SET is_active = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
             params![is_active as i32, id],
         )?;
         Ok(())
     }
+
+    /// Record a health check. Returns the status it had before.
+    pub fn set_status(conn: &Connection, id: &str, status: PrinterHealth) -> AppResult<PrinterHealth> {
+        let previous: Option<String> = conn
+            .query_row("SELECT status FROM printers WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?;
+
+        conn.execute(
+            "UPDATE printers SET status = ?1, status_checked_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![status.as_str(), id],
+        )?;
+        Ok(previous.map(health).unwrap_or_default())
+    }
+}
+
+/// A stored status; one this build doesn't know reads as unknown
+fn health(status: String) -> PrinterHealth {
+    PrinterHealth::parse(&status).unwrap_or_default()
 }
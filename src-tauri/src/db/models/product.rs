@@ -1,6 +1,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::money::Money;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Product {
     pub id: String,
@@ -8,7 +10,7 @@ pub struct Product {
     pub code: Option<String>,
     pub description: Option<String>,
     pub category_id: Option<String>,
-    pub price: f64,
+    pub price: Money,
     pub active: bool,
     pub sort_order: i32,
     pub is_sold_out: Option<i32>,
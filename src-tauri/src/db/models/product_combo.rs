@@ -1,11 +1,13 @@
 
 use serde::{Serialize};
 
+use crate::money::Money;
+
 #[derive(Debug, Serialize)]
 pub struct ProductWithCombinations {
     pub id: String,
     pub name: String,
-    pub price: f64,
+    pub price: Money,
     pub description: Option<String>,
     pub media: Option<String>,
     pub overrides:Option<String>,
@@ -27,5 +29,5 @@ pub struct ProductTagOption {
     pub id: String,
     pub product_id: String,
     pub name: String,
-    pub price: f64,
+    pub price: Money,
 }
@@ -1,3 +1,4 @@
+use crate::error::AppResult;
 use rusqlite::Connection;
 use crate::db::models::{
     product::Product,
@@ -12,7 +13,7 @@ use super::product_combo::{
 pub fn get_product_with_combinations(
     conn: &Connection,
     product_id: &str,
-) -> anyhow::Result<ProductWithCombinations> {
+) -> AppResult<ProductWithCombinations> {
     println!("🦀 get_product_with_combinations called for product_id: {}", product_id);
 
     // ---------------- PRODUCT ----------------
@@ -55,7 +56,7 @@ pub fn get_product_with_combinations(
     )?;
 
     // ---------------- TAG GROUPS (via mapping table) ----------------
-    let mut stmt_groups = conn.prepare(
+    let mut stmt_groups = conn.prepare_cached(
         r#"
         SELECT
           tg.id,
@@ -94,7 +95,7 @@ pub fn get_product_with_combinations(
         total_groups_found += 1;
         println!("🦀 Found tag group: {} (id: {})", g.name, g.id);
 
-        let mut stmt_tags = conn.prepare(
+        let mut stmt_tags = conn.prepare_cached(
             r#"
             SELECT
               id,
@@ -1,11 +1,12 @@
 
+use crate::error::AppResult;
 use rusqlite::{params, Connection};
 use super::product_group_category::ProductGroupCategory;
 
 pub fn save_product_group_categories(
     conn: &mut Connection,
     items: &[ProductGroupCategory],
-) -> anyhow::Result<()> {
+) -> AppResult<()> {
     let tx = conn.transaction()?;
 
     for c in items {
@@ -56,8 +57,8 @@ pub fn save_product_group_categories(
 
 pub fn get_product_group_categories(
     conn: &Connection,
-) -> anyhow::Result<Vec<ProductGroupCategory>> {
-    let mut stmt = conn.prepare(
+) -> AppResult<Vec<ProductGroupCategory>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, product_group_id,
@@ -1,11 +1,12 @@
 
+use crate::error::AppResult;
 use rusqlite::{params, Connection};
 use super::product_group::ProductGroup;
 
 pub fn save_product_groups(
     conn: &mut Connection,
     items: &[ProductGroup],
-) -> anyhow::Result<()> {
+) -> AppResult<()> {
     let tx = conn.transaction()?;
 
     for g in items {
@@ -56,8 +57,8 @@ pub fn save_product_groups(
 
 pub fn get_product_groups(
     conn: &Connection,
-) -> anyhow::Result<Vec<ProductGroup>> {
-    let mut stmt = conn.prepare(
+) -> AppResult<Vec<ProductGroup>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, name, code, description,
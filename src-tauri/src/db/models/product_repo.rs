@@ -1,8 +1,9 @@
 
+use crate::error::AppResult;
 use rusqlite::{params, Connection};
 use super::product::Product;
 
-pub fn save_products(conn: &mut Connection, items: &[Product]) -> anyhow::Result<()> {
+pub fn save_products(conn: &mut Connection, items: &[Product]) -> AppResult<()> {
     let tx = conn.transaction()?;
 
     // Debug: Log products with overrides
@@ -67,8 +68,8 @@ pub fn save_products(conn: &mut Connection, items: &[Product]) -> anyhow::Result
     Ok(())
 }
 
-pub fn get_products(conn: &Connection) -> anyhow::Result<Vec<Product>> {
-    let mut stmt = conn.prepare(
+pub fn get_products(conn: &Connection) -> AppResult<Vec<Product>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, name, code, description, category_id,
@@ -103,7 +104,7 @@ pub fn get_products(conn: &Connection) -> anyhow::Result<Vec<Product>> {
     Ok(rows.filter_map(Result::ok).collect())
 }
 
-pub fn update_sold_out_status(conn: &mut Connection, product_id: &str, is_sold_out: bool) -> anyhow::Result<()> {
+pub fn update_sold_out_status(conn: &mut Connection, product_id: &str, is_sold_out: bool) -> AppResult<()> {
     conn.execute(
         "UPDATE products SET is_sold_out = ?1 WHERE id = ?2",
         params![if is_sold_out { 1 } else { 0 }, product_id],
@@ -111,7 +112,7 @@ pub fn update_sold_out_status(conn: &mut Connection, product_id: &str, is_sold_o
     Ok(())
 }
 
-pub fn clear_all(conn: &mut Connection) -> anyhow::Result<()> {
+pub fn clear_all(conn: &mut Connection) -> AppResult<()> {
     let tx = conn.transaction()?;
 
     tx.execute("DELETE FROM product_tags", [])?;
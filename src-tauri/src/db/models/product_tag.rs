@@ -1,6 +1,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::money::Money;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProductTag {
     pub id: String,
@@ -9,7 +11,7 @@ pub struct ProductTag {
     pub product_id: String,
 
     pub name: String,
-    pub price: f64,
+    pub price: Money,
 
     pub active: i32,
     pub sort_order: i32,
@@ -1,10 +1,11 @@
+use crate::error::AppResult;
 use rusqlite::{params, Connection};
 use super::product_tag_group_mapping::ProductTagGroupMapping;
 
 pub fn save_product_tag_group_mappings(
     conn: &mut Connection,
     items: &[ProductTagGroupMapping],
-) -> anyhow::Result<()> {
+) -> AppResult<()> {
     let tx = conn.transaction()?;
 
     // Clear existing mappings before inserting new ones
@@ -28,7 +29,7 @@ pub fn save_product_tag_group_mappings(
 // pub fn get_tag_groups_by_product(
 //     conn: &Connection,
 //     product_id: &str,
-// ) -> anyhow::Result<Vec<String>> {
+// ) -> AppResult<Vec<String>> {
 //     let mut stmt = conn.prepare(
 //         r#"
 //         SELECT tag_group_id
@@ -1,11 +1,12 @@
 
+use crate::error::AppResult;
 use rusqlite::{params, Connection};
 use super::product_tag_group::ProductTagGroup;
 
 pub fn save_product_tag_groups(
     conn: &mut Connection,
     items: &[ProductTagGroup],
-) -> anyhow::Result<()> {
+) -> AppResult<()> {
     let tx = conn.transaction()?;
 
     for g in items {
@@ -50,8 +51,8 @@ pub fn save_product_tag_groups(
 
 pub fn get_product_tag_groups(
     conn: &Connection,
-) -> anyhow::Result<Vec<ProductTagGroup>> {
-    let mut stmt = conn.prepare(
+) -> AppResult<Vec<ProductTagGroup>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, product_id,
@@ -86,8 +87,8 @@ pub fn get_product_tag_groups(
 pub fn get_product_tag_groups_by_product(
     conn: &Connection,
     product_id: &str,
-) -> anyhow::Result<Vec<ProductTagGroup>> {
-    let mut stmt = conn.prepare(
+) -> AppResult<Vec<ProductTagGroup>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, product_id,
@@ -1,11 +1,12 @@
 
+use crate::error::AppResult;
 use rusqlite::{params, Connection};
 use super::product_tag::ProductTag;
 
 pub fn save_product_tags(
     conn: &mut Connection,
     items: &[ProductTag],
-) -> anyhow::Result<()> {
+) -> AppResult<()> {
     let tx = conn.transaction()?;
 
     for t in items {
@@ -49,8 +50,8 @@ pub fn save_product_tags(
 
 pub fn get_product_tags(
     conn: &Connection,
-) -> anyhow::Result<Vec<ProductTag>> {
-    let mut stmt = conn.prepare(
+) -> AppResult<Vec<ProductTag>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, tag_group_id, product_id,
@@ -84,8 +85,8 @@ pub fn get_product_tags(
 pub fn get_product_tags_by_group(
     conn: &Connection,
     tag_group_id: &str,
-) -> anyhow::Result<Vec<ProductTag>> {
-    let mut stmt = conn.prepare(
+) -> AppResult<Vec<ProductTag>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, tag_group_id, product_id,
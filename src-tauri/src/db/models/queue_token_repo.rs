@@ -1,12 +1,13 @@
 
 
+use crate::error::AppResult;
 use rusqlite::{params, Connection};
 use super::queue_token::QueueToken;
 
 pub fn save_queue_token(
     conn: &mut Connection,
     token: &QueueToken,
-) -> anyhow::Result<()> {
+) -> AppResult<()> {
     conn.execute(
         r#"
         INSERT INTO queue_tokens (
@@ -37,8 +38,8 @@ pub fn save_queue_token(
     Ok(())
 }
 
-pub fn get_active_queue_tokens(conn: &Connection) -> anyhow::Result<Vec<QueueToken>> {
-    let mut stmt = conn.prepare(
+pub fn get_active_queue_tokens(conn: &Connection) -> AppResult<Vec<QueueToken>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT *
         FROM queue_tokens
@@ -70,7 +71,7 @@ pub fn update_queue_token_status(
     conn: &mut Connection,
     token_number: i32,
     status: &str,
-) -> anyhow::Result<()> {
+) -> AppResult<()> {
     let now = chrono::Utc::now().to_rfc3339();
 
     let (called_at, served_at) = match status {
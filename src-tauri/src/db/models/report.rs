@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+use super::ticket_detail::PaymentTotal;
+use crate::money::{Money, Rate};
+
+/// ==============================
+/// Sales report (Z-report when a workday is closed)
+/// ==============================
+///
+/// Built in SQL from the normalized ticket tables for every ticket of one
+/// location created inside `[from, to]`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SalesReport {
+    pub location_id: String,
+    pub from: String,
+    pub to: String,
+    pub generated_at: String,
+    pub ticket_count: i64,
+    /// Item net amounts, voided and refunded lines excluded
+    pub gross_sales: Money,
+    pub total_taxes: Money,
+    pub total_charges: Money,
+    /// Sum of payments taken, tips excluded
+    pub total_payments: Money,
+    pub total_tips: Money,
+    pub by_payment_method: Vec<PaymentTotal>,
+    pub by_order_mode: Vec<OrderModeTotal>,
+    pub by_category: Vec<CategoryTotal>,
+    /// One row per tax or charge name
+    pub charges: Vec<ChargeTotal>,
+    pub voids: LineAdjustments,
+    pub refunds: LineAdjustments,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrderModeTotal {
+    pub order_mode_name: Option<String>,
+    pub ticket_count: i64,
+    pub amount: Money,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CategoryTotal {
+    pub category_id: Option<String>,
+    pub category_name: Option<String>,
+    pub quantity: f64,
+    pub net_amount: Money,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChargeTotal {
+    pub name: String,
+    pub is_tax: bool,
+    pub percentage: Option<Rate>,
+    pub ticket_count: i64,
+    pub amount: Money,
+}
+
+/// Voided or refunded item lines
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LineAdjustments {
+    pub lines: i64,
+    pub quantity: f64,
+    pub amount: Money,
+}
@@ -0,0 +1,194 @@
+use crate::error::AppResult;
+use crate::money::Money;
+use rusqlite::{params, Connection};
+use super::report::{
+    CategoryTotal, ChargeTotal, LineAdjustments, OrderModeTotal, SalesReport,
+};
+use super::ticket_detail::PaymentTotal;
+
+/// Tickets of one location created inside the report window.
+/// `?1` location_id, `?2` from, `?3` to (ISO-8601, same format as `created_at`)
+const WINDOW: &str = r#"
+    SELECT id, order_mode_name FROM tickets
+    WHERE location_id = ?1 AND created_at >= ?2 AND created_at <= ?3
+"#;
+
+pub fn build_sales_report(
+    conn: &Connection,
+    location_id: &str,
+    from: &str,
+    to: &str,
+) -> AppResult<SalesReport> {
+    let window = params![location_id, from, to];
+
+    let ticket_count: i64 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM ({})", WINDOW),
+        window,
+        |row| row.get(0),
+    )?;
+
+    let gross_sales: Money = conn.query_row(
+        &format!(
+            r#"
+            SELECT COALESCE(SUM(i.net_amount), 0)
+            FROM ticket_items i
+            JOIN ({}) w ON w.id = i.ticket_id
+            WHERE COALESCE(i.voided, 0) = 0 AND COALESCE(i.refunded, 0) = 0
+            "#,
+            WINDOW
+        ),
+        window,
+        |row| row.get(0),
+    )?;
+
+    let by_payment_method = {
+        let mut stmt = conn.prepare_cached(&format!(
+            r#"
+            SELECT
+              p.payment_type_id, p.payment_type,
+              COUNT(*), SUM(p.amount), COALESCE(SUM(p.tip_amount), 0)
+            FROM ticket_payments p
+            JOIN ({}) w ON w.id = p.ticket_id
+            GROUP BY COALESCE(p.payment_type_id, p.payment_type)
+            ORDER BY p.payment_type ASC
+            "#,
+            WINDOW
+        ))?;
+
+        let rows = stmt.query_map(window, |row| {
+            Ok(PaymentTotal {
+                payment_type_id: row.get(0)?,
+                payment_type: row.get(1)?,
+                count: row.get(2)?,
+                amount: row.get(3)?,
+                tip_amount: row.get(4)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()?
+    };
+
+    let by_order_mode = {
+        let mut stmt = conn.prepare_cached(&format!(
+            r#"
+            SELECT
+              w.order_mode_name, COUNT(DISTINCT w.id), COALESCE(SUM(p.amount), 0)
+            FROM ({}) w
+            LEFT JOIN ticket_payments p ON p.ticket_id = w.id
+            GROUP BY w.order_mode_name
+            ORDER BY w.order_mode_name ASC
+            "#,
+            WINDOW
+        ))?;
+
+        let rows = stmt.query_map(window, |row| {
+            Ok(OrderModeTotal {
+                order_mode_name: row.get(0)?,
+                ticket_count: row.get(1)?,
+                amount: row.get(2)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()?
+    };
+
+    let by_category = {
+        let mut stmt = conn.prepare_cached(&format!(
+            r#"
+            SELECT
+              i.category_id, c.name, SUM(i.quantity), SUM(i.net_amount)
+            FROM ticket_items i
+            JOIN ({}) w ON w.id = i.ticket_id
+            LEFT JOIN categories c ON c.id = i.category_id
+            WHERE COALESCE(i.voided, 0) = 0 AND COALESCE(i.refunded, 0) = 0
+            GROUP BY i.category_id
+            ORDER BY SUM(i.net_amount) DESC
+            "#,
+            WINDOW
+        ))?;
+
+        let rows = stmt.query_map(window, |row| {
+            Ok(CategoryTotal {
+                category_id: row.get(0)?,
+                category_name: row.get(1)?,
+                quantity: row.get(2)?,
+                net_amount: row.get(3)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()?
+    };
+
+    let charges = {
+        let mut stmt = conn.prepare_cached(&format!(
+            r#"
+            SELECT
+              ch.name, ch.is_tax, MAX(ch.percentage),
+              COUNT(DISTINCT ch.ticket_id), COALESCE(SUM(ch.amount), 0)
+            FROM ticket_charges ch
+            JOIN ({}) w ON w.id = ch.ticket_id
+            GROUP BY ch.name, ch.is_tax
+            ORDER BY ch.is_tax DESC, ch.name ASC
+            "#,
+            WINDOW
+        ))?;
+
+        let rows = stmt.query_map(window, |row| {
+            Ok(ChargeTotal {
+                name: row.get(0)?,
+                is_tax: row.get(1)?,
+                percentage: row.get(2)?,
+                ticket_count: row.get(3)?,
+                amount: row.get(4)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()?
+    };
+
+    let voids = line_adjustments(conn, "voided", window)?;
+    let refunds = line_adjustments(conn, "refunded", window)?;
+
+    Ok(SalesReport {
+        location_id: location_id.to_string(),
+        from: from.to_string(),
+        to: to.to_string(),
+        generated_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        ticket_count,
+        gross_sales,
+        total_taxes: charges.iter().filter(|c| c.is_tax).map(|c| c.amount).sum(),
+        total_charges: charges.iter().filter(|c| !c.is_tax).map(|c| c.amount).sum(),
+        total_payments: by_payment_method.iter().map(|p| p.amount).sum(),
+        total_tips: by_payment_method.iter().map(|p| p.tip_amount).sum(),
+        by_payment_method,
+        by_order_mode,
+        by_category,
+        charges,
+        voids,
+        refunds,
+    })
+}
+
+/// `flag` is a trusted column name (`voided` / `refunded`), never user input
+fn line_adjustments(
+    conn: &Connection,
+    flag: &str,
+    window: &[&dyn rusqlite::ToSql],
+) -> AppResult<LineAdjustments> {
+    let adjustments = conn.query_row(
+        &format!(
+            r#"
+            SELECT COUNT(*), COALESCE(SUM(i.quantity), 0), COALESCE(SUM(i.net_amount), 0)
+            FROM ticket_items i
+            JOIN ({}) w ON w.id = i.ticket_id
+            WHERE COALESCE(i.{}, 0) = 1
+            "#,
+            WINDOW, flag
+        ),
+        window,
+        |row| {
+            Ok(LineAdjustments {
+                lines: row.get(0)?,
+                quantity: row.get(1)?,
+                amount: row.get(2)?,
+            })
+        },
+    )?;
+    Ok(adjustments)
+}
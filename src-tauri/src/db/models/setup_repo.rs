@@ -1,8 +1,9 @@
 
+use crate::error::AppResult;
 use rusqlite::{Connection, params};
 use super::setup::Setup;
 
-pub fn upsert_setup(conn: &Connection, setup: &Setup) -> anyhow::Result<()> {
+pub fn upsert_setup(conn: &Connection, setup: &Setup) -> AppResult<()> {
     conn.execute(
         r#"
         INSERT INTO setups (
@@ -49,9 +50,9 @@ pub fn upsert_setup(conn: &Connection, setup: &Setup) -> anyhow::Result<()> {
 pub fn get_setup_by_code(
     conn: &Connection,
     code: &str,
-) -> anyhow::Result<Option<Setup>> {
+) -> AppResult<Option<Setup>> {
     // Use explicit column names to avoid order mismatch
-    let mut stmt = conn.prepare(
+    let mut stmt = conn.prepare_cached(
         r#"SELECT
             id, code, name, setup_type, channel, settings,
             country_code, currency_code, currency_symbol,
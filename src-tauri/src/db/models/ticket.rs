@@ -1,20 +1,26 @@
 use serde::{Deserialize, Serialize};
 
+use crate::money::Money;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Ticket {
     pub id: String,
     pub ticket_data: String, // JSON string of TicketRequest
-    pub sync_status: String, // PENDING, SYNCING, SYNCED, FAILED
+    pub sync_status: String, // PENDING, SYNCING, SYNCED, FAILED, DEAD
     pub sync_error: Option<String>,
     pub sync_attempts: i32,
     pub order_status: Option<String>, // PENDING, IN_PROGRESS, READY, COMPLETED
     pub location_id: Option<String>,
     pub order_mode_name: Option<String>,
-    pub ticket_amount: Option<i32>,
+    pub ticket_amount: Option<Money>,
     pub items_count: Option<i32>,
     pub queue_number: Option<i32>,
     pub ticket_number: Option<i32>,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
     pub synced_at: Option<String>,
+    /// Sent with every upload so the server can drop retries it already has.
+    /// Generated on first save when the caller leaves it empty.
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
 }
@@ -0,0 +1,365 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use crate::error::AppResult;
+use crate::money::{store_currency, Money, Rate, Rounding};
+
+/// One ordered product on a ticket (an `Order` with `parent_sort_order == 0`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TicketItem {
+    pub id: Option<i64>,
+    pub ticket_id: String,
+    pub sort_order: i32,
+    pub product_id: Option<String>,
+    pub product_name: String,
+    pub category_id: Option<String>,
+    pub product_group_id: Option<String>,
+    pub quantity: f64,
+    pub unit_price: Money,
+    pub net_amount: Money,
+    pub tax_amount: Money,
+    pub charge_amount: Money,
+    pub voided: bool,
+    /// Refunded or returned (`order_state.refund` / `order_state.return`)
+    #[serde(default)]
+    pub refunded: bool,
+    pub business_date: Option<String>,
+    pub order_time: Option<String>,
+    #[serde(default)]
+    pub modifiers: Vec<TicketItemModifier>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TicketItemModifier {
+    pub name: String,
+    pub quantity: f64,
+    pub price: Money,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TicketPayment {
+    pub ticket_id: String,
+    pub payment_type_id: Option<String>,
+    pub payment_type: String,
+    pub amount: Money,
+    pub tip_amount: Money,
+    pub tendered_amount: Option<Money>,
+    pub net_amount: Option<Money>,
+    pub currency: Option<String>,
+    pub business_date: Option<String>,
+    pub payment_date: Option<String>,
+    pub payment_time: Option<String>,
+}
+
+/// A tax or service charge applied to the whole ticket
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TicketCharge {
+    pub ticket_id: String,
+    pub name: String,
+    pub is_tax: bool,
+    pub percentage: Option<Rate>,
+    pub amount: Option<Money>,
+    pub transaction_type_id: Option<String>,
+}
+
+/// The normalized rows derived from a ticket's `ticket_data` blob
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TicketDetail {
+    pub items: Vec<TicketItem>,
+    pub payments: Vec<TicketPayment>,
+    pub charges: Vec<TicketCharge>,
+}
+
+/// Units sold and revenue per product, from `ticket_items`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProductSales {
+    pub product_id: Option<String>,
+    pub product_name: String,
+    pub quantity: f64,
+    pub net_amount: Money,
+}
+
+/// Takings per payment method, from `ticket_payments`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PaymentTotal {
+    pub payment_type_id: Option<String>,
+    pub payment_type: String,
+    pub count: i64,
+    pub amount: Money,
+    pub tip_amount: Money,
+}
+
+// ==============================
+// TicketRequest (src/types/ticket.ts), only the fields we keep
+// ==============================
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct TicketRequest {
+    ticket: TicketHeader,
+    orders: Vec<Order>,
+    payments: Vec<Payment>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct TicketHeader {
+    business_date: Option<String>,
+    extra_data: TicketExtra,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct TicketExtra {
+    /// Written by ticketBuilder since the line tables were added
+    charges: Option<Vec<ChargeLine>>,
+    #[serde(deserialize_with = "amount_opt")]
+    total_tax: Option<Money>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct ChargeLine {
+    name: String,
+    is_tax: bool,
+    #[serde(deserialize_with = "percentage_opt")]
+    percentage: Option<Rate>,
+    #[serde(deserialize_with = "amount_opt")]
+    amount: Option<Money>,
+    transaction_type_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct Order {
+    product_name: String,
+    #[serde(deserialize_with = "number")]
+    quantity: f64,
+    #[serde(deserialize_with = "amount")]
+    order_price: Money,
+    #[serde(deserialize_with = "amount")]
+    tax_amount: Money,
+    tax_detail: BTreeMap<String, Value>,
+    sort_order: Option<i32>,
+    parent_sort_order: i32,
+    #[serde(deserialize_with = "amount")]
+    net_amount: Money,
+    #[serde(deserialize_with = "amount")]
+    charge_amount: Money,
+    charge_details: BTreeMap<String, Value>,
+    order_state: OrderState,
+    extra_data: OrderExtra,
+    business_date: Option<String>,
+    order_time: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct OrderState {
+    void: bool,
+    refund: bool,
+    #[serde(rename = "return")]
+    returned: bool,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct OrderExtra {
+    product_id: Option<String>,
+    category_id: Option<String>,
+    product_group_id: Option<String>,
+    modifiers: Vec<ModifierLine>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct ModifierLine {
+    name: String,
+    #[serde(deserialize_with = "number")]
+    qty: f64,
+    #[serde(deserialize_with = "amount")]
+    price: Money,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct Payment {
+    payment_type_id: Option<String>,
+    payment_type: String,
+    #[serde(deserialize_with = "amount")]
+    payment_amount: Money,
+    #[serde(deserialize_with = "amount")]
+    tip_amount: Money,
+    #[serde(deserialize_with = "amount_opt")]
+    tendered_amount: Option<Money>,
+    #[serde(deserialize_with = "amount_opt")]
+    net_amount: Option<Money>,
+    currency: Option<String>,
+    payment_date: Option<String>,
+    payment_time: Option<String>,
+}
+
+/// Amounts arrive as `"12.50"` strings or as plain numbers, in major units
+/// of the store currency. Anything unreadable is treated as missing.
+fn amount_opt<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Money>, D::Error> {
+    Ok(value_money(&Value::deserialize(d)?))
+}
+
+fn amount<'de, D: Deserializer<'de>>(d: D) -> Result<Money, D::Error> {
+    Ok(amount_opt(d)?.unwrap_or_default())
+}
+
+fn percentage_opt<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Rate>, D::Error> {
+    Ok(value_rate(&Value::deserialize(d)?))
+}
+
+/// Quantities are not money and stay plain numbers
+fn number<'de, D: Deserializer<'de>>(d: D) -> Result<f64, D::Error> {
+    Ok(value_f64(&Value::deserialize(d)?).unwrap_or(0.0))
+}
+
+fn value_f64(v: &Value) -> Option<f64> {
+    match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn value_money(v: &Value) -> Option<Money> {
+    match v {
+        Value::Number(n) => n
+            .as_f64()
+            .map(|f| Money::from_major(f, store_currency(), Rounding::HalfUp)),
+        Value::String(s) => Money::parse(s, store_currency(), Rounding::HalfUp).ok(),
+        _ => None,
+    }
+}
+
+fn value_rate(v: &Value) -> Option<Rate> {
+    match v {
+        Value::Number(n) => n.as_f64().map(Rate::from_percent),
+        Value::String(s) => Rate::parse(s).ok(),
+        _ => None,
+    }
+}
+
+impl TicketDetail {
+    /// Split a stringified TicketRequest into item, modifier, payment and
+    /// charge rows. Same rules as the 0004_ticket_lines backfill.
+    pub fn from_ticket_data(ticket_id: &str, ticket_data: &str) -> AppResult<Self> {
+        let request: TicketRequest = serde_json::from_str(ticket_data)?;
+        let mut detail = TicketDetail::default();
+
+        for (index, order) in request.orders.iter().enumerate() {
+            if order.parent_sort_order != 0 {
+                continue;
+            }
+
+            let sort_order = order.sort_order.unwrap_or(index as i32 + 1);
+            let mut modifiers: Vec<TicketItemModifier> = request
+                .orders
+                .iter()
+                .filter(|child| child.parent_sort_order == sort_order)
+                .map(|child| TicketItemModifier {
+                    name: child.product_name.clone(),
+                    quantity: child.quantity,
+                    price: child.order_price,
+                })
+                .collect();
+
+            modifiers.extend(order.extra_data.modifiers.iter().map(|m| TicketItemModifier {
+                name: m.name.clone(),
+                quantity: m.qty,
+                price: m.price,
+            }));
+
+            detail.items.push(TicketItem {
+                id: None,
+                ticket_id: ticket_id.to_string(),
+                sort_order,
+                product_id: order.extra_data.product_id.clone(),
+                product_name: order.product_name.clone(),
+                category_id: order.extra_data.category_id.clone(),
+                product_group_id: order.extra_data.product_group_id.clone(),
+                quantity: order.quantity,
+                unit_price: order.order_price,
+                net_amount: order.net_amount,
+                tax_amount: order.tax_amount,
+                charge_amount: order.charge_amount,
+                voided: order.order_state.void,
+                refunded: order.order_state.refund || order.order_state.returned,
+                business_date: order.business_date.clone(),
+                order_time: order.order_time.clone(),
+                modifiers,
+            });
+        }
+
+        detail.payments = request
+            .payments
+            .into_iter()
+            .map(|p| TicketPayment {
+                ticket_id: ticket_id.to_string(),
+                payment_type_id: p.payment_type_id,
+                payment_type: p.payment_type,
+                amount: p.payment_amount,
+                tip_amount: p.tip_amount,
+                tendered_amount: p.tendered_amount,
+                net_amount: p.net_amount,
+                currency: p.currency,
+                business_date: request.ticket.business_date.clone(),
+                payment_date: p.payment_date,
+                payment_time: p.payment_time,
+            })
+            .collect();
+
+        detail.charges = match request.ticket.extra_data.charges {
+            Some(charges) => charges
+                .into_iter()
+                .map(|c| TicketCharge {
+                    ticket_id: ticket_id.to_string(),
+                    name: c.name,
+                    is_tax: c.is_tax,
+                    percentage: c.percentage,
+                    amount: c.amount,
+                    transaction_type_id: c.transaction_type_id,
+                })
+                .collect(),
+            // Older tickets only copy the charge maps onto every order
+            None => legacy_charges(ticket_id, request.orders.first(), request.ticket.extra_data.total_tax),
+        };
+
+        Ok(detail)
+    }
+}
+
+/// `tax_detail` maps tax name to percentage and `charge_details` maps charge
+/// name to amount. A tax amount is only known when there is a single tax.
+fn legacy_charges(ticket_id: &str, order: Option<&Order>, total_tax: Option<Money>) -> Vec<TicketCharge> {
+    let Some(order) = order else {
+        return Vec::new();
+    };
+
+    let single_tax = order.tax_detail.len() == 1;
+    let taxes = order.tax_detail.iter().map(|(name, percentage)| TicketCharge {
+        ticket_id: ticket_id.to_string(),
+        name: name.clone(),
+        is_tax: true,
+        percentage: value_rate(percentage),
+        amount: if single_tax { total_tax } else { None },
+        transaction_type_id: None,
+    });
+
+    let charges = order.charge_details.iter().map(|(name, amount)| TicketCharge {
+        ticket_id: ticket_id.to_string(),
+        name: name.clone(),
+        is_tax: false,
+        percentage: None,
+        amount: value_money(amount),
+        transaction_type_id: None,
+    });
+
+    taxes.chain(charges).collect()
+}
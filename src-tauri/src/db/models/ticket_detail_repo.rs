@@ -0,0 +1,321 @@
+use crate::error::AppResult;
+use crate::money::Money;
+use rusqlite::{params, Connection};
+use super::ticket_detail::{
+    PaymentTotal, ProductSales, TicketCharge, TicketDetail, TicketItem, TicketItemModifier,
+    TicketPayment,
+};
+
+/// Replace the line rows of a ticket. Called inside `save_ticket`'s
+/// transaction so the rows always match the stored `ticket_data`.
+pub fn replace_ticket_detail(
+    conn: &Connection,
+    ticket_id: &str,
+    detail: &TicketDetail,
+) -> AppResult<()> {
+    delete_ticket_detail(conn, ticket_id)?;
+
+    let mut item_stmt = conn.prepare_cached(
+        r#"
+        INSERT INTO ticket_items (
+          ticket_id, sort_order, product_id, product_name, category_id, product_group_id,
+          quantity, unit_price, net_amount, tax_amount, charge_amount, voided, refunded,
+          business_date, order_time
+        )
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+        "#,
+    )?;
+
+    let mut modifier_stmt = conn.prepare_cached(
+        r#"
+        INSERT INTO ticket_item_modifiers (ticket_item_id, ticket_id, name, quantity, price)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        "#,
+    )?;
+
+    for item in &detail.items {
+        item_stmt.execute(params![
+            ticket_id,
+            item.sort_order,
+            item.product_id,
+            item.product_name,
+            item.category_id,
+            item.product_group_id,
+            item.quantity,
+            item.unit_price,
+            item.net_amount,
+            item.tax_amount,
+            item.charge_amount,
+            item.voided,
+            item.refunded,
+            item.business_date,
+            item.order_time,
+        ])?;
+
+        let item_id = conn.last_insert_rowid();
+        for m in &item.modifiers {
+            modifier_stmt.execute(params![item_id, ticket_id, m.name, m.quantity, m.price])?;
+        }
+    }
+
+    let mut payment_stmt = conn.prepare_cached(
+        r#"
+        INSERT INTO ticket_payments (
+          ticket_id, payment_type_id, payment_type, amount, tip_amount,
+          tendered_amount, net_amount, currency, business_date, payment_date, payment_time
+        )
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+        "#,
+    )?;
+
+    for p in &detail.payments {
+        payment_stmt.execute(params![
+            ticket_id,
+            p.payment_type_id,
+            p.payment_type,
+            p.amount,
+            p.tip_amount,
+            p.tendered_amount,
+            p.net_amount,
+            p.currency,
+            p.business_date,
+            p.payment_date,
+            p.payment_time,
+        ])?;
+    }
+
+    let mut charge_stmt = conn.prepare_cached(
+        r#"
+        INSERT INTO ticket_charges (ticket_id, name, is_tax, percentage, amount, transaction_type_id)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        "#,
+    )?;
+
+    for c in &detail.charges {
+        charge_stmt.execute(params![
+            ticket_id,
+            c.name,
+            c.is_tax,
+            c.percentage,
+            c.amount,
+            c.transaction_type_id,
+        ])?;
+    }
+
+    Ok(())
+}
+
+/// Foreign keys are not enforced on our connections, so the cascades
+/// declared in the schema do not fire; child rows are removed here.
+pub fn delete_ticket_detail(conn: &Connection, ticket_id: &str) -> AppResult<()> {
+    conn.execute("DELETE FROM ticket_item_modifiers WHERE ticket_id = ?1", params![ticket_id])?;
+    conn.execute("DELETE FROM ticket_items WHERE ticket_id = ?1", params![ticket_id])?;
+    conn.execute("DELETE FROM ticket_payments WHERE ticket_id = ?1", params![ticket_id])?;
+    conn.execute("DELETE FROM ticket_charges WHERE ticket_id = ?1", params![ticket_id])?;
+    Ok(())
+}
+
+pub fn clear_all_ticket_details(conn: &Connection) -> AppResult<()> {
+    conn.execute("DELETE FROM ticket_item_modifiers", [])?;
+    conn.execute("DELETE FROM ticket_items", [])?;
+    conn.execute("DELETE FROM ticket_payments", [])?;
+    conn.execute("DELETE FROM ticket_charges", [])?;
+    Ok(())
+}
+
+pub fn get_ticket_detail(conn: &Connection, ticket_id: &str) -> AppResult<TicketDetail> {
+    let mut stmt = conn.prepare_cached(
+        r#"
+        SELECT
+          id, ticket_id, sort_order, product_id, product_name, category_id, product_group_id,
+          quantity, unit_price, net_amount, tax_amount, charge_amount, voided, refunded,
+          business_date, order_time
+        FROM ticket_items
+        WHERE ticket_id = ?1
+        ORDER BY sort_order ASC
+        "#,
+    )?;
+
+    let items: Vec<TicketItem> = stmt
+        .query_map(params![ticket_id], |row| {
+            Ok(TicketItem {
+                id: row.get(0)?,
+                ticket_id: row.get(1)?,
+                sort_order: row.get(2)?,
+                product_id: row.get(3)?,
+                product_name: row.get(4)?,
+                category_id: row.get(5)?,
+                product_group_id: row.get(6)?,
+                quantity: row.get(7)?,
+                unit_price: row.get(8)?,
+                net_amount: row.get(9)?,
+                tax_amount: row.get::<_, Option<Money>>(10)?.unwrap_or_default(),
+                charge_amount: row.get::<_, Option<Money>>(11)?.unwrap_or_default(),
+                voided: row.get::<_, Option<bool>>(12)?.unwrap_or(false),
+                refunded: row.get::<_, Option<bool>>(13)?.unwrap_or(false),
+                business_date: row.get(14)?,
+                order_time: row.get(15)?,
+                modifiers: Vec::new(),
+            })
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    let mut modifier_stmt = conn.prepare_cached(
+        r#"
+        SELECT name, quantity, price
+        FROM ticket_item_modifiers
+        WHERE ticket_item_id = ?1
+        ORDER BY id ASC
+        "#,
+    )?;
+
+    let items = items
+        .into_iter()
+        .map(|mut item| {
+            item.modifiers = modifier_stmt
+                .query_map(params![item.id], |row| {
+                    Ok(TicketItemModifier {
+                        name: row.get(0)?,
+                        quantity: row.get(1)?,
+                        price: row.get(2)?,
+                    })
+                })?
+                .filter_map(Result::ok)
+                .collect();
+            Ok(item)
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+
+    let mut stmt = conn.prepare_cached(
+        r#"
+        SELECT
+          ticket_id, payment_type_id, payment_type, amount, tip_amount,
+          tendered_amount, net_amount, currency, business_date, payment_date, payment_time
+        FROM ticket_payments
+        WHERE ticket_id = ?1
+        ORDER BY id ASC
+        "#,
+    )?;
+
+    let payments = stmt
+        .query_map(params![ticket_id], |row| {
+            Ok(TicketPayment {
+                ticket_id: row.get(0)?,
+                payment_type_id: row.get(1)?,
+                payment_type: row.get(2)?,
+                amount: row.get(3)?,
+                tip_amount: row.get::<_, Option<Money>>(4)?.unwrap_or_default(),
+                tendered_amount: row.get(5)?,
+                net_amount: row.get(6)?,
+                currency: row.get(7)?,
+                business_date: row.get(8)?,
+                payment_date: row.get(9)?,
+                payment_time: row.get(10)?,
+            })
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    let mut stmt = conn.prepare_cached(
+        r#"
+        SELECT ticket_id, name, is_tax, percentage, amount, transaction_type_id
+        FROM ticket_charges
+        WHERE ticket_id = ?1
+        ORDER BY id ASC
+        "#,
+    )?;
+
+    let charges = stmt
+        .query_map(params![ticket_id], |row| {
+            Ok(TicketCharge {
+                ticket_id: row.get(0)?,
+                name: row.get(1)?,
+                is_tax: row.get(2)?,
+                percentage: row.get(3)?,
+                amount: row.get(4)?,
+                transaction_type_id: row.get(5)?,
+            })
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    Ok(TicketDetail {
+        items,
+        payments,
+        charges,
+    })
+}
+
+// ==============================
+// Reporting
+// ==============================
+
+/// Units and revenue per product for a business date, voided and refunded
+/// lines excluded.
+/// `location_id` of None covers every location.
+pub fn get_product_sales(
+    conn: &Connection,
+    location_id: Option<&str>,
+    business_date: &str,
+) -> AppResult<Vec<ProductSales>> {
+    let mut stmt = conn.prepare_cached(
+        r#"
+        SELECT
+          i.product_id, i.product_name,
+          SUM(i.quantity) AS quantity, SUM(i.net_amount) AS net_amount
+        FROM ticket_items i
+        JOIN tickets t ON t.id = i.ticket_id
+        WHERE i.business_date = ?1
+          AND COALESCE(i.voided, 0) = 0
+          AND COALESCE(i.refunded, 0) = 0
+          AND (?2 IS NULL OR t.location_id = ?2)
+        GROUP BY COALESCE(i.product_id, i.product_name)
+        ORDER BY quantity DESC, i.product_name ASC
+        "#,
+    )?;
+
+    let rows = stmt.query_map(params![business_date, location_id], |row| {
+        Ok(ProductSales {
+            product_id: row.get(0)?,
+            product_name: row.get(1)?,
+            quantity: row.get(2)?,
+            net_amount: row.get(3)?,
+        })
+    })?;
+
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+/// Takings per payment method for a business date
+pub fn get_payment_totals(
+    conn: &Connection,
+    location_id: Option<&str>,
+    business_date: &str,
+) -> AppResult<Vec<PaymentTotal>> {
+    let mut stmt = conn.prepare_cached(
+        r#"
+        SELECT
+          p.payment_type_id, p.payment_type,
+          COUNT(*), SUM(p.amount), COALESCE(SUM(p.tip_amount), 0)
+        FROM ticket_payments p
+        JOIN tickets t ON t.id = p.ticket_id
+        WHERE p.business_date = ?1
+          AND (?2 IS NULL OR t.location_id = ?2)
+        GROUP BY COALESCE(p.payment_type_id, p.payment_type)
+        ORDER BY p.payment_type ASC
+        "#,
+    )?;
+
+    let rows = stmt.query_map(params![business_date, location_id], |row| {
+        Ok(PaymentTotal {
+            payment_type_id: row.get(0)?,
+            payment_type: row.get(1)?,
+            count: row.get(2)?,
+            amount: row.get(3)?,
+            tip_amount: row.get(4)?,
+        })
+    })?;
+
+    Ok(rows.filter_map(Result::ok).collect())
+}
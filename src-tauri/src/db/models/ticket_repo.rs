@@ -1,16 +1,31 @@
-use rusqlite::{params, Connection};
+use crate::error::{AppError, AppResult};
+use rusqlite::{params, Connection, OptionalExtension};
 use super::ticket::Ticket;
+use super::ticket_detail::TicketDetail;
+use super::ticket_detail_repo;
 
-pub fn save_ticket(conn: &mut Connection, ticket: &Ticket) -> anyhow::Result<()> {
-    conn.execute(
+/// Upsert the ticket and rebuild its item, payment and charge rows from
+/// `ticket_data` in the same transaction. A `ticket_data` the rows can't be
+/// read from is still stored, without rows, so the ticket isn't lost.
+pub fn save_ticket(conn: &mut Connection, ticket: &Ticket) -> AppResult<()> {
+    let detail = TicketDetail::from_ticket_data(&ticket.id, &ticket.ticket_data).unwrap_or_else(|e| {
+        log::warn!("⚠️ Ticket {} saved without item rows, ticket_data not readable: {}", ticket.id, e);
+        TicketDetail::default()
+    });
+    let tx = conn.transaction()?;
+
+    tx.execute(
         r#"
         INSERT INTO tickets (
           id, ticket_data, sync_status, sync_error, sync_attempts, order_status,
           location_id, order_mode_name, ticket_amount, items_count,
           queue_number, ticket_number,
-          created_at, updated_at, synced_at
+          created_at, updated_at, synced_at, idempotency_key
+        )
+        VALUES (
+          ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15,
+          COALESCE(?16, lower(hex(randomblob(16))))
         )
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
         ON CONFLICT(id) DO UPDATE SET
           ticket_data = excluded.ticket_data,
           sync_status = excluded.sync_status,
@@ -18,7 +33,8 @@ pub fn save_ticket(conn: &mut Connection, ticket: &Ticket) -> anyhow::Result<()>
           sync_attempts = excluded.sync_attempts,
           order_status = excluded.order_status,
           updated_at = excluded.updated_at,
-          synced_at = excluded.synced_at
+          synced_at = excluded.synced_at,
+          idempotency_key = COALESCE(tickets.idempotency_key, excluded.idempotency_key)
         "#,
         params![
             ticket.id,
@@ -36,19 +52,23 @@ pub fn save_ticket(conn: &mut Connection, ticket: &Ticket) -> anyhow::Result<()>
             ticket.created_at,
             ticket.updated_at,
             ticket.synced_at,
+            ticket.idempotency_key,
         ],
     )?;
+
+    ticket_detail_repo::replace_ticket_detail(&tx, &ticket.id, &detail)?;
+    tx.commit()?;
     Ok(())
 }
 
-pub fn get_all_tickets(conn: &Connection) -> anyhow::Result<Vec<Ticket>> {
-    let mut stmt = conn.prepare(
+pub fn get_all_tickets(conn: &Connection) -> AppResult<Vec<Ticket>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, ticket_data, sync_status, sync_error, sync_attempts, order_status,
           location_id, order_mode_name, ticket_amount, items_count,
           queue_number, ticket_number,
-          created_at, updated_at, synced_at
+          created_at, updated_at, synced_at, idempotency_key
         FROM tickets
         ORDER BY created_at DESC
         "#
@@ -71,20 +91,58 @@ pub fn get_all_tickets(conn: &Connection) -> anyhow::Result<Vec<Ticket>> {
             created_at: row.get(12)?,
             updated_at: row.get(13)?,
             synced_at: row.get(14)?,
+            idempotency_key: row.get(15)?,
         })
     })?;
 
     Ok(rows.filter_map(Result::ok).collect())
 }
 
-pub fn get_pending_tickets(conn: &Connection) -> anyhow::Result<Vec<Ticket>> {
-    let mut stmt = conn.prepare(
+pub fn get_ticket(conn: &Connection, ticket_id: &str) -> AppResult<Ticket> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, ticket_data, sync_status, sync_error, sync_attempts, order_status,
           location_id, order_mode_name, ticket_amount, items_count,
           queue_number, ticket_number,
-          created_at, updated_at, synced_at
+          created_at, updated_at, synced_at, idempotency_key
+        FROM tickets
+        WHERE id = ?1
+        "#
+    )?;
+
+    stmt.query_row(params![ticket_id], |row| {
+        Ok(Ticket {
+            id: row.get(0)?,
+            ticket_data: row.get(1)?,
+            sync_status: row.get(2)?,
+            sync_error: row.get(3)?,
+            sync_attempts: row.get(4)?,
+            order_status: row.get(5)?,
+            location_id: row.get(6)?,
+            order_mode_name: row.get(7)?,
+            ticket_amount: row.get(8)?,
+            items_count: row.get(9)?,
+            queue_number: row.get(10)?,
+            ticket_number: row.get(11)?,
+            created_at: row.get(12)?,
+            updated_at: row.get(13)?,
+            synced_at: row.get(14)?,
+            idempotency_key: row.get(15)?,
+        })
+    })
+    .optional()?
+    .ok_or_else(|| AppError::NotFound(format!("Ticket {}", ticket_id)))
+}
+
+pub fn get_pending_tickets(conn: &Connection) -> AppResult<Vec<Ticket>> {
+    let mut stmt = conn.prepare_cached(
+        r#"
+        SELECT
+          id, ticket_data, sync_status, sync_error, sync_attempts, order_status,
+          location_id, order_mode_name, ticket_amount, items_count,
+          queue_number, ticket_number,
+          created_at, updated_at, synced_at, idempotency_key
         FROM tickets
         WHERE sync_status = 'PENDING' OR sync_status = 'FAILED'
         ORDER BY created_at ASC
@@ -108,6 +166,7 @@ pub fn get_pending_tickets(conn: &Connection) -> anyhow::Result<Vec<Ticket>> {
             created_at: row.get(12)?,
             updated_at: row.get(13)?,
             synced_at: row.get(14)?,
+            idempotency_key: row.get(15)?,
         })
     })?;
 
@@ -119,7 +178,7 @@ pub fn update_ticket_sync_status(
     ticket_id: &str,
     status: &str,
     error: Option<&str>,
-) -> anyhow::Result<()> {
+) -> AppResult<()> {
     let now = chrono::Utc::now().to_rfc3339();
     let synced_at = if status == "SYNCED" {
         Some(now.clone())
@@ -138,13 +197,177 @@ pub fn update_ticket_sync_status(
     Ok(())
 }
 
-pub fn delete_ticket(conn: &mut Connection, ticket_id: &str) -> anyhow::Result<()> {
-    conn.execute("DELETE FROM tickets WHERE id = ?1", params![ticket_id])?;
+// ==============================
+// Background sync worker
+// ==============================
+
+/// Tickets the sync worker may upload now: PENDING or FAILED, under the
+/// attempt cap and past their backoff time. Oldest first.
+pub fn get_due_tickets(
+    conn: &Connection,
+    now: &str,
+    max_attempts: i32,
+    limit: i64,
+) -> AppResult<Vec<Ticket>> {
+    let mut stmt = conn.prepare_cached(
+        r#"
+        SELECT
+          id, ticket_data, sync_status, sync_error, sync_attempts, order_status,
+          location_id, order_mode_name, ticket_amount, items_count,
+          queue_number, ticket_number,
+          created_at, updated_at, synced_at, idempotency_key
+        FROM tickets
+        WHERE sync_status IN ('PENDING', 'FAILED')
+          AND COALESCE(sync_attempts, 0) < ?2
+          AND (next_attempt_at IS NULL OR next_attempt_at <= ?1)
+        ORDER BY created_at ASC
+        LIMIT ?3
+        "#
+    )?;
+
+    let rows = stmt.query_map(params![now, max_attempts, limit], |row| {
+        Ok(Ticket {
+            id: row.get(0)?,
+            ticket_data: row.get(1)?,
+            sync_status: row.get(2)?,
+            sync_error: row.get(3)?,
+            sync_attempts: row.get(4)?,
+            order_status: row.get(5)?,
+            location_id: row.get(6)?,
+            order_mode_name: row.get(7)?,
+            ticket_amount: row.get(8)?,
+            items_count: row.get(9)?,
+            queue_number: row.get(10)?,
+            ticket_number: row.get(11)?,
+            created_at: row.get(12)?,
+            updated_at: row.get(13)?,
+            synced_at: row.get(14)?,
+            idempotency_key: row.get(15)?,
+        })
+    })?;
+
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+pub fn mark_ticket_syncing(conn: &mut Connection, ticket_id: &str) -> AppResult<()> {
+    let now = chrono::Utc::now().to_rfc3339();
+
+    conn.execute(
+        "UPDATE tickets SET sync_status = 'SYNCING', updated_at = ?1 WHERE id = ?2",
+        params![now, ticket_id],
+    )?;
+    Ok(())
+}
+
+pub fn mark_ticket_synced(conn: &mut Connection, ticket_id: &str) -> AppResult<()> {
+    let now = chrono::Utc::now().to_rfc3339();
+
+    conn.execute(
+        r#"
+        UPDATE tickets
+        SET sync_status = 'SYNCED', sync_error = NULL, next_attempt_at = NULL,
+            sync_attempts = sync_attempts + 1, updated_at = ?1, synced_at = ?1
+        WHERE id = ?2
+        "#,
+        params![now, ticket_id],
+    )?;
+    Ok(())
+}
+
+/// Mark tickets the server already holds (matched by idempotency key) as
+/// SYNCED without counting an attempt. Returns the number of rows changed.
+pub fn mark_tickets_acknowledged(conn: &mut Connection, ticket_ids: &[String]) -> AppResult<usize> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let tx = conn.transaction()?;
+    let mut count = 0;
+
+    {
+        let mut stmt = tx.prepare_cached(
+            r#"
+            UPDATE tickets
+            SET sync_status = 'SYNCED', sync_error = NULL, next_attempt_at = NULL,
+                updated_at = ?1, synced_at = ?1
+            WHERE id = ?2 AND sync_status != 'SYNCED'
+            "#,
+        )?;
+
+        for id in ticket_ids {
+            count += stmt.execute(params![now, id])?;
+        }
+    }
+
+    tx.commit()?;
+    Ok(count)
+}
+
+/// Count a failed upload. `status` is FAILED (retried after `next_attempt_at`)
+/// or DEAD (parked until requeued by hand).
+pub fn record_ticket_sync_failure(
+    conn: &mut Connection,
+    ticket_id: &str,
+    status: &str,
+    error: &str,
+    next_attempt_at: Option<&str>,
+) -> AppResult<()> {
+    let now = chrono::Utc::now().to_rfc3339();
+
+    conn.execute(
+        r#"
+        UPDATE tickets
+        SET sync_status = ?1, sync_error = ?2, next_attempt_at = ?3,
+            sync_attempts = sync_attempts + 1, updated_at = ?4
+        WHERE id = ?5
+        "#,
+        params![status, error, next_attempt_at, now, ticket_id],
+    )?;
+    Ok(())
+}
+
+/// Undo `mark_ticket_syncing` without counting an attempt
+pub fn restore_ticket_sync_status(
+    conn: &mut Connection,
+    ticket_id: &str,
+    status: &str,
+) -> AppResult<()> {
+    conn.execute(
+        "UPDATE tickets SET sync_status = ?1 WHERE id = ?2",
+        params![status, ticket_id],
+    )?;
+    Ok(())
+}
+
+/// Put tickets left in SYNCING by a crash or shutdown back in the queue
+pub fn reset_interrupted_ticket_syncs(conn: &mut Connection) -> AppResult<usize> {
+    let count = conn.execute(
+        "UPDATE tickets SET sync_status = 'PENDING' WHERE sync_status = 'SYNCING'",
+        [],
+    )?;
+    Ok(count)
+}
+
+/// Move dead-lettered tickets back to PENDING with a fresh attempt budget
+pub fn requeue_dead_tickets(conn: &mut Connection) -> AppResult<usize> {
+    let count = conn.execute(
+        r#"
+        UPDATE tickets
+        SET sync_status = 'PENDING', sync_attempts = 0, next_attempt_at = NULL
+        WHERE sync_status = 'DEAD'
+        "#,
+        [],
+    )?;
+    Ok(count)
+}
+
+pub fn delete_ticket(conn: &mut Connection, ticket_id: &str) -> AppResult<()> {
+    let tx = conn.transaction()?;
+    ticket_detail_repo::delete_ticket_detail(&tx, ticket_id)?;
+    tx.execute("DELETE FROM tickets WHERE id = ?1", params![ticket_id])?;
+    tx.commit()?;
     Ok(())
 }
 
-pub fn get_sync_stats(conn: &Connection) -> anyhow::Result<(i32, i32, i32)> {
-    let mut stmt = conn.prepare(
+pub fn get_sync_stats(conn: &Connection) -> AppResult<(i32, i32, i32)> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           COUNT(CASE WHEN sync_status = 'PENDING' THEN 1 END) as pending,
@@ -161,8 +384,11 @@ pub fn get_sync_stats(conn: &Connection) -> anyhow::Result<(i32, i32, i32)> {
     Ok(result)
 }
 
-pub fn clear_all_tickets(conn: &mut Connection) -> anyhow::Result<()> {
-    conn.execute("DELETE FROM tickets", [])?;
+pub fn clear_all_tickets(conn: &mut Connection) -> AppResult<()> {
+    let tx = conn.transaction()?;
+    ticket_detail_repo::clear_all_ticket_details(&tx)?;
+    tx.execute("DELETE FROM tickets", [])?;
+    tx.commit()?;
     Ok(())
 }
 
@@ -170,7 +396,7 @@ pub fn update_ticket_order_status(
     conn: &mut Connection,
     ticket_id: &str,
     order_status: &str,
-) -> anyhow::Result<()> {
+) -> AppResult<()> {
     let now = chrono::Utc::now().to_rfc3339();
 
     conn.execute(
@@ -188,8 +414,8 @@ pub fn get_max_queue_number(
     conn: &Connection,
     location_id: &str,
     business_date: &str,
-) -> anyhow::Result<Option<i32>> {
-    let mut stmt = conn.prepare(
+) -> AppResult<Option<i32>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT MAX(queue_number) as max_queue
         FROM tickets
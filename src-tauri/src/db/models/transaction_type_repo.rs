@@ -1,7 +1,8 @@
+use crate::error::AppResult;
 use rusqlite::{params, Connection};
 use super::transaction_type::TransactionType;
 
-pub fn save_transaction_types(conn: &mut Connection, items: &[TransactionType]) -> anyhow::Result<()> {
+pub fn save_transaction_types(conn: &mut Connection, items: &[TransactionType]) -> AppResult<()> {
     let tx = conn.transaction()?;
 
     for tt in items {
@@ -44,8 +45,8 @@ pub fn save_transaction_types(conn: &mut Connection, items: &[TransactionType])
     Ok(())
 }
 
-pub fn get_transaction_types(conn: &Connection) -> anyhow::Result<Vec<TransactionType>> {
-    let mut stmt = conn.prepare(
+pub fn get_transaction_types(conn: &Connection) -> AppResult<Vec<TransactionType>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
           id, code, name,
@@ -77,7 +78,7 @@ pub fn get_transaction_types(conn: &Connection) -> anyhow::Result<Vec<Transactio
     Ok(rows.filter_map(Result::ok).collect())
 }
 
-pub fn clear_all(conn: &mut Connection) -> anyhow::Result<()> {
+pub fn clear_all(conn: &mut Connection) -> AppResult<()> {
     let tx = conn.transaction()?;
     tx.execute("DELETE FROM transaction_types", [])?;
     tx.commit()?;
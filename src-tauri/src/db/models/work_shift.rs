@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+use crate::money::Money;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkShift {
+    pub id: i64,
+    pub workday_id: i64, // Local workdays.id
+    pub location_id: String,
+    pub status: String, // OPEN, CLOSED
+    pub opened_by: String,
+    pub opened_at: String,
+    pub opening_float: Money,
+    pub closed_by: Option<String>,
+    pub closed_at: Option<String>,
+    /// Opening float plus cash taken during the shift
+    pub expected_cash: Option<Money>,
+    pub counted_cash: Option<Money>,
+    /// counted - expected: positive is over, negative is short
+    pub variance: Option<Money>,
+    pub cash_up: Option<String>,  // JSON Vec<CashUpLine>
+    pub x_report: Option<String>, // JSON SalesReport for the shift window
+    pub created_at: String,
+    pub updated_at: Option<String>,
+}
+
+/// Expected versus counted takings for one payment method
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CashUpLine {
+    pub payment_type_id: Option<String>,
+    pub payment_type: String,
+    pub is_cash: bool,
+    pub expected: Money,
+    pub counted: Option<Money>,
+    pub variance: Option<Money>,
+}
+
+/// What the cashier counted for a payment method when closing the shift
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CountedAmount {
+    pub payment_type_id: Option<String>,
+    pub payment_type: String,
+    pub amount: Money,
+}
@@ -0,0 +1,268 @@
+use crate::error::{AppError, AppResult};
+use crate::money::Money;
+use rusqlite::{params, Connection, OptionalExtension};
+use super::report::SalesReport;
+use super::report_repo;
+use super::work_shift::{CashUpLine, CountedAmount, WorkShift};
+
+const SELECT_SHIFT: &str = r#"
+    SELECT
+      id, workday_id, location_id, status, opened_by, opened_at, opening_float,
+      closed_by, closed_at, expected_cash, counted_cash, variance, cash_up, x_report,
+      created_at, updated_at
+    FROM work_shifts
+"#;
+
+fn map_shift(row: &rusqlite::Row) -> rusqlite::Result<WorkShift> {
+    Ok(WorkShift {
+        id: row.get(0)?,
+        workday_id: row.get(1)?,
+        location_id: row.get(2)?,
+        status: row.get(3)?,
+        opened_by: row.get(4)?,
+        opened_at: row.get(5)?,
+        opening_float: row.get(6)?,
+        closed_by: row.get(7)?,
+        closed_at: row.get(8)?,
+        expected_cash: row.get(9)?,
+        counted_cash: row.get(10)?,
+        variance: row.get(11)?,
+        cash_up: row.get(12)?,
+        x_report: row.get(13)?,
+        created_at: row.get(14)?,
+        updated_at: row.get(15)?,
+    })
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+pub fn get_work_shift(conn: &Connection, id: i64) -> AppResult<Option<WorkShift>> {
+    let mut stmt = conn.prepare_cached(&format!("{} WHERE id = ?1", SELECT_SHIFT))?;
+    Ok(stmt.query_row(params![id], map_shift).optional()?)
+}
+
+pub fn get_open_work_shift(conn: &Connection, location_id: &str) -> AppResult<Option<WorkShift>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "{} WHERE location_id = ?1 AND status = 'OPEN'",
+        SELECT_SHIFT
+    ))?;
+    Ok(stmt.query_row(params![location_id], map_shift).optional()?)
+}
+
+pub fn get_work_shifts_for_workday(conn: &Connection, workday_id: i64) -> AppResult<Vec<WorkShift>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "{} WHERE workday_id = ?1 ORDER BY opened_at ASC",
+        SELECT_SHIFT
+    ))?;
+    let rows = stmt.query_map(params![workday_id], map_shift)?;
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+pub fn count_open_work_shifts(conn: &Connection, workday_id: i64) -> AppResult<i64> {
+    let count = conn.query_row(
+        "SELECT COUNT(*) FROM work_shifts WHERE workday_id = ?1 AND status = 'OPEN'",
+        params![workday_id],
+        |row| row.get(0),
+    )?;
+    Ok(count)
+}
+
+/// Open a shift inside a workday that is still open. Only one shift per
+/// location may be open at a time.
+pub fn start_work_shift(
+    conn: &mut Connection,
+    workday_id: i64,
+    opened_by: &str,
+    opening_float: Money,
+) -> AppResult<WorkShift> {
+    if opening_float.is_negative() {
+        return Err(AppError::InvalidInput("opening float can't be negative".into()));
+    }
+
+    let tx = conn.transaction()?;
+
+    let (location_id, locked_at): (String, Option<String>) = tx
+        .query_row(
+            "SELECT location_id, locked_at FROM workdays WHERE id = ?1",
+            params![workday_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Workday with id {}", workday_id)))?;
+
+    if locked_at.is_some() {
+        return Err(AppError::Conflict(format!("workday {} is closed", workday_id)));
+    }
+
+    if let Some(open) = get_open_work_shift(&tx, &location_id)? {
+        return Err(AppError::Conflict(format!(
+            "shift {} opened by {} is still open",
+            open.id, open.opened_by
+        )));
+    }
+
+    let now = now();
+    tx.execute(
+        r#"
+        INSERT INTO work_shifts (
+          workday_id, location_id, status, opened_by, opened_at, opening_float, created_at
+        )
+        VALUES (?1, ?2, 'OPEN', ?3, ?4, ?5, ?4)
+        "#,
+        params![workday_id, location_id, opened_by, now, opening_float],
+    )?;
+
+    let id = tx.last_insert_rowid();
+    let shift = get_work_shift(&tx, id)?
+        .ok_or_else(|| AppError::Internal(format!("shift {} missing after insert", id)))?;
+
+    tx.commit()?;
+    Ok(shift)
+}
+
+/// Close a shift: build its X-report, compare the takings per payment method
+/// with what was counted, store the cash-up and mark it CLOSED.
+pub fn end_work_shift(
+    conn: &mut Connection,
+    id: i64,
+    closed_by: &str,
+    counted: &[CountedAmount],
+) -> AppResult<WorkShift> {
+    let tx = conn.transaction()?;
+
+    let shift = get_work_shift(&tx, id)?
+        .ok_or_else(|| AppError::NotFound(format!("Shift with id {}", id)))?;
+
+    if shift.status != "OPEN" {
+        return Err(AppError::Conflict(format!("shift {} is already closed", id)));
+    }
+
+    let now = now();
+    let report = report_repo::build_sales_report(&tx, &shift.location_id, &shift.opened_at, &now)?;
+    let lines = cash_up_lines(&tx, &report, shift.opening_float, counted)?;
+
+    let cash: Vec<&CashUpLine> = lines.iter().filter(|l| l.is_cash).collect();
+    let expected_cash: Money = cash.iter().map(|l| l.expected).sum();
+    let counted_cash: Option<Money> = cash
+        .iter()
+        .filter_map(|l| l.counted)
+        .fold(None, |sum, c| Some(sum.unwrap_or_default() + c));
+    let variance = counted_cash.map(|c| c - expected_cash);
+
+    tx.execute(
+        r#"
+        UPDATE work_shifts
+        SET status = 'CLOSED', closed_by = ?1, closed_at = ?2, expected_cash = ?3,
+            counted_cash = ?4, variance = ?5, cash_up = ?6, x_report = ?7, updated_at = ?2
+        WHERE id = ?8
+        "#,
+        params![
+            closed_by,
+            now,
+            expected_cash,
+            counted_cash,
+            variance,
+            serde_json::to_string(&lines)?,
+            serde_json::to_string(&report)?,
+            id,
+        ],
+    )?;
+
+    let shift = get_work_shift(&tx, id)?
+        .ok_or_else(|| AppError::Internal(format!("shift {} missing after update", id)))?;
+
+    tx.commit()?;
+    Ok(shift)
+}
+
+/// One line per payment method taken or counted. The opening float is
+/// expected back in the cash drawer.
+fn cash_up_lines(
+    conn: &Connection,
+    report: &SalesReport,
+    opening_float: Money,
+    counted: &[CountedAmount],
+) -> AppResult<Vec<CashUpLine>> {
+    let mut lines: Vec<CashUpLine> = Vec::new();
+
+    for p in &report.by_payment_method {
+        lines.push(CashUpLine {
+            is_cash: is_cash_method(conn, p.payment_type_id.as_deref(), &p.payment_type)?,
+            payment_type_id: p.payment_type_id.clone(),
+            payment_type: p.payment_type.clone(),
+            expected: p.amount,
+            counted: None,
+            variance: None,
+        });
+    }
+
+    for c in counted {
+        if !lines.iter().any(|l| same_method(l, c)) {
+            lines.push(CashUpLine {
+                is_cash: is_cash_method(conn, c.payment_type_id.as_deref(), &c.payment_type)?,
+                payment_type_id: c.payment_type_id.clone(),
+                payment_type: c.payment_type.clone(),
+                expected: Money::zero(),
+                counted: None,
+                variance: None,
+            });
+        }
+    }
+
+    match lines.iter_mut().find(|l| l.is_cash) {
+        Some(cash) => cash.expected += opening_float,
+        None if !opening_float.is_zero() => lines.push(CashUpLine {
+            payment_type_id: None,
+            payment_type: "Cash".to_string(),
+            is_cash: true,
+            expected: opening_float,
+            counted: None,
+            variance: None,
+        }),
+        None => {}
+    }
+
+    for line in &mut lines {
+        let total: Option<Money> = counted
+            .iter()
+            .filter(|c| same_method(line, c))
+            .fold(None, |sum, c| Some(sum.unwrap_or_default() + c.amount));
+        line.counted = total;
+        line.variance = total.map(|t| t - line.expected);
+    }
+
+    Ok(lines)
+}
+
+fn same_method(line: &CashUpLine, counted: &CountedAmount) -> bool {
+    match (&line.payment_type_id, &counted.payment_type_id) {
+        (Some(a), Some(b)) => a == b,
+        _ => line.payment_type.eq_ignore_ascii_case(&counted.payment_type),
+    }
+}
+
+/// Cash is recognised by the payment method's code (CASH) or its name
+fn is_cash_method(conn: &Connection, payment_type_id: Option<&str>, payment_type: &str) -> AppResult<bool> {
+    if payment_type.to_lowercase().contains("cash") {
+        return Ok(true);
+    }
+
+    let Some(id) = payment_type_id else {
+        return Ok(false);
+    };
+
+    let found = conn
+        .query_row(
+            r#"
+            SELECT 1 FROM payment_methods
+            WHERE id = ?1 AND (UPPER(code) = 'CASH' OR LOWER(name) LIKE '%cash%')
+            "#,
+            params![id],
+            |_| Ok(()),
+        )
+        .optional()?;
+
+    Ok(found.is_some())
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::money::Money;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Workday {
     pub id: Option<i64>,
@@ -9,8 +11,8 @@ pub struct Workday {
     pub start_time: Option<String>,
     pub end_time: Option<String>,
     pub location_id: String,
-    pub total_sales: Option<f64>,
-    pub total_taxes: Option<f64>,
+    pub total_sales: Option<Money>,
+    pub total_taxes: Option<Money>,
     pub total_ticket_count: Option<i32>,
     pub work_period_informations: Option<String>,
     pub department_ticket_informations: Option<String>,
@@ -19,8 +21,15 @@ pub struct Workday {
     pub external_processed: Option<bool>,
     pub work_period_day: Option<String>,
     pub business_date: Option<String>,
-    pub sync_status: String, // PENDING, SYNCING, SYNCED, FAILED
+    pub sync_status: String, // PENDING, SYNCING, SYNCED, FAILED, DEAD
     pub sync_error: Option<String>,
+    pub sync_attempts: Option<i32>,
     pub created_at: String,
     pub updated_at: Option<String>,
+    /// Set when the workday is closed; locked workdays can't be edited
+    #[serde(default)]
+    pub locked_at: Option<String>,
+    /// JSON `SalesReport` generated by `close_workday`
+    #[serde(default)]
+    pub z_report: Option<String>,
 }
@@ -1,8 +1,14 @@
+use crate::error::{AppError, AppResult};
 use rusqlite::{params, Connection, OptionalExtension};
+use super::report::SalesReport;
+use super::report_repo;
+use super::work_shift_repo;
 use super::workday::Workday;
 
-pub fn save_workday(conn: &mut Connection, workday: &Workday) -> anyhow::Result<i64> {
+pub fn save_workday(conn: &mut Connection, workday: &Workday) -> AppResult<i64> {
     if let Some(id) = workday.id {
+        ensure_unlocked(conn, id)?;
+
         // Update existing workday
         conn.execute(
             r#"
@@ -78,14 +84,15 @@ pub fn save_workday(conn: &mut Connection, workday: &Workday) -> anyhow::Result<
     }
 }
 
-pub fn get_all_workdays(conn: &Connection) -> anyhow::Result<Vec<Workday>> {
-    let mut stmt = conn.prepare(
+pub fn get_all_workdays(conn: &Connection) -> AppResult<Vec<Workday>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
             id, workday_id, start_user, end_user, start_time, end_time, location_id,
             total_sales, total_taxes, total_ticket_count, work_period_informations,
             department_ticket_informations, add_on, auto_closed, external_processed,
-            work_period_day, business_date, sync_status, sync_error, created_at, updated_at
+            work_period_day, business_date, sync_status, sync_error, created_at, updated_at, sync_attempts,
+            locked_at, z_report
         FROM workdays
         ORDER BY created_at DESC
         "#
@@ -114,20 +121,24 @@ pub fn get_all_workdays(conn: &Connection) -> anyhow::Result<Vec<Workday>> {
             sync_error: row.get(18)?,
             created_at: row.get(19)?,
             updated_at: row.get(20)?,
+            sync_attempts: row.get(21)?,
+            locked_at: row.get(22)?,
+            z_report: row.get(23)?,
         })
     })?;
 
     Ok(rows.filter_map(Result::ok).collect())
 }
 
-pub fn get_pending_workdays(conn: &Connection) -> anyhow::Result<Vec<Workday>> {
-    let mut stmt = conn.prepare(
+pub fn get_pending_workdays(conn: &Connection) -> AppResult<Vec<Workday>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
             id, workday_id, start_user, end_user, start_time, end_time, location_id,
             total_sales, total_taxes, total_ticket_count, work_period_informations,
             department_ticket_informations, add_on, auto_closed, external_processed,
-            work_period_day, business_date, sync_status, sync_error, created_at, updated_at
+            work_period_day, business_date, sync_status, sync_error, created_at, updated_at, sync_attempts,
+            locked_at, z_report
         FROM workdays
         WHERE sync_status = 'PENDING'
         ORDER BY created_at DESC
@@ -157,13 +168,16 @@ pub fn get_pending_workdays(conn: &Connection) -> anyhow::Result<Vec<Workday>> {
             sync_error: row.get(18)?,
             created_at: row.get(19)?,
             updated_at: row.get(20)?,
+            sync_attempts: row.get(21)?,
+            locked_at: row.get(22)?,
+            z_report: row.get(23)?,
         })
     })?;
 
     Ok(rows.filter_map(Result::ok).collect())
 }
 
-pub fn get_active_workday(conn: &Connection) -> anyhow::Result<Option<Workday>> {
+pub fn get_active_workday(conn: &Connection) -> AppResult<Option<Workday>> {
     let result = conn
         .query_row(
             r#"
@@ -171,7 +185,8 @@ pub fn get_active_workday(conn: &Connection) -> anyhow::Result<Option<Workday>>
                 id, workday_id, start_user, end_user, start_time, end_time, location_id,
                 total_sales, total_taxes, total_ticket_count, work_period_informations,
                 department_ticket_informations, add_on, auto_closed, external_processed,
-                work_period_day, business_date, sync_status, sync_error, created_at, updated_at
+                work_period_day, business_date, sync_status, sync_error, created_at, updated_at, sync_attempts,
+                locked_at, z_report
             FROM workdays
             WHERE end_time IS NULL
             ORDER BY created_at DESC
@@ -201,6 +216,9 @@ pub fn get_active_workday(conn: &Connection) -> anyhow::Result<Option<Workday>>
                     sync_error: row.get(18)?,
                     created_at: row.get(19)?,
                     updated_at: row.get(20)?,
+                    sync_attempts: row.get(21)?,
+                    locked_at: row.get(22)?,
+                    z_report: row.get(23)?,
                 })
             },
         )
@@ -209,7 +227,7 @@ pub fn get_active_workday(conn: &Connection) -> anyhow::Result<Option<Workday>>
     Ok(result)
 }
 
-pub fn get_workday_by_id(conn: &Connection, id: i64) -> anyhow::Result<Option<Workday>> {
+pub fn get_workday_by_id(conn: &Connection, id: i64) -> AppResult<Option<Workday>> {
     let result = conn
         .query_row(
             r#"
@@ -217,7 +235,8 @@ pub fn get_workday_by_id(conn: &Connection, id: i64) -> anyhow::Result<Option<Wo
                 id, workday_id, start_user, end_user, start_time, end_time, location_id,
                 total_sales, total_taxes, total_ticket_count, work_period_informations,
                 department_ticket_informations, add_on, auto_closed, external_processed,
-                work_period_day, business_date, sync_status, sync_error, created_at, updated_at
+                work_period_day, business_date, sync_status, sync_error, created_at, updated_at, sync_attempts,
+                locked_at, z_report
             FROM workdays
             WHERE id = ?1
             "#,
@@ -245,6 +264,9 @@ pub fn get_workday_by_id(conn: &Connection, id: i64) -> anyhow::Result<Option<Wo
                     sync_error: row.get(18)?,
                     created_at: row.get(19)?,
                     updated_at: row.get(20)?,
+                    sync_attempts: row.get(21)?,
+                    locked_at: row.get(22)?,
+                    z_report: row.get(23)?,
                 })
             },
         )
@@ -258,7 +280,7 @@ pub fn update_workday_sync_status(
     id: i64,
     status: &str,
     error: Option<&str>,
-) -> anyhow::Result<()> {
+) -> AppResult<()> {
     conn.execute(
         r#"
         UPDATE workdays
@@ -274,7 +296,7 @@ pub fn set_workday_server_id(
     conn: &mut Connection,
     id: i64,
     workday_id: &str,
-) -> anyhow::Result<()> {
+) -> AppResult<()> {
     conn.execute(
         r#"
         UPDATE workdays
@@ -286,8 +308,251 @@ pub fn set_workday_server_id(
     Ok(())
 }
 
-pub fn delete_workday(conn: &mut Connection, id: i64) -> anyhow::Result<()> {
-    conn.execute("DELETE FROM workdays WHERE id = ?1", params![id])?;
+// ==============================
+// Close (Z-report)
+// ==============================
+
+/// Closed workdays are read-only
+fn ensure_unlocked(conn: &Connection, id: i64) -> AppResult<()> {
+    let locked_at: Option<String> = conn
+        .query_row(
+            "SELECT locked_at FROM workdays WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+
+    match locked_at {
+        Some(at) => Err(AppError::Conflict(format!("workday {} was closed at {}", id, at))),
+        None => Ok(()),
+    }
+}
+
+/// X-report: the same figures as the Z-report for the workday's start up to
+/// now, or up to its end once closed, without touching the row. Works on
+/// open and closed workdays.
+pub fn build_x_report(conn: &Connection, id: i64) -> AppResult<SalesReport> {
+    let (location_id, start_time, created_at, end_time, locked_at): (
+        String,
+        Option<String>,
+        String,
+        Option<String>,
+        Option<String>,
+    ) = conn
+        .query_row(
+            "SELECT location_id, start_time, created_at, end_time, locked_at FROM workdays WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Workday with id {}", id)))?;
+
+    let from = start_time.unwrap_or(created_at);
+    let to = locked_at
+        .or(end_time)
+        .unwrap_or_else(|| chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true));
+    report_repo::build_sales_report(conn, &location_id, &from, &to)
+}
+
+/// Build the Z-report from the tickets created between the workday's start
+/// and now, store the totals on the row, lock it and queue it for upload.
+pub fn close_workday(conn: &mut Connection, id: i64, end_user: &str) -> AppResult<SalesReport> {
+    let tx = conn.transaction()?;
+
+    let (location_id, start_time, created_at): (String, Option<String>, String) = tx
+        .query_row(
+            "SELECT location_id, start_time, created_at FROM workdays WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Workday with id {}", id)))?;
+
+    ensure_unlocked(&tx, id)?;
+
+    let open_shifts = work_shift_repo::count_open_work_shifts(&tx, id)?;
+    if open_shifts > 0 {
+        return Err(AppError::Conflict(format!(
+            "workday {} still has {} open shift(s)",
+            id, open_shifts
+        )));
+    }
+
+    let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let from = start_time.unwrap_or(created_at);
+    let report = report_repo::build_sales_report(&tx, &location_id, &from, &now)?;
+
+    tx.execute(
+        r#"
+        UPDATE workdays
+        SET end_user = ?1, end_time = ?2, total_sales = ?3, total_taxes = ?4,
+            total_ticket_count = ?5, department_ticket_informations = ?6, z_report = ?7,
+            locked_at = ?2, updated_at = ?2,
+            sync_status = 'PENDING', sync_error = NULL, sync_attempts = 0, next_attempt_at = NULL
+        WHERE id = ?8
+        "#,
+        params![
+            end_user,
+            now,
+            report.total_payments,
+            report.total_taxes,
+            report.ticket_count,
+            serde_json::to_string(&report.by_category)?,
+            serde_json::to_string(&report)?,
+            id,
+        ],
+    )?;
+
+    tx.commit()?;
+    Ok(report)
+}
+
+// ==============================
+// Background sync worker
+// ==============================
+
+/// Workdays the sync worker may upload now: PENDING or FAILED, under the
+/// attempt cap and past their backoff time. Oldest first.
+pub fn get_due_workdays(
+    conn: &Connection,
+    now: &str,
+    max_attempts: i32,
+    limit: i64,
+) -> AppResult<Vec<Workday>> {
+    let mut stmt = conn.prepare_cached(
+        r#"
+        SELECT
+            id, workday_id, start_user, end_user, start_time, end_time, location_id,
+            total_sales, total_taxes, total_ticket_count, work_period_informations,
+            department_ticket_informations, add_on, auto_closed, external_processed,
+            work_period_day, business_date, sync_status, sync_error, created_at, updated_at, sync_attempts,
+            locked_at, z_report
+        FROM workdays
+        WHERE sync_status IN ('PENDING', 'FAILED')
+          AND COALESCE(sync_attempts, 0) < ?2
+          AND (next_attempt_at IS NULL OR next_attempt_at <= ?1)
+        ORDER BY created_at ASC
+        LIMIT ?3
+        "#
+    )?;
+
+    let rows = stmt.query_map(params![now, max_attempts, limit], |row| {
+        Ok(Workday {
+            id: row.get(0)?,
+            workday_id: row.get(1)?,
+            start_user: row.get(2)?,
+            end_user: row.get(3)?,
+            start_time: row.get(4)?,
+            end_time: row.get(5)?,
+            location_id: row.get(6)?,
+            total_sales: row.get(7)?,
+            total_taxes: row.get(8)?,
+            total_ticket_count: row.get(9)?,
+            work_period_informations: row.get(10)?,
+            department_ticket_informations: row.get(11)?,
+            add_on: row.get(12)?,
+            auto_closed: row.get(13)?,
+            external_processed: row.get(14)?,
+            work_period_day: row.get(15)?,
+            business_date: row.get(16)?,
+            sync_status: row.get(17)?,
+            sync_error: row.get(18)?,
+            created_at: row.get(19)?,
+            updated_at: row.get(20)?,
+            sync_attempts: row.get(21)?,
+            locked_at: row.get(22)?,
+            z_report: row.get(23)?,
+        })
+    })?;
+
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+pub fn mark_workday_syncing(conn: &mut Connection, id: i64) -> AppResult<()> {
+    conn.execute(
+        "UPDATE workdays SET sync_status = 'SYNCING', updated_at = datetime('now') WHERE id = ?1",
+        params![id],
+    )?;
+    Ok(())
+}
+
+pub fn mark_workday_synced(conn: &mut Connection, id: i64) -> AppResult<()> {
+    conn.execute(
+        r#"
+        UPDATE workdays
+        SET sync_status = 'SYNCED', sync_error = NULL, next_attempt_at = NULL,
+            sync_attempts = COALESCE(sync_attempts, 0) + 1, updated_at = datetime('now')
+        WHERE id = ?1
+        "#,
+        params![id],
+    )?;
+    Ok(())
+}
+
+/// Count a failed upload. `status` is FAILED (retried after `next_attempt_at`)
+/// or DEAD (parked until requeued by hand).
+pub fn record_workday_sync_failure(
+    conn: &mut Connection,
+    id: i64,
+    status: &str,
+    error: &str,
+    next_attempt_at: Option<&str>,
+) -> AppResult<()> {
+    conn.execute(
+        r#"
+        UPDATE workdays
+        SET sync_status = ?1, sync_error = ?2, next_attempt_at = ?3,
+            sync_attempts = COALESCE(sync_attempts, 0) + 1, updated_at = datetime('now')
+        WHERE id = ?4
+        "#,
+        params![status, error, next_attempt_at, id],
+    )?;
+    Ok(())
+}
+
+/// Undo `mark_workday_syncing` without counting an attempt
+pub fn restore_workday_sync_status(
+    conn: &mut Connection,
+    id: i64,
+    status: &str,
+) -> AppResult<()> {
+    conn.execute(
+        "UPDATE workdays SET sync_status = ?1 WHERE id = ?2",
+        params![status, id],
+    )?;
+    Ok(())
+}
+
+/// Put workdays left in SYNCING by a crash or shutdown back in the queue
+pub fn reset_interrupted_workday_syncs(conn: &mut Connection) -> AppResult<usize> {
+    let count = conn.execute(
+        "UPDATE workdays SET sync_status = 'PENDING' WHERE sync_status = 'SYNCING'",
+        [],
+    )?;
+    Ok(count)
+}
+
+/// Move dead-lettered workdays back to PENDING with a fresh attempt budget
+pub fn requeue_dead_workdays(conn: &mut Connection) -> AppResult<usize> {
+    let count = conn.execute(
+        r#"
+        UPDATE workdays
+        SET sync_status = 'PENDING', sync_attempts = 0, next_attempt_at = NULL
+        WHERE sync_status = 'DEAD'
+        "#,
+        [],
+    )?;
+    Ok(count)
+}
+
+pub fn delete_workday(conn: &mut Connection, id: i64) -> AppResult<()> {
+    ensure_unlocked(conn, id)?;
+
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM work_shifts WHERE workday_id = ?1", params![id])?;
+    tx.execute("DELETE FROM workdays WHERE id = ?1", params![id])?;
+    tx.commit()?;
     Ok(())
 }
 
@@ -295,14 +560,15 @@ pub fn get_workdays_by_date_range(
     conn: &Connection,
     start_date: &str,
     end_date: &str,
-) -> anyhow::Result<Vec<Workday>> {
-    let mut stmt = conn.prepare(
+) -> AppResult<Vec<Workday>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
             id, workday_id, start_user, end_user, start_time, end_time, location_id,
             total_sales, total_taxes, total_ticket_count, work_period_informations,
             department_ticket_informations, add_on, auto_closed, external_processed,
-            work_period_day, business_date, sync_status, sync_error, created_at, updated_at
+            work_period_day, business_date, sync_status, sync_error, created_at, updated_at, sync_attempts,
+            locked_at, z_report
         FROM workdays
         WHERE business_date BETWEEN ?1 AND ?2
         ORDER BY business_date DESC, created_at DESC
@@ -332,6 +598,9 @@ pub fn get_workdays_by_date_range(
             sync_error: row.get(18)?,
             created_at: row.get(19)?,
             updated_at: row.get(20)?,
+            sync_attempts: row.get(21)?,
+            locked_at: row.get(22)?,
+            z_report: row.get(23)?,
         })
     })?;
 
@@ -341,14 +610,15 @@ pub fn get_workdays_by_date_range(
 pub fn get_workdays_by_location(
     conn: &Connection,
     location_id: &str,
-) -> anyhow::Result<Vec<Workday>> {
-    let mut stmt = conn.prepare(
+) -> AppResult<Vec<Workday>> {
+    let mut stmt = conn.prepare_cached(
         r#"
         SELECT
             id, workday_id, start_user, end_user, start_time, end_time, location_id,
             total_sales, total_taxes, total_ticket_count, work_period_informations,
             department_ticket_informations, add_on, auto_closed, external_processed,
-            work_period_day, business_date, sync_status, sync_error, created_at, updated_at
+            work_period_day, business_date, sync_status, sync_error, created_at, updated_at, sync_attempts,
+            locked_at, z_report
         FROM workdays
         WHERE location_id = ?1
         ORDER BY created_at DESC
@@ -378,13 +648,19 @@ pub fn get_workdays_by_location(
             sync_error: row.get(18)?,
             created_at: row.get(19)?,
             updated_at: row.get(20)?,
+            sync_attempts: row.get(21)?,
+            locked_at: row.get(22)?,
+            z_report: row.get(23)?,
         })
     })?;
 
     Ok(rows.filter_map(Result::ok).collect())
 }
 
-pub fn clear_all_workdays(conn: &mut Connection) -> anyhow::Result<()> {
-    conn.execute("DELETE FROM workdays", [])?;
+pub fn clear_all_workdays(conn: &mut Connection) -> AppResult<()> {
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM work_shifts", [])?;
+    tx.execute("DELETE FROM workdays", [])?;
+    tx.commit()?;
     Ok(())
 }
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+/// A hub message kept until every device of its type has acknowledged it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub seq: i64,
+    pub message_id: String,
+    pub target_type: String, // POS | KDS | QUEUE
+    pub target_device_id: Option<String>,
+    /// The `DeviceMessage` as sent, without its `seq`
+    pub message: String,
+    pub created_at: String,
+}
+
+/// Where a device stands in the outbox
+#[derive(Debug, Serialize, Clone)]
+pub struct DeliveryStatus {
+    pub device_id: String,
+    pub device_type: String,
+    pub last_acked_seq: i64,
+    pub last_acked_at: Option<String>,
+    pub last_registered_at: Option<String>,
+    /// Messages for this device newer than its last ack
+    pub pending: i64,
+    /// Filled in from the live connection map
+    pub connected: bool,
+}
+
+
+/// A device's position in the outbox, as copied to a standby hub
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryCursor {
+    pub device_id: String,
+    pub device_type: String,
+    pub last_acked_seq: i64,
+    pub last_acked_at: Option<String>,
+    pub last_registered_at: Option<String>,
+}
@@ -0,0 +1,238 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use super::ws_outbox::{DeliveryCursor, DeliveryStatus, OutboxEntry};
+use crate::error::{AppError, AppResult};
+
+const SELECT_ENTRY: &str = r#"
+    SELECT seq, message_id, target_type, target_device_id, message, created_at
+    FROM ws_outbox
+"#;
+
+fn map_entry(row: &Row) -> rusqlite::Result<OutboxEntry> {
+    Ok(OutboxEntry {
+        seq: row.get(0)?,
+        message_id: row.get(1)?,
+        target_type: row.get(2)?,
+        target_device_id: row.get(3)?,
+        message: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+const SELECT_CURSOR: &str = r#"
+    SELECT device_id, device_type, last_acked_seq, last_acked_at, last_registered_at
+    FROM ws_delivery_cursors
+"#;
+
+fn map_cursor(row: &Row) -> rusqlite::Result<DeliveryCursor> {
+    Ok(DeliveryCursor {
+        device_id: row.get(0)?,
+        device_type: row.get(1)?,
+        last_acked_seq: row.get(2)?,
+        last_acked_at: row.get(3)?,
+        last_registered_at: row.get(4)?,
+    })
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Store a message for `target_type` (or one device) and return its sequence.
+/// Enqueuing the same `message_id` twice returns the original sequence.
+pub fn enqueue(
+    conn: &Connection,
+    message_id: &str,
+    target_type: &str,
+    target_device_id: Option<&str>,
+    message: &str,
+) -> AppResult<i64> {
+    conn.execute(
+        r#"
+        INSERT INTO ws_outbox (message_id, target_type, target_device_id, message, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        ON CONFLICT(message_id) DO NOTHING
+        "#,
+        params![message_id, target_type, target_device_id, message, now()],
+    )?;
+
+    Ok(conn.query_row(
+        "SELECT seq FROM ws_outbox WHERE message_id = ?1",
+        params![message_id],
+        |row| row.get(0),
+    )?)
+}
+
+/// Record that a device (re)registered and return its last acked sequence.
+/// A device seen for the first time starts at the head of the outbox, so it
+/// isn't flooded with history from before it existed.
+pub fn open_cursor(conn: &Connection, device_id: &str, device_type: &str) -> AppResult<i64> {
+    let now = now();
+
+    conn.execute(
+        r#"
+        INSERT INTO ws_delivery_cursors (device_id, device_type, last_acked_seq, last_registered_at, created_at)
+        VALUES (?1, ?2, (SELECT COALESCE(MAX(seq), 0) FROM ws_outbox), ?3, ?3)
+        ON CONFLICT(device_id) DO UPDATE SET
+          device_type = excluded.device_type,
+          last_registered_at = excluded.last_registered_at
+        "#,
+        params![device_id, device_type, now],
+    )?;
+
+    Ok(conn.query_row(
+        "SELECT last_acked_seq FROM ws_delivery_cursors WHERE device_id = ?1",
+        params![device_id],
+        |row| row.get(0),
+    )?)
+}
+
+/// Messages a device hasn't acknowledged yet, oldest first
+pub fn pending_for(
+    conn: &Connection,
+    device_id: &str,
+    device_type: &str,
+    after_seq: i64,
+) -> AppResult<Vec<OutboxEntry>> {
+    let sql = format!(
+        "{} WHERE seq > ?1 AND target_type = ?2 AND (target_device_id IS NULL OR target_device_id = ?3) ORDER BY seq",
+        SELECT_ENTRY
+    );
+    let mut stmt = conn.prepare_cached(&sql)?;
+    let rows = stmt.query_map(params![after_seq, device_type, device_id], map_entry)?;
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+/// Move a device's cursor forward. Messages arrive in order over one
+/// connection, so an ack for `seq` covers everything before it.
+pub fn ack(conn: &Connection, device_id: &str, seq: i64) -> AppResult<()> {
+    conn.execute(
+        r#"
+        UPDATE ws_delivery_cursors
+        SET last_acked_seq = MAX(last_acked_seq, ?2), last_acked_at = ?3
+        WHERE device_id = ?1
+        "#,
+        params![device_id, seq, now()],
+    )?;
+    Ok(())
+}
+
+pub fn get_entry(conn: &Connection, seq: i64) -> AppResult<OutboxEntry> {
+    let mut stmt = conn.prepare_cached(&format!("{} WHERE seq = ?1", SELECT_ENTRY))?;
+    stmt.query_row(params![seq], map_entry)
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Outbox message {}", seq)))
+}
+
+/// Every stored message, oldest first
+pub fn get_entries(conn: &Connection) -> AppResult<Vec<OutboxEntry>> {
+    let mut stmt = conn.prepare_cached(&format!("{} ORDER BY seq", SELECT_ENTRY))?;
+    let rows = stmt.query_map([], map_entry)?;
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+pub fn get_cursor(conn: &Connection, device_id: &str) -> AppResult<DeliveryCursor> {
+    let mut stmt = conn.prepare_cached(&format!("{} WHERE device_id = ?1", SELECT_CURSOR))?;
+    stmt.query_row(params![device_id], map_cursor)
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Delivery cursor {}", device_id)))
+}
+
+pub fn get_cursors(conn: &Connection) -> AppResult<Vec<DeliveryCursor>> {
+    let mut stmt = conn.prepare_cached(&format!("{} ORDER BY device_id", SELECT_CURSOR))?;
+    let rows = stmt.query_map([], map_cursor)?;
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+pub fn get_delivery_status(conn: &Connection) -> AppResult<Vec<DeliveryStatus>> {
+    let mut stmt = conn.prepare_cached(
+        r#"
+        SELECT c.device_id, c.device_type, c.last_acked_seq, c.last_acked_at, c.last_registered_at,
+               (SELECT COUNT(*) FROM ws_outbox o
+                WHERE o.seq > c.last_acked_seq
+                  AND o.target_type = c.device_type
+                  AND (o.target_device_id IS NULL OR o.target_device_id = c.device_id))
+        FROM ws_delivery_cursors c
+        ORDER BY c.device_type, c.device_id
+        "#,
+    )?;
+
+    let rows = stmt.query_map([], |row| {
+        Ok(DeliveryStatus {
+            device_id: row.get(0)?,
+            device_type: row.get(1)?,
+            last_acked_seq: row.get(2)?,
+            last_acked_at: row.get(3)?,
+            last_registered_at: row.get(4)?,
+            pending: row.get(5)?,
+            connected: false,
+        })
+    })?;
+
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+/// Drop messages older than `keep_hours`; a device offline for longer
+/// than that only gets what was sent since
+pub fn prune(conn: &Connection, keep_hours: i64) -> AppResult<usize> {
+    let cutoff = (chrono::Utc::now() - chrono::Duration::hours(keep_hours))
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+
+    Ok(conn.execute("DELETE FROM ws_outbox WHERE created_at < ?1", params![cutoff])?)
+}
+
+// ==============================
+// Mirroring (standby hub)
+// ==============================
+
+/// Store a message copied from the primary hub under its original seq, so
+/// devices keep their place in the outbox if this hub takes over
+pub fn restore_entry(conn: &Connection, entry: &OutboxEntry) -> AppResult<()> {
+    conn.execute(
+        r#"
+        INSERT INTO ws_outbox (seq, message_id, target_type, target_device_id, message, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        ON CONFLICT DO NOTHING
+        "#,
+        params![
+            entry.seq,
+            entry.message_id,
+            entry.target_type,
+            entry.target_device_id,
+            entry.message,
+            entry.created_at
+        ],
+    )?;
+    Ok(())
+}
+
+/// Store a cursor copied from the primary hub; never moves one back
+pub fn restore_cursor(conn: &Connection, cursor: &DeliveryCursor) -> AppResult<()> {
+    conn.execute(
+        r#"
+        INSERT INTO ws_delivery_cursors (device_id, device_type, last_acked_seq, last_acked_at, last_registered_at, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        ON CONFLICT(device_id) DO UPDATE SET
+          device_type = excluded.device_type,
+          last_acked_seq = MAX(last_acked_seq, excluded.last_acked_seq),
+          last_acked_at = COALESCE(excluded.last_acked_at, last_acked_at),
+          last_registered_at = COALESCE(excluded.last_registered_at, last_registered_at)
+        "#,
+        params![
+            cursor.device_id,
+            cursor.device_type,
+            cursor.last_acked_seq,
+            cursor.last_acked_at,
+            cursor.last_registered_at,
+            now()
+        ],
+    )?;
+    Ok(())
+}
+
+/// Forget every message and cursor, before loading a full copy from the primary
+pub fn clear(conn: &Connection) -> AppResult<()> {
+    conn.execute("DELETE FROM ws_outbox", [])?;
+    conn.execute("DELETE FROM ws_delivery_cursors", [])?;
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use rusqlite::Connection;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// How long SQLite retries a locked database before returning SQLITE_BUSY
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a command waits for a free connection from the pool
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Prepared statements kept per connection (rusqlite LRU cache)
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+
+/// Read connections kept open alongside the single writer
+const MAX_READERS: usize = 4;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PoolError {
+    #[error("failed to open database: {0}")]
+    Open(#[from] rusqlite::Error),
+
+    #[error("timed out waiting for a database connection")]
+    Timeout,
+
+    #[error("database pool is not initialised")]
+    NotInitialized,
+}
+
+/// Open a connection with the pragmas every pooled connection shares
+fn open(path: &Path) -> Result<Connection, PoolError> {
+    let conn = Connection::open(path)?;
+
+    // Mobile-safe pragmas
+    conn.pragma_update(None, "journal_mode", "WAL").ok();
+    conn.pragma_update(None, "synchronous", "NORMAL").ok();
+
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+
+    Ok(conn)
+}
+
+#[derive(Default)]
+struct SlotState {
+    idle: Vec<Connection>,
+    opened: usize,
+}
+
+/// A bounded set of connections handed out one at a time
+struct Slots {
+    state: Mutex<SlotState>,
+    available: Condvar,
+    max: usize,
+}
+
+impl Slots {
+    fn new(max: usize) -> Self {
+        Self {
+            state: Mutex::new(SlotState::default()),
+            available: Condvar::new(),
+            max,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SlotState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn release(&self, conn: Connection) {
+        self.lock().idle.push(conn);
+        self.available.notify_one();
+    }
+
+    /// Give back a slot whose connection could not be opened
+    fn forget(&self) {
+        self.lock().opened -= 1;
+        self.available.notify_one();
+    }
+}
+
+/// A connection borrowed from the pool, returned on drop
+pub struct PooledConnection {
+    conn: Option<Connection>,
+    slots: Arc<Slots>,
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("pooled connection already released")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("pooled connection already released")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.slots.release(conn);
+        }
+    }
+}
+
+/// ==============================
+/// SQLite pool: one writer + readers
+/// ==============================
+///
+/// All writes go through the single writer connection so they queue in the
+/// pool instead of fighting over the WAL write lock. Reads use up to
+/// `MAX_READERS` connections that are opened lazily and kept for reuse.
+#[derive(Clone)]
+pub struct DbPool {
+    path: PathBuf,
+    writer: Arc<Slots>,
+    readers: Arc<Slots>,
+}
+
+impl DbPool {
+    pub fn open(path: PathBuf) -> Result<Self, PoolError> {
+        let pool = Self {
+            path,
+            writer: Arc::new(Slots::new(1)),
+            readers: Arc::new(Slots::new(MAX_READERS)),
+        };
+
+        // Open the writer eagerly so a bad path fails at startup
+        let conn = open(&pool.path)?;
+        pool.writer.lock().opened = 1;
+        pool.writer.release(conn);
+
+        Ok(pool)
+    }
+
+    /// Connection for statements that modify the database
+    pub fn writer(&self) -> Result<PooledConnection, PoolError> {
+        self.acquire(&self.writer)
+    }
+
+    /// Connection for read-only queries
+    pub fn reader(&self) -> Result<PooledConnection, PoolError> {
+        self.acquire(&self.readers)
+    }
+
+    fn acquire(&self, slots: &Arc<Slots>) -> Result<PooledConnection, PoolError> {
+        let mut state = slots.lock();
+
+        loop {
+            if let Some(conn) = state.idle.pop() {
+                return Ok(PooledConnection {
+                    conn: Some(conn),
+                    slots: slots.clone(),
+                });
+            }
+
+            if state.opened < slots.max {
+                state.opened += 1;
+                drop(state);
+
+                return match open(&self.path) {
+                    Ok(conn) => Ok(PooledConnection {
+                        conn: Some(conn),
+                        slots: slots.clone(),
+                    }),
+                    Err(e) => {
+                        slots.forget();
+                        Err(e)
+                    }
+                };
+            }
+
+            let (guard, wait) = slots
+                .available
+                .wait_timeout(state, ACQUIRE_TIMEOUT)
+                .unwrap_or_else(|e| e.into_inner());
+            state = guard;
+
+            if wait.timed_out() && state.idle.is_empty() && state.opened >= slots.max {
+                return Err(PoolError::Timeout);
+            }
+        }
+    }
+
+    /// Flush the WAL into the main database file before the app exits
+    pub fn checkpoint(&self) -> Result<(), PoolError> {
+        let conn = self.writer()?;
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        Ok(())
+    }
+}
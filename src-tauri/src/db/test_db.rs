@@ -0,0 +1,36 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::migrate;
+use super::pool::DbPool;
+
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// A migrated database in a temp file of its own, removed on drop. For
+/// tests that need the real schema and pool.
+pub struct TestDb {
+    pub pool: DbPool,
+    path: PathBuf,
+}
+
+impl TestDb {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "hashtouch-{}-{}-{}.db",
+            name,
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let pool = DbPool::open(path.clone()).unwrap();
+        migrate::run_migrations(&mut pool.writer().unwrap()).unwrap();
+        Self { pool, path }
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.path.display(), suffix));
+        }
+    }
+}
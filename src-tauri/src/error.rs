@@ -0,0 +1,154 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::db::pool::PoolError;
+
+pub type AppResult<T> = Result<T, AppError>;
+
+/// ==============================
+/// Crate-wide error for repos and commands
+/// ==============================
+///
+/// Serialized to the frontend as `{ code, message, retryable }`.
+/// `code` values are stable and safe to branch on in TypeScript.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("{0} not found")]
+    NotFound(String),
+
+    #[error("database is busy, please retry")]
+    DbBusy,
+
+    #[error("database error: {0}")]
+    Database(String),
+
+    #[error("network error: {0}")]
+    Network(String),
+
+    #[error("upstream error{}: {message}", status.map(|s| format!(" ({})", s)).unwrap_or_default())]
+    Upstream { status: Option<u16>, message: String },
+
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+
+    /// The record exists but its state forbids the change (e.g. a closed workday)
+    #[error("conflict: {0}")]
+    Conflict(String),
+
+    /// A device that isn't paired, presented a bad token or was revoked
+    #[error("unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("serialization error: {0}")]
+    Serialization(String),
+
+    #[error("printer unavailable: {0}")]
+    PrinterUnavailable(String),
+
+    #[error("unsupported: {0}")]
+    Unsupported(String),
+
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl AppError {
+    /// Stable machine-readable code
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::DbBusy => "DB_BUSY",
+            AppError::Database(_) => "DB_ERROR",
+            AppError::Network(_) => "NETWORK",
+            AppError::Upstream { .. } => "UPSTREAM",
+            AppError::InvalidInput(_) => "INVALID_INPUT",
+            AppError::Conflict(_) => "CONFLICT",
+            AppError::Unauthorized(_) => "UNAUTHORIZED",
+            AppError::Serialization(_) => "SERIALIZATION",
+            AppError::PrinterUnavailable(_) => "PRINTER_UNAVAILABLE",
+            AppError::Unsupported(_) => "UNSUPPORTED",
+            AppError::Internal(_) => "INTERNAL",
+        }
+    }
+
+    /// Whether retrying the same call later may succeed
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AppError::DbBusy | AppError::Network(_) | AppError::PrinterUnavailable(_) => true,
+            AppError::Upstream { status: Some(s), .. } => *s == 408 || *s == 429 || *s >= 500,
+            _ => false,
+        }
+    }
+
+    pub fn upstream(status: Option<u16>, message: impl Into<String>) -> Self {
+        AppError::Upstream {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("retryable", &self.is_retryable())?;
+        state.end()
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        use rusqlite::ErrorCode;
+
+        match &e {
+            rusqlite::Error::SqliteFailure(err, _)
+                if matches!(err.code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) =>
+            {
+                AppError::DbBusy
+            }
+            rusqlite::Error::QueryReturnedNoRows => AppError::NotFound("record".into()),
+            _ => AppError::Database(e.to_string()),
+        }
+    }
+}
+
+impl From<PoolError> for AppError {
+    fn from(e: PoolError) -> Self {
+        match e {
+            PoolError::Open(e) => e.into(),
+            PoolError::Timeout => AppError::DbBusy,
+            PoolError::NotInitialized => AppError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Serialization(e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            AppError::Serialization(e.to_string())
+        } else if let Some(status) = e.status() {
+            AppError::upstream(Some(status.as_u16()), e.to_string())
+        } else {
+            AppError::Network(e.to_string())
+        }
+    }
+}
+
+impl From<base64::DecodeError> for AppError {
+    fn from(e: base64::DecodeError) -> Self {
+        AppError::InvalidInput(format!("invalid base64 data: {}", e))
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(e: tauri::Error) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
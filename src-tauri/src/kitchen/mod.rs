@@ -0,0 +1,98 @@
+pub mod split;
+
+use rusqlite::Connection;
+use serde_json::Value;
+
+use crate::db::models::device_repo;
+use crate::db::models::kitchen_station_repo;
+use crate::error::AppResult;
+
+/// An order ticket for one KDS
+#[derive(Debug, Clone)]
+pub struct KitchenDelivery {
+    pub device_id: String,
+    pub payload: Value,
+}
+
+/// What each KDS should get for `order`: its station's items, everything
+/// (tagged by station) for an expo, the whole order when it has no station.
+/// `unpaired` are KDS connected without pairing, which can't have one.
+///
+/// None while no paired KDS has a station: every KDS then gets the whole
+/// order, as before stations existed.
+pub fn plan_order(
+    conn: &Connection,
+    order: &Value,
+    unpaired: &[String],
+) -> AppResult<Option<Vec<KitchenDelivery>>> {
+    let devices: Vec<_> = device_repo::get_paired_devices(conn)?
+        .into_iter()
+        .filter(|d| d.role == "KDS" && d.revoked_at.is_none())
+        .collect();
+    if devices.iter().all(|d| d.station_id.is_none()) {
+        return Ok(None);
+    }
+
+    let setup = kitchen_station_repo::get_setup(conn)?;
+    let order = with_categories(conn, order)?;
+    let split = split::split_order(&order, &setup);
+
+    let mut deliveries = Vec::new();
+    for device in devices {
+        let station = device
+            .station_id
+            .as_deref()
+            .and_then(|id| setup.stations.iter().find(|s| s.id == id));
+
+        let payload = match station {
+            Some(station) if station.is_expo => Some(split.expo_ticket(station)),
+            Some(station) => split.station_ticket(&station.id),
+            None => Some(order.clone()),
+        };
+        if let Some(payload) = payload {
+            deliveries.push(KitchenDelivery {
+                device_id: device.id,
+                payload,
+            });
+        }
+    }
+
+    deliveries.extend(unpaired.iter().map(|id| KitchenDelivery {
+        device_id: id.clone(),
+        payload: order.clone(),
+    }));
+
+    Ok(Some(deliveries))
+}
+
+/// `order` with `category_id` filled in from the local catalog for items
+/// that only carry a `product_id`
+fn with_categories(conn: &Connection, order: &Value) -> AppResult<Value> {
+    let mut order = order.clone();
+    let Some(items) = order["items"].as_array_mut() else {
+        return Ok(order);
+    };
+
+    let missing: Vec<String> = items
+        .iter()
+        .filter(|item| item["category_id"].as_str().is_none())
+        .filter_map(|item| item["product_id"].as_str().map(str::to_string))
+        .collect();
+    if missing.is_empty() {
+        return Ok(order);
+    }
+
+    let ids: Vec<&str> = missing.iter().map(String::as_str).collect();
+    let categories = kitchen_station_repo::product_categories(conn, &ids)?;
+
+    for item in items.iter_mut() {
+        if item["category_id"].as_str().is_some() {
+            continue;
+        }
+        let category = item["product_id"].as_str().and_then(|id| categories.get(id)).cloned();
+        if let (Some(category), Value::Object(map)) = (category, item) {
+            map.insert("category_id".into(), Value::String(category));
+        }
+    }
+    Ok(order)
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use crate::db::models::kitchen_station::{KitchenSetup, KitchenStation, TARGET_CATEGORY, TARGET_PRODUCT};
+
+/// ==============================
+/// Order split
+/// ==============================
+///
+/// An order's items grouped by kitchen station. An item goes to the station
+/// mapped to its product; failing that, to its category. Items with neither
+/// belong to no station: only expo and station-less screens show them.
+pub struct OrderSplit<'a> {
+    order: &'a Value,
+    /// Every item, tagged with `station_id` / `station_name` (null if none)
+    items: Vec<Value>,
+    /// Prep stations with items in this order, in station order
+    stations: Vec<(&'a KitchenStation, Vec<Value>)>,
+}
+
+pub fn split_order<'a>(order: &'a Value, setup: &'a KitchenSetup) -> OrderSplit<'a> {
+    let stations: HashMap<&str, &KitchenStation> = setup
+        .stations
+        .iter()
+        .filter(|s| !s.is_expo)
+        .map(|s| (s.id.as_str(), s))
+        .collect();
+    let mapped = |target_type: &str, target_id: Option<&str>| {
+        let target_id = target_id?;
+        setup
+            .mappings
+            .iter()
+            .find(|m| m.target_type == target_type && m.target_id == target_id)
+            .and_then(|m| stations.get(m.station_id.as_str()).copied())
+    };
+
+    let mut items = Vec::new();
+    let mut by_station: HashMap<&str, Vec<Value>> = HashMap::new();
+
+    for item in order["items"].as_array().into_iter().flatten() {
+        let station = mapped(TARGET_PRODUCT, item["product_id"].as_str())
+            .or_else(|| mapped(TARGET_CATEGORY, item["category_id"].as_str()));
+
+        let mut tagged = item.clone();
+        if let Value::Object(map) = &mut tagged {
+            map.insert("station_id".into(), json!(station.map(|s| &s.id)));
+            map.insert("station_name".into(), json!(station.map(|s| &s.name)));
+        }
+
+        if let Some(station) = station {
+            by_station.entry(&station.id).or_default().push(item.clone());
+        }
+        items.push(tagged);
+    }
+
+    let stations = setup
+        .stations
+        .iter()
+        .filter_map(|s| by_station.remove(s.id.as_str()).map(|items| (s, items)))
+        .collect();
+
+    OrderSplit { order, items, stations }
+}
+
+impl OrderSplit<'_> {
+    /// The ticket a prep station's screen gets: only its items, or None
+    /// when this order has none for it
+    pub fn station_ticket(&self, station_id: &str) -> Option<Value> {
+        let (station, items) = self.stations.iter().find(|(s, _)| s.id == station_id)?;
+        Some(self.ticket(station, items.clone(), None))
+    }
+
+    /// The ticket an expo screen gets: every item tagged with its station,
+    /// plus what each station has to make
+    pub fn expo_ticket(&self, expo: &KitchenStation) -> Value {
+        let stations: Vec<Value> = self
+            .stations
+            .iter()
+            .map(|(s, items)| {
+                json!({
+                    "station_id": s.id,
+                    "station_name": s.name,
+                    "item_count": items.len(),
+                })
+            })
+            .collect();
+        self.ticket(expo, self.items.clone(), Some(stations))
+    }
+
+    fn ticket(&self, station: &KitchenStation, items: Vec<Value>, stations: Option<Vec<Value>>) -> Value {
+        let mut ticket = self.order.clone();
+        if let Value::Object(map) = &mut ticket {
+            // One KdsTicket per station, so each screen can bump its own
+            let kds_ticket_id = match map.get("ticket_id").and_then(Value::as_str) {
+                Some(ticket_id) => format!("{}:{}", ticket_id, station.id),
+                None => station.id.clone(),
+            };
+            map.insert("kds_ticket_id".into(), json!(kds_ticket_id));
+            map.insert("station_id".into(), json!(station.id));
+            map.insert("station_name".into(), json!(station.name));
+            map.insert("items".into(), Value::Array(items));
+            if let Some(stations) = stations {
+                map.insert("stations".into(), Value::Array(stations));
+            }
+        }
+        ticket
+    }
+}
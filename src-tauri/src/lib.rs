@@ -1,6 +1,13 @@
 mod db;
+mod error;
 mod commands;
+// Splitting orders by kitchen station is the hub's job (not iOS)
+#[cfg(any(desktop, target_os = "android"))]
+mod kitchen;
+mod money;
 mod printer;
+mod pricing;
+mod sync;
 
 // WebSocket module - used on desktop and Android (not iOS)
 #[cfg(any(desktop, target_os = "android"))]
@@ -12,12 +19,18 @@ use std::sync::Arc;
 #[cfg(any(desktop, target_os = "android"))]
 use tokio::sync::mpsc;
 #[cfg(any(desktop, target_os = "android"))]
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 #[cfg(any(desktop, target_os = "android"))]
 use websocket::WebSocketServer;
 #[cfg(any(desktop, target_os = "android"))]
+use websocket::client::{ClientEvent, HubClient};
+#[cfg(any(desktop, target_os = "android"))]
 use websocket::event_bus::EventBus;
 #[cfg(any(desktop, target_os = "android"))]
+use websocket::routing::RouteTable;
+#[cfg(any(desktop, target_os = "android"))]
+use websocket::standby::HubRole;
+#[cfg(any(desktop, target_os = "android"))]
 use websocket::ws_routes::register_ws_routes;
 
 
@@ -26,6 +39,11 @@ use websocket::ws_routes::register_ws_routes;
 #[derive(Clone)]
 pub struct WsState {
     pub server: Arc<WebSocketServer>,
+    /// Connection to the POS hub when running as KDS/QUEUE (or standby POS)
+    pub client: HubClient,
+    pub hub_role: Arc<std::sync::Mutex<HubRole>>,
+    /// Where the hub forwards device messages (`ws_routes` setting)
+    pub routes: Arc<std::sync::RwLock<RouteTable>>,
 }
 
 #[cfg(any(desktop, target_os = "android"))]
@@ -50,22 +68,138 @@ pub fn run() {
             }
 
             // Init DB (must already be sandbox-safe for iOS)
-            db::init(app.handle());
+            db::init(app.handle())?;
+
+            // Amounts in SQLite are minor units of the selected setup's currency
+            match db::reader(app.handle()) {
+                Ok(conn) => {
+                    if let Err(e) = money::currency::load_store_currency(&conn) {
+                        log::warn!("⚠️ Could not load the store currency: {}", e);
+                    }
+                }
+                Err(e) => log::warn!("⚠️ Could not load the store currency: {}", e),
+            }
+
+            // Background ticket/workday upload
+            sync::start(app.handle(), sync::SyncConfig::from_env())?;
+
+            // Print queue: retries, backup printers, job events
+            printer::spooler::start(app.handle(), printer::spooler::SpoolConfig::default())?;
+
+            // Paper/cover/offline status of the active printers
+            printer::monitor::start(app.handle(), printer::monitor::CHECK_INTERVAL);
 
             // ==============================
             // DESKTOP & ANDROID: WebSocket + EventBus
             // ==============================
             #[cfg(any(desktop, target_os = "android"))]
             {
-                let (event_tx, event_rx) = mpsc::unbounded_channel();
+                let limits = websocket::limits::LimitsConfig::from_env();
+                let (event_tx, event_rx) = mpsc::channel(limits.event_queue);
 
                 let event_bus = EventBus::new(event_rx);
                 let event_bus_clone = event_bus.clone();
 
-                let ws_server = Arc::new(WebSocketServer::new(event_tx));
+                // Check device role - POS devices run as server
+                let (device_role, tls_enabled, hub_standby, routes) = match db::reader(app.handle())
+                    .map_err(error::AppError::from)
+                    .and_then(|conn| db::models::app_state_repo::get_app_state(&conn))
+                {
+                    Ok(state) => (
+                        state.device_role.clone(),
+                        state.ws_tls_enabled.unwrap_or(0) == 1,
+                        state.ws_hub_standby.unwrap_or(0) == 1,
+                        load_routes(&state),
+                    ),
+                    Err(e) => {
+                        log::warn!(
+                            "⚠️ Failed to read device role: {}, will start server when role is set",
+                            e
+                        );
+                        (None, false, false, RouteTable::default())
+                    }
+                };
+
+                // Self-signed hub certificate for wss://, generated on first use.
+                // A standby gets the primary's when (if) it takes over.
+                let tls_dir = app.path().app_data_dir()?.join("ws_tls");
+                let tls = if tls_enabled && !hub_standby && device_role.as_deref() == Some("POS") {
+                    hub_tls(&tls_dir)
+                } else {
+                    None
+                };
+
+                let ws_server = Arc::new(WebSocketServer::new(
+                    event_tx,
+                    db::pool(app.handle())?,
+                    tls,
+                    websocket::presence::HeartbeatConfig::from_env(),
+                    limits,
+                ));
+
+                // Forward device connect/disconnect to the frontend
+                let mut presence = ws_server.subscribe_presence();
+                let presence_app = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    use tokio::sync::broadcast::error::RecvError;
+                    loop {
+                        match presence.recv().await {
+                            Ok(ev) => {
+                                let _ = presence_app.emit(ev.event_name(), &ev);
+                            }
+                            Err(RecvError::Lagged(n)) => {
+                                log::warn!("⚠️ Dropped {} presence events", n);
+                            }
+                            Err(RecvError::Closed) => break,
+                        }
+                    }
+                });
+
+                let hub_client = HubClient::new(
+                    Default::default(),
+                    websocket::presence::HeartbeatConfig::from_env(),
+                );
+
+                // Forward hub messages and connection state to the frontend
+                let mut client_events = hub_client.subscribe();
+                let client_app = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    use tokio::sync::broadcast::error::RecvError;
+                    loop {
+                        match client_events.recv().await {
+                            Ok(ev) => {
+                                let _ = match &ev {
+                                    ClientEvent::Message(msg) => client_app.emit(ev.event_name(), msg),
+                                    ClientEvent::Status(status) => client_app.emit(ev.event_name(), status),
+                                    ClientEvent::HubMoved(url) => {
+                                        // Keep the new address for the next start
+                                        let saved = db::writer(&client_app)
+                                            .map_err(error::AppError::from)
+                                            .and_then(|conn| {
+                                                db::models::app_state_repo::update_app_state(&conn, "ws_server_url", url)
+                                            });
+                                        if let Err(e) = saved {
+                                            log::warn!("⚠️ Failed to save the new hub URL: {}", e);
+                                        }
+                                        client_app.emit(ev.event_name(), url)
+                                    }
+                                    // Handled by the standby loop; holds device tokens
+                                    ClientEvent::Mirror(_) => Ok(()),
+                                };
+                            }
+                            Err(RecvError::Lagged(n)) => {
+                                log::warn!("⚠️ Dropped {} hub client events", n);
+                            }
+                            Err(RecvError::Closed) => break,
+                        }
+                    }
+                });
 
                 let ws_state = WsState {
                     server: ws_server.clone(),
+                    client: hub_client.clone(),
+                    hub_role: Arc::new(std::sync::Mutex::new(HubRole::None)),
+                    routes: Arc::new(std::sync::RwLock::new(routes)),
                 };
 
                 app.manage(ws_state.clone());
@@ -86,43 +220,69 @@ pub fn run() {
                     event_bus_clone.start().await;
                 });
 
-                // Check device role - POS devices run as server
-                let conn = db::migrate::connection(app.handle());
-                let device_role = match db::models::app_state_repo::get_app_state(&conn) {
-                    Ok(state) => state.device_role.clone(),
-                    Err(e) => {
-                        log::warn!(
-                            "⚠️ Failed to read device role: {}, will start server when role is set",
-                            e
-                        );
-                        None
-                    }
-                };
-
                 if let Some(role) = device_role {
-                    if role == "POS" {
-                        let ws_addr = "0.0.0.0:9001";
+                    if role == "POS" && hub_standby {
+                        log::info!("🪞 Standby POS - mirroring the hub, ready to take over");
+                        set_hub_role(app.handle(), HubRole::Standby);
+
+                        let primary = db::reader(app.handle())
+                            .map_err(error::AppError::from)
+                            .and_then(|conn| db::models::app_state_repo::get_app_state(&conn))
+                            .and_then(|state| websocket::client::HubConfig::from_app_state(&state, "POS", None));
+
+                        match primary {
+                            // Without a certificate a restarted primary couldn't
+                            // tell this POS took over, and both would serve
+                            Ok(config) if config.fingerprint.is_none() => {
+                                log::error!("❌ Standby needs a wss:// primary, {} is not pinned", config.url);
+                            }
+                            Ok(config) => {
+                                let config = websocket::client::HubConfig { standby: true, ..config };
+                                let pool = db::pool(app.handle())?;
+                                let settings = websocket::standby::StandbyConfig::from_env(
+                                    &websocket::presence::HeartbeatConfig::from_env(),
+                                );
+                                let app_handle = app.handle().clone();
+
+                                tauri::async_runtime::spawn(async move {
+                                    match websocket::standby::follow(&hub_client, config, pool, tls_dir.clone(), settings).await {
+                                        Ok(()) => {
+                                            promote(&app_handle, &ws_server, &tls_dir);
+                                            serve_hub(app_handle, ws_server, HubRole::Promoted).await;
+                                        }
+                                        Err(e) => log::error!("❌ Standby stopped: {}", e),
+                                    }
+                                });
+                            }
+                            Err(e) => log::warn!("⚠️ Standby POS is not paired with a primary hub: {}", e),
+                        }
+                    } else if role == "POS" {
                         log::info!(
-                            "🔧 POS device detected - Starting WebSocket server on {}",
-                            ws_addr
+                            "🔧 POS device detected - Starting WebSocket server on port {}",
+                            websocket::HUB_PORT
                         );
-
-                        tauri::async_runtime::spawn(async move {
-                            match ws_server.start(ws_addr).await {
-                                Ok(_) => log::info!("✅ WebSocket server stopped gracefully"),
-                                Err(e) => {
-                                    log::error!(
-                                        "❌ WebSocket server FAILED to start: {}",
-                                        e
-                                    );
-                                }
-                            }
-                        });
+                        tauri::async_runtime::spawn(serve_hub(app.handle().clone(), ws_server, HubRole::Primary));
                     } else {
                         log::info!(
                             "🔴 Non-POS device ({}) - Running as client only",
                             role
                         );
+
+                        // Paired devices connect from Rust right away, before the UI loads
+                        let paired = db::reader(app.handle())
+                            .map_err(error::AppError::from)
+                            .and_then(|conn| db::models::app_state_repo::get_app_state(&conn))
+                            .ok()
+                            .filter(|state| state.ws_device_token.is_some())
+                            .and_then(|state| {
+                                websocket::client::HubConfig::from_app_state(&state, &role, None).ok()
+                            });
+
+                        if let Some(config) = paired {
+                            tauri::async_runtime::spawn(async move {
+                                hub_client.start(config);
+                            });
+                        }
                     }
                 }
             }
@@ -150,6 +310,7 @@ pub fn run() {
         // App state
         commands::app_state::get_app_state,
         commands::app_state::clear_app_state,
+        commands::app_state::set_ws_credentials,
         commands::app_state::clear_all_data,
         commands::app_state::set_tenant,
         commands::app_state::set_location,
@@ -163,6 +324,8 @@ pub fn run() {
         commands::app_state::set_kds_view_mode,
         commands::app_state::get_ws_settings,
         commands::app_state::set_ws_server_mode,
+        commands::app_state::set_ws_tls_enabled,
+        commands::app_state::set_ws_hub_standby,
         commands::app_state::set_ws_server_url,
         commands::app_state::get_local_ip,
         commands::app_state::set_setup_code,
@@ -172,6 +335,7 @@ pub fn run() {
 
         // Device
         commands::device::get_devices,
+        commands::device::get_paired_devices,
         commands::device::save_device,
         commands::device::get_device,
 
@@ -220,6 +384,10 @@ pub fn run() {
         commands::work_shift::get_work_shift_draft,
         commands::work_shift::save_work_shift_draft,
         commands::work_shift::clear_work_shift_draft,
+        commands::work_shift::start_shift,
+        commands::work_shift::end_shift,
+        commands::work_shift::get_open_shift,
+        commands::work_shift::get_workday_shifts,
 
         // Workday
         commands::workday::save_workday,
@@ -234,6 +402,8 @@ pub fn run() {
         commands::workday::get_workdays_by_date_range,
         commands::workday::get_workdays_by_location,
         commands::workday::clear_all_workdays,
+        commands::workday::get_x_report,
+        commands::workday::close_workday,
 
         // Charges
         commands::charges::save_charges,
@@ -241,6 +411,7 @@ pub fn run() {
         commands::charges::get_charges,
         commands::charges::get_charge_mappings,
         commands::charges::clear_charges_cache,
+        commands::charges::calculate_charges,
 
         // Payment Methods
         commands::payment_method::save_payment_methods,
@@ -262,6 +433,13 @@ pub fn run() {
         commands::ticket::clear_all_tickets,
         commands::ticket::get_max_queue_number,
         commands::ticket::update_ticket_order_status,
+        commands::ticket::get_ticket_detail,
+        commands::ticket::get_product_sales,
+        commands::ticket::get_payment_totals,
+
+        // Sync worker
+        commands::sync::sync_now,
+        commands::sync::requeue_dead_letters,
 
         // KDS Tickets
         commands::kds_ticket::save_kds_ticket,
@@ -279,14 +457,51 @@ pub fn run() {
         commands::printer::delete_printer,
         commands::printer::set_printer_active,
         commands::printer::test_printer,
+        commands::printer::check_printer_status,
+        commands::printer::list_printer_devices,
         commands::printer::print_raw,
         commands::printer::print_raw_to_all_active,
+        commands::printer::print_receipt,
+        commands::printer::render_receipt,
+        commands::printer::print_document,
+        commands::printer::render_document,
+        commands::printer::get_print_jobs,
+        commands::printer::reprint_print_job,
+        commands::printer::cancel_print_job,
+        commands::print_template::get_print_templates,
+        commands::print_template::get_print_template,
+        commands::print_template::save_print_template,
+        commands::print_template::delete_print_template,
+        commands::print_template::preview_print_template,
 
         // WebSocket (desktop & Android)
         commands::websocket::broadcast_to_kds,
         commands::websocket::broadcast_to_queue,
         commands::websocket::broadcast_to_pos,
         commands::websocket::broadcast_order,
+        commands::websocket::create_pairing_code,
+        commands::websocket::revoke_device,
+        commands::websocket::pair_with_hub,
+        commands::websocket::get_delivery_status,
+        commands::websocket::get_connected_devices,
+        commands::websocket::start_hub_client,
+        commands::websocket::stop_hub_client,
+        commands::websocket::get_hub_client_status,
+        commands::websocket::send_to_hub,
+        commands::websocket::get_hub_messages,
+        commands::websocket::discover_hubs,
+        commands::websocket::auto_select_hub,
+        commands::websocket::get_hub_role,
+        commands::websocket::get_ws_metrics,
+        commands::websocket::get_ws_routes,
+        commands::websocket::set_ws_routes,
+
+        // Kitchen stations (hub)
+        commands::kitchen_station::get_kitchen_setup,
+        commands::kitchen_station::save_kitchen_station,
+        commands::kitchen_station::delete_kitchen_station,
+        commands::kitchen_station::set_station_mapping,
+        commands::kitchen_station::set_device_station,
 
         // Queue token
         commands::queue_token::save_queue_token,
@@ -313,6 +528,7 @@ pub fn run() {
         // App state
         commands::app_state::get_app_state,
         commands::app_state::clear_app_state,
+        commands::app_state::set_ws_credentials,
         commands::app_state::clear_all_data,
         commands::app_state::set_tenant,
         commands::app_state::set_location,
@@ -326,6 +542,8 @@ pub fn run() {
         commands::app_state::set_kds_view_mode,
         commands::app_state::get_ws_settings,
         commands::app_state::set_ws_server_mode,
+        commands::app_state::set_ws_tls_enabled,
+        commands::app_state::set_ws_hub_standby,
         commands::app_state::set_ws_server_url,
         commands::app_state::get_local_ip,
         commands::app_state::set_setup_code,
@@ -335,6 +553,7 @@ pub fn run() {
 
         // Device
         commands::device::get_devices,
+        commands::device::get_paired_devices,
         commands::device::save_device,
         commands::device::get_device,
 
@@ -383,6 +602,10 @@ pub fn run() {
         commands::work_shift::get_work_shift_draft,
         commands::work_shift::save_work_shift_draft,
         commands::work_shift::clear_work_shift_draft,
+        commands::work_shift::start_shift,
+        commands::work_shift::end_shift,
+        commands::work_shift::get_open_shift,
+        commands::work_shift::get_workday_shifts,
 
         // Workday
         commands::workday::save_workday,
@@ -397,6 +620,8 @@ pub fn run() {
         commands::workday::get_workdays_by_date_range,
         commands::workday::get_workdays_by_location,
         commands::workday::clear_all_workdays,
+        commands::workday::get_x_report,
+        commands::workday::close_workday,
 
         // Charges
         commands::charges::save_charges,
@@ -404,6 +629,7 @@ pub fn run() {
         commands::charges::get_charges,
         commands::charges::get_charge_mappings,
         commands::charges::clear_charges_cache, 
+        commands::charges::calculate_charges,
 
         // Payment Methods
         commands::payment_method::save_payment_methods,
@@ -425,6 +651,13 @@ pub fn run() {
         commands::ticket::clear_all_tickets,
         commands::ticket::get_max_queue_number,
         commands::ticket::update_ticket_order_status,
+        commands::ticket::get_ticket_detail,
+        commands::ticket::get_product_sales,
+        commands::ticket::get_payment_totals,
+
+        // Sync worker
+        commands::sync::sync_now,
+        commands::sync::requeue_dead_letters,
 
         // KDS Tickets
         commands::kds_ticket::save_kds_ticket,
@@ -442,8 +675,22 @@ pub fn run() {
         commands::printer::delete_printer,
         commands::printer::set_printer_active,
         commands::printer::test_printer,
+        commands::printer::check_printer_status,
+        commands::printer::list_printer_devices,
         commands::printer::print_raw,
         commands::printer::print_raw_to_all_active,
+        commands::printer::print_receipt,
+        commands::printer::render_receipt,
+        commands::printer::print_document,
+        commands::printer::render_document,
+        commands::printer::get_print_jobs,
+        commands::printer::reprint_print_job,
+        commands::printer::cancel_print_job,
+        commands::print_template::get_print_templates,
+        commands::print_template::get_print_template,
+        commands::print_template::save_print_template,
+        commands::print_template::delete_print_template,
+        commands::print_template::preview_print_template,
 
         // Queue token
         commands::queue_token::save_queue_token,
@@ -461,6 +708,127 @@ pub fn run() {
     ]);
 
     builder
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                db::shutdown(app_handle);
+            }
+        });
+}
+
+/// Load (or create) the hub certificate kept in `dir`
+#[cfg(any(desktop, target_os = "android"))]
+fn hub_tls(dir: &std::path::Path) -> Option<websocket::tls::ServerTls> {
+    match websocket::tls::load_or_create(dir) {
+        Ok(tls) => Some(tls),
+        Err(e) => {
+            log::error!("❌ WebSocket TLS unavailable, serving plain ws://: {}", e);
+            None
+        }
+    }
+}
+
+/// The saved route table; the defaults if it no longer parses, so a bad
+/// edit can't stop orders reaching the POS
+#[cfg(any(desktop, target_os = "android"))]
+fn load_routes(state: &db::models::app_state::AppState) -> RouteTable {
+    RouteTable::from_app_state(state).unwrap_or_else(|e| {
+        log::error!("❌ Invalid ws_routes, using the default routes: {}", e);
+        RouteTable::default()
+    })
+}
+
+#[cfg(any(desktop, target_os = "android"))]
+fn set_hub_role(app: &tauri::AppHandle, role: HubRole) {
+    let state = app.state::<WsState>();
+    *state.hub_role.lock().unwrap_or_else(|e| e.into_inner()) = role;
+    let _ = app.emit(websocket::standby::EVENT_HUB_ROLE, role);
+}
+
+/// A standby taking over: serve with the certificate mirrored from the
+/// primary, and stay the hub after a restart
+#[cfg(any(desktop, target_os = "android"))]
+fn promote(app: &tauri::AppHandle, ws_server: &WebSocketServer, tls_dir: &std::path::Path) {
+    let state = db::reader(app)
+        .map_err(error::AppError::from)
+        .and_then(|conn| db::models::app_state_repo::get_app_state(&conn));
+    let tls_enabled = state
+        .as_ref()
+        .map(|state| state.ws_tls_enabled.unwrap_or(0) == 1)
+        .unwrap_or(false);
+    ws_server.set_tls(if tls_enabled { hub_tls(tls_dir) } else { None });
+
+    // Route like the primary did
+    if let Ok(state) = &state {
+        let ws_state = app.state::<WsState>();
+        *ws_state.routes.write().unwrap_or_else(|e| e.into_inner()) = load_routes(state);
+    }
+
+    let saved = db::writer(app)
+        .map_err(error::AppError::from)
+        .and_then(|conn| db::models::app_state_repo::update_app_state(&conn, "ws_hub_standby", "0"));
+    if let Err(e) = saved {
+        log::warn!("⚠️ Failed to save the hub takeover: {}", e);
+    }
+}
+
+/// Advertise the hub over mDNS and run the WebSocket server until it stops
+#[cfg(any(desktop, target_os = "android"))]
+async fn serve_hub(app: tauri::AppHandle, ws_server: Arc<WebSocketServer>, role: HubRole) {
+    // A standby that took over while this POS was down serves our
+    // certificate now; serving it too would split the kitchen in two.
+    // Standbys only follow wss:// hubs, so a ws:// one has no successor.
+    if role == HubRole::Primary {
+        if let Some(fingerprint) = ws_server.tls_fingerprint() {
+            if let Some(hub) = websocket::standby::find_successor(&fingerprint).await {
+                log::error!(
+                    "⛔ {} at {} already serves this hub, not starting the WebSocket server",
+                    hub.name,
+                    hub.url
+                );
+                set_hub_role(&app, HubRole::Superseded);
+                return;
+            }
+        }
+    }
+    set_hub_role(&app, role);
+
+    // Let KDS/QUEUE screens find this hub without typing its IP
+    let ws_tls_fingerprint = ws_server.tls_fingerprint();
+    let advert = db::reader(&app)
+        .map_err(error::AppError::from)
+        .and_then(|conn| {
+            let state = db::models::app_state_repo::get_app_state(&conn)?;
+            let suffix = db::models::device_repo::random_hex(&conn, 4)?;
+            websocket::discovery::advertise(
+                websocket::HUB_PORT,
+                &websocket::discovery::HubAdvert {
+                    name: format!("HashTouch POS {}", suffix),
+                    location_id: state.selected_location_id,
+                    brand_id: state.brand_id,
+                    tls: ws_tls_fingerprint.is_some(),
+                    fingerprint: ws_tls_fingerprint.clone(),
+                },
+            )
+        });
+    // Advertised for as long as the server runs
+    let _advert = match advert {
+        Ok(advert) => Some(advert),
+        Err(e) => {
+            log::warn!("⚠️ Hub not advertised over mDNS: {}", e);
+            None
+        }
+    };
+
+    let ws_addr = format!("0.0.0.0:{}", websocket::HUB_PORT);
+    match ws_server.start(&ws_addr).await {
+        Ok(_) => log::info!("✅ WebSocket server stopped gracefully"),
+        Err(e) => {
+            log::error!(
+                "❌ WebSocket server FAILED to start: {}",
+                e
+            );
+        }
+    }
 }
@@ -0,0 +1,128 @@
+use std::fmt;
+use std::sync::RwLock;
+
+use rusqlite::Connection;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::db::models::{app_state_repo, setup_repo};
+use crate::error::{AppError, AppResult};
+
+/// ISO 4217 currency and the number of decimals of its minor unit
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Currency {
+    code: [u8; 3],
+    exponent: u8,
+}
+
+impl Currency {
+    /// Used when no setup (or no `currency_code`) is stored, same as SetupContext
+    pub const USD: Currency = Currency { code: *b"USD", exponent: 2 };
+
+    pub fn from_code(code: &str) -> AppResult<Self> {
+        let upper = code.trim().to_ascii_uppercase();
+
+        let bytes: [u8; 3] = upper
+            .as_bytes()
+            .try_into()
+            .ok()
+            .filter(|b: &[u8; 3]| b.iter().all(u8::is_ascii_uppercase))
+            .ok_or_else(|| AppError::InvalidInput(format!("invalid currency code: {:?}", code)))?;
+
+        Ok(Self {
+            code: bytes,
+            exponent: minor_units(&upper),
+        })
+    }
+
+    pub fn code(&self) -> &str {
+        // Only ever built from three ASCII letters
+        std::str::from_utf8(&self.code).unwrap_or("XXX")
+    }
+
+    /// Decimals of the minor unit: 2 for USD, 0 for JPY, 3 for KWD
+    pub fn exponent(&self) -> u32 {
+        self.exponent as u32
+    }
+
+    /// Minor units per major unit
+    pub fn factor(&self) -> i64 {
+        10i64.pow(self.exponent())
+    }
+}
+
+/// ISO 4217 minor units. Anything not listed uses 2.
+fn minor_units(code: &str) -> u8 {
+    match code {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX"
+        | "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        "CLF" | "UYW" => 4,
+        _ => 2,
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl fmt::Debug for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(d)?;
+        Currency::from_code(&code).map_err(serde::de::Error::custom)
+    }
+}
+
+/// ==============================
+/// Store currency
+/// ==============================
+///
+/// Amounts in SQLite and in the webview payloads don't repeat their currency:
+/// they are in the currency of the selected setup. It's loaded at startup and
+/// again whenever the setups or the selected setup change.
+static STORE_CURRENCY: RwLock<Currency> = RwLock::new(Currency::USD);
+
+pub fn store_currency() -> Currency {
+    *STORE_CURRENCY.read().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn set_store_currency(currency: Currency) {
+    *STORE_CURRENCY.write().unwrap_or_else(|e| e.into_inner()) = currency;
+}
+
+/// Read `currency_code` of the setup selected in app_state and make it the
+/// store currency
+pub fn load_store_currency(conn: &Connection) -> AppResult<Currency> {
+    let setup = match app_state_repo::get_app_state(conn)?.setup_code {
+        Some(code) => setup_repo::get_setup_by_code(conn, &code)?,
+        None => None,
+    };
+
+    let currency = match setup.and_then(|s| s.currency_code) {
+        Some(code) if !code.trim().is_empty() => Currency::from_code(&code).unwrap_or_else(|e| {
+            log::warn!("⚠️ {}, falling back to {}", e, Currency::USD);
+            Currency::USD
+        }),
+        _ => Currency::USD,
+    };
+
+    if currency != store_currency() {
+        log::info!("💱 Store currency: {}", currency);
+    }
+
+    set_store_currency(currency);
+    Ok(currency)
+}
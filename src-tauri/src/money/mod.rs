@@ -0,0 +1,395 @@
+pub mod currency;
+pub mod rate;
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::error::{AppError, AppResult};
+pub use currency::{store_currency, Currency};
+pub use rate::Rate;
+
+/// How a fraction of a minor unit is resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    /// Half a unit goes away from zero (1.005 → 1.01, -1.005 → -1.01)
+    #[default]
+    HalfUp,
+    /// Half a unit goes to the even neighbour (1.005 → 1.00, 1.015 → 1.02)
+    HalfEven,
+    /// The fraction is dropped (1.009 → 1.00)
+    TowardZero,
+}
+
+impl Rounding {
+    /// `value / divisor` as a whole number. `divisor` must be positive.
+    pub(crate) fn div(self, value: i128, divisor: i128) -> i128 {
+        let quotient = value / divisor;
+        let remainder = (value % divisor).abs();
+
+        if remainder == 0 {
+            return quotient;
+        }
+
+        let away = quotient + value.signum();
+        let twice = remainder * 2;
+
+        match self {
+            Rounding::TowardZero => quotient,
+            Rounding::HalfUp if twice >= divisor => away,
+            Rounding::HalfUp => quotient,
+            Rounding::HalfEven if twice > divisor => away,
+            Rounding::HalfEven if twice == divisor && quotient % 2 != 0 => away,
+            Rounding::HalfEven => quotient,
+        }
+    }
+}
+
+/// ==============================
+/// Money
+/// ==============================
+///
+/// A whole number of minor units (cents, fils, yen) of one currency. This is
+/// what SQLite stores. The webview and the tenant API keep using decimal
+/// major units (`12.5`), so that is what goes over serde.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Money {
+    minor: i64,
+    currency: Currency,
+}
+
+impl Money {
+    pub fn new(minor: i64, currency: Currency) -> Self {
+        Self { minor, currency }
+    }
+
+    /// Minor units of the store currency
+    pub fn from_minor(minor: i64) -> Self {
+        Self::new(minor, store_currency())
+    }
+
+    pub fn zero() -> Self {
+        Self::from_minor(0)
+    }
+
+    /// From a floating point major amount. The value is snapped to a
+    /// millionth of a minor unit first, so 1.005 (stored as 1.00499…) still
+    /// counts as half a cent.
+    pub fn from_major(amount: f64, currency: Currency, rounding: Rounding) -> Self {
+        let micro = (amount * currency.factor() as f64 * 1e6).round() as i128;
+        Self::from_scaled(micro, 1_000_000, currency, rounding)
+    }
+
+    /// Exact decimal string (`"12.50"`, `"-3"`) in major units
+    pub fn parse(s: &str, currency: Currency, rounding: Rounding) -> AppResult<Self> {
+        let (value, scale) = parse_decimal(s)
+            .ok_or_else(|| AppError::InvalidInput(format!("invalid amount: {:?}", s)))?;
+
+        Ok(Self::from_scaled(value * currency.factor() as i128, scale, currency, rounding))
+    }
+
+    /// `value / scale` minor units, rounded. Saturates at the i64 range.
+    pub fn from_scaled(value: i128, scale: i128, currency: Currency, rounding: Rounding) -> Self {
+        let minor = rounding.div(value, scale);
+        let minor = minor.clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+        Self::new(minor, currency)
+    }
+
+    pub fn minor(self) -> i64 {
+        self.minor
+    }
+
+    pub fn currency(self) -> Currency {
+        self.currency
+    }
+
+    /// For display and for APIs that want a JSON number. Not for arithmetic.
+    pub fn to_major(self) -> f64 {
+        self.minor as f64 / self.currency.factor() as f64
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.minor == 0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.minor < 0
+    }
+
+    pub fn abs(self) -> Self {
+        Self::new(self.minor.abs(), self.currency)
+    }
+
+    /// Unit price × quantity (quantities can be fractional, e.g. weighed items)
+    pub fn times(self, quantity: f64, rounding: Rounding) -> Self {
+        let quantity = (quantity * 1e6).round() as i128;
+        Self::from_scaled(self.minor as i128 * quantity, 1_000_000, self.currency, rounding)
+    }
+
+    /// `rate` percent of this amount
+    pub fn percent(self, rate: Rate, rounding: Rounding) -> Self {
+        Self::from_scaled(
+            self.minor as i128 * rate.scaled() as i128,
+            100 * rate::SCALE as i128,
+            self.currency,
+            rounding,
+        )
+    }
+
+    /// Same amount in another currency's minor units (no exchange rate)
+    pub fn with_currency(self, currency: Currency, rounding: Rounding) -> Self {
+        Self::from_scaled(
+            self.minor as i128 * currency.factor() as i128,
+            self.currency.factor() as i128,
+            currency,
+            rounding,
+        )
+    }
+
+    /// Adding zero keeps the other side's currency, so sums can start from
+    /// `Money::zero()` whatever the store currency is
+    fn combine(self, rhs: Self, minor: i64) -> Self {
+        debug_assert!(
+            self.currency == rhs.currency || self.minor == 0 || rhs.minor == 0,
+            "mixing {} and {}",
+            self.currency,
+            rhs.currency
+        );
+
+        let currency = if self.minor == 0 { rhs.currency } else { self.currency };
+        Self::new(minor, currency)
+    }
+}
+
+/// `"-12.50"` → `(-1250, 100)`: the digits as an integer and the power of
+/// ten to divide them by
+pub(crate) fn parse_decimal(s: &str) -> Option<(i128, i128)> {
+    let s = s.trim();
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    let digits = whole.len() + fraction.len();
+
+    if digits == 0
+        || digits > 30
+        || !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let value: i128 = format!("{}{}", whole, fraction).parse().ok()?;
+    let scale = 10i128.pow(fraction.len() as u32);
+
+    Some((if negative { -value } else { value }, scale))
+}
+
+impl Default for Money {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, rhs: Money) -> Money {
+        self.combine(rhs, self.minor + rhs.minor)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, rhs: Money) -> Money {
+        self.combine(rhs, self.minor - rhs.minor)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money::new(-self.minor, self.currency)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        *self = *self - rhs;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::zero(), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let factor = self.currency.factor();
+        let sign = if self.minor < 0 { "-" } else { "" };
+        let whole = (self.minor / factor).abs();
+        let fraction = (self.minor % factor).abs();
+
+        match self.currency.exponent() {
+            0 => write!(f, "{}{}", sign, whole),
+            width => write!(f, "{}{}.{:0width$}", sign, whole, fraction, width = width as usize),
+        }
+    }
+}
+
+impl fmt::Debug for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Money({} {})", self, self.currency)
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_f64(self.to_major())
+    }
+}
+
+/// Major units in the store currency, as a JSON number or a `"12.50"` string
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let currency = store_currency();
+
+        match Value::deserialize(d)? {
+            Value::Number(n) => n
+                .as_f64()
+                .map(|v| Money::from_major(v, currency, Rounding::HalfUp))
+                .ok_or_else(|| serde::de::Error::custom("amount out of range")),
+            Value::String(s) => {
+                Money::parse(&s, currency, Rounding::HalfUp).map_err(serde::de::Error::custom)
+            }
+            other => Err(serde::de::Error::custom(format!("expected an amount, got {}", other))),
+        }
+    }
+}
+
+impl ToSql for Money {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.minor))
+    }
+}
+
+/// Minor units of the store currency. A real (e.g. from `ROUND()`) is
+/// already in minor units and only loses its fraction.
+impl FromSql for Money {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Integer(i) => Ok(Money::from_minor(i)),
+            ValueRef::Real(f) => Ok(Money::from_minor(f.round() as i64)),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn currency(code: &str) -> Currency {
+        Currency::from_code(code).unwrap()
+    }
+
+    #[test]
+    fn rounding_table() {
+        let table = [
+            (1.005, "USD", Rounding::HalfUp, "1.01"),
+            (-1.005, "USD", Rounding::HalfUp, "-1.01"),
+            (2.675, "EUR", Rounding::HalfUp, "2.68"),
+            (0.125, "usd", Rounding::HalfUp, "0.13"),
+            (1.005, "USD", Rounding::HalfEven, "1.00"),
+            (1.015, "USD", Rounding::HalfEven, "1.02"),
+            (-1.015, "USD", Rounding::HalfEven, "-1.02"),
+            (1.009, "USD", Rounding::TowardZero, "1.00"),
+            (-1.009, "USD", Rounding::TowardZero, "-1.00"),
+            (99.5, "JPY", Rounding::HalfUp, "100"),
+            (1.0005, "KWD", Rounding::HalfUp, "1.001"),
+            (10.0, "XXX", Rounding::HalfUp, "10.00"),
+        ];
+
+        for (amount, code, rounding, want) in table {
+            let got = Money::from_major(amount, currency(code), rounding);
+            assert_eq!(got.to_string(), want, "{} {} {:?}", amount, code, rounding);
+        }
+    }
+
+    #[test]
+    fn parse_table() {
+        let table = [
+            ("12.50", "USD", Some(1250)),
+            (" -3 ", "USD", Some(-300)),
+            ("+0.005", "USD", Some(1)),
+            ("1.2345", "KWD", Some(1235)),
+            ("950", "JPY", Some(950)),
+            (".5", "USD", Some(50)),
+            ("", "USD", None),
+            ("1,50", "USD", None),
+            ("1e3", "USD", None),
+        ];
+
+        for (input, code, want) in table {
+            let got = Money::parse(input, currency(code), Rounding::HalfUp).ok().map(Money::minor);
+            assert_eq!(got, want, "{:?} {}", input, code);
+        }
+    }
+
+    #[test]
+    fn arithmetic_table() {
+        let usd = currency("USD");
+        let m = |minor| Money::new(minor, usd);
+
+        let table = [
+            ("times", m(333).times(3.0, Rounding::HalfUp), m(999)),
+            ("times fractional", m(1999).times(0.25, Rounding::HalfUp), m(500)),
+            ("percent", m(1005).percent(Rate::from_percent(10.0), Rounding::HalfUp), m(101)),
+            ("percent exact decimals", m(10000).percent(Rate::parse("8.875").unwrap(), Rounding::HalfUp), m(888)),
+            ("percent half even", m(1005).percent(Rate::from_percent(10.0), Rounding::HalfEven), m(100)),
+            ("add", m(10) + m(5), m(15)),
+            ("sub", m(10) - m(15), m(-5)),
+            ("neg", -m(7), m(-7)),
+            ("sum", [m(1), m(2), m(3)].iter().sum(), m(6)),
+            ("to JPY", m(12345).with_currency(currency("JPY"), Rounding::HalfUp), Money::new(123, currency("JPY"))),
+            ("to KWD", m(12345).with_currency(currency("KWD"), Rounding::HalfUp), Money::new(123450, currency("KWD"))),
+        ];
+
+        for (name, got, want) in table {
+            assert_eq!(got, want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn serde_uses_major_units() {
+        let m = Money::new(1250, currency("USD"));
+        assert_eq!(serde_json::to_string(&m).unwrap(), "12.5");
+
+        let parsed: Money = serde_json::from_str("\"12.50\"").unwrap();
+        assert_eq!(parsed.minor(), 1250 * store_currency().factor() / 100);
+
+        let rate: Rate = serde_json::from_str("\"12.5\"").unwrap();
+        assert_eq!(serde_json::to_string(&rate).unwrap(), "12.5");
+    }
+}
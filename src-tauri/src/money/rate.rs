@@ -0,0 +1,99 @@
+use std::fmt;
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::error::{AppError, AppResult};
+use super::{parse_decimal, Rounding};
+
+/// Ten-thousandths of a percent per unit
+pub const SCALE: i64 = 10_000;
+
+/// A percentage with up to four decimals: `8.875` % is `Rate(88_750)`
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Rate(i64);
+
+impl Rate {
+    pub fn from_percent(percent: f64) -> Self {
+        Rate((percent * SCALE as f64).round() as i64)
+    }
+
+    /// `"12.5"` → 12.5 %. Extra decimals are rounded half up.
+    pub fn parse(s: &str) -> AppResult<Self> {
+        let (value, scale) = parse_decimal(s)
+            .ok_or_else(|| AppError::InvalidInput(format!("invalid percentage: {:?}", s)))?;
+
+        Ok(Rate(Rounding::HalfUp.div(value * SCALE as i128, scale) as i64))
+    }
+
+    pub fn as_percent(self) -> f64 {
+        self.0 as f64 / SCALE as f64
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+
+    /// Ten-thousandths of a percent
+    pub fn scaled(self) -> i64 {
+        self.0
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%", self.as_percent())
+    }
+}
+
+impl fmt::Debug for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Rate({})", self)
+    }
+}
+
+/// Sent to the webview as a plain percentage number
+impl Serialize for Rate {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_f64(self.as_percent())
+    }
+}
+
+/// The API sends percentages as `"10.00"` strings, the webview as numbers.
+/// A blank string is 0 %.
+impl<'de> Deserialize<'de> for Rate {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        match Value::deserialize(d)? {
+            Value::Number(n) => n
+                .as_f64()
+                .map(Rate::from_percent)
+                .ok_or_else(|| serde::de::Error::custom("percentage out of range")),
+            Value::String(s) if s.trim().is_empty() => Ok(Rate::default()),
+            Value::String(s) => Rate::parse(&s).map_err(serde::de::Error::custom),
+            other => Err(serde::de::Error::custom(format!("expected a percentage, got {}", other))),
+        }
+    }
+}
+
+impl ToSql for Rate {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_percent()))
+    }
+}
+
+/// `charges.percentage` is a text column, `ticket_charges.percentage` a real one
+impl FromSql for Rate {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Integer(i) => Ok(Rate(i * SCALE)),
+            ValueRef::Real(f) => Ok(Rate::from_percent(f)),
+            ValueRef::Text(t) => match std::str::from_utf8(t).map(str::trim) {
+                Ok("") => Ok(Rate::default()),
+                Ok(s) => Rate::parse(s).map_err(|e| FromSqlError::Other(Box::new(e))),
+                Err(e) => Err(FromSqlError::Other(Box::new(e))),
+            },
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
@@ -0,0 +1,555 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::db::models::charges::{Charge, ChargeMapping};
+use crate::error::{AppError, AppResult};
+use crate::money::{rate, Currency, Money, Rate, Rounding};
+use super::{AppliedCharge, LineCharge, PricedLine, PricingLine, PricingResult};
+
+/// Working precision: millionths of a minor unit
+const MICRO: i128 = 1_000_000;
+
+/// A whole amount as a `Rate` (100 %)
+const PERCENT: i128 = 100 * rate::SCALE as i128;
+
+/// ==============================
+/// Charge rules
+/// ==============================
+///
+/// Active charges with a positive percentage, ordered so a parent is always
+/// evaluated before the charges that compound on it.
+///
+/// A line gets the charges mapped to its product; failing that, the ones
+/// mapped to its product group; failing that, to its category. A line with
+/// no mapping at all gets every charge that has no mappings.
+pub struct ChargeRules {
+    charges: Vec<RuleCharge>,
+    mappings: Vec<ChargeMapping>,
+}
+
+struct RuleCharge {
+    charge: Charge,
+    percentage: Rate,
+    mapped: bool,
+    depth: usize,
+}
+
+impl ChargeRules {
+    pub fn new(charges: Vec<Charge>, mappings: Vec<ChargeMapping>) -> AppResult<Self> {
+        let charges: Vec<(Charge, Rate)> = charges
+            .into_iter()
+            .filter(|c| c.active == 1 && c.deleted_at.is_none())
+            .filter_map(|c| {
+                let percentage = c.percentage.unwrap_or_default();
+                percentage.is_positive().then_some((c, percentage))
+            })
+            .collect();
+
+        let ids: HashSet<&str> = charges.iter().map(|(c, _)| c.id.as_str()).collect();
+
+        let mappings: Vec<ChargeMapping> = mappings
+            .into_iter()
+            .filter(|m| m.active == 1 && m.deleted_at.is_none())
+            .filter(|m| ids.contains(m.charge_id.as_str()))
+            .collect();
+
+        let parents: HashMap<&str, &str> = charges
+            .iter()
+            .filter_map(|(c, _)| {
+                c.parent_charge_id
+                    .as_deref()
+                    .filter(|p| ids.contains(p))
+                    .map(|p| (c.id.as_str(), p))
+            })
+            .collect();
+
+        let mut depths: HashMap<String, usize> = HashMap::new();
+        for (c, _) in &charges {
+            let mut depth = 0;
+            let mut current = c.id.as_str();
+            while let Some(parent) = parents.get(current) {
+                depth += 1;
+                if depth > charges.len() {
+                    return Err(AppError::InvalidInput(format!(
+                        "charge {} has a circular parent_charge_id",
+                        c.id
+                    )));
+                }
+                current = parent;
+            }
+            depths.insert(c.id.clone(), depth);
+        }
+
+        let mut charges: Vec<RuleCharge> = charges
+            .into_iter()
+            .map(|(charge, percentage)| RuleCharge {
+                mapped: mappings.iter().any(|m| m.charge_id == charge.id),
+                depth: depths[&charge.id],
+                charge,
+                percentage,
+            })
+            .collect();
+
+        charges.sort_by(|a, b| {
+            a.depth
+                .cmp(&b.depth)
+                .then(a.charge.sort_order.cmp(&b.charge.sort_order))
+                .then_with(|| a.charge.name.cmp(&b.charge.name))
+        });
+
+        Ok(Self { charges, mappings })
+    }
+
+    /// Ids of the charges that apply to a line, by mapping precedence
+    fn charges_for(&self, line: &PricingLine) -> HashSet<&str> {
+        let product_id = line.product_id.as_deref().unwrap_or(&line.id);
+
+        let mapped = |matches: &dyn Fn(&ChargeMapping) -> bool| -> HashSet<&str> {
+            self.mappings
+                .iter()
+                .filter(|m| matches(m))
+                .map(|m| m.charge_id.as_str())
+                .collect()
+        };
+
+        let by_product = mapped(&|m| m.product_id.as_deref() == Some(product_id));
+        if !by_product.is_empty() {
+            return by_product;
+        }
+
+        if let Some(group_id) = line.product_group_id.as_deref() {
+            let by_group = mapped(&|m| m.product_group_id.as_deref() == Some(group_id));
+            if !by_group.is_empty() {
+                return by_group;
+            }
+        }
+
+        if let Some(category_id) = line.category_id.as_deref() {
+            let by_category = mapped(&|m| m.category_id.as_deref() == Some(category_id));
+            if !by_category.is_empty() {
+                return by_category;
+            }
+        }
+
+        self.charges
+            .iter()
+            .filter(|c| !c.mapped)
+            .map(|c| c.charge.id.as_str())
+            .collect()
+    }
+}
+
+/// Compute line and ticket charges (exclusive of the line prices). A charge
+/// with a parent is taken on the line amount plus the parent's amount on
+/// that line.
+///
+/// Amounts are carried exactly (in millionths of a minor unit) and rounded
+/// half up at the end, once per line figure and once per ticket figure, so
+/// ticket totals can differ from the sum of the rounded lines by a minor unit.
+pub fn calculate(rules: &ChargeRules, lines: &[PricingLine], currency: Currency) -> PricingResult {
+    let money = |micro: i128| Money::from_scaled(micro, MICRO, currency, Rounding::HalfUp);
+
+    let mut applicable: HashMap<&str, i128> = HashMap::new();
+    let mut amounts: HashMap<&str, i128> = HashMap::new();
+    let mut subtotal: i128 = 0;
+
+    let priced: Vec<PricedLine> = lines
+        .iter()
+        .map(|line| {
+            let price = line.price.with_currency(currency, Rounding::HalfUp);
+            let base = price.minor() as i128 * (line.quantity * MICRO as f64).round() as i128;
+            subtotal += base;
+
+            let applies = rules.charges_for(line);
+            let mut line_amounts: HashMap<&str, i128> = HashMap::new();
+            let mut charges = Vec::new();
+
+            for rule in rules.charges.iter().filter(|r| applies.contains(r.charge.id.as_str())) {
+                let id = rule.charge.id.as_str();
+                let parent_amount = rule
+                    .charge
+                    .parent_charge_id
+                    .as_deref()
+                    .and_then(|p| line_amounts.get(p))
+                    .copied()
+                    .unwrap_or(0);
+
+                let on = base + parent_amount;
+                let amount = Rounding::HalfUp.div(on * rule.percentage.scaled() as i128, PERCENT);
+
+                line_amounts.insert(id, amount);
+                *applicable.entry(id).or_default() += on;
+                *amounts.entry(id).or_default() += amount;
+
+                charges.push(LineCharge {
+                    charge_id: id.to_string(),
+                    applicable_amount: money(on),
+                    amount: money(amount),
+                });
+            }
+
+            let total_charges: i128 = line_amounts.values().sum();
+
+            PricedLine {
+                id: line.id.clone(),
+                amount: money(base),
+                charges,
+                total_charges: money(total_charges),
+                total: money(base + total_charges),
+            }
+        })
+        .collect();
+
+    let mut charges: Vec<AppliedCharge> = rules
+        .charges
+        .iter()
+        .map(|rule| {
+            let c = &rule.charge;
+            let on = applicable.get(c.id.as_str()).copied().unwrap_or(0);
+            let amount = amounts.get(c.id.as_str()).copied().unwrap_or(0);
+
+            AppliedCharge {
+                id: c.id.clone(),
+                name: c.name.clone(),
+                code: c.code.clone(),
+                percentage: rule.percentage,
+                is_tax: c.is_tax == 1,
+                sort_order: c.sort_order,
+                transaction_type_id: c.transaction_type_id.clone(),
+                parent_charge_id: c.parent_charge_id.clone(),
+                applicable_amount: money(on),
+                amount: money(amount),
+                applied: on > 0,
+            }
+        })
+        .collect();
+
+    charges.sort_by(|a, b| a.sort_order.cmp(&b.sort_order).then_with(|| a.name.cmp(&b.name)));
+
+    let subtotal = money(subtotal);
+    let total_taxes: Money = charges.iter().filter(|c| c.is_tax).map(|c| c.amount).sum();
+    let total_charges: Money = charges.iter().filter(|c| !c.is_tax).map(|c| c.amount).sum();
+
+    PricingResult {
+        currency_code: currency,
+        lines: priced,
+        charges,
+        subtotal,
+        total_taxes,
+        total_charges,
+        total: subtotal + total_taxes + total_charges,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn charge(id: &str, percentage: &str, is_tax: bool, parent: Option<&str>) -> Charge {
+        Charge {
+            id: id.to_string(),
+            code: None,
+            name: id.to_string(),
+            percentage: Some(Rate::parse(percentage).unwrap()),
+            is_tax: is_tax as i32,
+            transaction_type_id: None,
+            parent_charge_id: parent.map(str::to_string),
+            active: 1,
+            sort_order: 0,
+            created_at: None,
+            updated_at: None,
+            deleted_at: None,
+            created_by: None,
+            updated_by: None,
+            deleted_by: None,
+        }
+    }
+
+    fn inactive(mut c: Charge) -> Charge {
+        c.active = 0;
+        c
+    }
+
+    fn mapping(
+        charge_id: &str,
+        product: Option<&str>,
+        group: Option<&str>,
+        category: Option<&str>,
+    ) -> ChargeMapping {
+        ChargeMapping {
+            id: format!("{}-{:?}-{:?}-{:?}", charge_id, product, group, category),
+            charge_id: charge_id.to_string(),
+            category_id: category.map(str::to_string),
+            product_id: product.map(str::to_string),
+            product_group_id: group.map(str::to_string),
+            active: 1,
+            sort_order: 0,
+            created_at: None,
+            updated_at: None,
+            deleted_at: None,
+            created_by: None,
+            updated_by: None,
+            deleted_by: None,
+        }
+    }
+
+    fn line(id: &str, group: Option<&str>, category: Option<&str>, price: f64, quantity: f64) -> PricingLine {
+        PricingLine {
+            id: id.to_string(),
+            product_id: Some(id.to_string()),
+            product_group_id: group.map(str::to_string),
+            category_id: category.map(str::to_string),
+            price: Money::from_major(price, Currency::USD, Rounding::HalfUp),
+            quantity,
+        }
+    }
+
+    struct Case {
+        name: &'static str,
+        charges: Vec<Charge>,
+        mappings: Vec<ChargeMapping>,
+        lines: Vec<PricingLine>,
+        currency: &'static str,
+        /// (charge id, ticket amount)
+        expect_charges: Vec<(&'static str, f64)>,
+        /// (line id, line charges total)
+        expect_lines: Vec<(&'static str, f64)>,
+        subtotal: f64,
+        total_taxes: f64,
+        total_charges: f64,
+        total: f64,
+    }
+
+    fn cases() -> Vec<Case> {
+        vec![
+            Case {
+                name: "no charges",
+                charges: vec![],
+                mappings: vec![],
+                lines: vec![line("burger", None, None, 10.0, 2.0)],
+                currency: "USD",
+                expect_charges: vec![],
+                expect_lines: vec![("burger", 0.0)],
+                subtotal: 20.0,
+                total_taxes: 0.0,
+                total_charges: 0.0,
+                total: 20.0,
+            },
+            Case {
+                name: "unmapped charges apply to every line",
+                charges: vec![charge("vat", "10", true, None), charge("service", "5", false, None)],
+                mappings: vec![],
+                lines: vec![line("burger", None, None, 10.0, 2.0), line("fries", None, None, 5.0, 1.0)],
+                currency: "USD",
+                expect_charges: vec![("vat", 2.5), ("service", 1.25)],
+                expect_lines: vec![("burger", 3.0), ("fries", 0.75)],
+                subtotal: 25.0,
+                total_taxes: 2.5,
+                total_charges: 1.25,
+                total: 28.75,
+            },
+            Case {
+                name: "mapped charge skips unmapped lines and unmapped charge skips mapped lines",
+                charges: vec![charge("vat", "10", true, None), charge("alcohol", "20", true, None)],
+                mappings: vec![mapping("alcohol", None, None, Some("drinks"))],
+                lines: vec![line("burger", None, Some("food"), 10.0, 1.0), line("beer", None, Some("drinks"), 5.0, 2.0)],
+                currency: "USD",
+                expect_charges: vec![("alcohol", 2.0), ("vat", 1.0)],
+                expect_lines: vec![("burger", 1.0), ("beer", 2.0)],
+                subtotal: 20.0,
+                total_taxes: 3.0,
+                total_charges: 0.0,
+                total: 23.0,
+            },
+            Case {
+                name: "product mapping wins over group and category",
+                charges: vec![
+                    charge("cat", "10", true, None),
+                    charge("grp", "20", true, None),
+                    charge("prod", "30", true, None),
+                ],
+                mappings: vec![
+                    mapping("cat", None, None, Some("food")),
+                    mapping("grp", None, Some("mains"), None),
+                    mapping("prod", Some("burger"), None, None),
+                ],
+                lines: vec![line("burger", Some("mains"), Some("food"), 10.0, 1.0)],
+                currency: "USD",
+                expect_charges: vec![("cat", 0.0), ("grp", 0.0), ("prod", 3.0)],
+                expect_lines: vec![("burger", 3.0)],
+                subtotal: 10.0,
+                total_taxes: 3.0,
+                total_charges: 0.0,
+                total: 13.0,
+            },
+            Case {
+                name: "group mapping wins over category",
+                charges: vec![charge("cat", "10", true, None), charge("grp", "20", true, None)],
+                mappings: vec![
+                    mapping("cat", None, None, Some("food")),
+                    mapping("grp", None, Some("mains"), None),
+                ],
+                lines: vec![
+                    line("burger", Some("mains"), Some("food"), 10.0, 1.0),
+                    line("salad", Some("sides"), Some("food"), 10.0, 1.0),
+                ],
+                currency: "USD",
+                expect_charges: vec![("cat", 1.0), ("grp", 2.0)],
+                expect_lines: vec![("burger", 2.0), ("salad", 1.0)],
+                subtotal: 20.0,
+                total_taxes: 3.0,
+                total_charges: 0.0,
+                total: 23.0,
+            },
+            Case {
+                name: "compound tax is charged on base plus parent",
+                charges: vec![charge("gst", "10", true, None), charge("pst", "5", true, Some("gst"))],
+                mappings: vec![],
+                lines: vec![line("burger", None, None, 100.0, 1.0)],
+                currency: "USD",
+                expect_charges: vec![("gst", 10.0), ("pst", 5.5)],
+                expect_lines: vec![("burger", 15.5)],
+                subtotal: 100.0,
+                total_taxes: 15.5,
+                total_charges: 0.0,
+                total: 115.5,
+            },
+            Case {
+                name: "compound chain: each charge is on the line plus its direct parent",
+                charges: vec![
+                    Charge { sort_order: 0, ..charge("c", "10", false, Some("b")) },
+                    Charge { sort_order: 1, ..charge("b", "10", true, Some("a")) },
+                    Charge { sort_order: 2, ..charge("a", "10", true, None) },
+                ],
+                mappings: vec![],
+                lines: vec![line("burger", None, None, 100.0, 1.0)],
+                currency: "USD",
+                expect_charges: vec![("c", 11.1), ("b", 11.0), ("a", 10.0)],
+                expect_lines: vec![("burger", 32.1)],
+                subtotal: 100.0,
+                total_taxes: 21.0,
+                total_charges: 11.1,
+                total: 132.1,
+            },
+            Case {
+                name: "child without its parent on the line uses the line amount",
+                charges: vec![charge("gst", "10", true, None), charge("pst", "5", true, Some("gst"))],
+                mappings: vec![mapping("gst", None, None, Some("food")), mapping("pst", None, None, Some("drinks"))],
+                lines: vec![line("beer", None, Some("drinks"), 10.0, 1.0)],
+                currency: "USD",
+                expect_charges: vec![("gst", 0.0), ("pst", 0.5)],
+                expect_lines: vec![("beer", 0.5)],
+                subtotal: 10.0,
+                total_taxes: 0.5,
+                total_charges: 0.0,
+                total: 10.5,
+            },
+            Case {
+                name: "inactive and zero-rate charges are ignored",
+                charges: vec![
+                    inactive(charge("old", "50", true, None)),
+                    charge("zero", "0", true, None),
+                    charge("vat", "10", true, None),
+                ],
+                mappings: vec![],
+                lines: vec![line("burger", None, None, 10.0, 1.0)],
+                currency: "USD",
+                expect_charges: vec![("vat", 1.0)],
+                expect_lines: vec![("burger", 1.0)],
+                subtotal: 10.0,
+                total_taxes: 1.0,
+                total_charges: 0.0,
+                total: 11.0,
+            },
+            Case {
+                name: "half cent rounds away from zero",
+                charges: vec![charge("vat", "10", true, None)],
+                mappings: vec![],
+                lines: vec![line("tea", None, None, 10.05, 1.0)],
+                currency: "USD",
+                expect_charges: vec![("vat", 1.01)],
+                expect_lines: vec![("tea", 1.01)],
+                subtotal: 10.05,
+                total_taxes: 1.01,
+                total_charges: 0.0,
+                total: 11.06,
+            },
+            Case {
+                name: "ticket amount is rounded once, not per line",
+                charges: vec![charge("vat", "5", true, None)],
+                mappings: vec![],
+                lines: vec![
+                    line("a", None, None, 0.1, 1.0),
+                    line("b", None, None, 0.1, 1.0),
+                    line("c", None, None, 0.1, 1.0),
+                ],
+                currency: "USD",
+                expect_charges: vec![("vat", 0.02)],
+                expect_lines: vec![("a", 0.01), ("b", 0.01), ("c", 0.01)],
+                subtotal: 0.3,
+                total_taxes: 0.02,
+                total_charges: 0.0,
+                total: 0.32,
+            },
+            Case {
+                name: "zero decimal currency",
+                charges: vec![charge("tax", "8", true, None)],
+                mappings: vec![],
+                lines: vec![line("ramen", None, None, 950.0, 1.0)],
+                currency: "JPY",
+                expect_charges: vec![("tax", 76.0)],
+                expect_lines: vec![("ramen", 76.0)],
+                subtotal: 950.0,
+                total_taxes: 76.0,
+                total_charges: 0.0,
+                total: 1026.0,
+            },
+            Case {
+                name: "three decimal currency",
+                charges: vec![charge("service", "12.5", false, None)],
+                mappings: vec![],
+                lines: vec![line("machboos", None, None, 3.25, 1.0)],
+                currency: "KWD",
+                expect_charges: vec![("service", 0.406)],
+                expect_lines: vec![("machboos", 0.406)],
+                subtotal: 3.25,
+                total_taxes: 0.0,
+                total_charges: 0.406,
+                total: 3.656,
+            },
+        ]
+    }
+
+    #[test]
+    fn pricing_table() {
+        for case in cases() {
+            let rules = ChargeRules::new(case.charges, case.mappings).unwrap();
+            let currency = Currency::from_code(case.currency).unwrap();
+            let result = calculate(&rules, &case.lines, currency);
+
+            let mut got: Vec<(&str, f64)> = result.charges.iter().map(|c| (c.id.as_str(), c.amount.to_major())).collect();
+            let mut want = case.expect_charges.clone();
+            got.sort_by(|a, b| a.0.cmp(b.0));
+            want.sort_by(|a, b| a.0.cmp(b.0));
+            assert_eq!(got, want, "{}: charges", case.name);
+
+            let lines: Vec<(&str, f64)> = result.lines.iter().map(|l| (l.id.as_str(), l.total_charges.to_major())).collect();
+            assert_eq!(lines, case.expect_lines, "{}: lines", case.name);
+
+            assert_eq!(result.subtotal.to_major(), case.subtotal, "{}: subtotal", case.name);
+            assert_eq!(result.total_taxes.to_major(), case.total_taxes, "{}: taxes", case.name);
+            assert_eq!(result.total_charges.to_major(), case.total_charges, "{}: charges total", case.name);
+            assert_eq!(result.total.to_major(), case.total, "{}: total", case.name);
+        }
+    }
+
+    #[test]
+    fn circular_parents_are_rejected() {
+        let err = ChargeRules::new(
+            vec![charge("a", "10", true, Some("b")), charge("b", "10", true, Some("a"))],
+            vec![],
+        )
+        .err();
+
+        assert!(matches!(err, Some(AppError::InvalidInput(_))));
+    }
+}
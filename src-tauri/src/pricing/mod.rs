@@ -0,0 +1,88 @@
+pub mod engine;
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::charges_repo;
+use crate::error::AppResult;
+use crate::money::{store_currency, Currency, Money, Rate};
+pub use engine::ChargeRules;
+
+/// ==============================
+/// Cart input
+/// ==============================
+///
+/// Field names match the webview's `CartItem`, so the cart can be passed as is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricingLine {
+    pub id: String,
+    #[serde(default)]
+    pub product_id: Option<String>,
+    #[serde(default)]
+    pub product_group_id: Option<String>,
+    #[serde(default)]
+    pub category_id: Option<String>,
+    /// Unit price, modifiers included
+    pub price: Money,
+    pub quantity: f64,
+}
+
+/// ==============================
+/// Result
+/// ==============================
+#[derive(Debug, Clone, Serialize)]
+pub struct LineCharge {
+    pub charge_id: String,
+    /// Line amount plus any parent charge it compounds on
+    pub applicable_amount: Money,
+    pub amount: Money,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PricedLine {
+    pub id: String,
+    pub amount: Money,
+    pub charges: Vec<LineCharge>,
+    pub total_charges: Money,
+    pub total: Money,
+}
+
+/// Ticket-level total for one charge. Same shape as `CalculatedCharge`
+/// in the webview, plus what it was computed on.
+#[derive(Debug, Clone, Serialize)]
+pub struct AppliedCharge {
+    pub id: String,
+    pub name: String,
+    pub code: Option<String>,
+    pub percentage: Rate,
+    pub is_tax: bool,
+    pub sort_order: i32,
+    pub transaction_type_id: Option<String>,
+    pub parent_charge_id: Option<String>,
+    pub applicable_amount: Money,
+    pub amount: Money,
+    pub applied: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PricingResult {
+    pub currency_code: Currency,
+    pub lines: Vec<PricedLine>,
+    pub charges: Vec<AppliedCharge>,
+    pub subtotal: Money,
+    pub total_taxes: Money,
+    /// Non-tax charges (service charge, packaging…)
+    pub total_charges: Money,
+    pub total: Money,
+}
+
+/// Price a cart with the charges and mappings stored locally, in the
+/// store currency
+pub fn price_cart(conn: &Connection, lines: &[PricingLine]) -> AppResult<PricingResult> {
+    let rules = ChargeRules::new(
+        charges_repo::get_charges(conn)?,
+        charges_repo::get_charge_mappings(conn)?,
+    )?;
+
+    Ok(engine::calculate(&rules, lines, store_currency()))
+}
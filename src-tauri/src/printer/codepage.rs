@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+
+/// ==============================
+/// Code pages
+/// ==============================
+///
+/// Thermal printers take one byte per character in the selected code page
+/// (`ESC t n`). ASCII is the same in all of them; the upper half differs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CodePage {
+    /// ASCII only; anything else prints as `?`
+    Pc437,
+    /// Western European (Windows-1252)
+    #[default]
+    Wpc1252,
+    /// Arabic (Windows-1256). The printer must shape the letters itself.
+    Wpc1256,
+}
+
+impl CodePage {
+    /// `ESC t` table number in Epson's numbering, which most ESC/POS
+    /// printers follow. Others can be given theirs with `EscPos::code_page_as`.
+    pub fn table(self) -> u8 {
+        match self {
+            CodePage::Pc437 => 0,
+            CodePage::Wpc1252 => 16,
+            CodePage::Wpc1256 => 50,
+        }
+    }
+
+    /// The page behind an `ESC t` table number, if it's one of ours
+    pub fn from_table(table: u8) -> Option<Self> {
+        [CodePage::Pc437, CodePage::Wpc1252, CodePage::Wpc1256]
+            .into_iter()
+            .find(|page| page.table() == table)
+    }
+
+    /// The page `text` needs: Arabic if it has any Arabic letters
+    pub fn for_text(text: &str) -> Self {
+        if text.chars().any(is_arabic) {
+            CodePage::Wpc1256
+        } else {
+            CodePage::Wpc1252
+        }
+    }
+
+    /// `text` as bytes in this page, `?` for characters it doesn't have
+    pub fn encode(self, text: &str) -> Vec<u8> {
+        let upper = match self {
+            CodePage::Pc437 => None,
+            CodePage::Wpc1252 => Some(&WPC1252),
+            CodePage::Wpc1256 => Some(&WPC1256),
+        };
+
+        text.chars()
+            .map(|c| {
+                if c.is_ascii() {
+                    return c as u8;
+                }
+                upper
+                    .and_then(|table| table.iter().position(|&u| u as u32 == c as u32))
+                    .map(|i| 0x80 + i as u8)
+                    .unwrap_or(b'?')
+            })
+            .collect()
+    }
+
+    /// The character `byte` prints as, `?` where the page has none
+    pub fn decode(self, byte: u8) -> char {
+        if byte.is_ascii() {
+            return byte as char;
+        }
+        let upper = match self {
+            CodePage::Pc437 => return '?',
+            CodePage::Wpc1252 => &WPC1252,
+            CodePage::Wpc1256 => &WPC1256,
+        };
+        match upper[(byte - 0x80) as usize] {
+            0 => '?',
+            u => char::from_u32(u as u32).unwrap_or('?'),
+        }
+    }
+}
+
+pub fn is_arabic(c: char) -> bool {
+    matches!(c, '\u{0600}'..='\u{06FF}' | '\u{0750}'..='\u{077F}' | '\u{FB50}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}')
+}
+
+/// `line` in the order it must be printed: printers lay bytes out left to
+/// right, so Arabic runs are reversed while numbers and Latin text inside
+/// them keep their own order. Lines without Arabic are left alone.
+pub fn visual_order(line: &str) -> String {
+    if !line.chars().any(is_arabic) {
+        return line.to_string();
+    }
+
+    // Runs of left-to-right text (Latin letters, digits and what joins them)
+    let mut runs: Vec<(bool, Vec<char>)> = Vec::new();
+    for c in line.chars() {
+        let ltr = c.is_ascii_alphanumeric()
+            || (!is_arabic(c) && c.is_alphanumeric())
+            || (matches!(c, '.' | ',' | ':' | '/' | '-')
+                && runs.last().is_some_and(|(ltr, _)| *ltr));
+        match runs.last_mut() {
+            Some((run_ltr, chars)) if *run_ltr == ltr => chars.push(c),
+            _ => runs.push((ltr, vec![c])),
+        }
+    }
+
+    runs.iter()
+        .rev()
+        .flat_map(|(ltr, chars)| {
+            let chars: Vec<char> = if *ltr {
+                chars.clone()
+            } else {
+                chars.iter().rev().map(|&c| mirror(c)).collect()
+            };
+            chars
+        })
+        .collect()
+}
+
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        other => other,
+    }
+}
+
+/// Bytes 0x80..=0xFF of Windows-1252 (0 = unassigned)
+const WPC1252: [u16; 128] = [
+    0x20AC, 0, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160, 0x2039, 0x0152, 0, 0x017D, 0,
+    0, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014, 0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0, 0x017E, 0x0178,
+    0x00A0, 0x00A1, 0x00A2, 0x00A3, 0x00A4, 0x00A5, 0x00A6, 0x00A7, 0x00A8, 0x00A9, 0x00AA, 0x00AB, 0x00AC, 0x00AD, 0x00AE, 0x00AF,
+    0x00B0, 0x00B1, 0x00B2, 0x00B3, 0x00B4, 0x00B5, 0x00B6, 0x00B7, 0x00B8, 0x00B9, 0x00BA, 0x00BB, 0x00BC, 0x00BD, 0x00BE, 0x00BF,
+    0x00C0, 0x00C1, 0x00C2, 0x00C3, 0x00C4, 0x00C5, 0x00C6, 0x00C7, 0x00C8, 0x00C9, 0x00CA, 0x00CB, 0x00CC, 0x00CD, 0x00CE, 0x00CF,
+    0x00D0, 0x00D1, 0x00D2, 0x00D3, 0x00D4, 0x00D5, 0x00D6, 0x00D7, 0x00D8, 0x00D9, 0x00DA, 0x00DB, 0x00DC, 0x00DD, 0x00DE, 0x00DF,
+    0x00E0, 0x00E1, 0x00E2, 0x00E3, 0x00E4, 0x00E5, 0x00E6, 0x00E7, 0x00E8, 0x00E9, 0x00EA, 0x00EB, 0x00EC, 0x00ED, 0x00EE, 0x00EF,
+    0x00F0, 0x00F1, 0x00F2, 0x00F3, 0x00F4, 0x00F5, 0x00F6, 0x00F7, 0x00F8, 0x00F9, 0x00FA, 0x00FB, 0x00FC, 0x00FD, 0x00FE, 0x00FF,
+];
+
+/// Bytes 0x80..=0xFF of Windows-1256
+const WPC1256: [u16; 128] = [
+    0x20AC, 0x067E, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0679, 0x2039, 0x0152, 0x0686, 0x0698, 0x0688,
+    0x06AF, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014, 0x06A9, 0x2122, 0x0691, 0x203A, 0x0153, 0x200C, 0x200D, 0x06BA,
+    0x00A0, 0x060C, 0x00A2, 0x00A3, 0x00A4, 0x00A5, 0x00A6, 0x00A7, 0x00A8, 0x00A9, 0x06BE, 0x00AB, 0x00AC, 0x00AD, 0x00AE, 0x00AF,
+    0x00B0, 0x00B1, 0x00B2, 0x00B3, 0x00B4, 0x00B5, 0x00B6, 0x00B7, 0x00B8, 0x00B9, 0x061B, 0x00BB, 0x00BC, 0x00BD, 0x00BE, 0x061F,
+    0x06C1, 0x0621, 0x0622, 0x0623, 0x0624, 0x0625, 0x0626, 0x0627, 0x0628, 0x0629, 0x062A, 0x062B, 0x062C, 0x062D, 0x062E, 0x062F,
+    0x0630, 0x0631, 0x0632, 0x0633, 0x0634, 0x0635, 0x0636, 0x00D7, 0x0637, 0x0638, 0x0639, 0x063A, 0x0640, 0x0641, 0x0642, 0x0643,
+    0x00E0, 0x0644, 0x00E2, 0x0645, 0x0646, 0x0647, 0x0648, 0x00E7, 0x00E8, 0x00E9, 0x00EA, 0x00EB, 0x0649, 0x064A, 0x00EE, 0x00EF,
+    0x064B, 0x064C, 0x064D, 0x064E, 0x00F4, 0x064F, 0x0650, 0x00F7, 0x0651, 0x00F9, 0x0652, 0x00FB, 0x00FC, 0x200E, 0x200F, 0x06D2,
+];
@@ -0,0 +1,69 @@
+use rusqlite::Connection;
+
+use super::kitchen_chit::{self, KitchenChit};
+use super::raster::Raster;
+use super::receipt::{self, Receipt};
+use super::z_report::{self, ZReport};
+use crate::db::models::print_template::{PrintTemplate, TemplateKind};
+use crate::error::{AppError, AppResult};
+
+/// ==============================
+/// Printable document
+/// ==============================
+///
+/// Any of the template kinds with the data it prints, so printing and
+/// previews go through one path.
+#[derive(Debug, Clone)]
+pub enum Document {
+    Receipt(Receipt),
+    KitchenChit(KitchenChit),
+    VoidSlip(Receipt),
+    ZReport(ZReport),
+}
+
+impl Document {
+    /// Load what `kind` prints from `source_id`: a ticket id for receipts
+    /// and void slips, a KDS ticket id for chits, a workday id for Z-reports
+    pub fn load(conn: &Connection, kind: TemplateKind, source_id: &str) -> AppResult<Self> {
+        Ok(match kind {
+            TemplateKind::Receipt => Document::Receipt(Receipt::load(conn, source_id)?),
+            TemplateKind::KitchenChit => Document::KitchenChit(KitchenChit::load(conn, source_id)?),
+            TemplateKind::VoidSlip => Document::VoidSlip(Receipt::load(conn, source_id)?),
+            TemplateKind::ZReport => {
+                let workday_id = source_id
+                    .parse()
+                    .map_err(|_| AppError::InvalidInput(format!("Workday id '{}'", source_id)))?;
+                Document::ZReport(ZReport::load(conn, workday_id)?)
+            }
+        })
+    }
+
+    /// Made-up data of `kind`, for previews
+    pub fn sample(kind: TemplateKind) -> Self {
+        match kind {
+            TemplateKind::Receipt => Document::Receipt(Receipt::sample()),
+            TemplateKind::KitchenChit => Document::KitchenChit(KitchenChit::sample()),
+            TemplateKind::VoidSlip => Document::VoidSlip(Receipt::sample()),
+            TemplateKind::ZReport => Document::ZReport(ZReport::sample()),
+        }
+    }
+
+    /// The location whose template it prints with. Z-reports carry theirs
+    /// in the report.
+    pub fn location_id(&self) -> Option<&str> {
+        match self {
+            Document::Receipt(r) | Document::VoidSlip(r) => r.location_id.as_deref(),
+            Document::KitchenChit(c) => c.location_id.as_deref(),
+            Document::ZReport(z) => Some(z.report.location_id.as_str()).filter(|id| !id.is_empty()),
+        }
+    }
+
+    pub fn render(&self, template: &PrintTemplate, logo: Option<&Raster>) -> Vec<u8> {
+        match self {
+            Document::Receipt(r) => receipt::render(r, template, logo),
+            Document::KitchenChit(c) => kitchen_chit::render(c, template, logo),
+            Document::VoidSlip(r) => receipt::render_void_slip(r, template, logo),
+            Document::ZReport(z) => z_report::render(z, template, logo),
+        }
+    }
+}
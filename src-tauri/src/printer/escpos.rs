@@ -1,12 +1,781 @@
 use serde::{Deserialize, Serialize};
 
+use super::codepage::{self, CodePage};
+use super::raster::Raster;
+use crate::db::models::printer::Printer;
+use crate::error::{AppError, AppResult};
+
 /// Printer configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PrinterConfig {
     pub id: String,
     pub name: String,
-    pub printer_type: String, // "network", "usb", "bluetooth", "builtin"
+    pub printer_type: String, // "network", "usb", "serial", "bluetooth", "builtin"
     pub ip_address: Option<String>,
     pub port: Option<u16>,
+    /// USB and serial printers: `/dev/usb/lp0`, `/dev/ttyUSB0`, `COM3`
+    #[serde(default)]
+    pub device_path: Option<String>,
+    /// Serial printers; `transport::DEFAULT_BAUD_RATE` when unset
+    #[serde(default)]
+    pub baud_rate: Option<u32>,
     pub is_active: bool,
 }
+
+impl From<&Printer> for PrinterConfig {
+    fn from(printer: &Printer) -> Self {
+        Self {
+            id: printer.id.clone(),
+            name: printer.name.clone(),
+            printer_type: printer.printer_type.clone(),
+            ip_address: printer.ip_address.clone(),
+            port: printer.port.map(|p| p as u16),
+            device_path: printer.device_path.clone(),
+            baud_rate: printer.baud_rate.map(|b| b as u32),
+            is_active: printer.is_active,
+        }
+    }
+}
+
+const ESC: u8 = 0x1B;
+const GS: u8 = 0x1D;
+const LF: u8 = 0x0A;
+
+/// Rows sent per `GS v 0`; small printers choke on one large image
+const RASTER_BAND: usize = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaperWidth {
+    #[default]
+    Mm58,
+    Mm80,
+}
+
+impl PaperWidth {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PaperWidth::Mm58 => "mm58",
+            PaperWidth::Mm80 => "mm80",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "mm58" => Some(PaperWidth::Mm58),
+            "mm80" => Some(PaperWidth::Mm80),
+            _ => None,
+        }
+    }
+
+    /// Characters per line in `font`
+    pub fn columns(self, font: Font) -> usize {
+        (self.dots() / font.cell().0) as usize
+    }
+
+    /// Printable dots per line
+    pub fn dots(self) -> u32 {
+        match self {
+            PaperWidth::Mm58 => 384,
+            PaperWidth::Mm80 => 576,
+        }
+    }
+}
+
+/// Font A is the normal one; B is smaller and fits more on a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Font {
+    #[default]
+    A,
+    B,
+}
+
+impl Font {
+    /// Character cell in dots, width by height
+    pub fn cell(self) -> (u32, u32) {
+        match self {
+            Font::A => (12, 24),
+            Font::B => (9, 17),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Barcode {
+    UpcA,
+    UpcE,
+    Ean13,
+    Ean8,
+    Code39,
+    Itf,
+    Codabar,
+    Code93,
+    Code128,
+}
+
+/// QR error correction: how much of the code can be damaged and still read
+#[allow(dead_code)] // No document prints a QR code yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum QrLevel {
+    L,
+    #[default]
+    M,
+    Q,
+    H,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cut {
+    Full,
+    /// Leaves a strip joined, for printers whose paper falls into the bowl
+    #[allow(dead_code)]
+    Partial,
+}
+
+/// Connector pin the cash drawer is wired to
+#[allow(dead_code)] // For a drawer kick, which nothing sends yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawerPin {
+    Pin2,
+    Pin5,
+}
+
+/// One column of a `row`: `width` characters (0 takes what the others
+/// leave), its text aligned within them
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+    pub width: usize,
+    pub align: Align,
+}
+
+impl Column {
+    pub fn left(width: usize) -> Self {
+        Self { width, align: Align::Left }
+    }
+
+    pub fn right(width: usize) -> Self {
+        Self { width, align: Align::Right }
+    }
+
+    /// Left aligned, taking the remaining width
+    pub fn fill() -> Self {
+        Self { width: 0, align: Align::Left }
+    }
+}
+
+/// ==============================
+/// ESC/POS document
+/// ==============================
+///
+/// Builds the bytes for one print job. Text goes through the code page
+/// (picked per line from what it contains unless fixed with `code_page`)
+/// and is wrapped to the paper width at the current character size.
+pub struct EscPos {
+    buf: Vec<u8>,
+    paper: PaperWidth,
+    /// Fixed by `code_page`; None picks one per line
+    fixed_page: Option<(CodePage, u8)>,
+    current_page: Option<(CodePage, u8)>,
+    font: Font,
+    width_scale: usize,
+}
+
+impl EscPos {
+    pub fn new(paper: PaperWidth) -> Self {
+        Self {
+            buf: vec![ESC, b'@'],
+            paper,
+            fixed_page: None,
+            current_page: None,
+            font: Font::A,
+            width_scale: 1,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn paper(&self) -> PaperWidth {
+        self.paper
+    }
+
+    /// Characters per line at the current size
+    pub fn width(&self) -> usize {
+        self.paper.columns(self.font) / self.width_scale
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub fn raw(&mut self, bytes: &[u8]) -> &mut Self {
+        self.buf.extend_from_slice(bytes);
+        self
+    }
+
+    // ---------- Text style ----------
+
+    /// Use `page` for all text from here on
+    #[allow(dead_code)] // Documents pick their page per line
+    pub fn code_page(&mut self, page: CodePage) -> &mut Self {
+        self.code_page_as(page, page.table())
+    }
+
+    /// Use `page`, which this printer numbers `table`
+    #[allow(dead_code)]
+    pub fn code_page_as(&mut self, page: CodePage, table: u8) -> &mut Self {
+        self.fixed_page = Some((page, table));
+        self.select_page((page, table))
+    }
+
+    pub fn align(&mut self, align: Align) -> &mut Self {
+        let n = match align {
+            Align::Left => 0,
+            Align::Center => 1,
+            Align::Right => 2,
+        };
+        self.raw(&[ESC, b'a', n])
+    }
+
+    pub fn bold(&mut self, on: bool) -> &mut Self {
+        self.raw(&[ESC, b'E', on as u8])
+    }
+
+    pub fn underline(&mut self, on: bool) -> &mut Self {
+        self.raw(&[ESC, b'-', on as u8])
+    }
+
+    /// White on black
+    pub fn invert(&mut self, on: bool) -> &mut Self {
+        self.raw(&[GS, b'B', on as u8])
+    }
+
+    pub fn font(&mut self, font: Font) -> &mut Self {
+        self.font = font;
+        self.raw(&[ESC, b'M', font as u8])
+    }
+
+    /// Character size as multiples of normal, 1 to 8 each way
+    pub fn size(&mut self, width: u8, height: u8) -> &mut Self {
+        let (width, height) = (width.clamp(1, 8), height.clamp(1, 8));
+        self.width_scale = width as usize;
+        self.raw(&[GS, b'!', ((width - 1) << 4) | (height - 1)])
+    }
+
+    /// Back to left aligned, normal size, no emphasis
+    pub fn reset_style(&mut self) -> &mut Self {
+        self.align(Align::Left)
+            .bold(false)
+            .underline(false)
+            .invert(false)
+            .size(1, 1)
+    }
+
+    // ---------- Text ----------
+
+    /// `text` as it is, without a line feed
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        self.ordered(&codepage::visual_order(text))
+    }
+
+    /// `text` wrapped to the line width, each line ending in a line feed
+    pub fn line(&mut self, text: &str) -> &mut Self {
+        for line in wrap(text, self.width()) {
+            self.text(&line).raw(&[LF]);
+        }
+        self
+    }
+
+    pub fn feed(&mut self, lines: u8) -> &mut Self {
+        self.raw(&[ESC, b'd', lines])
+    }
+
+    /// A line of `c` across the paper
+    pub fn rule(&mut self, c: char) -> &mut Self {
+        let line: String = std::iter::repeat(c).take(self.width()).collect();
+        self.text(&line).raw(&[LF])
+    }
+
+    /// One row of columns. A cell longer than its column wraps onto more
+    /// lines; the other cells stay on the first.
+    pub fn row(&mut self, columns: &[Column], cells: &[&str]) -> &mut Self {
+        // Cells are put in print order one by one, not the whole line
+        for line in layout_row(columns, cells, self.width()) {
+            self.ordered(&line).raw(&[LF]);
+        }
+        self
+    }
+
+    /// `left` and `right` on one line, e.g. a label and an amount
+    pub fn pair(&mut self, left: &str, right: &str) -> &mut Self {
+        let right_width = right.chars().count().min(self.width());
+        self.row(&[Column::fill(), Column::right(right_width)], &[left, right])
+    }
+
+    // ---------- Codes and images ----------
+
+    /// A QR code of `data`, `module` dots per square (1 to 16)
+    #[allow(dead_code)]
+    pub fn qr(&mut self, data: &str, module: u8, level: QrLevel) -> AppResult<&mut Self> {
+        let data = data.as_bytes();
+        if data.is_empty() || data.len() > 7089 {
+            return Err(AppError::InvalidInput("QR data must be 1 to 7089 bytes".into()));
+        }
+
+        let level = match level {
+            QrLevel::L => 48,
+            QrLevel::M => 49,
+            QrLevel::Q => 50,
+            QrLevel::H => 51,
+        };
+        let stored = data.len() + 3;
+
+        // Model 2, module size, error correction, store the data, print it
+        self.raw(&[GS, b'(', b'k', 4, 0, 49, 65, 50, 0])
+            .raw(&[GS, b'(', b'k', 3, 0, 49, 67, module.clamp(1, 16)])
+            .raw(&[GS, b'(', b'k', 3, 0, 49, 69, level])
+            .raw(&[GS, b'(', b'k', (stored & 0xFF) as u8, (stored >> 8) as u8, 49, 80, 48])
+            .raw(data)
+            .raw(&[GS, b'(', b'k', 3, 0, 49, 81, 48]);
+        Ok(self)
+    }
+
+    /// A barcode `height` dots tall with its digits printed below
+    pub fn barcode(&mut self, kind: Barcode, data: &str, height: u8) -> AppResult<&mut Self> {
+        validate_barcode(kind, data)?;
+
+        let (m, data) = match kind {
+            Barcode::UpcA => (65, data.to_string()),
+            Barcode::UpcE => (66, data.to_string()),
+            Barcode::Ean13 => (67, data.to_string()),
+            Barcode::Ean8 => (68, data.to_string()),
+            Barcode::Code39 => (69, data.to_string()),
+            Barcode::Itf => (70, data.to_string()),
+            Barcode::Codabar => (71, data.to_string()),
+            Barcode::Code93 => (72, data.to_string()),
+            // Code set B covers printable ASCII
+            Barcode::Code128 => (73, format!("{{B{}", data)),
+        };
+
+        self.raw(&[GS, b'h', height.max(1)])
+            .raw(&[GS, b'w', 2])
+            .raw(&[GS, b'H', 2])
+            .raw(&[GS, b'k', m, data.len() as u8])
+            .raw(data.as_bytes());
+        Ok(self)
+    }
+
+    /// A raster image, cut to the paper width
+    pub fn image(&mut self, image: &Raster) -> &mut Self {
+        let stride = image.stride();
+        let width_bytes = stride.min(self.paper.dots().div_ceil(8) as usize);
+
+        for band in image.bits.chunks(stride * RASTER_BAND) {
+            let rows = band.len() / stride;
+            self.raw(&[
+                GS,
+                b'v',
+                b'0',
+                0,
+                (width_bytes & 0xFF) as u8,
+                (width_bytes >> 8) as u8,
+                (rows & 0xFF) as u8,
+                (rows >> 8) as u8,
+            ]);
+            for row in band.chunks(stride) {
+                self.raw(&row[..width_bytes]);
+            }
+        }
+        self
+    }
+
+    // ---------- Paper and drawer ----------
+
+    /// Feed past the cutter and cut
+    pub fn cut(&mut self, cut: Cut) -> &mut Self {
+        let m = match cut {
+            Cut::Full => 65,
+            Cut::Partial => 66,
+        };
+        self.raw(&[GS, b'V', m, 3])
+    }
+
+    /// Pulse the cash drawer
+    #[allow(dead_code)]
+    pub fn open_drawer(&mut self, pin: DrawerPin) -> &mut Self {
+        let m = match pin {
+            DrawerPin::Pin2 => 0,
+            DrawerPin::Pin5 => 1,
+        };
+        self.raw(&[ESC, b'p', m, 25, 250])
+    }
+
+    fn page_for(&self, text: &str) -> (CodePage, u8) {
+        self.fixed_page.unwrap_or_else(|| {
+            let page = CodePage::for_text(text);
+            (page, page.table())
+        })
+    }
+
+    /// `text` already in print order
+    fn ordered(&mut self, text: &str) -> &mut Self {
+        let page = self.page_for(text);
+        self.select_page(page);
+        let bytes = page.0.encode(text);
+        self.raw(&bytes)
+    }
+
+    fn select_page(&mut self, page: (CodePage, u8)) -> &mut Self {
+        if self.current_page != Some(page) {
+            self.current_page = Some(page);
+            self.raw(&[ESC, b't', page.1]);
+        }
+        self
+    }
+}
+
+/// `text` broken into lines of at most `width` characters, at spaces where
+/// possible. Explicit line breaks are kept, and so is the spacing of lines
+/// that already fit.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+
+    for paragraph in text.split('\n') {
+        if paragraph.chars().count() <= width {
+            lines.push(paragraph.to_string());
+            continue;
+        }
+
+        // The first line keeps its indent
+        let indent = paragraph.len() - paragraph.trim_start_matches(' ').len();
+        let mut line = " ".repeat(indent.min(width - 1));
+        let mut len = line.len();
+        let mut words = 0;
+
+        for word in paragraph.split(' ').filter(|w| !w.is_empty()) {
+            let mut word: Vec<char> = word.chars().collect();
+            let gap = usize::from(words > 0);
+
+            if len + gap + word.len() <= width {
+                if gap > 0 {
+                    line.push(' ');
+                }
+                line.extend(&word);
+                len += gap + word.len();
+                words += 1;
+                continue;
+            }
+            if words > 0 {
+                lines.push(std::mem::take(&mut line));
+            } else {
+                line.clear();
+            }
+            // Longer than a line on its own: hard break it
+            while word.len() > width {
+                lines.push(word.drain(..width).collect());
+            }
+            line.extend(&word);
+            len = word.len();
+            words = 1;
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Lines of `cells` padded into `columns` across `width` characters
+pub fn layout_row(columns: &[Column], cells: &[&str], width: usize) -> Vec<String> {
+    let fixed: usize = columns.iter().map(|c| c.width).sum();
+    let fill = width.saturating_sub(fixed);
+    let widths: Vec<usize> = columns
+        .iter()
+        .map(|c| if c.width == 0 { fill } else { c.width })
+        .collect();
+
+    let wrapped: Vec<Vec<String>> = widths
+        .iter()
+        .zip(cells)
+        .map(|(&w, cell)| if w == 0 { Vec::new() } else { wrap(cell, w) })
+        .collect();
+    let height = wrapped.iter().map(Vec::len).max().unwrap_or(0);
+
+    (0..height)
+        .map(|i| {
+            columns
+                .iter()
+                .zip(&widths)
+                .zip(&wrapped)
+                .map(|((column, &w), lines)| {
+                    let text = lines.get(i).map(String::as_str).unwrap_or("");
+                    pad(&codepage::visual_order(text), w, column.align)
+                })
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+fn pad(text: &str, width: usize, align: Align) -> String {
+    let len = text.chars().count();
+    let space = width.saturating_sub(len);
+    let (left, right) = match align {
+        Align::Left => (0, space),
+        Align::Right => (space, 0),
+        Align::Center => (space / 2, space - space / 2),
+    };
+    format!("{}{}{}", " ".repeat(left), text, " ".repeat(right))
+}
+
+fn validate_barcode(kind: Barcode, data: &str) -> AppResult<()> {
+    let digits = data.chars().all(|c| c.is_ascii_digit());
+    let len = data.len();
+
+    let valid = match kind {
+        Barcode::UpcA => digits && (11..=12).contains(&len),
+        Barcode::UpcE => digits && ((6..=8).contains(&len) || (11..=12).contains(&len)),
+        Barcode::Ean13 => digits && (12..=13).contains(&len),
+        Barcode::Ean8 => digits && (7..=8).contains(&len),
+        Barcode::Code39 => {
+            len > 0 && data.chars().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase() || " $%*+-./".contains(c))
+        }
+        Barcode::Itf => digits && len >= 2 && len % 2 == 0,
+        Barcode::Codabar => {
+            len >= 2 && data.chars().all(|c| c.is_ascii_digit() || "ABCDabcd$+-./:".contains(c))
+        }
+        Barcode::Code93 | Barcode::Code128 => {
+            len > 0 && data.chars().all(|c| c.is_ascii() && !c.is_ascii_control())
+        }
+    };
+
+    // GS k takes the length in one byte (plus the Code128 prefix)
+    if !valid || len > 253 {
+        return Err(AppError::InvalidInput(format!("{:?} barcode can't encode {:?}", kind, data)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct WrapCase {
+        name: &'static str,
+        text: &'static str,
+        width: usize,
+        expect: Vec<&'static str>,
+    }
+
+    fn wrap_cases() -> Vec<WrapCase> {
+        vec![
+            WrapCase { name: "fits", text: "Hello  world", width: 20, expect: vec!["Hello  world"] },
+            WrapCase { name: "empty", text: "", width: 10, expect: vec![""] },
+            WrapCase {
+                name: "breaks at spaces",
+                text: "Classic Burger with cheese",
+                width: 12,
+                expect: vec!["Classic", "Burger with", "cheese"],
+            },
+            WrapCase {
+                name: "keeps line breaks",
+                text: "Thank You!\nPlease Come Again",
+                width: 12,
+                expect: vec!["Thank You!", "Please Come", "Again"],
+            },
+            WrapCase {
+                name: "first line keeps its indent",
+                text: "  + Extra cheese topping",
+                width: 12,
+                expect: vec!["  + Extra", "cheese", "topping"],
+            },
+            WrapCase {
+                name: "hard breaks a long word",
+                text: "ABCDEFGHIJ KL",
+                width: 4,
+                expect: vec!["ABCD", "EFGH", "IJ", "KL"],
+            },
+            WrapCase { name: "zero width", text: "ab", width: 0, expect: vec!["a", "b"] },
+        ]
+    }
+
+    #[test]
+    fn wrap_table() {
+        for case in wrap_cases() {
+            assert_eq!(wrap(case.text, case.width), case.expect, "{}: lines", case.name);
+        }
+    }
+
+    struct RowCase {
+        name: &'static str,
+        columns: Vec<Column>,
+        cells: Vec<&'static str>,
+        width: usize,
+        expect: Vec<&'static str>,
+    }
+
+    fn row_cases() -> Vec<RowCase> {
+        vec![
+            RowCase {
+                name: "label and amount",
+                columns: vec![Column::fill(), Column::right(6)],
+                cells: vec!["Fries", "4.50"],
+                width: 20,
+                expect: vec!["Fries           4.50"],
+            },
+            RowCase {
+                name: "long cell wraps, others stay on the first line",
+                columns: vec![Column::fill(), Column::right(6)],
+                cells: vec!["Classic Burger with cheese", "24.00"],
+                width: 20,
+                expect: vec!["Classic Burger 24.00", "with cheese"],
+            },
+            RowCase {
+                name: "every column wraps",
+                columns: vec![Column::left(5), Column::fill()],
+                cells: vec!["x12 pcs", "Onion Rings"],
+                width: 12,
+                expect: vec!["x12  Onion", "pcs  Rings"],
+            },
+            RowCase {
+                name: "centered",
+                columns: vec![Column { width: 8, align: Align::Center }],
+                cells: vec!["VOID"],
+                width: 8,
+                expect: vec!["  VOID"],
+            },
+            RowCase {
+                name: "no room left for the fill column",
+                columns: vec![Column::fill(), Column::left(5)],
+                cells: vec!["dropped", "abc"],
+                width: 4,
+                expect: vec!["abc"],
+            },
+        ]
+    }
+
+    #[test]
+    fn layout_row_table() {
+        for case in row_cases() {
+            assert_eq!(layout_row(&case.columns, &case.cells, case.width), case.expect, "{}: lines", case.name);
+        }
+    }
+
+    /// The bytes a command adds after the initialize
+    fn commands(build: impl FnOnce(&mut EscPos)) -> Vec<u8> {
+        let mut doc = EscPos::new(PaperWidth::Mm80);
+        build(&mut doc);
+        doc.build().split_off(2)
+    }
+
+    #[test]
+    fn qr_bytes() {
+        let bytes = commands(|doc| {
+            doc.qr("HI", 20, QrLevel::H).unwrap();
+        });
+        let expect = [
+            vec![GS, b'(', b'k', 4, 0, 49, 65, 50, 0],
+            // Module size clamped to 16
+            vec![GS, b'(', b'k', 3, 0, 49, 67, 16],
+            vec![GS, b'(', b'k', 3, 0, 49, 69, 51],
+            vec![GS, b'(', b'k', 5, 0, 49, 80, 48, b'H', b'I'],
+            vec![GS, b'(', b'k', 3, 0, 49, 81, 48],
+        ]
+        .concat();
+        assert_eq!(bytes, expect);
+
+        // The stored length is little endian
+        let long = "x".repeat(300);
+        let bytes = commands(|doc| {
+            doc.qr(&long, 4, QrLevel::M).unwrap();
+        });
+        assert_eq!(&bytes[25..33], &[GS, b'(', b'k', 47, 1, 49, 80, 48]);
+
+        let mut doc = EscPos::new(PaperWidth::Mm58);
+        assert!(matches!(doc.qr("", 4, QrLevel::M), Err(AppError::InvalidInput(_))));
+        assert!(matches!(doc.qr(&"x".repeat(7090), 4, QrLevel::M), Err(AppError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn barcode_bytes() {
+        let setup = |height: u8| vec![GS, b'h', height, GS, b'w', 2, GS, b'H', 2];
+
+        let bytes = commands(|doc| {
+            doc.barcode(Barcode::Code128, "1024", 60).unwrap();
+        });
+        let expect = [setup(60), vec![GS, b'k', 73, 6], b"{B1024".to_vec()].concat();
+        assert_eq!(bytes, expect, "Code128 gets its code set");
+
+        let bytes = commands(|doc| {
+            doc.barcode(Barcode::Ean13, "5901234123457", 0).unwrap();
+        });
+        let expect = [setup(1), vec![GS, b'k', 67, 13], b"5901234123457".to_vec()].concat();
+        assert_eq!(bytes, expect, "EAN-13, height at least 1");
+    }
+
+    #[test]
+    fn barcode_data_is_checked() {
+        let cases = [
+            (Barcode::UpcA, "01234567890", true),
+            (Barcode::UpcA, "0123456789A", false),
+            (Barcode::Ean8, "1234567", true),
+            (Barcode::Ean8, "123456", false),
+            (Barcode::Code39, "ABC-12", true),
+            (Barcode::Code39, "abc", false),
+            (Barcode::Itf, "1234", true),
+            (Barcode::Itf, "123", false),
+            (Barcode::Codabar, "A123B", true),
+            (Barcode::Code128, "Order #12", true),
+            (Barcode::Code128, "tab\there", false),
+            (Barcode::Code128, "", false),
+        ];
+        for (kind, data, valid) in cases {
+            assert_eq!(validate_barcode(kind, data).is_ok(), valid, "{:?} {:?}", kind, data);
+        }
+        assert!(validate_barcode(Barcode::Code128, &"x".repeat(254)).is_err(), "length fits a byte");
+    }
+
+    #[test]
+    fn paper_and_drawer_bytes() {
+        let full = commands(|doc| {
+            doc.cut(Cut::Full);
+        });
+        assert_eq!(full, [GS, b'V', 65, 3]);
+
+        let partial = commands(|doc| {
+            doc.cut(Cut::Partial);
+        });
+        assert_eq!(partial, [GS, b'V', 66, 3]);
+
+        let drawer = commands(|doc| {
+            doc.open_drawer(DrawerPin::Pin5);
+        });
+        assert_eq!(drawer, [ESC, b'p', 1, 25, 250]);
+    }
+
+    #[test]
+    fn code_page_is_sent_when_it_changes() {
+        let bytes = commands(|doc| {
+            doc.text("a").text("b").text("مرحبا").text("c");
+        });
+        let switches: Vec<u8> = bytes.windows(3).filter(|w| w[0] == ESC && w[1] == b't').map(|w| w[2]).collect();
+        assert_eq!(
+            switches,
+            vec![CodePage::Wpc1252.table(), CodePage::Wpc1256.table(), CodePage::Wpc1252.table()]
+        );
+
+        let bytes = commands(|doc| {
+            doc.code_page_as(CodePage::Wpc1256, 22).text("a").text("مرحبا");
+        });
+        assert_eq!(&bytes[..3], &[ESC, b't', 22]);
+        assert_eq!(bytes.windows(2).filter(|w| w[0] == ESC && w[1] == b't').count(), 1, "fixed page");
+    }
+}
@@ -0,0 +1,151 @@
+use rusqlite::Connection;
+use serde::Deserialize;
+
+use super::escpos::{Align, Column};
+use super::raster::Raster;
+use super::receipt::{location_name, quantity};
+use super::template;
+use crate::db::models::print_template::{PrintTemplate, TemplateField};
+use crate::db::models::{kds_ticket_repo, kitchen_station_repo};
+use crate::error::AppResult;
+
+/// ==============================
+/// Kitchen chit
+/// ==============================
+///
+/// A KDS ticket on paper: one station's items (or the whole order) with
+/// their modifiers and notes, no prices.
+#[derive(Debug, Clone)]
+pub struct KitchenChit {
+    pub location_id: Option<String>,
+    pub location_name: String,
+    pub station_name: Option<String>,
+    pub ticket_number: String,
+    pub token_number: Option<i32>,
+    pub order_mode: Option<String>,
+    pub created_at: String,
+    pub items: Vec<ChitItem>,
+}
+
+/// An item as the POS put it in `kds_tickets.items`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChitItem {
+    pub name: String,
+    #[serde(default = "one")]
+    pub quantity: f64,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub modifiers: Vec<ChitModifier>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChitModifier {
+    pub name: String,
+    #[serde(default = "one")]
+    pub qty: f64,
+}
+
+fn one() -> f64 {
+    1.0
+}
+
+impl KitchenChit {
+    pub fn load(conn: &Connection, kds_ticket_id: &str) -> AppResult<Self> {
+        let ticket = kds_ticket_repo::get_kds_ticket(conn, kds_ticket_id)?;
+        let station_name = match &ticket.station_id {
+            Some(id) => Some(kitchen_station_repo::get_station(conn, id)?.name),
+            None => None,
+        };
+
+        Ok(Self {
+            location_name: location_name(conn, ticket.location_id.as_deref())?,
+            location_id: ticket.location_id,
+            station_name,
+            ticket_number: ticket.ticket_number,
+            token_number: ticket.token_number,
+            order_mode: ticket.order_mode_name,
+            created_at: ticket.created_at,
+            items: serde_json::from_str(&ticket.items)?,
+        })
+    }
+
+    /// A made-up chit for previewing templates
+    pub fn sample() -> Self {
+        let item = |name: &str, quantity: f64, notes: Option<&str>, modifiers: &[&str]| ChitItem {
+            name: name.into(),
+            quantity,
+            notes: notes.map(Into::into),
+            modifiers: modifiers
+                .iter()
+                .map(|m| ChitModifier { name: (*m).into(), qty: 1.0 })
+                .collect(),
+        };
+
+        Self {
+            location_id: None,
+            location_name: "Sample Location".into(),
+            station_name: Some("Grill".into()),
+            ticket_number: "1024".into(),
+            token_number: Some(17),
+            order_mode: Some("Dine In".into()),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            items: vec![
+                item("Classic Burger", 2.0, Some("No onions"), &["Extra cheese"]),
+                item("Chicken Wings", 1.0, None, &["Hot sauce", "Blue cheese dip"]),
+            ],
+        }
+    }
+}
+
+/// The ESC/POS bytes for `chit` laid out by `template`
+pub fn render(chit: &KitchenChit, template: &PrintTemplate, logo: Option<&Raster>) -> Vec<u8> {
+    let title = chit
+        .station_name
+        .as_deref()
+        .filter(|_| template.shows(TemplateField::Station))
+        .unwrap_or(&chit.location_name);
+    let mut doc = template::open(template, logo, title);
+
+    if template.shows(TemplateField::TicketNumber) {
+        template::heading(&mut doc, template, &format!("#{}", chit.ticket_number));
+    }
+    if let Some(token) = chit.token_number.filter(|_| template.shows(TemplateField::QueueNumber)) {
+        doc.align(Align::Center).line(&format!("Token: {}", token)).align(Align::Left);
+    }
+    if let Some(mode) = chit.order_mode.as_ref().filter(|_| template.shows(TemplateField::OrderMode)) {
+        doc.line(&format!("Mode: {}", mode));
+    }
+    if template.shows(TemplateField::DateTime) {
+        doc.line(&template::local_time(&chit.created_at));
+    }
+    doc.rule('=');
+
+    let columns = [Column::left(5), Column::fill()];
+    for item in &chit.items {
+        doc.bold(true)
+            .row(&columns, &[&format!("{}x", quantity(item.quantity)), &item.name])
+            .bold(false);
+        if template.shows(TemplateField::Modifiers) {
+            for modifier in &item.modifiers {
+                let name = if modifier.qty > 1.0 {
+                    format!("+ {} x{}", modifier.name, quantity(modifier.qty))
+                } else {
+                    format!("+ {}", modifier.name)
+                };
+                doc.row(&columns, &["", &name]);
+            }
+        }
+        if let Some(notes) = item
+            .notes
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty() && template.shows(TemplateField::Notes))
+        {
+            doc.row(&columns, &["", &format!("* {}", notes)]);
+        }
+    }
+    doc.rule('=');
+
+    template::close(doc, template)
+}
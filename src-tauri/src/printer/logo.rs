@@ -0,0 +1,50 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ring::digest::{digest, SHA256};
+
+use super::raster::Raster;
+use crate::error::{AppError, AppResult};
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The store logo at `url` as a raster at most `max_width` dots wide.
+///
+/// Downloaded once into `cache_dir` and read from there afterwards, so
+/// receipts still get their logo while offline.
+pub async fn load(cache_dir: &Path, url: &str, max_width: u32) -> AppResult<Raster> {
+    let path = cached_path(cache_dir, url);
+
+    let bytes = match tokio::fs::read(&path).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            let bytes = fetch(url).await?;
+            tokio::fs::create_dir_all(cache_dir)
+                .await
+                .map_err(|e| AppError::Internal(format!("logo cache: {}", e)))?;
+            tokio::fs::write(&path, &bytes)
+                .await
+                .map_err(|e| AppError::Internal(format!("logo cache: {}", e)))?;
+            bytes
+        }
+    };
+
+    Raster::from_png(&bytes, max_width)
+}
+
+async fn fetch(url: &str) -> AppResult<Vec<u8>> {
+    let client = reqwest::Client::builder().timeout(FETCH_TIMEOUT).build()?;
+    let response = client.get(url).send().await?.error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// One file per URL, so a new logo is fetched as soon as it's set
+fn cached_path(cache_dir: &Path, url: &str) -> PathBuf {
+    let hash: String = digest(&SHA256, url.as_bytes())
+        .as_ref()
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    cache_dir.join(format!("logo-{}.png", hash))
+}
@@ -1,5 +1,19 @@
+pub mod codepage;
+pub mod document;
 pub mod escpos;
+pub mod font;
+pub mod kitchen_chit;
+pub mod logo;
+pub mod monitor;
+pub mod preview;
+pub mod raster;
+pub mod receipt;
 pub mod service;
+pub mod spooler;
+pub mod status;
+pub mod template;
+pub mod transport;
+pub mod z_report;
 
 pub use escpos::PrinterConfig;
 pub use service::PrinterService;
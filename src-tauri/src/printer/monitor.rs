@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::escpos::PrinterConfig;
+use super::service::PrinterService;
+use super::spooler::PrintSpooler;
+use super::status::{PrinterHealth, PrinterStatus};
+use super::transport;
+use crate::db;
+use crate::db::models::print_job_repo;
+use crate::db::models::printer::Printer;
+use crate::db::models::printer_repo::PrinterRepo;
+use crate::error::{AppError, AppResult};
+
+/// Emitted when a printer's health changes (`StatusChange`)
+pub const EVENT_STATUS: &str = "printer:status";
+
+/// Time between health checks of the active printers
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A printer whose health changed
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusChange {
+    pub printer_id: String,
+    pub name: String,
+    pub status: PrinterHealth,
+    pub previous: PrinterHealth,
+    /// What it reported, None when it couldn't be reached or didn't answer
+    pub flags: Option<PrinterStatus>,
+    pub error: Option<String>,
+}
+
+/// ==============================
+/// Printer health check
+/// ==============================
+///
+/// Asks each active network, USB and serial printer for its status, stores
+/// it on the printer and emits `EVENT_STATUS` when it changed, so the POS
+/// can warn about low paper before a printer stops.
+pub fn start(app: &AppHandle, interval: Duration) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        log::info!("🩺 Printer health check started");
+
+        loop {
+            if let Err(e) = check_all(&app).await {
+                log::warn!("⚠️ Printer health check failed: {}", e);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+/// Check every active printer the app drives itself
+pub async fn check_all(app: &AppHandle) -> AppResult<()> {
+    let printers = {
+        let conn = db::reader(app)?;
+        PrinterRepo::get_active(&conn)?
+    };
+
+    for printer in printers.into_iter().filter(|p| transport::supports(&p.printer_type)) {
+        let name = printer.name.clone();
+        if let Err(e) = check(app, printer).await {
+            log::warn!("⚠️ Could not check printer '{}': {}", name, e);
+        }
+    }
+    Ok(())
+}
+
+/// Check one printer, store the result and emit it if it changed
+pub async fn check(app: &AppHandle, printer: Printer) -> AppResult<PrinterHealth> {
+    let config = PrinterConfig::from(&printer);
+
+    let result = tauri::async_runtime::spawn_blocking(move || PrinterService::query_status(&config)).await?;
+    let (status, flags, error) = match result {
+        Ok(Some(flags)) => (flags.health(), Some(flags), None),
+        Ok(None) => (PrinterHealth::Unknown, None, None),
+        Err(e @ (AppError::InvalidInput(_) | AppError::Unsupported(_))) => return Err(e),
+        Err(e) => (PrinterHealth::Offline, None, Some(e.to_string())),
+    };
+
+    let previous = {
+        let conn = db::writer(app)?;
+        PrinterRepo::set_status(&conn, &printer.id, status)?
+    };
+    if status == previous {
+        return Ok(status);
+    }
+
+    log::info!(
+        "🩺 Printer '{}': {} -> {}",
+        printer.name,
+        previous.describe(),
+        status.describe()
+    );
+    let _ = app.emit(
+        EVENT_STATUS,
+        &StatusChange {
+            printer_id: printer.id.clone(),
+            name: printer.name,
+            status,
+            previous,
+            flags,
+            error,
+        },
+    );
+
+    // Jobs held back by the problem go out now instead of after their backoff
+    if status.can_print() && !previous.can_print() {
+        print_job_repo::release_printer_jobs(&*db::writer(app)?, &printer.id)?;
+        if let Some(spooler) = app.try_state::<PrintSpooler>() {
+            spooler.wake();
+        }
+    }
+
+    Ok(status)
+}
@@ -0,0 +1,502 @@
+use serde::Deserialize;
+
+use super::codepage::{self, CodePage};
+use super::escpos::{Align, Font, PaperWidth};
+use super::font;
+use super::raster::Raster;
+use crate::error::{AppError, AppResult};
+
+// ==============================
+// Print preview
+// ==============================
+//
+// Reads ESC/POS bytes back into what the printer would put on paper, so a
+// layout can be checked as plain text or as a PNG without a printer. QR
+// codes and barcodes are drawn as placeholders.
+
+const ESC: u8 = 0x1B;
+const GS: u8 = 0x1D;
+const LF: u8 = 0x0A;
+
+/// White border around the PNG, in dots
+const MARGIN: u32 = 16;
+/// Space between text lines on top of the character height
+const LINE_GAP: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewFormat {
+    Text,
+    /// Base64 PNG
+    Png,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Style {
+    bold: bool,
+    underline: bool,
+    invert: bool,
+    width: u32,
+    height: u32,
+    font: Font,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self { bold: false, underline: false, invert: false, width: 1, height: 1, font: Font::A }
+    }
+}
+
+impl Style {
+    fn cell(&self) -> (u32, u32) {
+        let (w, h) = self.font.cell();
+        (w * self.width, h * self.height)
+    }
+}
+
+#[derive(Debug)]
+struct Run {
+    style: Style,
+    text: Vec<char>,
+}
+
+#[derive(Debug)]
+enum Block {
+    /// A printed line; `style` is what an empty one is fed at
+    Line { runs: Vec<Run>, align: Align, style: Style },
+    Image { raster: Raster, align: Align },
+    Code { label: String, qr: bool, align: Align },
+    Cut,
+}
+
+/// The document as text, one printed line per line. Images and codes show
+/// as bracketed placeholders and cuts as a dashed line.
+pub fn to_text(bytes: &[u8], paper: PaperWidth) -> String {
+    let placeholder = |text: String, align: Align| {
+        let columns = paper.columns(Font::A);
+        pad(&text, text.chars().count(), columns, align)
+    };
+
+    let mut lines = Vec::new();
+    for block in read(bytes) {
+        let line = match block {
+            Block::Line { runs, align, style } => {
+                let font = runs.first().map_or(style.font, |r| r.style.font);
+                let width: usize = runs.iter().map(|r| r.text.len() * r.style.width as usize).sum();
+                let text: String = runs.iter().flat_map(|r| r.text.iter()).collect();
+                let line = pad(&text, width, paper.columns(font), align);
+                if line.chars().any(codepage::is_arabic) {
+                    // Already in print order; keep viewers from reordering it
+                    format!("\u{202D}{}\u{202C}", line)
+                } else {
+                    line
+                }
+            }
+            Block::Image { raster, align } => {
+                placeholder(format!("[image {}x{}]", raster.width, raster.height), align)
+            }
+            Block::Code { label, qr, align } => {
+                placeholder(format!("[{}: {}]", if qr { "QR" } else { "barcode" }, label), align)
+            }
+            Block::Cut => "- ".repeat(paper.columns(Font::A) / 2),
+        };
+        lines.push(line.trim_end().to_string());
+    }
+    lines.join("\n")
+}
+
+/// The document drawn as a black and white PNG, one pixel per dot. The
+/// preview font has no Arabic letters, so Arabic (WPC1256) text is refused
+/// rather than drawn as boxes; `to_text` shows it.
+pub fn to_png(bytes: &[u8], paper: PaperWidth) -> AppResult<Vec<u8>> {
+    let blocks = read(bytes);
+    let arabic = blocks.iter().any(|block| match block {
+        Block::Line { runs, .. } => runs.iter().any(|r| r.text.iter().copied().any(codepage::is_arabic)),
+        _ => false,
+    });
+    if arabic {
+        return Err(AppError::Unsupported(
+            "PNG preview of Arabic (WPC1256) text, use the text preview".into(),
+        ));
+    }
+
+    let mut canvas = Canvas::new(paper.dots() + 2 * MARGIN);
+    canvas.y = MARGIN;
+
+    for block in blocks {
+        match block {
+            Block::Line { runs, align, style } => canvas.text(&runs, align, style, paper),
+            Block::Image { raster, align } => canvas.image(&raster, align, paper),
+            Block::Code { label, qr, align } => canvas.code(&label, qr, align, paper),
+            Block::Cut => canvas.cut(),
+        }
+    }
+    canvas.y += MARGIN;
+    canvas.encode()
+}
+
+// ==============================
+// ESC/POS reader
+// ==============================
+
+fn read(bytes: &[u8]) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut runs: Vec<Run> = Vec::new();
+    let mut style = Style::default();
+    let mut align = Align::Left;
+    let mut page = CodePage::Pc437;
+    let mut qr_data = String::new();
+
+    let arg = |i: usize| bytes.get(i).copied().unwrap_or(0) as usize;
+    let mut i = 0;
+
+    // Text not yet ended by a line feed goes out before anything else
+    fn flush(blocks: &mut Vec<Block>, runs: &mut Vec<Run>, align: Align, style: Style) {
+        if !runs.is_empty() {
+            blocks.push(Block::Line { runs: std::mem::take(runs), align, style });
+        }
+    }
+
+    while i < bytes.len() {
+        match bytes[i] {
+            ESC => {
+                let n = arg(i + 2);
+                match bytes.get(i + 1) {
+                    Some(b'@') => {
+                        style = Style::default();
+                        align = Align::Left;
+                        page = CodePage::Pc437;
+                        i += 2;
+                    }
+                    Some(b'a') => {
+                        align = match n % 48 {
+                            1 => Align::Center,
+                            2 => Align::Right,
+                            _ => Align::Left,
+                        };
+                        i += 3;
+                    }
+                    Some(b'E') => {
+                        style.bold = n & 1 == 1;
+                        i += 3;
+                    }
+                    Some(b'-') => {
+                        style.underline = n % 48 > 0;
+                        i += 3;
+                    }
+                    Some(b'M') => {
+                        style.font = if n % 48 == 1 { Font::B } else { Font::A };
+                        i += 3;
+                    }
+                    Some(b't') => {
+                        // Tables this side doesn't know were numbered by
+                        // `code_page_as`; Latin is the best guess
+                        page = CodePage::from_table(n as u8).unwrap_or(CodePage::Wpc1252);
+                        i += 3;
+                    }
+                    Some(b'd') => {
+                        flush(&mut blocks, &mut runs, align, style);
+                        for _ in 0..n {
+                            blocks.push(Block::Line { runs: Vec::new(), align, style });
+                        }
+                        i += 3;
+                    }
+                    // Drawer kick: nothing on paper
+                    Some(b'p') => i += 5,
+                    _ => i += 2,
+                }
+            }
+            GS => match bytes.get(i + 1) {
+                Some(b'!') => {
+                    let n = arg(i + 2);
+                    style.width = (n >> 4) as u32 + 1;
+                    style.height = (n & 0x0F) as u32 + 1;
+                    i += 3;
+                }
+                Some(b'B') => {
+                    style.invert = arg(i + 2) & 1 == 1;
+                    i += 3;
+                }
+                Some(b'V') => {
+                    flush(&mut blocks, &mut runs, align, style);
+                    blocks.push(Block::Cut);
+                    i += if matches!(arg(i + 2), 65 | 66) { 4 } else { 3 };
+                }
+                Some(b'v') => {
+                    flush(&mut blocks, &mut runs, align, style);
+                    let stride = arg(i + 4) + arg(i + 5) * 256;
+                    let height = arg(i + 6) + arg(i + 7) * 256;
+                    let start = (i + 8).min(bytes.len());
+                    let end = (start + stride * height).min(bytes.len());
+                    let band = &bytes[start..end];
+
+                    // `EscPos::image` sends tall images in bands; join them
+                    match blocks.last_mut() {
+                        Some(Block::Image { raster, align: a }) if *a == align && raster.stride() == stride => {
+                            raster.bits.extend_from_slice(band);
+                            raster.height += height as u32;
+                        }
+                        _ => blocks.push(Block::Image {
+                            raster: Raster { width: stride as u32 * 8, height: height as u32, bits: band.to_vec() },
+                            align,
+                        }),
+                    }
+                    i = end;
+                }
+                Some(b'(') if bytes.get(i + 2) == Some(&b'k') => {
+                    let len = arg(i + 3) + arg(i + 4) * 256;
+                    let start = (i + 5).min(bytes.len());
+                    let payload = &bytes[start..(start + len).min(bytes.len())];
+                    match payload {
+                        // Store the QR data, then print it
+                        [49, 80, _, data @ ..] => qr_data = String::from_utf8_lossy(data).into_owned(),
+                        [49, 81, ..] => {
+                            flush(&mut blocks, &mut runs, align, style);
+                            blocks.push(Block::Code { label: qr_data.clone(), qr: true, align });
+                        }
+                        _ => {}
+                    }
+                    i = start + len;
+                }
+                Some(b'k') => {
+                    flush(&mut blocks, &mut runs, align, style);
+                    let m = arg(i + 2);
+                    let (data, next) = if m <= 6 {
+                        let start = (i + 3).min(bytes.len());
+                        let len = bytes[start..].iter().position(|&b| b == 0).unwrap_or(bytes.len() - start);
+                        (&bytes[start..start + len], start + len + 1)
+                    } else {
+                        let start = (i + 4).min(bytes.len());
+                        let end = (start + arg(i + 3)).min(bytes.len());
+                        (&bytes[start..end], end)
+                    };
+                    // Code128 data starts with its code set, e.g. `{B`
+                    let data = data.strip_prefix(b"{B").unwrap_or(data);
+                    blocks.push(Block::Code { label: String::from_utf8_lossy(data).into_owned(), qr: false, align });
+                    i = next;
+                }
+                Some(b'h' | b'w' | b'H' | b'f') => i += 3,
+                _ => i += 2,
+            },
+            LF => {
+                blocks.push(Block::Line { runs: std::mem::take(&mut runs), align, style });
+                i += 1;
+            }
+            b if b >= 0x20 => {
+                let c = page.decode(b);
+                match runs.last_mut() {
+                    Some(run) if run.style == style => run.text.push(c),
+                    _ => runs.push(Run { style, text: vec![c] }),
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    flush(&mut blocks, &mut runs, align, style);
+    blocks
+}
+
+/// `text`, `width` columns wide when printed, placed in `columns`
+fn pad(text: &str, width: usize, columns: usize, align: Align) -> String {
+    let space = columns.saturating_sub(width);
+    let left = match align {
+        Align::Left => 0,
+        Align::Center => space / 2,
+        Align::Right => space,
+    };
+    format!("{}{}", " ".repeat(left), text)
+}
+
+// ==============================
+// Drawing
+// ==============================
+
+/// Rows of dots, `true` = black, growing downwards as blocks are drawn
+struct Canvas {
+    width: u32,
+    dots: Vec<bool>,
+    y: u32,
+}
+
+impl Canvas {
+    fn new(width: u32) -> Self {
+        Self { width, dots: Vec::new(), y: 0 }
+    }
+
+    fn set(&mut self, x: u32, y: u32, black: bool) {
+        if x >= self.width {
+            return;
+        }
+        let i = (y * self.width + x) as usize;
+        if i >= self.dots.len() {
+            self.dots.resize((y as usize + 1) * self.width as usize, false);
+        }
+        self.dots[i] = black;
+    }
+
+    fn get(&self, x: u32, y: u32) -> bool {
+        self.dots.get((y * self.width + x) as usize).copied().unwrap_or(false)
+    }
+
+    fn fill(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        for dy in 0..height {
+            for dx in 0..width {
+                self.set(x + dx, y + dy, true);
+            }
+        }
+    }
+
+    /// Left edge for something `width` dots wide
+    fn left(&self, width: u32, align: Align, paper: PaperWidth) -> u32 {
+        let space = paper.dots().saturating_sub(width);
+        MARGIN
+            + match align {
+                Align::Left => 0,
+                Align::Center => space / 2,
+                Align::Right => space,
+            }
+    }
+
+    fn text(&mut self, runs: &[Run], align: Align, style: Style, paper: PaperWidth) {
+        let height = runs
+            .iter()
+            .map(|r| r.style.cell().1)
+            .max()
+            .unwrap_or(style.cell().1);
+        let width: u32 = runs.iter().map(|r| r.style.cell().0 * r.text.len() as u32).sum();
+
+        let mut x = self.left(width, align, paper);
+        for run in runs {
+            let (cw, ch) = run.style.cell();
+            // Characters sit on the line's baseline
+            let top = self.y + height - ch;
+            for &c in &run.text {
+                self.glyph(c, x, top, run.style);
+                x += cw;
+            }
+        }
+        self.y += height + LINE_GAP;
+    }
+
+    fn glyph(&mut self, c: char, x: u32, y: u32, style: Style) {
+        let (cw, ch) = style.cell();
+        // The 5×7 glyph scaled into the cell, leaving a dot of space
+        let (gw, gh) = (cw * 5 / 6, ch * 7 / 8);
+        for py in 0..gh {
+            for px in 0..gw {
+                if font::dot(c, (px * 5 / gw) as usize, (py * 7 / gh) as usize) {
+                    self.set(x + px, y + py, true);
+                    if style.bold {
+                        self.set(x + px + style.width, y + py, true);
+                    }
+                }
+            }
+        }
+        if style.underline {
+            self.fill(x, y + ch - 2 * style.height, cw, style.height);
+        }
+        if style.invert {
+            for py in 0..ch {
+                for px in 0..cw {
+                    let black = self.get(x + px, y + py);
+                    self.set(x + px, y + py, !black);
+                }
+            }
+        }
+    }
+
+    fn image(&mut self, raster: &Raster, align: Align, paper: PaperWidth) {
+        let x = self.left(raster.width, align, paper);
+        let stride = raster.stride();
+        for row in 0..raster.height {
+            for col in 0..raster.width {
+                let byte = raster.bits.get(row as usize * stride + col as usize / 8).copied().unwrap_or(0);
+                if byte & (0x80 >> (col % 8)) != 0 {
+                    self.set(x + col, self.y + row, true);
+                }
+            }
+        }
+        self.y += raster.height;
+    }
+
+    /// A stand-in for a QR code or barcode with its data printed under it
+    fn code(&mut self, label: &str, qr: bool, align: Align, paper: PaperWidth) {
+        let seed: Vec<u8> = if label.is_empty() { vec![0x5A] } else { label.bytes().collect() };
+        let bit = |i: usize| seed[i / 8 % seed.len()].rotate_left(i as u32 % 8) & 1 == 1;
+
+        if qr {
+            const MODULES: u32 = 25;
+            const MODULE: u32 = 6;
+            let x = self.left(MODULES * MODULE, align, paper);
+            let finder = |mx: u32, my: u32| {
+                let near = |m: u32, at: u32| m >= at && m < at + 7;
+                [(0, 0), (MODULES - 7, 0), (0, MODULES - 7)]
+                    .iter()
+                    .find(|&&(fx, fy)| near(mx, fx) && near(my, fy))
+                    .map(|&(fx, fy)| {
+                        let (dx, dy) = (mx - fx, my - fy);
+                        let ring = dx.min(dy).min(6 - dx).min(6 - dy);
+                        ring != 1
+                    })
+            };
+            for my in 0..MODULES {
+                for mx in 0..MODULES {
+                    let black = finder(mx, my).unwrap_or_else(|| bit((my * MODULES + mx) as usize * 7));
+                    if black {
+                        self.fill(x + mx * MODULE, self.y + my * MODULE, MODULE, MODULE);
+                    }
+                }
+            }
+            self.y += MODULES * MODULE + LINE_GAP;
+        } else {
+            const HEIGHT: u32 = 80;
+            let bars = (paper.dots() * 3 / 4) / 2;
+            let x = self.left(bars * 2, align, paper);
+            for b in 0..bars {
+                if b < 2 || b >= bars - 2 || bit(b as usize * 3) {
+                    self.fill(x + b * 2, self.y, 2, HEIGHT);
+                }
+            }
+            self.y += HEIGHT + LINE_GAP;
+        }
+
+        let style = Style { font: Font::B, ..Style::default() };
+        let run = Run { style, text: label.chars().take(paper.columns(Font::B)).collect() };
+        self.text(&[run], align, style, paper);
+    }
+
+    fn cut(&mut self) {
+        self.y += LINE_GAP;
+        for x in (0..self.width).step_by(12) {
+            self.fill(x, self.y, 6, 2);
+        }
+        self.y += 2 + LINE_GAP;
+    }
+
+    fn encode(mut self) -> AppResult<Vec<u8>> {
+        let height = self.y.max(1);
+        self.dots.resize((height * self.width) as usize, false);
+
+        // 1-bit greyscale: 1 is white
+        let stride = self.width.div_ceil(8) as usize;
+        let mut data = vec![0xFF; stride * height as usize];
+        for y in 0..height {
+            for x in 0..self.width {
+                if self.get(x, y) {
+                    data[y as usize * stride + x as usize / 8] &= !(0x80 >> (x % 8));
+                }
+            }
+        }
+
+        let failed = |e: png::EncodingError| AppError::Internal(format!("preview image: {}", e));
+        let mut png_bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png_bytes, self.width, height);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::One);
+        let mut writer = encoder.write_header().map_err(failed)?;
+        writer.write_image_data(&data).map_err(failed)?;
+        writer.finish().map_err(failed)?;
+        Ok(png_bytes)
+    }
+}
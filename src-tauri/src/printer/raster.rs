@@ -0,0 +1,125 @@
+use crate::error::{AppError, AppResult};
+
+/// ==============================
+/// Raster image
+/// ==============================
+///
+/// A 1-bit image for `GS v 0`: rows of `width` dots packed eight to a byte,
+/// most significant bit first, 1 = black.
+#[derive(Debug, Clone)]
+pub struct Raster {
+    pub width: u32,
+    pub height: u32,
+    pub bits: Vec<u8>,
+}
+
+impl Raster {
+    /// Bytes per row
+    pub fn stride(&self) -> usize {
+        self.width.div_ceil(8) as usize
+    }
+
+    /// Decode a PNG, scale it down to at most `max_width` dots and dither
+    /// it to black and white. Transparent pixels are paper.
+    pub fn from_png(bytes: &[u8], max_width: u32) -> AppResult<Self> {
+        let invalid = |e: png::DecodingError| AppError::InvalidInput(format!("logo image: {}", e));
+
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info().map_err(invalid)?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).map_err(invalid)?;
+
+        let channels = info.color_type.samples();
+        let (width, height) = (info.width as usize, info.height as usize);
+        let luma: Vec<f32> = (0..width * height)
+            .map(|i| {
+                let (y, x) = (i / width, i % width);
+                let px = &buf[y * info.line_size + x * channels..][..channels];
+                let (value, alpha) = match channels {
+                    1 => (px[0] as f32, 255.0),
+                    2 => (px[0] as f32, px[1] as f32),
+                    3 => (0.299 * px[0] as f32 + 0.587 * px[1] as f32 + 0.114 * px[2] as f32, 255.0),
+                    _ => (0.299 * px[0] as f32 + 0.587 * px[1] as f32 + 0.114 * px[2] as f32, px[3] as f32),
+                };
+                // Over white paper
+                let a = alpha / 255.0;
+                value * a + 255.0 * (1.0 - a)
+            })
+            .collect();
+
+        Ok(Self::dither(&scale(&luma, width, height, max_width as usize)))
+    }
+
+    /// Floyd–Steinberg from 0..=255 grey levels
+    fn dither(image: &Grey) -> Self {
+        let (width, height) = (image.width, image.height);
+        let mut levels = image.pixels.clone();
+        let mut raster = Raster {
+            width: width as u32,
+            height: height as u32,
+            bits: vec![0; width.div_ceil(8) * height],
+        };
+        let stride = raster.stride();
+
+        for y in 0..height {
+            for x in 0..width {
+                let old = levels[y * width + x];
+                let black = old < 128.0;
+                let error = old - if black { 0.0 } else { 255.0 };
+                if black {
+                    raster.bits[y * stride + x / 8] |= 0x80 >> (x % 8);
+                }
+
+                let mut spread = |dx: isize, dy: usize, share: f32| {
+                    let nx = x as isize + dx;
+                    if nx >= 0 && (nx as usize) < width && y + dy < height {
+                        levels[(y + dy) * width + nx as usize] += error * share;
+                    }
+                };
+                spread(1, 0, 7.0 / 16.0);
+                spread(-1, 1, 3.0 / 16.0);
+                spread(0, 1, 5.0 / 16.0);
+                spread(1, 1, 1.0 / 16.0);
+            }
+        }
+        raster
+    }
+}
+
+struct Grey {
+    width: usize,
+    height: usize,
+    pixels: Vec<f32>,
+}
+
+/// Box-average `pixels` down to `max_width` (never up)
+fn scale(pixels: &[f32], width: usize, height: usize, max_width: usize) -> Grey {
+    if width <= max_width || width == 0 {
+        return Grey { width, height, pixels: pixels.to_vec() };
+    }
+
+    let ratio = width as f32 / max_width as f32;
+    let new_height = ((height as f32 / ratio).round() as usize).max(1);
+    let mut scaled = Vec::with_capacity(max_width * new_height);
+
+    for ny in 0..new_height {
+        let (y0, y1) = span(ny, ratio, height);
+        for nx in 0..max_width {
+            let (x0, x1) = span(nx, ratio, width);
+            let mut sum = 0.0;
+            for y in y0..y1 {
+                sum += pixels[y * width + x0..y * width + x1].iter().sum::<f32>();
+            }
+            scaled.push(sum / ((y1 - y0) * (x1 - x0)) as f32);
+        }
+    }
+
+    Grey { width: max_width, height: new_height, pixels: scaled }
+}
+
+fn span(i: usize, ratio: f32, limit: usize) -> (usize, usize) {
+    let start = ((i as f32 * ratio) as usize).min(limit - 1);
+    let end = (((i + 1) as f32 * ratio) as usize).clamp(start + 1, limit);
+    (start, end)
+}
@@ -0,0 +1,321 @@
+use rusqlite::Connection;
+
+use super::escpos::{Align, Barcode, Column, EscPos};
+use super::raster::Raster;
+use super::template;
+use crate::db::models::print_template::{PrintTemplate, TemplateField};
+use crate::db::models::ticket_detail::{TicketCharge, TicketItem, TicketItemModifier, TicketPayment};
+use crate::db::models::{app_state_repo, location, ticket_detail_repo, ticket_repo};
+use crate::error::AppResult;
+use crate::money::{store_currency, Money, Rate, Rounding};
+
+/// ==============================
+/// Customer receipt
+/// ==============================
+///
+/// What a receipt shows, read back from a saved ticket so a reprint
+/// matches the original. Void slips print from the same data.
+#[derive(Debug, Clone)]
+pub struct Receipt {
+    pub location_id: Option<String>,
+    pub location_name: String,
+    pub ticket_number: Option<i32>,
+    pub queue_number: Option<i32>,
+    pub order_mode: Option<String>,
+    pub created_at: Option<String>,
+    pub items: Vec<TicketItem>,
+    pub voided: Vec<TicketItem>,
+    pub charges: Vec<TicketCharge>,
+    pub payments: Vec<TicketPayment>,
+    pub total: Option<Money>,
+}
+
+impl Receipt {
+    pub fn load(conn: &Connection, ticket_id: &str) -> AppResult<Self> {
+        let ticket = ticket_repo::get_ticket(conn, ticket_id)?;
+        let detail = ticket_detail_repo::get_ticket_detail(conn, ticket_id)?;
+        let (voided, items) = detail.items.into_iter().partition(|i| i.voided);
+
+        Ok(Self {
+            location_name: location_name(conn, ticket.location_id.as_deref())?,
+            location_id: ticket.location_id,
+            ticket_number: ticket.ticket_number,
+            queue_number: ticket.queue_number,
+            order_mode: ticket.order_mode_name,
+            created_at: ticket.created_at,
+            items,
+            voided,
+            charges: detail.charges,
+            payments: detail.payments,
+            total: ticket.ticket_amount,
+        })
+    }
+
+    /// A made-up ticket for previewing templates
+    pub fn sample() -> Self {
+        let item = |name: &str, quantity: f64, price: f64, modifiers: Vec<TicketItemModifier>, voided: bool| TicketItem {
+            id: None,
+            ticket_id: "sample".into(),
+            sort_order: 0,
+            product_id: None,
+            product_name: name.into(),
+            category_id: None,
+            product_group_id: None,
+            quantity,
+            unit_price: money(price),
+            net_amount: money(price).times(quantity, Rounding::HalfUp),
+            tax_amount: Money::zero(),
+            charge_amount: Money::zero(),
+            voided,
+            refunded: false,
+            business_date: None,
+            order_time: None,
+            modifiers,
+        };
+        let cheese = TicketItemModifier { name: "Extra cheese".into(), quantity: 1.0, price: money(1.50) };
+
+        Self {
+            location_id: None,
+            location_name: "Sample Location".into(),
+            ticket_number: Some(1024),
+            queue_number: Some(17),
+            order_mode: Some("Dine In".into()),
+            created_at: Some(chrono::Utc::now().to_rfc3339()),
+            items: vec![
+                item("Classic Burger", 2.0, 12.00, vec![cheese], false),
+                item("Fries", 1.0, 4.50, Vec::new(), false),
+                item("Lemonade", 2.0, 3.25, Vec::new(), false),
+            ],
+            voided: vec![item("Onion Rings", 1.0, 5.00, Vec::new(), true)],
+            charges: vec![TicketCharge {
+                ticket_id: "sample".into(),
+                name: "VAT".into(),
+                is_tax: true,
+                percentage: Some(Rate::from_percent(5.0)),
+                amount: Some(money(2.28)),
+                transaction_type_id: None,
+            }],
+            payments: vec![TicketPayment {
+                ticket_id: "sample".into(),
+                payment_type_id: None,
+                payment_type: "Cash".into(),
+                amount: money(47.78),
+                tip_amount: Money::zero(),
+                tendered_amount: Some(money(50.00)),
+                net_amount: None,
+                currency: None,
+                business_date: None,
+                payment_date: None,
+                payment_time: None,
+            }],
+            total: None,
+        }
+    }
+
+    /// Items before charges
+    pub fn subtotal(&self) -> Money {
+        self.items.iter().map(line_total).sum()
+    }
+
+    /// Payment surcharges (or discounts), kept on the payments as tips
+    pub fn surcharge(&self) -> Money {
+        self.payments.iter().map(|p| p.tip_amount).sum()
+    }
+
+    pub fn total(&self) -> Money {
+        self.total.unwrap_or_else(|| {
+            self.subtotal() + self.charges.iter().filter_map(|c| c.amount).sum::<Money>() + self.surcharge()
+        })
+    }
+
+    pub fn tendered(&self) -> Money {
+        self.payments
+            .iter()
+            .map(|p| p.tendered_amount.unwrap_or(p.amount))
+            .sum()
+    }
+
+    pub fn change(&self) -> Money {
+        let paid: Money = self.payments.iter().map(|p| p.amount).sum();
+        let change = self.tendered() - paid;
+        if change.is_negative() {
+            Money::zero()
+        } else {
+            change
+        }
+    }
+
+    /// `created_at` in local time
+    pub fn printed_time(&self) -> String {
+        self.created_at.as_deref().map(template::local_time).unwrap_or_default()
+    }
+
+    /// Ticket, queue, mode and time lines, as far as `template` shows them
+    fn ticket_lines(&self, doc: &mut EscPos, template: &PrintTemplate) {
+        doc.align(Align::Center);
+        if let Some(number) = self.ticket_number.filter(|_| template.shows(TemplateField::TicketNumber)) {
+            doc.line(&format!("Ticket: {}", number));
+        }
+        if let Some(number) = self.queue_number.filter(|_| template.shows(TemplateField::QueueNumber)) {
+            doc.line(&format!("Queue: {}", number));
+        }
+        doc.align(Align::Left);
+        if let Some(mode) = self.order_mode.as_ref().filter(|_| template.shows(TemplateField::OrderMode)) {
+            doc.line(&format!("Mode: {}", mode));
+        }
+        if template.shows(TemplateField::DateTime) {
+            doc.line(&self.printed_time());
+        }
+        doc.rule('-');
+    }
+}
+
+/// The ESC/POS bytes for `receipt` laid out by `template`
+pub fn render(receipt: &Receipt, template: &PrintTemplate, logo: Option<&Raster>) -> Vec<u8> {
+    let mut doc = template::open(template, logo, &receipt.location_name);
+    receipt.ticket_lines(&mut doc, template);
+
+    // -------- ITEMS --------
+    let prices: Vec<(String, String)> = receipt
+        .items
+        .iter()
+        .map(|i| (i.unit_price.to_string(), line_total(i).to_string()))
+        .collect();
+    let amount_width = |pick: fn(&(String, String)) -> usize| prices.iter().map(pick).max().unwrap_or(0).max(5) + 1;
+    let columns = [
+        Column::fill(),
+        Column::right(4),
+        Column::right(amount_width(|p| p.0.len())),
+        Column::right(amount_width(|p| p.1.len())),
+    ];
+
+    doc.row(&columns, &["Item", "Qty", "Price", "Total"]).rule('-');
+    for (item, (price, total)) in receipt.items.iter().zip(&prices) {
+        doc.row(&columns, &[&item.product_name, &format!("x{}", quantity(item.quantity)), price, total]);
+        if template.shows(TemplateField::Modifiers) {
+            // Priced per item; the item's total already includes them
+            for modifier in &item.modifiers {
+                let price = modifier.price.times(modifier.quantity, Rounding::HalfUp);
+                let price = if price.is_zero() { String::new() } else { price.to_string() };
+                doc.row(&columns, &[&format!("  + {}", modifier.name), "", &price, ""]);
+            }
+        }
+    }
+    doc.rule('-');
+
+    // -------- TOTALS --------
+    doc.pair("Subtotal", &receipt.subtotal().to_string());
+    if template.shows(TemplateField::Charges) {
+        for charge in &receipt.charges {
+            let label = match charge.percentage {
+                Some(rate) => format!("{} ({})", charge.name, rate),
+                None => charge.name.clone(),
+            };
+            if let Some(amount) = charge.amount {
+                doc.pair(&label, &amount.to_string());
+            }
+        }
+        let surcharge = receipt.surcharge();
+        if !surcharge.is_zero() {
+            let label = if surcharge.is_negative() { "Payment Discount" } else { "Payment Surcharge" };
+            doc.pair(label, &surcharge.to_string());
+        }
+    }
+    doc.rule('-');
+
+    template::heading(&mut doc, template, &format!("TOTAL  {}", receipt.total()));
+    doc.rule('-');
+
+    // -------- PAYMENT --------
+    if template.shows(TemplateField::Payments) {
+        for payment in &receipt.payments {
+            doc.pair(&payment.payment_type, &payment.amount.to_string());
+        }
+    }
+    if template.shows(TemplateField::Change) {
+        doc.pair("Tendered", &receipt.tendered().to_string())
+            .pair("Change", &receipt.change().to_string());
+    }
+    if let Some(number) = receipt.ticket_number.filter(|_| template.shows(TemplateField::TicketBarcode)) {
+        doc.feed(1).align(Align::Center);
+        // Digits always encode in Code128
+        let _ = doc.barcode(Barcode::Code128, &number.to_string(), 60);
+        doc.align(Align::Left);
+    }
+
+    template::close(doc, template)
+}
+
+/// The voided items of `receipt`, for the kitchen or the manager
+pub fn render_void_slip(receipt: &Receipt, template: &PrintTemplate, logo: Option<&Raster>) -> Vec<u8> {
+    let mut doc = template::open(template, logo, &receipt.location_name);
+    // White on black until the heading resets the style
+    doc.invert(true);
+    template::heading(&mut doc, template, " VOID ");
+    receipt.ticket_lines(&mut doc, template);
+
+    let prices = template.shows(TemplateField::Prices);
+    let totals: Vec<String> = receipt.voided.iter().map(|i| line_total(i).to_string()).collect();
+    let mut columns = vec![Column::left(5), Column::fill()];
+    if prices {
+        columns.push(Column::right(totals.iter().map(String::len).max().unwrap_or(0) + 1));
+    }
+
+    for (item, total) in receipt.voided.iter().zip(&totals) {
+        doc.bold(true)
+            .row(&columns, &[&format!("x{}", quantity(item.quantity)), &item.product_name, total])
+            .bold(false);
+        if template.shows(TemplateField::Modifiers) {
+            for modifier in &item.modifiers {
+                doc.row(&columns, &["", &format!("+ {}", modifier.name)]);
+            }
+        }
+    }
+    if receipt.voided.is_empty() {
+        doc.line("No voided items");
+    }
+    doc.rule('-');
+
+    if prices {
+        let voided: Money = receipt.voided.iter().map(line_total).sum();
+        doc.pair("Voided", &voided.to_string());
+    }
+
+    template::close(doc, template)
+}
+
+/// The name printed for `location_id`, falling back to the selected location
+pub fn location_name(conn: &Connection, location_id: Option<&str>) -> AppResult<String> {
+    let name = match location_id {
+        Some(id) => location::get_name(conn, id)?,
+        None => None,
+    };
+    Ok(match name {
+        Some(name) => name,
+        None => app_state_repo::get_app_state(conn)?
+            .selected_location_name
+            .unwrap_or_default(),
+    })
+}
+
+fn line_total(item: &TicketItem) -> Money {
+    let modifiers: Money = item
+        .modifiers
+        .iter()
+        .map(|m| m.price.times(m.quantity, Rounding::HalfUp))
+        .sum();
+    (item.unit_price + modifiers).times(item.quantity, Rounding::HalfUp)
+}
+
+/// `2` rather than `2.0`, `0.5` as it is
+pub fn quantity(quantity: f64) -> String {
+    if quantity.fract() == 0.0 {
+        format!("{}", quantity as i64)
+    } else {
+        format!("{}", quantity)
+    }
+}
+
+fn money(amount: f64) -> Money {
+    Money::from_major(amount, store_currency(), Rounding::HalfUp)
+}
@@ -1,31 +1,27 @@
-use std::io::Write;
-use std::net::TcpStream;
-use std::time::Duration;
+use std::io::{ErrorKind, Write};
 // use log::info;
 
 use super::escpos::PrinterConfig;
+use super::status::{self, Paper, PrinterStatus};
+use super::transport::{self, Timeouts, Transport};
+use crate::error::{AppError, AppResult};
+
+/// Bytes skipped while waiting for a status answer before giving up
+const STRAY_BYTES: usize = 8;
 
 pub struct PrinterService;
 
 impl PrinterService {
 
-    pub fn test_print(config: &PrinterConfig) -> Result<(), String> {
+    pub fn test_print(config: &PrinterConfig) -> AppResult<()> {
         match config.printer_type.as_str() {
 
-            "network" => {
-                let ip = config.ip_address.as_ref()
-                    .ok_or("IP address not configured")?;
-                let port = config.port.unwrap_or(9100);
-                let address = format!("{}:{}", ip, port);
-
-                let stream = TcpStream::connect_timeout(
-                    &address.parse().map_err(|e| format!("Invalid address: {}", e))?,
-                    Duration::from_secs(5),
-                ).map_err(|e| format!("Failed to connect: {}", e))?;
+            "builtin" => {
+                Err(AppError::Unsupported("Builtin printer must be handled via Android bridge".to_string()))
+            }
 
-                let mut stream = stream;
-                stream.set_write_timeout(Some(Duration::from_secs(10)))
-                    .map_err(|e| format!("Timeout set failed: {}", e))?;
+            _ => {
+                let mut printer = transport::open(config, Timeouts::PRINT)?;
 
                 let commands: Vec<u8> = vec![
                     0x1B, 0x40,
@@ -40,56 +36,81 @@ impl PrinterService {
                     0x1D, 0x56, 0x00,
                 ];
 
-                stream.write_all(&commands)
-                    .map_err(|e| format!("Send failed: {}", e))?;
+                printer.write_all(&commands)
+                    .map_err(|e| AppError::PrinterUnavailable(format!("Send failed: {}", e)))?;
 
-                stream.flush()
-                    .map_err(|e| format!("Flush failed: {}", e))?;
+                printer.flush()
+                    .map_err(|e| AppError::PrinterUnavailable(format!("Flush failed: {}", e)))?;
 
                 Ok(())
             }
-
-            "builtin" => {
-                Err("Builtin printer must be handled via Android bridge".to_string())
-            }
-
-            _ => Err("Unsupported printer type".to_string()),
         }
     }
 
-    pub fn print_raw(config: &PrinterConfig, data: &[u8]) -> Result<(), String> {
+    pub fn print_raw(config: &PrinterConfig, data: &[u8]) -> AppResult<()> {
         match config.printer_type.as_str() {
 
-            "network" => {
-                let ip = config.ip_address.as_ref()
-                    .ok_or("IP not configured")?;
-                let port = config.port.unwrap_or(9100);
-
-                let address = format!("{}:{}", ip, port);
-
-                let stream = TcpStream::connect_timeout(
-                    &address.parse().map_err(|e| format!("Invalid address: {}", e))?,
-                    Duration::from_secs(5),
-                ).map_err(|e| format!("Connect failed: {}", e))?;
+            "builtin" => {
+                Err(AppError::Unsupported("Builtin printer must be printed via Android bridge".to_string()))
+            }
 
-                let mut stream = stream;
-                stream.set_write_timeout(Some(Duration::from_secs(10)))
-                    .map_err(|e| format!("Timeout set failed: {}", e))?;
+            _ => {
+                let mut printer = transport::open(config, Timeouts::PRINT)?;
 
-                stream.write_all(data)
-                    .map_err(|e| format!("Write failed: {}", e))?;
+                printer.write_all(data)
+                    .map_err(|e| AppError::PrinterUnavailable(format!("Write failed: {}", e)))?;
 
-                stream.flush()
-                    .map_err(|e| format!("Flush failed: {}", e))?;
+                printer.flush()
+                    .map_err(|e| AppError::PrinterUnavailable(format!("Flush failed: {}", e)))?;
 
                 Ok(())
             }
+        }
+    }
+
+    /// Ask the printer what state it's in with DLE EOT (GS r for the paper
+    /// sensor when DLE EOT 4 isn't answered). None when it doesn't answer
+    /// status queries at all; an error when it can't be reached.
+    pub fn query_status(config: &PrinterConfig) -> AppResult<Option<PrinterStatus>> {
+        match config.printer_type.as_str() {
 
             "builtin" => {
-                Err("Builtin printer must be printed via Android bridge".to_string())
+                Err(AppError::Unsupported("Builtin printer status must be read via Android bridge".to_string()))
             }
 
-            _ => Err("Unsupported printer type".to_string()),
+            _ => {
+                let mut stream = transport::open(config, Timeouts::STATUS)?;
+
+                let Some(printer) = ask(&mut *stream, &status::DLE_EOT_PRINTER, status::is_dle_eot_reply)? else {
+                    return Ok(None);
+                };
+                let offline_cause = ask(&mut *stream, &status::DLE_EOT_OFFLINE_CAUSE, status::is_dle_eot_reply)?;
+                let paper = match ask(&mut *stream, &status::DLE_EOT_PAPER, status::is_dle_eot_reply)? {
+                    Some(byte) => Some(Paper::DleEot(byte)),
+                    None => ask(&mut *stream, &status::GS_R_PAPER, status::is_gs_r_reply)?.map(Paper::GsR),
+                };
+
+                Ok(Some(PrinterStatus::decode(printer, offline_cause, paper)))
+            }
+        }
+    }
+}
+
+/// Send a status request and read the one-byte answer, skipping bytes that
+/// aren't one (`is_reply`). None when the printer stays silent.
+fn ask(stream: &mut dyn Transport, request: &[u8], is_reply: fn(u8) -> bool) -> AppResult<Option<u8>> {
+    stream.write_all(request)
+        .map_err(|e| AppError::PrinterUnavailable(format!("Write failed: {}", e)))?;
+
+    let mut byte = [0u8; 1];
+    for _ in 0..STRAY_BYTES {
+        match stream.read(&mut byte) {
+            Ok(0) => return Err(AppError::PrinterUnavailable("Connection closed".to_string())),
+            Ok(_) if is_reply(byte[0]) => return Ok(Some(byte[0])),
+            Ok(_) => continue,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => return Ok(None),
+            Err(e) => return Err(AppError::PrinterUnavailable(format!("Read failed: {}", e))),
         }
     }
+    Ok(None)
 }
@@ -0,0 +1,348 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{Mutex, Notify};
+
+use super::escpos::PrinterConfig;
+use super::service::PrinterService;
+use crate::db;
+use crate::db::models::print_job::PrintJob;
+use crate::db::models::print_job_repo;
+use crate::db::models::printer::Printer;
+use crate::db::models::printer_repo::PrinterRepo;
+use crate::db::pool::DbPool;
+use crate::error::{AppError, AppResult};
+
+/// Emitted for every job state change (`PrintJob`)
+pub const EVENT_JOB: &str = "print:job";
+
+/// ==============================
+/// Spooler configuration
+/// ==============================
+#[derive(Debug, Clone)]
+pub struct SpoolConfig {
+    /// Time between passes when nothing wakes the spooler
+    pub interval: Duration,
+    /// Attempts before a job is moved to DEAD
+    pub max_attempts: i32,
+    /// Delay after the first failure, doubled on each retry
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Jobs picked up per pass
+    pub batch_size: i64,
+    /// How long printed and cancelled jobs are kept for reprints
+    pub keep_printed: Duration,
+}
+
+impl Default for SpoolConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(10),
+            max_attempts: 10,
+            base_delay: Duration::from_secs(5),
+            max_delay: Duration::from_secs(5 * 60),
+            batch_size: 20,
+            keep_printed: Duration::from_secs(7 * 24 * 60 * 60),
+        }
+    }
+}
+
+impl SpoolConfig {
+    /// Exponential backoff: `base_delay * 2^(attempts - 1)`, capped at `max_delay`
+    pub fn backoff(&self, attempts: i32) -> Duration {
+        let exp = attempts.saturating_sub(1).clamp(0, 20) as u32;
+        self.base_delay.saturating_mul(1 << exp).min(self.max_delay)
+    }
+}
+
+/// Summary of one pass over the queue
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SpoolReport {
+    pub printed: usize,
+    /// Printed by a backup printer
+    pub failed_over: usize,
+    pub failed: usize,
+    pub dead: usize,
+}
+
+impl SpoolReport {
+    pub fn is_empty(&self) -> bool {
+        self.printed + self.failed + self.dead == 0
+    }
+}
+
+/// Where a job goes after an attempt
+enum Decision {
+    Printed { by: String },
+    Retry { error: String, next_attempt_at: String },
+    Dead { error: String },
+}
+
+fn timestamp(at: chrono::DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// ==============================
+/// Print spooler
+/// ==============================
+///
+/// Sends queued print jobs. A printer that can't print (unreachable, or
+/// last seen by the health check offline, out of paper or with its cover
+/// open) hands its jobs to its backup printer; when neither prints, the job
+/// is retried with exponential backoff and parked as DEAD once it runs out
+/// of attempts.
+///
+/// Jobs for one printer go out in the order they were queued: after a
+/// failure the rest of that printer's jobs wait as long as the failed one.
+#[derive(Clone)]
+pub struct PrintSpooler {
+    pool: DbPool,
+    config: Arc<SpoolConfig>,
+    run_lock: Arc<Mutex<()>>,
+    wake: Arc<Notify>,
+}
+
+impl PrintSpooler {
+    pub fn new(pool: DbPool, config: SpoolConfig) -> Self {
+        Self {
+            pool,
+            config: Arc::new(config),
+            run_lock: Arc::new(Mutex::new(())),
+            wake: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Cut the current sleep short and start the next pass
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Sleep for the poll interval, or until `wake` is called
+    pub async fn wait(&self) {
+        tokio::select! {
+            _ = self.wake.notified() => {}
+            _ = tokio::time::sleep(self.config.interval) => {}
+        }
+    }
+
+    /// Requeue jobs left in PRINTING (app closed mid-print) and drop old
+    /// printed ones. Returns (requeued, pruned).
+    pub fn recover(&self) -> AppResult<(usize, usize)> {
+        let conn = self.pool.writer()?;
+        let requeued = print_job_repo::reset_interrupted_jobs(&conn)?;
+
+        let keep = chrono::Duration::from_std(self.config.keep_printed).unwrap_or_default();
+        let pruned = print_job_repo::prune_jobs(&conn, &timestamp(Utc::now() - keep))?;
+
+        Ok((requeued, pruned))
+    }
+
+    /// Run one pass over due jobs. Passes never overlap, so a job can't be
+    /// sent twice by a wake-up arriving mid-pass.
+    pub async fn run_once<F>(&self, on_job: F) -> AppResult<SpoolReport>
+    where
+        F: Fn(PrintJob) + Send + Sync,
+    {
+        let _guard = self.run_lock.lock().await;
+        let mut report = SpoolReport::default();
+
+        let (jobs, printers) = {
+            let conn = self.pool.reader()?;
+            let now = timestamp(Utc::now());
+            let jobs = print_job_repo::get_due_jobs(&conn, &now, self.config.max_attempts, self.config.batch_size)?;
+            if jobs.is_empty() {
+                return Ok(report);
+            }
+
+            let printers: HashMap<String, Printer> = PrinterRepo::get_all(&conn)?
+                .into_iter()
+                .map(|p| (p.id.clone(), p))
+                .collect();
+            (jobs, printers)
+        };
+
+        // Printers that failed this pass; their later jobs keep their place
+        // (deferred with the failed one) rather than being tried again now
+        let mut down = HashSet::new();
+
+        for (job, data) in jobs {
+            if down.contains(&job.printer_id) {
+                continue;
+            }
+
+            print_job_repo::mark_job_printing(&*self.pool.writer()?, &job.id)?;
+            self.emit(&job.id, &on_job);
+
+            let result = send(&printers, &job.printer_id, data).await;
+            let decision = self.decide(result, job.attempts);
+
+            {
+                let conn = self.pool.writer()?;
+                match &decision {
+                    Decision::Printed { by } => print_job_repo::mark_job_printed(&conn, &job.id, by)?,
+                    Decision::Retry { error, next_attempt_at } => {
+                        print_job_repo::record_job_failure(&conn, &job.id, "FAILED", error, Some(next_attempt_at))?;
+                        print_job_repo::defer_printer_jobs(&conn, &job.printer_id, next_attempt_at)?;
+                    }
+                    Decision::Dead { error } => print_job_repo::record_job_failure(&conn, &job.id, "DEAD", error, None)?,
+                }
+            }
+
+            match decision {
+                Decision::Printed { by } => {
+                    report.printed += 1;
+                    if by != job.printer_id {
+                        report.failed_over += 1;
+                    }
+                }
+                Decision::Retry { error, next_attempt_at } => {
+                    report.failed += 1;
+                    down.insert(job.printer_id.clone());
+                    log::warn!("⚠️ Print job {} failed, retry at {}: {}", job.id, next_attempt_at, error);
+                }
+                Decision::Dead { error } => {
+                    report.dead += 1;
+                    down.insert(job.printer_id.clone());
+                    log::error!("☠️ Print job {} gave up: {}", job.id, error);
+                }
+            }
+            self.emit(&job.id, &on_job);
+        }
+
+        Ok(report)
+    }
+
+    /// Send the job's state as it is in the database now
+    fn emit(&self, id: &str, on_job: &(dyn Fn(PrintJob) + Send + Sync)) {
+        let job = self
+            .pool
+            .reader()
+            .map_err(AppError::from)
+            .and_then(|conn| print_job_repo::get_job(&conn, id));
+        match job {
+            Ok(job) => on_job(job),
+            Err(e) => log::warn!("⚠️ Could not read print job {}: {}", id, e),
+        }
+    }
+
+    fn decide(&self, result: AppResult<String>, attempts: i32) -> Decision {
+        let e = match result {
+            Ok(by) => return Decision::Printed { by },
+            Err(e) => e,
+        };
+
+        let attempts = attempts + 1;
+        if !e.is_retryable() || attempts >= self.config.max_attempts {
+            return Decision::Dead { error: e.to_string() };
+        }
+
+        let delay = chrono::Duration::from_std(self.config.backoff(attempts)).unwrap_or_default();
+        Decision::Retry {
+            error: e.to_string(),
+            next_attempt_at: timestamp(Utc::now() + delay),
+        }
+    }
+}
+
+/// Print `data` on `printer_id`, or on its backup when that fails.
+/// Returns the id of the printer that printed it.
+async fn send(printers: &HashMap<String, Printer>, printer_id: &str, data: Vec<u8>) -> AppResult<String> {
+    let printer = printers
+        .get(printer_id)
+        .ok_or_else(|| AppError::NotFound(format!("Printer {}", printer_id)))?;
+
+    let backup = printer
+        .backup_printer_id
+        .as_ref()
+        .and_then(|id| printers.get(id))
+        .filter(|b| b.id != printer.id && b.is_active && b.printer_type != "builtin");
+
+    let data = Arc::new(data);
+    let error = if printer.is_active {
+        match print(printer, data.clone()).await {
+            Ok(()) => return Ok(printer.id.clone()),
+            Err(e) => e,
+        }
+    } else {
+        // Still printable on the backup; retried in case it's switched back on
+        AppError::PrinterUnavailable(format!("Printer {} is not active", printer.name))
+    };
+
+    let Some(backup) = backup else {
+        return Err(error);
+    };
+    log::warn!("⚠️ Printer '{}' failed, trying backup '{}': {}", printer.name, backup.name, error);
+
+    match print(backup, data).await {
+        Ok(()) => Ok(backup.id.clone()),
+        Err(e) => Err(AppError::PrinterUnavailable(format!(
+            "{} (backup '{}': {})",
+            error, backup.name, e
+        ))),
+    }
+}
+
+async fn print(printer: &Printer, data: Arc<Vec<u8>>) -> AppResult<()> {
+    // Out of paper or with the cover open, a printer still takes the data
+    // and loses it; this way the job goes to the backup instead. The status
+    // is what the health check stored, not asked for again per job.
+    if !printer.status.can_print() {
+        return Err(AppError::PrinterUnavailable(format!(
+            "Printer {}: {}",
+            printer.name,
+            printer.status.describe()
+        )));
+    }
+
+    let config = PrinterConfig::from(printer);
+    tokio::task::spawn_blocking(move || PrinterService::print_raw(&config, &data))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+/// Start the spooler and register it as Tauri state
+pub fn start(app: &AppHandle, config: SpoolConfig) -> anyhow::Result<()> {
+    let spooler = PrintSpooler::new(db::pool(app)?, config);
+    app.manage(spooler.clone());
+
+    match spooler.recover() {
+        Ok((0, 0)) => {}
+        Ok((requeued, pruned)) => {
+            log::info!("🖨️ Requeued {} interrupted print jobs, pruned {} old ones", requeued, pruned)
+        }
+        Err(e) => log::warn!("⚠️ Failed to recover print jobs: {}", e),
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        log::info!("🖨️ Print spooler started");
+
+        loop {
+            let result = spooler
+                .run_once(|job| {
+                    let _ = app.emit(EVENT_JOB, &job);
+                })
+                .await;
+
+            match result {
+                Ok(report) if !report.is_empty() => log::info!(
+                    "🖨️ Spool pass: {} printed ({} on a backup), {} failed, {} dead",
+                    report.printed,
+                    report.failed_over,
+                    report.failed,
+                    report.dead
+                ),
+                Ok(_) => {}
+                Err(e) => log::warn!("⚠️ Spool pass failed: {}", e),
+            }
+
+            spooler.wait().await;
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+
+/// DLE EOT n: real-time status, answered even while the printer is busy
+pub const DLE_EOT_PRINTER: [u8; 3] = [0x10, 0x04, 1];
+pub const DLE_EOT_OFFLINE_CAUSE: [u8; 3] = [0x10, 0x04, 2];
+pub const DLE_EOT_PAPER: [u8; 3] = [0x10, 0x04, 4];
+/// GS r 1: paper sensor status, for printers whose DLE EOT 4 is missing
+pub const GS_R_PAPER: [u8; 3] = [0x1D, 0x72, 1];
+
+/// What a printer last reported, as stored in `printers.status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrinterHealth {
+    Online,
+    /// Near-end sensor: a few metres of paper left
+    PaperLow,
+    PaperOut,
+    CoverOpen,
+    /// Cutter jam, overheating and the like
+    Error,
+    /// Unreachable, or reports itself offline
+    Offline,
+    /// Never checked, or doesn't answer status queries
+    #[default]
+    Unknown,
+}
+
+impl PrinterHealth {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PrinterHealth::Online => "online",
+            PrinterHealth::PaperLow => "paper_low",
+            PrinterHealth::PaperOut => "paper_out",
+            PrinterHealth::CoverOpen => "cover_open",
+            PrinterHealth::Error => "error",
+            PrinterHealth::Offline => "offline",
+            PrinterHealth::Unknown => "unknown",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "online" => Some(PrinterHealth::Online),
+            "paper_low" => Some(PrinterHealth::PaperLow),
+            "paper_out" => Some(PrinterHealth::PaperOut),
+            "cover_open" => Some(PrinterHealth::CoverOpen),
+            "error" => Some(PrinterHealth::Error),
+            "offline" => Some(PrinterHealth::Offline),
+            "unknown" => Some(PrinterHealth::Unknown),
+            _ => None,
+        }
+    }
+
+    /// Whether a job sent now should come out. Unknown is given the benefit
+    /// of the doubt: plenty of printers don't answer status queries.
+    pub fn can_print(self) -> bool {
+        matches!(self, PrinterHealth::Online | PrinterHealth::PaperLow | PrinterHealth::Unknown)
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            PrinterHealth::Online => "ready",
+            PrinterHealth::PaperLow => "paper low",
+            PrinterHealth::PaperOut => "out of paper",
+            PrinterHealth::CoverOpen => "cover open",
+            PrinterHealth::Error => "printer error",
+            PrinterHealth::Offline => "offline",
+            PrinterHealth::Unknown => "status unknown",
+        }
+    }
+}
+
+/// ==============================
+/// Printer status
+/// ==============================
+///
+/// The flags a printer reports back, decoded from its DLE EOT / GS r
+/// status bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PrinterStatus {
+    pub offline: bool,
+    pub cover_open: bool,
+    pub paper_near_end: bool,
+    pub paper_out: bool,
+    pub error: bool,
+}
+
+impl PrinterStatus {
+    /// From the answers to DLE EOT 1 and 2, and the paper sensor byte from
+    /// DLE EOT 4 or GS r 1 when the printer answered either
+    pub fn decode(printer: u8, offline_cause: Option<u8>, paper: Option<Paper>) -> Self {
+        let cause = offline_cause.unwrap_or(0);
+        let (paper_near_end, paper_out) = match paper {
+            // Bits 2-3 near end, 5-6 paper end
+            Some(Paper::DleEot(b)) => (b & 0x0C != 0, b & 0x60 != 0),
+            // Bits 0-1 near end, 2-3 paper end
+            Some(Paper::GsR(b)) => (b & 0x03 != 0, b & 0x0C != 0),
+            None => (false, false),
+        };
+
+        Self {
+            offline: printer & 0x08 != 0,
+            cover_open: cause & 0x04 != 0,
+            paper_near_end,
+            // Offline because the paper ran out, even without a paper sensor answer
+            paper_out: paper_out || cause & 0x20 != 0,
+            error: cause & 0x40 != 0,
+        }
+    }
+
+    /// The most pressing condition
+    pub fn health(&self) -> PrinterHealth {
+        if self.paper_out {
+            PrinterHealth::PaperOut
+        } else if self.cover_open {
+            PrinterHealth::CoverOpen
+        } else if self.error {
+            PrinterHealth::Error
+        } else if self.offline {
+            PrinterHealth::Offline
+        } else if self.paper_near_end {
+            PrinterHealth::PaperLow
+        } else {
+            PrinterHealth::Online
+        }
+    }
+}
+
+/// A paper sensor status byte, by the command that returned it
+#[derive(Debug, Clone, Copy)]
+pub enum Paper {
+    DleEot(u8),
+    GsR(u8),
+}
+
+/// Whether `byte` looks like a DLE EOT answer: bits 1 and 4 set, bits 0
+/// and 7 clear. Filters out stray bytes such as automatic status back.
+pub fn is_dle_eot_reply(byte: u8) -> bool {
+    byte & 0x93 == 0x12
+}
+
+/// GS r answers have bits 4 and 7 clear
+pub fn is_gs_r_reply(byte: u8) -> bool {
+    byte & 0x90 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Case {
+        name: &'static str,
+        /// DLE EOT 1
+        printer: u8,
+        /// DLE EOT 2
+        offline_cause: Option<u8>,
+        paper: Option<Paper>,
+        expect: PrinterHealth,
+    }
+
+    // Every DLE EOT answer has bits 1 and 4 set (0x12) on top of its flags
+    fn cases() -> Vec<Case> {
+        let case = |name, printer, offline_cause, paper, expect| Case { name, printer, offline_cause, paper, expect };
+        vec![
+            case("online", 0x12, Some(0x12), Some(Paper::DleEot(0x12)), PrinterHealth::Online),
+            case("only DLE EOT 1 answered", 0x12, None, None, PrinterHealth::Online),
+            case("feed button held", 0x12, Some(0x1A), Some(Paper::DleEot(0x12)), PrinterHealth::Online),
+            case("offline", 0x1A, Some(0x12), Some(Paper::DleEot(0x12)), PrinterHealth::Offline),
+            case("cover open", 0x1A, Some(0x16), Some(Paper::DleEot(0x12)), PrinterHealth::CoverOpen),
+            case("error", 0x1A, Some(0x52), Some(Paper::DleEot(0x12)), PrinterHealth::Error),
+            case("paper end, DLE EOT 4", 0x1A, Some(0x12), Some(Paper::DleEot(0x72)), PrinterHealth::PaperOut),
+            case("paper end, one DLE EOT 4 bit", 0x1A, Some(0x12), Some(Paper::DleEot(0x32)), PrinterHealth::PaperOut),
+            case("paper end, offline cause only", 0x1A, Some(0x32), None, PrinterHealth::PaperOut),
+            case("paper end beats cover open", 0x1A, Some(0x36), Some(Paper::DleEot(0x72)), PrinterHealth::PaperOut),
+            case("near end, DLE EOT 4", 0x12, Some(0x12), Some(Paper::DleEot(0x1E)), PrinterHealth::PaperLow),
+            case("near end, one DLE EOT 4 bit", 0x12, Some(0x12), Some(Paper::DleEot(0x16)), PrinterHealth::PaperLow),
+            case("near end, GS r", 0x12, Some(0x12), Some(Paper::GsR(0x03)), PrinterHealth::PaperLow),
+            case("paper end, GS r", 0x12, Some(0x12), Some(Paper::GsR(0x0C)), PrinterHealth::PaperOut),
+            case("paper present, GS r", 0x12, Some(0x12), Some(Paper::GsR(0x00)), PrinterHealth::Online),
+            case("offline beats near end", 0x1A, Some(0x12), Some(Paper::GsR(0x03)), PrinterHealth::Offline),
+        ]
+    }
+
+    #[test]
+    fn status_bytes_to_health() {
+        for case in cases() {
+            let status = PrinterStatus::decode(case.printer, case.offline_cause, case.paper);
+            assert_eq!(status.health(), case.expect, "{}: health", case.name);
+        }
+    }
+
+    #[test]
+    fn only_status_replies_are_taken() {
+        for byte in [0x12, 0x16, 0x1A, 0x32, 0x52, 0x72, 0x7E] {
+            assert!(is_dle_eot_reply(byte), "DLE EOT {:#04x}", byte);
+        }
+        // Bit 0 or 7 set, or bit 1 or 4 clear
+        for byte in [0x00, 0x02, 0x10, 0x13, 0x92] {
+            assert!(!is_dle_eot_reply(byte), "not DLE EOT {:#04x}", byte);
+        }
+
+        for byte in [0x00, 0x03, 0x0C, 0x0F] {
+            assert!(is_gs_r_reply(byte), "GS r {:#04x}", byte);
+        }
+        for byte in [0x10, 0x12, 0x80] {
+            assert!(!is_gs_r_reply(byte), "not GS r {:#04x}", byte);
+        }
+    }
+
+    #[test]
+    fn only_a_printer_that_can_print_gets_jobs() {
+        let printing = [PrinterHealth::Online, PrinterHealth::PaperLow, PrinterHealth::Unknown];
+        for health in [
+            PrinterHealth::Online,
+            PrinterHealth::PaperLow,
+            PrinterHealth::PaperOut,
+            PrinterHealth::CoverOpen,
+            PrinterHealth::Error,
+            PrinterHealth::Offline,
+            PrinterHealth::Unknown,
+        ] {
+            assert_eq!(health.can_print(), printing.contains(&health), "{:?}", health);
+            assert_eq!(PrinterHealth::parse(health.as_str()), Some(health));
+        }
+    }
+}
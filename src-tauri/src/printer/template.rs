@@ -0,0 +1,67 @@
+use super::escpos::{Align, Cut, EscPos, Font};
+use super::raster::Raster;
+use crate::db::models::print_template::{FontSize, PrintTemplate};
+
+// ==============================
+// Template layout
+// ==============================
+//
+// The parts every document shares: logo, title and header on top, the
+// footer and cut at the bottom, and the body font in between.
+
+/// A new document for `template`, up to and including its header, left
+/// in the body font
+pub fn open(template: &PrintTemplate, logo: Option<&Raster>, title: &str) -> EscPos {
+    let mut doc = EscPos::new(template.paper_width);
+
+    if let Some(logo) = logo.filter(|_| template.show_logo) {
+        doc.align(Align::Center).image(logo).feed(1);
+    }
+    heading(&mut doc, template, title);
+    if let Some(header) = non_empty(&template.header) {
+        doc.align(Align::Center).line(header).align(Align::Left);
+    }
+    doc
+}
+
+/// The footer, a cut, and the finished bytes
+pub fn close(mut doc: EscPos, template: &PrintTemplate) -> Vec<u8> {
+    doc.feed(1);
+    if let Some(footer) = non_empty(&template.footer) {
+        doc.align(Align::Center).line(footer);
+    }
+    doc.feed(2).cut(Cut::Full);
+    doc.build()
+}
+
+/// `text` large, bold and centered, then back to the body font
+pub fn heading(doc: &mut EscPos, template: &PrintTemplate, text: &str) {
+    doc.font(Font::A)
+        .align(Align::Center)
+        .bold(true)
+        .size(2, 2)
+        .line(text)
+        .reset_style();
+    body(doc, template);
+}
+
+/// Switch to the template's body font, left aligned
+pub fn body(doc: &mut EscPos, template: &PrintTemplate) {
+    let (font, height) = match template.font_size {
+        FontSize::Small => (Font::B, 1),
+        FontSize::Normal => (Font::A, 1),
+        FontSize::Large => (Font::A, 2),
+    };
+    doc.font(font).size(1, height).align(Align::Left);
+}
+
+/// An RFC 3339 timestamp as local time for print
+pub fn local_time(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+fn non_empty(text: &Option<String>) -> Option<&str> {
+    text.as_deref().map(str::trim).filter(|t| !t.is_empty())
+}
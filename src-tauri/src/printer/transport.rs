@@ -0,0 +1,367 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::escpos::PrinterConfig;
+use crate::error::{AppError, AppResult};
+
+/// Baud rate for serial printers that don't set one; the factory default of
+/// most ESC/POS printers
+pub const DEFAULT_BAUD_RATE: u32 = 9600;
+
+/// A connection to a printer, whatever the bytes travel over
+pub trait Transport: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Transport for T {}
+
+/// How long to wait on a printer
+#[derive(Debug, Clone, Copy)]
+pub struct Timeouts {
+    /// Connecting to a network printer
+    pub connect: Duration,
+    pub write: Duration,
+    pub read: Duration,
+}
+
+impl Timeouts {
+    pub const PRINT: Timeouts = Timeouts {
+        connect: Duration::from_secs(5),
+        write: Duration::from_secs(10),
+        read: Duration::from_secs(1),
+    };
+
+    /// Printers that support status queries answer within a few milliseconds
+    pub const STATUS: Timeouts = Timeouts {
+        connect: Duration::from_secs(5),
+        write: Duration::from_secs(2),
+        read: Duration::from_secs(1),
+    };
+}
+
+/// A printer attached to this machine, as offered when adding one
+#[derive(Debug, Clone, Serialize)]
+pub struct PrinterDevice {
+    /// "usb" or "serial", as `printer_type`
+    pub printer_type: String,
+    pub device_path: String,
+    /// Maker and model, when the system knows them
+    pub description: Option<String>,
+}
+
+/// Whether this build can drive `printer_type` printers itself
+pub fn supports(printer_type: &str) -> bool {
+    printer_type == "network"
+        || (cfg!(target_os = "linux") && printer_type == "usb")
+        || (cfg!(desktop) && printer_type == "serial")
+}
+
+/// ==============================
+/// Printer transports
+/// ==============================
+///
+/// Opens the printer `config` points at: a raw TCP socket (port 9100) for
+/// "network", the kernel's USB printer device (`/dev/usb/lp*`) for "usb",
+/// a serial or USB-serial port for "serial".
+pub fn open(config: &PrinterConfig, timeouts: Timeouts) -> AppResult<Box<dyn Transport>> {
+    match config.printer_type.as_str() {
+        "network" => open_network(config, timeouts),
+        #[cfg(target_os = "linux")]
+        "usb" => usb::open(device_path(config)?, timeouts),
+        #[cfg(desktop)]
+        "serial" => serial::open(device_path(config)?, config.baud_rate.unwrap_or(DEFAULT_BAUD_RATE), timeouts),
+        _ => Err(AppError::Unsupported(format!("printer type {}", config.printer_type))),
+    }
+}
+
+/// USB and serial printers attached to this machine
+pub fn list_devices() -> AppResult<Vec<PrinterDevice>> {
+    #[allow(unused_mut)]
+    let mut devices = Vec::new();
+    #[cfg(target_os = "linux")]
+    devices.extend(usb::list()?);
+    #[cfg(desktop)]
+    devices.extend(serial::list()?);
+    Ok(devices)
+}
+
+fn open_network(config: &PrinterConfig, timeouts: Timeouts) -> AppResult<Box<dyn Transport>> {
+    let ip = config.ip_address.as_ref()
+        .ok_or_else(|| AppError::InvalidInput("IP not configured".to_string()))?;
+    let port = config.port.unwrap_or(9100);
+
+    let address = format!("{}:{}", ip, port);
+
+    let stream = TcpStream::connect_timeout(
+        &address.parse().map_err(|e| AppError::InvalidInput(format!("Invalid address: {}", e)))?,
+        timeouts.connect,
+    ).map_err(|e| AppError::PrinterUnavailable(format!("Connect failed: {}", e)))?;
+
+    stream.set_write_timeout(Some(timeouts.write))
+        .map_err(|e| AppError::PrinterUnavailable(format!("Timeout set failed: {}", e)))?;
+    stream.set_read_timeout(Some(timeouts.read))
+        .map_err(|e| AppError::PrinterUnavailable(format!("Timeout set failed: {}", e)))?;
+
+    Ok(Box::new(stream))
+}
+
+#[cfg(any(target_os = "linux", desktop))]
+fn device_path(config: &PrinterConfig) -> AppResult<&str> {
+    config.device_path.as_deref()
+        .filter(|path| !path.is_empty())
+        .ok_or_else(|| AppError::InvalidInput("Device path not configured".to_string()))
+}
+
+/// Device nodes open in this process. The kernel lets one file handle at a
+/// time have a printer port, so the spooler and the health check wait for
+/// each other here instead of failing with EBUSY. The wait covers a long job
+/// on a slow serial line: a check arriving mid-print shouldn't report the
+/// printer offline.
+#[cfg(any(target_os = "linux", desktop))]
+mod claim {
+    use std::collections::HashSet;
+    use std::sync::{Condvar, Mutex};
+    use std::time::Duration;
+
+    use once_cell::sync::Lazy;
+
+    use crate::error::{AppError, AppResult};
+
+    const WAIT: Duration = Duration::from_secs(30);
+
+    static OPEN: Lazy<(Mutex<HashSet<String>>, Condvar)> = Lazy::new(Default::default);
+
+    /// A device held by this process until dropped
+    pub struct Claim(String);
+
+    pub fn claim(path: &str) -> AppResult<Claim> {
+        let (open, released) = &*OPEN;
+        let guard = open.lock().map_err(|e| AppError::Internal(e.to_string()))?;
+        let (mut guard, _) = released
+            .wait_timeout_while(guard, WAIT, |open| open.contains(path))
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+
+        if !guard.insert(path.to_string()) {
+            return Err(AppError::PrinterUnavailable(format!("{} is busy", path)));
+        }
+        Ok(Claim(path.to_string()))
+    }
+
+    impl Drop for Claim {
+        fn drop(&mut self) {
+            let (open, released) = &*OPEN;
+            if let Ok(mut open) = open.lock() {
+                open.remove(&self.0);
+            }
+            released.notify_all();
+        }
+    }
+}
+
+/// Raw USB printers, through the kernel's usblp driver
+#[cfg(target_os = "linux")]
+mod usb {
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{self, Read, Write};
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::os::unix::io::AsRawFd;
+    use std::time::Instant;
+
+    use super::claim::{claim, Claim};
+    use super::{PrinterDevice, Timeouts, Transport};
+    use crate::error::{AppError, AppResult};
+
+    const DEVICE_DIR: &str = "/dev/usb";
+
+    /// Opened non-blocking so every read and write can time out: a blocking
+    /// write to a printer that is out of paper never returns.
+    struct LinePrinter {
+        file: File,
+        timeouts: Timeouts,
+        _claim: Claim,
+    }
+
+    pub fn open(path: &str, timeouts: Timeouts) -> AppResult<Box<dyn Transport>> {
+        let claim = claim(path)?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)
+            .map_err(|e| AppError::PrinterUnavailable(format!("Open {} failed: {}", path, e)))?;
+
+        Ok(Box::new(LinePrinter { file, timeouts, _claim: claim }))
+    }
+
+    /// `/dev/usb/lp*`, named after the product the printer reports
+    pub fn list() -> AppResult<Vec<PrinterDevice>> {
+        let entries = match fs::read_dir(DEVICE_DIR) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(AppError::Internal(format!("Read {} failed: {}", DEVICE_DIR, e))),
+        };
+
+        let mut names: Vec<String> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("lp"))
+            .collect();
+        names.sort();
+
+        Ok(names
+            .into_iter()
+            .map(|name| PrinterDevice {
+                printer_type: "usb".to_string(),
+                device_path: format!("{}/{}", DEVICE_DIR, name),
+                description: describe(&name),
+            })
+            .collect())
+    }
+
+    /// "Manufacturer Product" from sysfs; the usbmisc node sits on the
+    /// interface, the strings on its parent device
+    fn describe(name: &str) -> Option<String> {
+        let read = |file: &str| {
+            fs::read_to_string(format!("/sys/class/usbmisc/{}/device/../{}", name, file))
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+
+        match (read("manufacturer"), read("product")) {
+            (Some(maker), Some(product)) => Some(format!("{} {}", maker, product)),
+            (maker, product) => product.or(maker),
+        }
+    }
+
+    impl LinePrinter {
+        /// Wait until the device is ready for `events`, or time out at `deadline`
+        fn wait(&self, events: libc::c_short, deadline: Instant) -> io::Result<()> {
+            loop {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if left.is_zero() {
+                    return Err(io::ErrorKind::TimedOut.into());
+                }
+
+                let mut fd = libc::pollfd { fd: self.file.as_raw_fd(), events, revents: 0 };
+                let millis = left.as_millis().clamp(1, i32::MAX as u128) as libc::c_int;
+                // SAFETY: `fd` is a single valid pollfd for the duration of the call
+                match unsafe { libc::poll(&mut fd, 1, millis) } {
+                    -1 => {
+                        let e = io::Error::last_os_error();
+                        if e.kind() != io::ErrorKind::Interrupted {
+                            return Err(e);
+                        }
+                    }
+                    0 => return Err(io::ErrorKind::TimedOut.into()),
+                    _ => return Ok(()),
+                }
+            }
+        }
+    }
+
+    impl Read for LinePrinter {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let deadline = Instant::now() + self.timeouts.read;
+            loop {
+                match self.file.read(buf) {
+                    // Nothing yet, or an empty status transfer
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => self.wait(libc::POLLIN, deadline)?,
+                    Ok(0) if !buf.is_empty() => self.wait(libc::POLLIN, deadline)?,
+                    // usblp refuses reads from printers that can't talk back
+                    Err(e) if e.kind() == io::ErrorKind::InvalidInput => return Err(io::ErrorKind::TimedOut.into()),
+                    result => return result,
+                }
+            }
+        }
+    }
+
+    impl Write for LinePrinter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let deadline = Instant::now() + self.timeouts.write;
+            loop {
+                match self.file.write(buf) {
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => self.wait(libc::POLLOUT, deadline)?,
+                    result => return result,
+                }
+            }
+        }
+
+        /// Wait for the last transfer: closing the device cancels one still
+        /// in flight, cutting the end off the job
+        fn flush(&mut self) -> io::Result<()> {
+            self.wait(libc::POLLOUT, Instant::now() + self.timeouts.write)
+        }
+    }
+}
+
+/// Serial and USB-serial printers
+#[cfg(desktop)]
+mod serial {
+    use std::io::{self, Read, Write};
+
+    use serialport::{SerialPort, SerialPortType};
+
+    use super::claim::{claim, Claim};
+    use super::{PrinterDevice, Timeouts, Transport};
+    use crate::error::{AppError, AppResult};
+
+    /// serialport has one timeout for reads and writes; set before each
+    struct SerialPrinter {
+        port: Box<dyn SerialPort>,
+        timeouts: Timeouts,
+        _claim: Claim,
+    }
+
+    pub fn open(path: &str, baud_rate: u32, timeouts: Timeouts) -> AppResult<Box<dyn Transport>> {
+        let claim = claim(path)?;
+        let port = serialport::new(path, baud_rate)
+            .timeout(timeouts.write)
+            .open()
+            .map_err(|e| AppError::PrinterUnavailable(format!("Open {} failed: {}", path, e)))?;
+
+        Ok(Box::new(SerialPrinter { port, timeouts, _claim: claim }))
+    }
+
+    pub fn list() -> AppResult<Vec<PrinterDevice>> {
+        let ports = serialport::available_ports()
+            .map_err(|e| AppError::Internal(format!("Listing serial ports failed: {}", e)))?;
+
+        Ok(ports
+            .into_iter()
+            .map(|port| PrinterDevice {
+                printer_type: "serial".to_string(),
+                device_path: port.port_name,
+                description: match port.port_type {
+                    SerialPortType::UsbPort(usb) => match (usb.manufacturer, usb.product) {
+                        (Some(maker), Some(product)) => Some(format!("{} {}", maker, product)),
+                        (maker, product) => product
+                            .or(maker)
+                            .or_else(|| Some(format!("USB {:04x}:{:04x}", usb.vid, usb.pid))),
+                    },
+                    SerialPortType::BluetoothPort => Some("Bluetooth".to_string()),
+                    SerialPortType::PciPort | SerialPortType::Unknown => None,
+                },
+            })
+            .collect())
+    }
+
+    impl Read for SerialPrinter {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.port.set_timeout(self.timeouts.read)?;
+            self.port.read(buf)
+        }
+    }
+
+    impl Write for SerialPrinter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.port.set_timeout(self.timeouts.write)?;
+            self.port.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.port.flush()
+        }
+    }
+}
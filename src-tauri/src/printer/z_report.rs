@@ -0,0 +1,208 @@
+use rusqlite::Connection;
+
+use super::escpos::EscPos;
+use super::raster::Raster;
+use super::receipt::{location_name, quantity};
+use super::template;
+use crate::db::models::print_template::{PrintTemplate, TemplateField};
+use crate::db::models::report::{CategoryTotal, ChargeTotal, LineAdjustments, OrderModeTotal, SalesReport};
+use crate::db::models::ticket_detail::PaymentTotal;
+use crate::db::models::workday_repo;
+use crate::error::{AppError, AppResult};
+use crate::money::{store_currency, Money, Rate, Rounding};
+
+/// ==============================
+/// Z-report
+/// ==============================
+///
+/// A workday's sales report on paper: the stored Z-report once the day is
+/// closed, an X-report up to now while it's open.
+#[derive(Debug, Clone)]
+pub struct ZReport {
+    pub location_name: String,
+    /// False for an X-report of an open workday
+    pub closed: bool,
+    pub closed_by: Option<String>,
+    pub report: SalesReport,
+}
+
+impl ZReport {
+    pub fn load(conn: &Connection, workday_id: i64) -> AppResult<Self> {
+        let workday = workday_repo::get_workday_by_id(conn, workday_id)?
+            .ok_or_else(|| AppError::NotFound(format!("Workday with id {}", workday_id)))?;
+
+        let (closed, report) = match &workday.z_report {
+            Some(z_report) => (true, serde_json::from_str(z_report)?),
+            None => (false, workday_repo::build_x_report(conn, workday_id)?),
+        };
+
+        Ok(Self {
+            location_name: location_name(conn, Some(&workday.location_id))?,
+            closed,
+            closed_by: workday.end_user.filter(|_| closed),
+            report,
+        })
+    }
+
+    /// A made-up day for previewing templates
+    pub fn sample() -> Self {
+        let now = chrono::Utc::now();
+        let money = |amount: f64| Money::from_major(amount, store_currency(), Rounding::HalfUp);
+
+        Self {
+            location_name: "Sample Location".into(),
+            closed: true,
+            closed_by: Some("Manager".into()),
+            report: SalesReport {
+                location_id: String::new(),
+                from: (now - chrono::Duration::hours(10)).to_rfc3339(),
+                to: now.to_rfc3339(),
+                generated_at: now.to_rfc3339(),
+                ticket_count: 48,
+                gross_sales: money(1250.00),
+                total_taxes: money(62.50),
+                total_charges: money(40.00),
+                total_payments: money(1352.50),
+                total_tips: money(35.00),
+                by_payment_method: vec![
+                    PaymentTotal {
+                        payment_type_id: None,
+                        payment_type: "Cash".into(),
+                        count: 30,
+                        amount: money(802.50),
+                        tip_amount: money(10.00),
+                    },
+                    PaymentTotal {
+                        payment_type_id: None,
+                        payment_type: "Card".into(),
+                        count: 18,
+                        amount: money(550.00),
+                        tip_amount: money(25.00),
+                    },
+                ],
+                by_order_mode: vec![
+                    OrderModeTotal { order_mode_name: Some("Dine In".into()), ticket_count: 28, amount: money(830.00) },
+                    OrderModeTotal { order_mode_name: Some("Takeaway".into()), ticket_count: 20, amount: money(522.50) },
+                ],
+                by_category: vec![
+                    CategoryTotal {
+                        category_id: None,
+                        category_name: Some("Mains".into()),
+                        quantity: 64.0,
+                        net_amount: money(960.00),
+                    },
+                    CategoryTotal {
+                        category_id: None,
+                        category_name: Some("Drinks".into()),
+                        quantity: 90.0,
+                        net_amount: money(290.00),
+                    },
+                ],
+                charges: vec![
+                    ChargeTotal {
+                        name: "VAT".into(),
+                        is_tax: true,
+                        percentage: Some(Rate::from_percent(5.0)),
+                        ticket_count: 48,
+                        amount: money(62.50),
+                    },
+                    ChargeTotal {
+                        name: "Service".into(),
+                        is_tax: false,
+                        percentage: None,
+                        ticket_count: 12,
+                        amount: money(40.00),
+                    },
+                ],
+                voids: LineAdjustments { lines: 3, quantity: 3.0, amount: money(21.00) },
+                refunds: LineAdjustments { lines: 1, quantity: 1.0, amount: money(12.00) },
+            },
+        }
+    }
+}
+
+/// The ESC/POS bytes for `z` laid out by `template`
+pub fn render(z: &ZReport, template: &PrintTemplate, logo: Option<&Raster>) -> Vec<u8> {
+    let report = &z.report;
+    let mut doc = template::open(template, logo, &z.location_name);
+    template::heading(&mut doc, template, if z.closed { "Z REPORT" } else { "X REPORT" });
+
+    if template.shows(TemplateField::DateTime) {
+        doc.pair("From", &template::local_time(&report.from))
+            .pair("To", &template::local_time(&report.to));
+    }
+    if let Some(user) = &z.closed_by {
+        doc.pair("Closed by", user);
+    }
+    doc.rule('-');
+
+    doc.pair("Tickets", &report.ticket_count.to_string())
+        .pair("Gross sales", &report.gross_sales.to_string())
+        .pair("Charges", &report.total_charges.to_string())
+        .pair("Taxes", &report.total_taxes.to_string());
+    if template.shows(TemplateField::Tips) {
+        doc.pair("Tips", &report.total_tips.to_string());
+    }
+    doc.bold(true)
+        .pair("Payments", &report.total_payments.to_string())
+        .bold(false);
+
+    if template.shows(TemplateField::Payments) && !report.by_payment_method.is_empty() {
+        section(&mut doc, "PAYMENTS");
+        for payment in &report.by_payment_method {
+            doc.pair(
+                &format!("{} ({})", payment.payment_type, payment.count),
+                &payment.amount.to_string(),
+            );
+        }
+    }
+
+    if template.shows(TemplateField::OrderModes) && !report.by_order_mode.is_empty() {
+        section(&mut doc, "ORDER MODES");
+        for mode in &report.by_order_mode {
+            let name = mode.order_mode_name.as_deref().unwrap_or("Other");
+            doc.pair(&format!("{} ({})", name, mode.ticket_count), &mode.amount.to_string());
+        }
+    }
+
+    if template.shows(TemplateField::Categories) && !report.by_category.is_empty() {
+        section(&mut doc, "CATEGORIES");
+        for category in &report.by_category {
+            let name = category.category_name.as_deref().unwrap_or("Uncategorized");
+            doc.pair(
+                &format!("{} x{}", name, quantity(category.quantity)),
+                &category.net_amount.to_string(),
+            );
+        }
+    }
+
+    if template.shows(TemplateField::Charges) && !report.charges.is_empty() {
+        section(&mut doc, "TAXES & CHARGES");
+        for charge in &report.charges {
+            let name = match charge.percentage {
+                Some(rate) => format!("{} ({})", charge.name, rate),
+                None => charge.name.clone(),
+            };
+            doc.pair(&name, &charge.amount.to_string());
+        }
+    }
+
+    let voids = template.shows(TemplateField::Voids);
+    let refunds = template.shows(TemplateField::Refunds);
+    if voids || refunds {
+        section(&mut doc, "ADJUSTMENTS");
+        if voids {
+            doc.pair(&format!("Voids ({})", report.voids.lines), &report.voids.amount.to_string());
+        }
+        if refunds {
+            doc.pair(&format!("Refunds ({})", report.refunds.lines), &report.refunds.amount.to_string());
+        }
+    }
+    doc.rule('-');
+
+    template::close(doc, template)
+}
+
+fn section(doc: &mut EscPos, title: &str) {
+    doc.rule('-').bold(true).line(title).bold(false);
+}
@@ -0,0 +1,119 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::{AppError, AppResult};
+
+/// ==============================
+/// Tenant inbound API
+/// ==============================
+///
+/// Same endpoints the webview used: `{api_base}/api/{domain}/inbound/...`
+/// with the tenant access token as a bearer token.
+pub struct TenantClient {
+    http: reqwest::Client,
+    api_base: String,
+    domain: String,
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkdayResponse {
+    wid: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AcknowledgedResponse {
+    #[serde(default)]
+    acknowledged: Vec<String>,
+}
+
+impl TenantClient {
+    pub fn new(api_base: &str, domain: &str, token: &str, timeout: Duration) -> AppResult<Self> {
+        let http = reqwest::Client::builder().timeout(timeout).build()?;
+
+        Ok(Self {
+            http,
+            api_base: api_base.trim_end_matches('/').to_string(),
+            domain: domain.to_string(),
+            token: token.to_string(),
+        })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/api/{}/inbound/{}", self.api_base, self.domain, path)
+    }
+
+    /// Send the request and turn a non-2xx status into `AppError::Upstream`
+    async fn send(&self, request: reqwest::RequestBuilder) -> AppResult<reqwest::Response> {
+        let response = request
+            .bearer_auth(&self.token)
+            .header("Accept", "*/*")
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(AppError::upstream(
+                Some(status.as_u16()),
+                format!("{} {}", status, text),
+            ));
+        }
+
+        Ok(response)
+    }
+
+    /// POST /inbound/sync-tickets with a single TicketRequest payload.
+    /// The key goes in the `Idempotency-Key` header and in the body, so the
+    /// server can drop a retry of a ticket it already committed.
+    pub async fn sync_ticket(&self, idempotency_key: &str, mut ticket: Value) -> AppResult<()> {
+        if let Value::Object(fields) = &mut ticket {
+            fields.insert("idempotency_key".into(), Value::String(idempotency_key.to_string()));
+        }
+
+        let request = self
+            .http
+            .post(self.url("sync-tickets"))
+            .header("Idempotency-Key", idempotency_key)
+            .json(&[ticket]);
+
+        self.send(request).await?;
+        Ok(())
+    }
+
+    /// POST /inbound/sync-tickets/acknowledged, returns the subset of
+    /// `idempotency_keys` the server has already stored
+    pub async fn acknowledged_tickets(&self, idempotency_keys: &[String]) -> AppResult<HashSet<String>> {
+        let response = self
+            .send(
+                self.http
+                    .post(self.url("sync-tickets/acknowledged"))
+                    .json(&json!({ "idempotency_keys": idempotency_keys })),
+            )
+            .await?;
+
+        let body: AcknowledgedResponse = response.json().await?;
+        Ok(body.acknowledged.into_iter().collect())
+    }
+
+    /// POST /inbound/sync-workdays, returns the server workday id
+    pub async fn sync_workday(&self, workday: &Value) -> AppResult<String> {
+        let response = self
+            .send(self.http.post(self.url("sync-workdays")).json(workday))
+            .await?;
+
+        let body: WorkdayResponse = response.json().await?;
+        Ok(body.wid)
+    }
+
+    /// PATCH /inbound/update-workday/{wid}
+    pub async fn update_workday(&self, wid: &str, workday: &Value) -> AppResult<()> {
+        let path = format!("update-workday/{}", wid);
+        self.send(self.http.patch(self.url(&path)).json(workday))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+pub mod client;
+pub mod worker;
+
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db;
+use crate::error::AppResult;
+pub use worker::{SyncReport, SyncWorker};
+
+/// Same default as `API_BASE` in src/config/env.ts
+const DEFAULT_API_BASE: &str = "https://development.hc.hashtape.com";
+
+/// Emitted for every ticket/workday state change (`SyncProgress`)
+pub const EVENT_PROGRESS: &str = "sync:progress";
+
+/// Emitted after a pass that did something (`SyncReport`)
+pub const EVENT_FINISHED: &str = "sync:finished";
+
+/// ==============================
+/// Sync configuration
+/// ==============================
+#[derive(Debug, Clone)]
+pub struct SyncConfig {
+    /// Tenant API origin, without the `/api/{domain}` suffix
+    pub api_base: String,
+    /// Time between passes when nothing wakes the worker
+    pub interval: Duration,
+    /// Attempts before a row is moved to DEAD
+    pub max_attempts: i32,
+    /// Delay after the first failure, doubled on each retry
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Rows of each kind picked up per pass
+    pub batch_size: i64,
+    pub request_timeout: Duration,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            api_base: DEFAULT_API_BASE.to_string(),
+            interval: Duration::from_secs(30),
+            max_attempts: 8,
+            base_delay: Duration::from_secs(10),
+            max_delay: Duration::from_secs(30 * 60),
+            batch_size: 50,
+            request_timeout: Duration::from_secs(20),
+        }
+    }
+}
+
+impl SyncConfig {
+    /// Defaults, with the API base taken from `HASHTOUCH_API_BASE` at runtime
+    /// or `VITE_API_BASE` at build time when set
+    pub fn from_env() -> Self {
+        let api_base = std::env::var("HASHTOUCH_API_BASE")
+            .ok()
+            .or_else(|| option_env!("VITE_API_BASE").map(str::to_string))
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| DEFAULT_API_BASE.to_string());
+
+        Self {
+            api_base,
+            ..Default::default()
+        }
+    }
+
+    /// Exponential backoff: `base_delay * 2^(attempts - 1)`, capped at `max_delay`
+    pub fn backoff(&self, attempts: i32) -> Duration {
+        let exp = attempts.saturating_sub(1).clamp(0, 20) as u32;
+        self.base_delay.saturating_mul(1 << exp).min(self.max_delay)
+    }
+}
+
+/// Start the background worker and register it as Tauri state
+pub fn start(app: &AppHandle, config: SyncConfig) -> anyhow::Result<()> {
+    let worker = SyncWorker::new(db::pool(app)?, config);
+    app.manage(worker.clone());
+
+    match worker.recover_interrupted() {
+        Ok(0) => {}
+        Ok(n) => log::info!("🔁 Re-queued {} rows interrupted mid-sync", n),
+        Err(e) => log::warn!("⚠️ Failed to recover interrupted syncs: {}", e),
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        log::info!("🔄 Sync worker started");
+
+        loop {
+            let _ = run_and_emit(&app, &worker).await;
+            worker.wait().await;
+        }
+    });
+
+    Ok(())
+}
+
+/// Run one pass, forwarding progress and the final report as Tauri events
+pub async fn run_and_emit(app: &AppHandle, worker: &SyncWorker) -> AppResult<SyncReport> {
+    let result = worker
+        .run_once(|progress| {
+            let _ = app.emit(EVENT_PROGRESS, &progress);
+        })
+        .await;
+
+    match &result {
+        Ok(report) => {
+            if !report.is_empty() {
+                log::info!(
+                    "🔄 Sync pass: {} synced, {} reconciled, {} failed, {} dead{}",
+                    report.synced,
+                    report.reconciled,
+                    report.failed,
+                    report.dead,
+                    report.halted.as_deref().map(|h| format!(", halted: {}", h)).unwrap_or_default()
+                );
+                let _ = app.emit(EVENT_FINISHED, &report);
+            }
+        }
+        Err(e) => log::warn!("⚠️ Sync pass failed: {}", e),
+    }
+
+    result
+}
@@ -0,0 +1,711 @@
+use std::sync::Arc;
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::{Mutex, Notify};
+
+use super::client::TenantClient;
+use super::SyncConfig;
+use crate::db::models::ticket::Ticket;
+use crate::db::models::workday::Workday;
+use crate::db::models::{app_state_repo, ticket_repo, workday_repo};
+use crate::db::pool::DbPool;
+use crate::error::{AppError, AppResult};
+
+/// Progress for a single ticket or workday, emitted as it changes state
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncProgress {
+    pub kind: &'static str,   // ticket | workday
+    pub id: String,
+    pub status: &'static str, // SYNCING | SYNCED | FAILED | DEAD
+    pub attempts: i32,
+    pub error: Option<String>,
+    pub next_attempt_at: Option<String>,
+}
+
+/// Summary of one pass over the queue
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    pub synced: usize,
+    pub failed: usize,
+    pub dead: usize,
+    /// Already on the server from an earlier attempt, marked SYNCED without re-posting
+    pub reconciled: usize,
+    /// Why the pass stopped early (token rejected by the tenant)
+    pub halted: Option<String>,
+}
+
+impl SyncReport {
+    pub fn is_empty(&self) -> bool {
+        self.synced + self.failed + self.dead + self.reconciled == 0 && self.halted.is_none()
+    }
+}
+
+/// Where a row goes after an upload attempt
+enum Decision {
+    Synced,
+    Retry { error: String, next_attempt_at: String },
+    Dead { error: String },
+    /// Token rejected: leave the row as it was and stop the pass
+    Halt { error: String },
+}
+
+fn timestamp(at: chrono::DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// ==============================
+/// Sync worker
+/// ==============================
+///
+/// Uploads due tickets and workdays to the tenant API. Failures that may
+/// succeed later are retried with exponential backoff; permanent failures
+/// and rows that run out of attempts are parked as DEAD until requeued.
+///
+/// Holds no Tauri types, so a pass can be run against a mock server by
+/// pointing `SyncConfig::api_base` at it, as the tests below do.
+#[derive(Clone)]
+pub struct SyncWorker {
+    pool: DbPool,
+    config: Arc<SyncConfig>,
+    run_lock: Arc<Mutex<()>>,
+    wake: Arc<Notify>,
+}
+
+impl SyncWorker {
+    pub fn new(pool: DbPool, config: SyncConfig) -> Self {
+        Self {
+            pool,
+            config: Arc::new(config),
+            run_lock: Arc::new(Mutex::new(())),
+            wake: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Cut the current sleep short and start the next pass
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Sleep for the poll interval, or until `wake` is called
+    pub async fn wait(&self) {
+        tokio::select! {
+            _ = self.wake.notified() => {}
+            _ = tokio::time::sleep(self.config.interval) => {}
+        }
+    }
+
+    /// Return rows left in SYNCING (app closed mid-upload) to the queue
+    pub fn recover_interrupted(&self) -> AppResult<usize> {
+        let mut conn = self.pool.writer()?;
+        let tickets = ticket_repo::reset_interrupted_ticket_syncs(&mut conn)?;
+        let workdays = workday_repo::reset_interrupted_workday_syncs(&mut conn)?;
+        Ok(tickets + workdays)
+    }
+
+    /// Run one pass over due workdays, then due tickets.
+    /// Passes never overlap, so a manual sync waits for one already in flight
+    /// instead of uploading the same rows twice.
+    pub async fn run_once<F>(&self, on_progress: F) -> AppResult<SyncReport>
+    where
+        F: Fn(SyncProgress) + Send + Sync,
+    {
+        let _guard = self.run_lock.lock().await;
+        let mut report = SyncReport::default();
+
+        let state = {
+            let conn = self.pool.reader()?;
+            app_state_repo::get_app_state(&conn)?
+        };
+
+        let (domain, token) = match (state.tenant_domain, state.access_token) {
+            (Some(d), Some(t)) if !d.is_empty() && !t.is_empty() => (d, t),
+            _ => {
+                log::debug!("🔄 Sync skipped, tenant not configured");
+                return Ok(report);
+            }
+        };
+
+        let client = TenantClient::new(
+            &self.config.api_base,
+            &domain,
+            &token,
+            self.config.request_timeout,
+        )?;
+
+        let now = timestamp(Utc::now());
+        let (workdays, tickets) = {
+            let conn = self.pool.reader()?;
+            (
+                workday_repo::get_due_workdays(&conn, &now, self.config.max_attempts, self.config.batch_size)?,
+                ticket_repo::get_due_tickets(&conn, &now, self.config.max_attempts, self.config.batch_size)?,
+            )
+        };
+
+        // Workdays first so the server knows the day before its tickets arrive
+        for workday in workdays {
+            let Some(id) = workday.id else { continue };
+            let attempts = workday.sync_attempts.unwrap_or(0);
+
+            workday_repo::mark_workday_syncing(&mut *self.pool.writer()?, id)?;
+            on_progress(progress("workday", &id.to_string(), "SYNCING", attempts, None, None));
+
+            let result = self.upload_workday(&client, id, &workday).await;
+            let decision = self.decide(result, attempts);
+
+            {
+                let mut conn = self.pool.writer()?;
+                match &decision {
+                    Decision::Synced => workday_repo::mark_workday_synced(&mut conn, id)?,
+                    Decision::Retry { error, next_attempt_at } => workday_repo::record_workday_sync_failure(
+                        &mut conn, id, "FAILED", error, Some(next_attempt_at),
+                    )?,
+                    Decision::Dead { error } => {
+                        workday_repo::record_workday_sync_failure(&mut conn, id, "DEAD", error, None)?
+                    }
+                    Decision::Halt { .. } => {
+                        workday_repo::restore_workday_sync_status(&mut conn, id, &workday.sync_status)?
+                    }
+                }
+            }
+
+            if report.tally("workday", &id.to_string(), attempts, decision, &on_progress) {
+                return Ok(report);
+            }
+        }
+
+        let tickets = match self.reconcile_tickets(&client, tickets, &mut report, &on_progress).await {
+            Ok(tickets) => tickets,
+            Err(e @ AppError::Upstream { status: Some(401 | 403), .. }) => {
+                report.halt(e.to_string());
+                return Ok(report);
+            }
+            Err(e) => {
+                // Posting now could duplicate a ticket the server already has
+                log::warn!("⚠️ Ticket reconciliation failed, uploads deferred: {}", e);
+                return Ok(report);
+            }
+        };
+
+        for ticket in tickets {
+            let attempts = ticket.sync_attempts;
+
+            ticket_repo::mark_ticket_syncing(&mut *self.pool.writer()?, &ticket.id)?;
+            on_progress(progress("ticket", &ticket.id, "SYNCING", attempts, None, None));
+
+            let result = upload_ticket(&client, &ticket).await;
+            let decision = self.decide(result, attempts);
+
+            {
+                let mut conn = self.pool.writer()?;
+                match &decision {
+                    Decision::Synced => ticket_repo::mark_ticket_synced(&mut conn, &ticket.id)?,
+                    Decision::Retry { error, next_attempt_at } => ticket_repo::record_ticket_sync_failure(
+                        &mut conn, &ticket.id, "FAILED", error, Some(next_attempt_at),
+                    )?,
+                    Decision::Dead { error } => {
+                        ticket_repo::record_ticket_sync_failure(&mut conn, &ticket.id, "DEAD", error, None)?
+                    }
+                    Decision::Halt { .. } => {
+                        ticket_repo::restore_ticket_sync_status(&mut conn, &ticket.id, &ticket.sync_status)?
+                    }
+                }
+            }
+
+            if report.tally("ticket", &ticket.id, attempts, decision, &on_progress) {
+                return Ok(report);
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Ask the server which due tickets it already holds, mark those SYNCED
+    /// and return the rest. Catches uploads that timed out on our side after
+    /// the server had committed them.
+    async fn reconcile_tickets<F>(
+        &self,
+        client: &TenantClient,
+        tickets: Vec<Ticket>,
+        report: &mut SyncReport,
+        on_progress: &F,
+    ) -> AppResult<Vec<Ticket>>
+    where
+        F: Fn(SyncProgress) + Send + Sync,
+    {
+        let keys: Vec<String> = tickets
+            .iter()
+            .filter_map(|t| t.idempotency_key.clone())
+            .collect();
+
+        if keys.is_empty() {
+            return Ok(tickets);
+        }
+
+        let acknowledged = match client.acknowledged_tickets(&keys).await {
+            Ok(acknowledged) => acknowledged,
+            // Server without the endpoint: the idempotency key alone has to do
+            Err(AppError::Upstream { status: Some(404 | 405), .. }) => return Ok(tickets),
+            Err(e) => return Err(e),
+        };
+
+        let (done, pending): (Vec<Ticket>, Vec<Ticket>) = tickets.into_iter().partition(|t| {
+            t.idempotency_key
+                .as_ref()
+                .is_some_and(|k| acknowledged.contains(k))
+        });
+
+        if !done.is_empty() {
+            let ids: Vec<String> = done.iter().map(|t| t.id.clone()).collect();
+            ticket_repo::mark_tickets_acknowledged(&mut *self.pool.writer()?, &ids)?;
+
+            log::info!("🔄 {} tickets already on the server, marked SYNCED", done.len());
+            for ticket in &done {
+                report.reconciled += 1;
+                on_progress(progress("ticket", &ticket.id, "SYNCED", ticket.sync_attempts, None, None));
+            }
+        }
+
+        Ok(pending)
+    }
+
+    /// POST a new workday (and store the returned id) or PATCH a known one
+    async fn upload_workday(&self, client: &TenantClient, id: i64, workday: &Workday) -> AppResult<()> {
+        let payload = workday_payload(workday);
+
+        match &workday.workday_id {
+            Some(wid) => client.update_workday(wid, &payload).await,
+            None => {
+                let wid = client.sync_workday(&payload).await?;
+                workday_repo::set_workday_server_id(&mut *self.pool.writer()?, id, &wid)
+            }
+        }
+    }
+
+    fn decide(&self, result: AppResult<()>, attempts: i32) -> Decision {
+        let e = match result {
+            Ok(()) => return Decision::Synced,
+            Err(e) => e,
+        };
+
+        if matches!(e, AppError::Upstream { status: Some(401 | 403), .. }) {
+            return Decision::Halt { error: e.to_string() };
+        }
+
+        let attempts = attempts + 1;
+        if !e.is_retryable() || attempts >= self.config.max_attempts {
+            return Decision::Dead { error: e.to_string() };
+        }
+
+        let delay = chrono::Duration::from_std(self.config.backoff(attempts)).unwrap_or_default();
+        Decision::Retry {
+            error: e.to_string(),
+            next_attempt_at: timestamp(Utc::now() + delay),
+        }
+    }
+}
+
+impl SyncReport {
+    /// Count the decision and emit its progress event.
+    /// Returns true when the pass has to stop.
+    fn tally(
+        &mut self,
+        kind: &'static str,
+        id: &str,
+        attempts: i32,
+        decision: Decision,
+        on_progress: &(dyn Fn(SyncProgress) + Send + Sync),
+    ) -> bool {
+        let attempts = attempts + 1;
+
+        match decision {
+            Decision::Synced => {
+                self.synced += 1;
+                on_progress(progress(kind, id, "SYNCED", attempts, None, None));
+                false
+            }
+            Decision::Retry { error, next_attempt_at } => {
+                self.failed += 1;
+                log::warn!("⚠️ {} {} sync failed, retry at {}: {}", kind, id, next_attempt_at, error);
+                on_progress(progress(kind, id, "FAILED", attempts, Some(error), Some(next_attempt_at)));
+                false
+            }
+            Decision::Dead { error } => {
+                self.dead += 1;
+                log::error!("☠️ {} {} moved to dead letter: {}", kind, id, error);
+                on_progress(progress(kind, id, "DEAD", attempts, Some(error), None));
+                false
+            }
+            Decision::Halt { error } => {
+                self.halt(error);
+                true
+            }
+        }
+    }
+
+    fn halt(&mut self, error: String) {
+        log::warn!("🔐 Sync halted, tenant rejected the access token: {}", error);
+        self.halted = Some(error);
+    }
+}
+
+async fn upload_ticket(client: &TenantClient, ticket: &Ticket) -> AppResult<()> {
+    let payload: Value = serde_json::from_str(&ticket.ticket_data)?;
+    let key = ticket.idempotency_key.as_deref().unwrap_or(&ticket.id);
+    client.sync_ticket(key, payload).await
+}
+
+fn progress(
+    kind: &'static str,
+    id: &str,
+    status: &'static str,
+    attempts: i32,
+    error: Option<String>,
+    next_attempt_at: Option<String>,
+) -> SyncProgress {
+    SyncProgress {
+        kind,
+        id: id.to_string(),
+        status,
+        attempts,
+        error,
+        next_attempt_at,
+    }
+}
+
+/// Body for sync-workdays / update-workday, same shape as `WorkdayPayload` in TS
+fn workday_payload(w: &Workday) -> Value {
+    json!({
+        "location_id": w.location_id,
+        "start_user": w.start_user,
+        "end_user": w.end_user,
+        "start_time": w.start_time,
+        "end_time": w.end_time,
+        "total_sales": w.total_sales,
+        "total_taxes": w.total_taxes,
+        "total_ticket_count": w.total_ticket_count,
+        "work_period_informations": w.work_period_informations,
+        "department_ticket_informations": w.department_ticket_informations,
+        "add_on": w.add_on,
+        "auto_closed": w.auto_closed,
+        "external_processed": w.external_processed,
+        "work_period_day": w.work_period_day,
+        "business_date": w.business_date,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Deref;
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::db::test_db;
+
+    /// A tenant API answering uploads with `status` and recording the
+    /// idempotency key of each ticket posted
+    struct MockTenant {
+        api_base: String,
+        uploads: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl MockTenant {
+        fn uploads(&self) -> Vec<String> {
+            self.uploads.lock().unwrap().clone()
+        }
+    }
+
+    /// Holds none of the tickets it is asked about
+    async fn mock_tenant(status: u16) -> MockTenant {
+        mock_tenant_with(status, Ok(&[])).await
+    }
+
+    /// `acknowledged` answers `sync-tickets/acknowledged`: the keys the
+    /// server already holds, or the status it fails with
+    async fn mock_tenant_with(status: u16, acknowledged: Result<&'static [&'static str], u16>) -> MockTenant {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_base = format!("http://{}", listener.local_addr().unwrap());
+        let uploads = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let posted = uploads.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else { return };
+                let posted = posted.clone();
+                tokio::spawn(async move {
+                    let request = read_request(&mut socket).await;
+
+                    let (status, body) = if request.starts_with("POST /api/acme/inbound/sync-tickets/acknowledged ") {
+                        match acknowledged {
+                            Ok(keys) => (200, json!({ "acknowledged": keys }).to_string()),
+                            Err(status) => (status, "{}".to_string()),
+                        }
+                    } else {
+                        if request.starts_with("POST /api/acme/inbound/sync-tickets ") {
+                            let key = request.lines().find_map(|l| {
+                                let (name, value) = l.split_once(':')?;
+                                name.eq_ignore_ascii_case("idempotency-key").then(|| value.trim().to_string())
+                            });
+                            posted.lock().unwrap().push(key.unwrap_or_default());
+                        }
+                        (status, "{}".to_string())
+                    };
+
+                    let response = format!(
+                        "HTTP/1.1 {} Mock\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+
+        MockTenant { api_base, uploads }
+    }
+
+    /// Read one request, headers and body
+    async fn read_request(socket: &mut tokio::net::TcpStream) -> String {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            let n = socket.read(&mut chunk).await.unwrap_or(0);
+            buf.extend_from_slice(&chunk[..n]);
+            let text = String::from_utf8_lossy(&buf).to_string();
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text
+                    .lines()
+                    .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .unwrap_or(0);
+                if buf.len() >= end + 4 + length {
+                    return text;
+                }
+            }
+            if n == 0 {
+                return text;
+            }
+        }
+    }
+
+    /// A test database with a configured tenant
+    struct TestDb(test_db::TestDb);
+
+    impl Deref for TestDb {
+        type Target = test_db::TestDb;
+
+        fn deref(&self) -> &test_db::TestDb {
+            &self.0
+        }
+    }
+
+    impl TestDb {
+        fn new(name: &str) -> Self {
+            let db = test_db::TestDb::new(&format!("sync-{}", name));
+
+            let conn = db.pool.writer().unwrap();
+            conn.execute("INSERT OR IGNORE INTO app_state (id) VALUES (1)", []).unwrap();
+            conn.execute("UPDATE app_state SET tenant_domain = 'acme', access_token = 'token' WHERE id = 1", [])
+                .unwrap();
+            drop(conn);
+
+            Self(db)
+        }
+
+        /// A PENDING ticket with idempotency key `key-{id}`
+        fn add_ticket(&self, id: &str) {
+            self.pool
+                .writer()
+                .unwrap()
+                .execute(
+                    r#"
+                    INSERT INTO tickets (id, ticket_data, sync_status, created_at, idempotency_key)
+                    VALUES (?1, ?2, 'PENDING', ?1, 'key-' || ?1)
+                    "#,
+                    [id, &format!(r#"{{"ticket":{{"id":"{}"}}}}"#, id)],
+                )
+                .unwrap();
+        }
+
+        /// (status, attempts, next_attempt_at)
+        fn ticket(&self, id: &str) -> (String, i32, Option<String>) {
+            self.pool
+                .reader()
+                .unwrap()
+                .query_row(
+                    "SELECT sync_status, sync_attempts, next_attempt_at FROM tickets WHERE id = ?1",
+                    [id],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .unwrap()
+        }
+
+        /// Pretend the backoff has passed
+        fn make_due(&self, id: &str) {
+            self.pool
+                .writer()
+                .unwrap()
+                .execute("UPDATE tickets SET next_attempt_at = '2000-01-01T00:00:00.000Z' WHERE id = ?1", [id])
+                .unwrap();
+        }
+    }
+
+    fn worker(db: &TestDb, tenant: &MockTenant, max_attempts: i32) -> SyncWorker {
+        SyncWorker::new(
+            db.pool.clone(),
+            SyncConfig {
+                api_base: tenant.api_base.clone(),
+                max_attempts,
+                base_delay: Duration::from_secs(60),
+                max_delay: Duration::from_secs(3600),
+                request_timeout: Duration::from_secs(5),
+                ..SyncConfig::default()
+            },
+        )
+    }
+
+    /// Seconds from now until `at`
+    fn seconds_until(at: &str) -> i64 {
+        (chrono::DateTime::parse_from_rfc3339(at).unwrap().with_timezone(&Utc) - Utc::now()).num_seconds()
+    }
+
+    #[tokio::test]
+    async fn retries_with_backoff() {
+        let db = TestDb::new("retry");
+        let tenant = mock_tenant(503).await;
+        let worker = worker(&db, &tenant, 10);
+        db.add_ticket("T1");
+
+        let report = worker.run_once(|_| {}).await.unwrap();
+        assert_eq!(report.failed, 1);
+        let (status, attempts, next) = db.ticket("T1");
+        assert_eq!((status.as_str(), attempts), ("FAILED", 1));
+        assert!((55..=60).contains(&seconds_until(&next.unwrap())));
+
+        // Not due again until the backoff has passed
+        let report = worker.run_once(|_| {}).await.unwrap();
+        assert!(report.is_empty());
+        assert_eq!(tenant.uploads().len(), 1);
+
+        // The second failure waits twice as long
+        db.make_due("T1");
+        worker.run_once(|_| {}).await.unwrap();
+        let (status, attempts, next) = db.ticket("T1");
+        assert_eq!((status.as_str(), attempts), ("FAILED", 2));
+        assert!((115..=120).contains(&seconds_until(&next.unwrap())));
+        assert_eq!(tenant.uploads().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn dead_letters_after_max_attempts() {
+        let db = TestDb::new("dead");
+        let tenant = mock_tenant(500).await;
+        let worker = worker(&db, &tenant, 3);
+        db.add_ticket("T1");
+
+        for attempt in 1..3 {
+            worker.run_once(|_| {}).await.unwrap();
+            assert_eq!(db.ticket("T1").0, "FAILED", "attempt {}", attempt);
+            db.make_due("T1");
+        }
+
+        let report = worker.run_once(|_| {}).await.unwrap();
+        assert_eq!(report.dead, 1);
+        assert_eq!(db.ticket("T1"), ("DEAD".to_string(), 3, None));
+
+        // DEAD rows are left alone until requeued
+        db.make_due("T1");
+        assert!(worker.run_once(|_| {}).await.unwrap().is_empty());
+        assert_eq!(tenant.uploads().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn permanent_failures_go_straight_to_dead_letter() {
+        let db = TestDb::new("permanent");
+        let tenant = mock_tenant(400).await;
+        let worker = worker(&db, &tenant, 10);
+        db.add_ticket("T1");
+
+        let report = worker.run_once(|_| {}).await.unwrap();
+        assert_eq!(report.dead, 1);
+        assert_eq!(db.ticket("T1"), ("DEAD".to_string(), 1, None));
+    }
+
+    #[tokio::test]
+    async fn halts_on_unauthorized() {
+        let db = TestDb::new("halt");
+        let tenant = mock_tenant(401).await;
+        let worker = worker(&db, &tenant, 10);
+        db.add_ticket("T1");
+        db.add_ticket("T2");
+
+        let progress = std::sync::Mutex::new(Vec::new());
+        let report = worker.run_once(|p| progress.lock().unwrap().push(p.status)).await.unwrap();
+
+        assert!(report.halted.is_some());
+        assert_eq!((report.failed, report.dead), (0, 0));
+        // The token is the problem, not the tickets: both keep their place
+        assert_eq!(db.ticket("T1"), ("PENDING".to_string(), 0, None));
+        assert_eq!(db.ticket("T2"), ("PENDING".to_string(), 0, None));
+        assert_eq!(tenant.uploads().len(), 1);
+        assert_eq!(*progress.lock().unwrap(), vec!["SYNCING"]);
+    }
+
+    #[tokio::test]
+    async fn reconciles_tickets_the_server_already_has() {
+        let db = TestDb::new("reconcile");
+        let tenant = mock_tenant_with(200, Ok(&["key-T1"])).await;
+        let worker = worker(&db, &tenant, 10);
+        db.add_ticket("T1");
+        db.add_ticket("T2");
+
+        let progress = std::sync::Mutex::new(Vec::new());
+        let report = worker
+            .run_once(|p| progress.lock().unwrap().push((p.id, p.status)))
+            .await
+            .unwrap();
+
+        assert_eq!((report.reconciled, report.synced), (1, 1));
+        assert_eq!(db.ticket("T1").0, "SYNCED");
+        assert_eq!(db.ticket("T2").0, "SYNCED");
+        // T1 was only marked, never posted again
+        assert_eq!(tenant.uploads(), vec!["key-T2"]);
+        assert!(progress.lock().unwrap().contains(&("T1".to_string(), "SYNCED")));
+
+        assert!(worker.run_once(|_| {}).await.unwrap().is_empty());
+        assert_eq!(tenant.uploads(), vec!["key-T2"]);
+    }
+
+    #[tokio::test]
+    async fn uploads_by_key_when_the_server_cant_reconcile() {
+        for status in [404, 405] {
+            let db = TestDb::new(&format!("no-reconcile-{}", status));
+            let tenant = mock_tenant_with(200, Err(status)).await;
+            let worker = worker(&db, &tenant, 10);
+            db.add_ticket("T1");
+
+            let report = worker.run_once(|_| {}).await.unwrap();
+            assert_eq!((report.reconciled, report.synced), (0, 1), "{}", status);
+            assert_eq!(tenant.uploads(), vec!["key-T1"], "{}", status);
+        }
+    }
+
+    #[tokio::test]
+    async fn defers_uploads_when_reconciliation_fails() {
+        let db = TestDb::new("reconcile-down");
+        let tenant = mock_tenant_with(200, Err(503)).await;
+        let worker = worker(&db, &tenant, 10);
+        db.add_ticket("T1");
+
+        // Posting blind could duplicate a ticket the server already has
+        let report = worker.run_once(|_| {}).await.unwrap();
+        assert!(report.is_empty());
+        assert!(tenant.uploads().is_empty());
+        assert_eq!(db.ticket("T1"), ("PENDING".to_string(), 0, None));
+    }
+}
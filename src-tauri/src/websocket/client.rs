@@ -0,0 +1,683 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use futures_util::{SinkExt, StreamExt};
+use log::{info, warn};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::Serialize;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::discovery;
+use super::presence::HeartbeatConfig;
+use super::standby::{MirrorUpdate, MIRROR_MESSAGE, STANDBYS_MESSAGE};
+use super::tls::{self, ClientStream};
+use super::DeviceMessage;
+use crate::db::models::app_state::AppState;
+use crate::error::{AppError, AppResult};
+
+/// Emitted for every message from the hub (`DeviceMessage`)
+pub const EVENT_MESSAGE: &str = "ws:message";
+
+/// Emitted whenever the connection state changes (`ClientStatus`)
+pub const EVENT_STATUS: &str = "ws:client-status";
+
+/// Emitted when discovery finds the hub at a new URL (the URL)
+pub const EVENT_HUB_MOVED: &str = "ws:hub-moved";
+
+/// Hub messages kept for a UI that reloads and asks what it missed
+const RECENT_MESSAGES: usize = 200;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const REGISTER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Messages waiting to go out to the hub before `send` refuses more
+const OUTGOING_QUEUE: usize = 256;
+
+/// Failed attempts between looks on the LAN for the hub at a new address
+const REDISCOVER_EVERY: u32 = 3;
+
+/// ==============================
+/// Hub connection settings
+/// ==============================
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HubConfig {
+    pub url: String,
+    pub device_id: String,
+    pub device_type: String,
+    pub token: Option<String>,
+    /// Certificate pinned at pairing, checked on `wss://`
+    pub fingerprint: Option<String>,
+    /// Used to pick the right hub when rediscovering it
+    pub location_id: Option<String>,
+    /// Register as a standby POS mirroring the hub rather than as a display
+    pub standby: bool,
+}
+
+impl HubConfig {
+    /// Connection details saved by pairing. `fallback_id` is used when the
+    /// device was never paired (only accepted by a hub on the same machine).
+    pub fn from_app_state(
+        state: &AppState,
+        device_type: &str,
+        fallback_id: Option<String>,
+    ) -> AppResult<Self> {
+        let url = state
+            .ws_server_url
+            .clone()
+            .filter(|u| !u.trim().is_empty())
+            .ok_or_else(|| AppError::InvalidInput("no hub URL saved".into()))?;
+
+        let device_id = state
+            .ws_device_id
+            .clone()
+            .or(fallback_id)
+            .ok_or_else(|| AppError::InvalidInput("device is not paired with a hub".into()))?;
+
+        Ok(Self {
+            url,
+            device_id,
+            device_type: device_type.to_uppercase(),
+            token: state.ws_device_token.clone(),
+            fingerprint: state.ws_tls_fingerprint.clone(),
+            location_id: state.selected_location_id.clone(),
+            standby: false,
+        })
+    }
+
+    /// Same hub outbox and the same device in it, so seqs carry on
+    fn same_outbox(&self, other: &HubConfig) -> bool {
+        self.url == other.url && self.device_id == other.device_id && self.fingerprint == other.fingerprint
+    }
+}
+
+/// ==============================
+/// Reconnect backoff
+/// ==============================
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// Delay after the first failure, doubled on each retry
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Half of `base_delay * 2^(attempt - 1)` (capped at `max_delay`) plus a
+    /// random part of the other half, so screens that lost the hub together
+    /// don't all come back at the same instant
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exp = attempt.saturating_sub(1).min(20);
+        let ceiling = self.base_delay.saturating_mul(1 << exp).min(self.max_delay);
+
+        let mut bytes = [0u8; 4];
+        let jitter = match SystemRandom::new().fill(&mut bytes) {
+            Ok(()) => u32::from_le_bytes(bytes) as f64 / u32::MAX as f64,
+            Err(_) => 0.5,
+        };
+
+        ceiling / 2 + ceiling.mul_f64(jitter / 2.0)
+    }
+}
+
+/// Connection state as shown to the UI
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ClientStatus {
+    /// idle | connecting | connected | reconnecting | rejected
+    pub state: String,
+    pub url: Option<String>,
+    pub device_id: Option<String>,
+    /// Failed attempts since the last successful registration
+    pub attempt: u32,
+    pub last_error: Option<String>,
+    pub retry_in_ms: Option<u64>,
+    pub connected_at: Option<String>,
+    /// Highest outbox seq received and acked
+    pub last_seq: i64,
+}
+
+#[derive(Debug, Clone)]
+pub enum ClientEvent {
+    Message(DeviceMessage),
+    Status(ClientStatus),
+    /// The hub answered at this URL (discovery or a standby that took
+    /// over); worth saving as `ws_server_url`
+    HubMoved(String),
+    /// State to copy, when connected as a standby. Carries device tokens,
+    /// so it is never forwarded to the webview.
+    Mirror(Box<MirrorUpdate>),
+}
+
+impl ClientEvent {
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            ClientEvent::Message(_) => EVENT_MESSAGE,
+            ClientEvent::Status(_) => EVENT_STATUS,
+            ClientEvent::HubMoved(_) => EVENT_HUB_MOVED,
+            ClientEvent::Mirror(_) => MIRROR_MESSAGE,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Session {
+    config: Option<HubConfig>,
+    task: Option<JoinHandle<()>>,
+    outgoing: Option<mpsc::Sender<DeviceMessage>>,
+    status: ClientStatus,
+    recent: VecDeque<DeviceMessage>,
+    /// Standby hubs the primary announced, tried when it stops answering
+    standbys: Vec<String>,
+    /// The hub `status.last_seq` and `recent` came from; kept when stopped
+    seq_source: Option<HubConfig>,
+}
+
+struct Inner {
+    events: broadcast::Sender<ClientEvent>,
+    policy: ReconnectPolicy,
+    heartbeat: HeartbeatConfig,
+    session: Mutex<Session>,
+}
+
+impl Inner {
+    fn session(&self) -> MutexGuard<'_, Session> {
+        self.session.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn emit(&self, event: ClientEvent) {
+        // Nobody listening (UI reloading) is fine: messages stay in `recent`
+        let _ = self.events.send(event);
+    }
+
+    fn update_status(&self, f: impl FnOnce(&mut ClientStatus)) {
+        let status = {
+            let mut session = self.session();
+            f(&mut session.status);
+            session.status.clone()
+        };
+        self.emit(ClientEvent::Status(status));
+    }
+
+    /// Keep the standby hub URLs from a `register_ack` or `hub_standbys`
+    fn set_standbys(&self, urls: &serde_json::Value) {
+        let urls: Vec<String> = urls
+            .as_array()
+            .map(|urls| urls.iter().filter_map(|u| u.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+
+        if !urls.is_empty() {
+            info!("🪞 Standby hubs: {}", urls.join(", "));
+        }
+        self.session().standbys = urls;
+    }
+
+    /// Where to connect next: the hub itself first, then after each failure
+    /// the next standby in turn, so whichever took over is found
+    fn next_url(&self, config: &HubConfig, attempt: u32) -> String {
+        let standbys = self.session().standbys.clone();
+        let candidates: Vec<&String> = std::iter::once(&config.url)
+            .chain(standbys.iter().filter(|url| **url != config.url))
+            .collect();
+        candidates[attempt as usize % candidates.len()].clone()
+    }
+
+    /// Record a hub message; false if it is a replay of one already seen
+    fn accept(&self, seq: i64, message: &DeviceMessage) -> bool {
+        let mut session = self.session();
+        if seq <= session.status.last_seq {
+            return false;
+        }
+
+        session.status.last_seq = seq;
+        if session.recent.len() == RECENT_MESSAGES {
+            session.recent.pop_front();
+        }
+        session.recent.push_back(message.clone());
+        true
+    }
+}
+
+/// ==============================
+/// Hub client (KDS / QUEUE / standby POS)
+/// ==============================
+///
+/// Holds one connection to the POS hub for the whole app rather than one per
+/// webview, so it survives UI reloads and keeps going while the window is in
+/// the background. Reconnects on its own until stopped or rejected, trying
+/// the hub's standbys too once the hub stops answering.
+#[derive(Clone)]
+pub struct HubClient {
+    inner: Arc<Inner>,
+}
+
+impl HubClient {
+    pub fn new(policy: ReconnectPolicy, heartbeat: HeartbeatConfig) -> Self {
+        let (events, _) = broadcast::channel(256);
+
+        Self {
+            inner: Arc::new(Inner {
+                events,
+                policy,
+                heartbeat,
+                session: Mutex::new(Session {
+                    status: ClientStatus {
+                        state: "idle".into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }),
+            }),
+        }
+    }
+
+    /// Messages and status changes, e.g. to forward as Tauri events
+    pub fn subscribe(&self) -> broadcast::Receiver<ClientEvent> {
+        self.inner.events.subscribe()
+    }
+
+    /// Connect with `config`, replacing any connection with different
+    /// settings. Calling it again with the same settings (a reloaded UI)
+    /// leaves the running connection alone. Must run inside the async runtime.
+    pub fn start(&self, config: HubConfig) -> ClientStatus {
+        let mut session = self.inner.session();
+
+        let running = session.task.as_ref().is_some_and(|t| !t.is_finished());
+        if running && session.config.as_ref() == Some(&config) {
+            return session.status.clone();
+        }
+
+        if let Some(task) = session.task.take() {
+            task.abort();
+        }
+
+        info!("🔌 Hub client starting: {} as {} ({})", config.url, config.device_id, config.device_type);
+
+        // Another hub (or this device re-paired) numbers its outbox afresh;
+        // the old seq would drop everything until it caught up
+        let same_outbox = session.seq_source.as_ref().is_some_and(|prev| prev.same_outbox(&config));
+        if !same_outbox {
+            session.status.last_seq = 0;
+            session.recent.clear();
+        }
+
+        session.outgoing = None;
+        session.status = ClientStatus {
+            state: "connecting".into(),
+            url: Some(config.url.clone()),
+            device_id: Some(config.device_id.clone()),
+            last_seq: session.status.last_seq,
+            ..Default::default()
+        };
+        session.config = Some(config.clone());
+        session.seq_source = Some(config.clone());
+        session.task = Some(tokio::spawn(run(self.inner.clone(), config)));
+
+        let status = session.status.clone();
+        drop(session);
+        self.inner.emit(ClientEvent::Status(status.clone()));
+        status
+    }
+
+    pub fn stop(&self) {
+        {
+            let mut session = self.inner.session();
+            if let Some(task) = session.task.take() {
+                task.abort();
+                info!("🔌 Hub client stopped");
+            }
+            session.config = None;
+            session.outgoing = None;
+        }
+
+        self.inner.update_status(|s| {
+            *s = ClientStatus {
+                state: "idle".into(),
+                last_seq: s.last_seq,
+                ..Default::default()
+            }
+        });
+    }
+
+    pub fn status(&self) -> ClientStatus {
+        self.inner.session().status.clone()
+    }
+
+    /// Send to the hub; fails while disconnected, or while the hub is
+    /// slower than what is being sent, instead of queueing
+    pub fn send(&self, message: DeviceMessage) -> AppResult<()> {
+        let session = self.inner.session();
+        let outgoing = session
+            .outgoing
+            .as_ref()
+            .ok_or_else(|| AppError::Network("not connected to the hub".into()))?;
+
+        outgoing.try_send(message).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => AppError::Network("hub connection is backed up".into()),
+            mpsc::error::TrySendError::Closed(_) => AppError::Network("hub connection closed".into()),
+        })
+    }
+
+    /// Hub messages after `seq` still held in memory
+    pub fn messages_since(&self, seq: i64) -> Vec<DeviceMessage> {
+        self.inner
+            .session()
+            .recent
+            .iter()
+            .filter(|m| m.seq.is_some_and(|s| s > seq))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Connect, and keep reconnecting with backoff until rejected or aborted
+async fn run(inner: Arc<Inner>, mut config: HubConfig) {
+    let mut attempt: u32 = 0;
+
+    loop {
+        let url = inner.next_url(&config, attempt);
+        let reason = connect_once(&inner, &mut config, &url, &mut attempt).await;
+        inner.session().outgoing = None;
+
+        // Unknown or revoked device: retrying won't help
+        if let AppError::Unauthorized(reason) = &reason {
+            warn!("⛔ Hub rejected {}: {}", config.device_id, reason);
+            inner.update_status(|s| {
+                s.state = "rejected".into();
+                s.last_error = Some(reason.clone());
+                s.retry_in_ms = None;
+                s.connected_at = None;
+            });
+            return;
+        }
+
+        attempt += 1;
+        let delay = inner.policy.delay(attempt);
+        warn!(
+            "🔄 Hub connection lost ({}), retry {} in {}ms",
+            reason,
+            attempt,
+            delay.as_millis()
+        );
+        inner.update_status(|s| {
+            s.state = "reconnecting".into();
+            s.attempt = attempt;
+            s.last_error = Some(reason.to_string());
+            s.retry_in_ms = Some(delay.as_millis() as u64);
+            s.connected_at = None;
+        });
+
+        tokio::time::sleep(delay).await;
+
+        // The POS may have come back on another address (new DHCP lease)
+        if attempt % REDISCOVER_EVERY == 0 {
+            rediscover(&inner, &mut config).await;
+        }
+    }
+}
+
+async fn rediscover(inner: &Inner, config: &mut HubConfig) {
+    let hubs = match discovery::discover(discovery::BROWSE_TIME).await {
+        Ok(hubs) => hubs,
+        Err(e) => {
+            warn!("⚠️ Hub discovery failed: {}", e);
+            return;
+        }
+    };
+
+    let Some(hub) = discovery::pick_hub(&hubs, config.location_id.as_deref(), config.fingerprint.as_deref()) else {
+        return;
+    };
+    if hub.url == config.url {
+        return;
+    }
+
+    let url = hub.url.clone();
+    adopt_url(inner, config, &url);
+}
+
+/// From now on the hub is at `url`, with the same outbox (a standby that
+/// took over mirrored it)
+fn adopt_url(inner: &Inner, config: &mut HubConfig, url: &str) {
+    info!("🧭 Hub moved: {} -> {}", config.url, url);
+    config.url = url.to_string();
+    {
+        let mut session = inner.session();
+        session.config = Some(config.clone());
+        session.seq_source = Some(config.clone());
+        session.status.url = Some(config.url.clone());
+    }
+    inner.emit(ClientEvent::HubMoved(config.url.clone()));
+}
+
+/// One connection to `url` (the hub or one of its standbys), from TCP
+/// connect to whatever ended it
+async fn connect_once(inner: &Inner, config: &mut HubConfig, url: &str, attempt: &mut u32) -> AppError {
+    let mut ws = match tokio::time::timeout(
+        CONNECT_TIMEOUT,
+        tls::connect(url, config.fingerprint.as_deref()),
+    )
+    .await
+    {
+        Ok(Ok((ws, _))) => ws,
+        Ok(Err(e)) => return e,
+        Err(_) => return AppError::Network(format!("{}: connect timed out", url)),
+    };
+
+    let register = DeviceMessage {
+        message_type: "register".into(),
+        device_id: Some(config.device_id.clone()),
+        device_type: config.device_type.clone(),
+        payload: serde_json::json!({ "token": config.token, "standby": config.standby }),
+        ..Default::default()
+    };
+    if let Err(e) = send(&mut ws, &register).await {
+        return e;
+    }
+
+    // register_ack comes before any replayed outbox messages
+    match tokio::time::timeout(REGISTER_TIMEOUT, wait_for_register_ack(&mut ws)).await {
+        Ok(Ok(ack)) => inner.set_standbys(&ack.payload["standby_hubs"]),
+        Ok(Err(e)) => return e,
+        Err(_) => return AppError::Network("no register_ack from the hub".into()),
+    }
+
+    // A standby answered: the primary is gone and it serves the hub now
+    if url != config.url {
+        adopt_url(inner, config, url);
+    }
+
+    *attempt = 0;
+    let (out_tx, mut out_rx) = mpsc::channel::<DeviceMessage>(OUTGOING_QUEUE);
+    inner.session().outgoing = Some(out_tx);
+    info!("✅ Registered with hub {} as {}", url, config.device_id);
+    inner.update_status(|s| {
+        s.state = "connected".into();
+        s.attempt = 0;
+        s.last_error = None;
+        s.retry_in_ms = None;
+        s.connected_at = Some(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true));
+    });
+
+    // The hub pings every interval; silence past the timeout means it's gone
+    let mut last_seen = Instant::now();
+    let mut heartbeat = tokio::time::interval(inner.heartbeat.interval);
+    heartbeat.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    heartbeat.tick().await;
+
+    loop {
+        tokio::select! {
+            frame = ws.next() => {
+                last_seen = Instant::now();
+                match frame {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Err(e) = deliver(inner, config, &mut ws, &text).await {
+                            return e;
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => {
+                        return AppError::Network("hub closed the connection".into());
+                    }
+                    // Pings are answered by tungstenite
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return AppError::Network(e.to_string()),
+                }
+            }
+            Some(message) = out_rx.recv() => {
+                if let Err(e) = send(&mut ws, &message).await {
+                    return e;
+                }
+            }
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > inner.heartbeat.timeout {
+                    let _ = ws.close(None).await;
+                    return AppError::Network("no heartbeat from the hub".into());
+                }
+            }
+        }
+    }
+}
+
+async fn wait_for_register_ack(ws: &mut ClientStream) -> AppResult<DeviceMessage> {
+    while let Some(frame) = ws.next().await {
+        let text = match frame {
+            Ok(Message::Text(text)) => text,
+            Ok(_) => continue,
+            Err(e) => return Err(AppError::Network(e.to_string())),
+        };
+
+        let Ok(reply) = serde_json::from_str::<DeviceMessage>(&text) else {
+            continue;
+        };
+
+        match reply.message_type.as_str() {
+            "register_ack" => return Ok(reply),
+            "register_rejected" => {
+                let reason = reply.payload["reason"].as_str().unwrap_or("registration rejected");
+                return Err(AppError::Unauthorized(
+                    reason.strip_prefix("unauthorized: ").unwrap_or(reason).to_string(),
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    Err(AppError::Network("hub closed the connection while registering".into()))
+}
+
+/// Ack outbox messages (replays included) and pass new ones on
+async fn deliver(inner: &Inner, config: &HubConfig, ws: &mut ClientStream, text: &str) -> AppResult<()> {
+    let message = match serde_json::from_str::<DeviceMessage>(text) {
+        Ok(message) => message,
+        Err(e) => {
+            warn!("⚠️ Ignoring malformed hub message: {}", e);
+            return Ok(());
+        }
+    };
+
+    // Hub-to-hub housekeeping, not for the UI
+    match message.message_type.as_str() {
+        MIRROR_MESSAGE => {
+            match serde_json::from_value::<MirrorUpdate>(message.payload) {
+                Ok(update) => inner.emit(ClientEvent::Mirror(Box::new(update))),
+                Err(e) => warn!("⚠️ Ignoring malformed hub mirror: {}", e),
+            }
+            return Ok(());
+        }
+        STANDBYS_MESSAGE => {
+            inner.set_standbys(&message.payload["standby_hubs"]);
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    if let Some(seq) = message.seq {
+        let fresh = inner.accept(seq, &message);
+
+        let ack = DeviceMessage {
+            message_type: "ack".into(),
+            device_id: Some(config.device_id.clone()),
+            device_type: config.device_type.clone(),
+            payload: serde_json::json!({ "seq": seq }),
+            ..Default::default()
+        };
+        send(ws, &ack).await?;
+
+        if !fresh {
+            return Ok(());
+        }
+    }
+
+    inner.emit(ClientEvent::Message(message));
+    Ok(())
+}
+
+async fn send(ws: &mut ClientStream, message: &DeviceMessage) -> AppResult<()> {
+    ws.send(Message::Text(serde_json::to_string(message)?.into()))
+        .await
+        .map_err(|e| AppError::Network(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(url: &str, device_id: &str) -> HubConfig {
+        HubConfig {
+            url: url.into(),
+            device_id: device_id.into(),
+            device_type: "KDS".into(),
+            token: Some("token".into()),
+            fingerprint: None,
+            location_id: None,
+            standby: false,
+        }
+    }
+
+    fn receive(client: &HubClient, seq: i64) {
+        let message = DeviceMessage {
+            message_type: "order".into(),
+            seq: Some(seq),
+            ..Default::default()
+        };
+        assert!(client.inner.accept(seq, &message));
+    }
+
+    #[tokio::test]
+    async fn last_seq_starts_over_for_another_hub() {
+        // Nothing listens on port 1: the client just keeps retrying
+        let hub = config("ws://127.0.0.1:1", "kds_1");
+        let client = HubClient::new(ReconnectPolicy::default(), HeartbeatConfig::default());
+
+        client.start(hub.clone());
+        receive(&client, 7);
+
+        // Stopped and started again, or moved to a standby: same outbox
+        client.stop();
+        assert_eq!(client.start(hub.clone()).last_seq, 7);
+        let mut moved = hub.clone();
+        adopt_url(&client.inner, &mut moved, "ws://127.0.0.1:2");
+        assert_eq!(client.start(moved.clone()).last_seq, 7);
+        assert_eq!(client.messages_since(0).len(), 1);
+
+        // Re-paired, then a new hub
+        assert_eq!(client.start(config("ws://127.0.0.1:2", "kds_2")).last_seq, 0);
+        assert!(client.messages_since(0).is_empty());
+        receive(&client, 3);
+        assert_eq!(client.start(config("ws://127.0.0.1:3", "kds_2")).last_seq, 0);
+
+        let pinned = HubConfig { fingerprint: Some("AB".into()), ..config("ws://127.0.0.1:3", "kds_2") };
+        receive(&client, 1);
+        assert_eq!(client.start(pinned).last_seq, 0, "another certificate");
+        client.stop();
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use log::{info, warn};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+
+/// DNS-SD service type the POS hub advertises
+pub const SERVICE_TYPE: &str = "_hashtouch._tcp.local.";
+
+/// Bumped if the TXT keys change meaning
+const TXT_VERSION: &str = "1";
+
+/// How long a discovery listens for answers
+pub const BROWSE_TIME: Duration = Duration::from_secs(3);
+
+fn mdns_error(e: mdns_sd::Error) -> AppError {
+    AppError::Network(format!("mDNS: {}", e))
+}
+
+// ==============================
+// Advertising (POS)
+// ==============================
+
+/// What the POS puts in its TXT record
+#[derive(Debug, Clone, Default)]
+pub struct HubAdvert {
+    /// Instance name, unique on the LAN
+    pub name: String,
+    pub location_id: Option<String>,
+    pub brand_id: Option<String>,
+    pub tls: bool,
+    /// Certificate fingerprint, so a pinned client can find its hub again
+    pub fingerprint: Option<String>,
+}
+
+/// Keeps the hub advertised until dropped
+pub struct Advertisement {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+impl Drop for Advertisement {
+    fn drop(&mut self) {
+        let _ = self.daemon.unregister(&self.fullname);
+        let _ = self.daemon.shutdown();
+    }
+}
+
+/// Announce the hub listening on `port`. Addresses follow the host's
+/// interfaces, so a new DHCP lease is picked up without restarting.
+pub fn advertise(port: u16, advert: &HubAdvert) -> AppResult<Advertisement> {
+    let daemon = ServiceDaemon::new().map_err(mdns_error)?;
+
+    let mut txt = HashMap::from([
+        ("v".to_string(), TXT_VERSION.to_string()),
+        ("tls".to_string(), if advert.tls { "1" } else { "0" }.to_string()),
+    ]);
+    let optional = [
+        ("location_id", &advert.location_id),
+        ("brand_id", &advert.brand_id),
+        ("fp", &advert.fingerprint),
+    ];
+    txt.extend(
+        optional
+            .into_iter()
+            .filter_map(|(key, value)| value.clone().map(|v| (key.to_string(), v))),
+    );
+
+    let host: String = advert
+        .name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+
+    let service = ServiceInfo::new(SERVICE_TYPE, &advert.name, &format!("{}.local.", host), "", port, txt)
+        .map_err(mdns_error)?
+        .enable_addr_auto();
+    let fullname = service.get_fullname().to_string();
+
+    daemon.register(service).map_err(mdns_error)?;
+    info!("📣 Advertising {} on port {}", fullname, port);
+
+    Ok(Advertisement { daemon, fullname })
+}
+
+// ==============================
+// Discovery (KDS / QUEUE)
+// ==============================
+
+/// A hub that answered on the LAN
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveredHub {
+    pub name: String,
+    /// Ready to save as `ws_server_url`
+    pub url: String,
+    pub addresses: Vec<String>,
+    pub port: u16,
+    pub location_id: Option<String>,
+    pub brand_id: Option<String>,
+    pub tls: bool,
+    pub fingerprint: Option<String>,
+}
+
+impl DiscoveredHub {
+    fn from_service(service: &ServiceInfo) -> Option<Self> {
+        // IPv4 first; link-local v6 needs a scope id a URL can't carry
+        let mut addresses: Vec<IpAddr> = service
+            .get_addresses()
+            .iter()
+            .copied()
+            .filter(|ip| match ip {
+                IpAddr::V4(v4) => !v4.is_loopback(),
+                IpAddr::V6(v6) => !v6.is_loopback() && (v6.segments()[0] & 0xffc0) != 0xfe80,
+            })
+            .collect();
+        addresses.sort_by_key(|ip| (ip.is_ipv6(), *ip));
+
+        let host = match addresses.first()? {
+            IpAddr::V4(v4) => v4.to_string(),
+            IpAddr::V6(v6) => format!("[{}]", v6),
+        };
+
+        let txt = |key: &str| {
+            service
+                .get_property_val_str(key)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        let tls = txt("tls").as_deref() == Some("1");
+
+        let suffix = format!(".{}", SERVICE_TYPE);
+        let fullname = service.get_fullname();
+
+        Some(Self {
+            name: fullname.strip_suffix(&suffix).unwrap_or(fullname).to_string(),
+            url: format!("{}://{}:{}", if tls { "wss" } else { "ws" }, host, service.get_port()),
+            addresses: addresses.iter().map(IpAddr::to_string).collect(),
+            port: service.get_port(),
+            location_id: txt("location_id"),
+            brand_id: txt("brand_id"),
+            tls,
+            fingerprint: txt("fp"),
+        })
+    }
+}
+
+/// Listen for hubs for `browse_time` and return every one that resolved
+pub async fn discover(browse_time: Duration) -> AppResult<Vec<DiscoveredHub>> {
+    let daemon = ServiceDaemon::new().map_err(mdns_error)?;
+    let events = daemon.browse(SERVICE_TYPE).map_err(mdns_error)?;
+
+    let mut found: HashMap<String, DiscoveredHub> = HashMap::new();
+    let deadline = tokio::time::Instant::now() + browse_time;
+
+    while let Ok(Ok(event)) = tokio::time::timeout_at(deadline, events.recv_async()).await {
+        match event {
+            ServiceEvent::ServiceResolved(service) => {
+                if let Some(hub) = DiscoveredHub::from_service(&service) {
+                    found.insert(service.get_fullname().to_string(), hub);
+                }
+            }
+            ServiceEvent::ServiceRemoved(_, fullname) => {
+                found.remove(&fullname);
+            }
+            _ => {}
+        }
+    }
+
+    if let Err(e) = daemon.stop_browse(SERVICE_TYPE) {
+        warn!("⚠️ Failed to stop mDNS browse: {}", e);
+    }
+    let _ = daemon.shutdown();
+
+    let mut hubs: Vec<DiscoveredHub> = found.into_values().collect();
+    hubs.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(hubs)
+}
+
+/// Hubs that serve `location_id` (all of them when it is None)
+pub fn for_location(hubs: Vec<DiscoveredHub>, location_id: Option<&str>) -> Vec<DiscoveredHub> {
+    match location_id {
+        Some(location) => hubs
+            .into_iter()
+            .filter(|h| h.location_id.as_deref() == Some(location))
+            .collect(),
+        None => hubs,
+    }
+}
+
+/// The hub a client should use. With a pinned certificate only the hub
+/// serving it qualifies (same POS, possibly on a new address); otherwise
+/// the first hub for the location.
+pub fn pick_hub<'a>(
+    hubs: &'a [DiscoveredHub],
+    location_id: Option<&str>,
+    pinned: Option<&str>,
+) -> Option<&'a DiscoveredHub> {
+    let mut candidates = hubs
+        .iter()
+        .filter(|h| location_id.is_none() || h.location_id.as_deref() == location_id);
+
+    match pinned {
+        Some(pin) => candidates.find(|h| {
+            h.fingerprint
+                .as_deref()
+                .is_some_and(|fp| fp.eq_ignore_ascii_case(pin))
+        }),
+        None => candidates.next(),
+    }
+}
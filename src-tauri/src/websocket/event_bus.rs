@@ -2,7 +2,7 @@
 
 use super::DeviceMessage;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tokio::sync::{mpsc, RwLock};
 use log::{info, error};
 
@@ -15,15 +15,16 @@ pub type EventHandler = Arc<dyn Fn(&DeviceMessage) + Send + Sync>;
 #[derive(Clone)]
 pub struct EventBus {
     handlers: Arc<RwLock<HashMap<String, Vec<EventHandler>>>>,
-    rx: Arc<RwLock<mpsc::UnboundedReceiver<DeviceMessage>>>,
+    /// Taken by `start`, so receiving never holds a lock
+    rx: Arc<Mutex<Option<mpsc::Receiver<DeviceMessage>>>>,
 }
 
 impl EventBus {
     /// Create a new EventBus
-    pub fn new(rx: mpsc::UnboundedReceiver<DeviceMessage>) -> Self {
+    pub fn new(rx: mpsc::Receiver<DeviceMessage>) -> Self {
         Self {
             handlers: Arc::new(RwLock::new(HashMap::new())),
-            rx: Arc::new(RwLock::new(rx)),
+            rx: Arc::new(Mutex::new(Some(rx))),
         }
     }
 
@@ -44,24 +45,18 @@ impl EventBus {
     }
 
     /// Start listening for incoming events
-    /// This should be run inside an async task
+    /// This should be run inside an async task, once
     pub async fn start(&self) {
+        let Some(mut rx) = self.rx.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+            error!("❌ EventBus already started");
+            return;
+        };
         info!("🚀 EventBus started");
 
-        loop {
-            let message = {
-                let mut rx = self.rx.write().await;
-                rx.recv().await
-            };
-
-            match message {
-                Some(msg) => self.dispatch(&msg).await,
-                None => {
-                    error!("❌ EventBus channel closed");
-                    break;
-                }
-            }
+        while let Some(msg) = rx.recv().await {
+            self.dispatch(&msg).await;
         }
+        error!("❌ EventBus channel closed");
     }
 
     /// Dispatch event to subscribers
@@ -0,0 +1,329 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::{mpsc, Notify};
+use tokio_tungstenite::tungstenite::Message;
+
+/// ==============================
+/// Connection limits
+/// ==============================
+#[derive(Debug, Clone)]
+pub struct LimitsConfig {
+    /// Frames queued for one device before its overflow policy applies
+    pub device_queue: usize,
+    /// Device messages queued for the EventBus before new ones are dropped
+    pub event_queue: usize,
+    /// Largest frame (and message) accepted from a device
+    pub max_frame_bytes: usize,
+    /// Frames per second a device may send, on average
+    pub rate_per_sec: u32,
+    /// Frames a device may send at once above the average
+    pub burst: u32,
+    /// What happens when a device can't keep up with what it is sent
+    pub on_overflow: OverflowPolicy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the frame. Not for outbox messages: an ack covers every seq
+    /// before it, so a dropped one would never be replayed, and a device
+    /// that can't take one is disconnected under either policy.
+    Drop,
+    /// Drop the connection; the device catches up from the outbox when it
+    /// reconnects
+    Disconnect,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            device_queue: 256,
+            event_queue: 1024,
+            max_frame_bytes: 256 * 1024,
+            rate_per_sec: 20,
+            burst: 60,
+            on_overflow: OverflowPolicy::Disconnect,
+        }
+    }
+}
+
+impl LimitsConfig {
+    /// Defaults, overridden by `HASHTOUCH_WS_DEVICE_QUEUE`,
+    /// `HASHTOUCH_WS_EVENT_QUEUE`, `HASHTOUCH_WS_MAX_FRAME_KB`,
+    /// `HASHTOUCH_WS_RATE`, `HASHTOUCH_WS_BURST` and
+    /// `HASHTOUCH_WS_OVERFLOW` (`drop` | `disconnect`) when set
+    pub fn from_env() -> Self {
+        let num = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|n| *n > 0)
+        };
+
+        let defaults = Self::default();
+        let on_overflow = match std::env::var("HASHTOUCH_WS_OVERFLOW").ok().as_deref() {
+            Some("drop") => OverflowPolicy::Drop,
+            Some("disconnect") => OverflowPolicy::Disconnect,
+            _ => defaults.on_overflow,
+        };
+
+        Self {
+            device_queue: num("HASHTOUCH_WS_DEVICE_QUEUE").unwrap_or(defaults.device_queue),
+            event_queue: num("HASHTOUCH_WS_EVENT_QUEUE").unwrap_or(defaults.event_queue),
+            max_frame_bytes: num("HASHTOUCH_WS_MAX_FRAME_KB")
+                .map(|kb| kb * 1024)
+                .unwrap_or(defaults.max_frame_bytes),
+            rate_per_sec: num("HASHTOUCH_WS_RATE").map_or(defaults.rate_per_sec, |n| n as u32),
+            burst: num("HASHTOUCH_WS_BURST").map_or(defaults.burst, |n| n as u32),
+            on_overflow,
+        }
+    }
+}
+
+/// ==============================
+/// Rate limiter
+/// ==============================
+///
+/// Token bucket: `burst` frames at once, refilled at `rate_per_sec`.
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(config: &LimitsConfig) -> Self {
+        let burst = config.burst.max(1) as f64;
+        Self {
+            rate: config.rate_per_sec.max(1) as f64,
+            burst,
+            tokens: burst,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Take a token for one frame; false when the device is over its rate
+    pub fn allow(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// ==============================
+/// Device sender
+/// ==============================
+///
+/// The bounded queue of frames going out to one device. Sends never wait:
+/// when the queue is full the frame is dropped and, under
+/// `OverflowPolicy::Disconnect` or for an outbox message, the connection
+/// task is told to hang up.
+#[derive(Clone)]
+pub struct DeviceSender {
+    tx: mpsc::Sender<Message>,
+    shared: Arc<SenderShared>,
+}
+
+struct SenderShared {
+    disconnect_on_full: AtomicBool,
+    /// Wakes the connection task when the device must be dropped, and why
+    kick: Notify,
+    kick_reason: OnceLock<&'static str>,
+    dropped: AtomicU64,
+    metrics: Arc<WsMetrics>,
+}
+
+impl DeviceSender {
+    pub fn channel(
+        config: &LimitsConfig,
+        metrics: Arc<WsMetrics>,
+    ) -> (Self, mpsc::Receiver<Message>) {
+        let (tx, rx) = mpsc::channel(config.device_queue.max(1));
+        let sender = Self {
+            tx,
+            shared: Arc::new(SenderShared {
+                disconnect_on_full: AtomicBool::new(config.on_overflow == OverflowPolicy::Disconnect),
+                kick: Notify::new(),
+                kick_reason: OnceLock::new(),
+                dropped: AtomicU64::new(0),
+                metrics,
+            }),
+        };
+        (sender, rx)
+    }
+
+    /// Override the configured policy, e.g. for a standby that must not
+    /// miss a mirror update
+    pub fn set_policy(&self, policy: OverflowPolicy) {
+        self.shared
+            .disconnect_on_full
+            .store(policy == OverflowPolicy::Disconnect, Ordering::Relaxed);
+    }
+
+    /// Queue `msg`; false if it was dropped
+    pub fn send(&self, msg: Message) -> bool {
+        self.try_send(msg, false)
+    }
+
+    /// Queue an outbox message (one with a `seq`). If there's no room the
+    /// device is disconnected whatever the policy, to get it on replay.
+    pub fn send_stored(&self, msg: Message) -> bool {
+        self.try_send(msg, true)
+    }
+
+    /// Queue an outbox message, waiting up to `within` for room (for a
+    /// replay, which can be longer than the queue). Past that the device is
+    /// disconnected, as with `send_stored`.
+    pub async fn send_within(&self, msg: Message, within: Duration) -> bool {
+        match tokio::time::timeout(within, self.tx.reserve()).await {
+            Ok(Ok(permit)) => {
+                permit.send(msg);
+                true
+            }
+            Ok(Err(_)) => false,
+            Err(_) => {
+                self.overflowed(&msg, true);
+                false
+            }
+        }
+    }
+
+    /// Queue a close frame, or hang up right away when there's no room
+    pub fn close(&self) {
+        if self.tx.try_send(Message::Close(None)).is_err() {
+            self.kick("closed");
+        }
+    }
+
+    /// Resolves when the device must be dropped, with the reason
+    /// (`backpressure` when it fell behind its queue)
+    pub async fn kicked(&self) -> &'static str {
+        self.shared.kick.notified().await;
+        self.shared.kick_reason.get().copied().unwrap_or("closed")
+    }
+
+    pub fn same_channel(&self, other: &Self) -> bool {
+        self.tx.same_channel(&other.tx)
+    }
+
+    pub fn queue(&self) -> QueueDepth {
+        QueueDepth::of(&self.tx)
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    fn try_send(&self, msg: Message, stored: bool) -> bool {
+        match self.tx.try_send(msg) {
+            Ok(()) => true,
+            Err(mpsc::error::TrySendError::Full(msg)) => {
+                self.overflowed(&msg, stored);
+                false
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+        }
+    }
+
+    fn overflowed(&self, msg: &Message, stored: bool) {
+        // A missed ping costs nothing; the next one follows
+        if matches!(msg, Message::Ping(_)) {
+            return;
+        }
+
+        self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+        self.shared.metrics.dropped_frames.fetch_add(1, Ordering::Relaxed);
+        if stored || self.shared.disconnect_on_full.load(Ordering::Relaxed) {
+            self.kick("backpressure");
+        }
+    }
+
+    fn kick(&self, reason: &'static str) {
+        let _ = self.shared.kick_reason.set(reason);
+        self.shared.kick.notify_one();
+    }
+}
+
+/// ==============================
+/// Metrics
+/// ==============================
+#[derive(Debug, Default)]
+pub struct WsMetrics {
+    /// Device messages dropped because the EventBus (or router) queue was full
+    pub dropped_events: AtomicU64,
+    /// Frames dropped because a device's queue was full
+    pub dropped_frames: AtomicU64,
+    /// Devices dropped for not keeping up with their queue
+    pub backpressure_disconnects: AtomicU64,
+    /// Frames ignored for going over a device's rate
+    pub rate_limited_frames: AtomicU64,
+    /// Devices dropped for staying over their rate
+    pub rate_limit_disconnects: AtomicU64,
+    /// Devices dropped for sending a frame over `max_frame_bytes`
+    pub oversized_frames: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueDepth {
+    pub len: usize,
+    pub capacity: usize,
+}
+
+impl QueueDepth {
+    pub fn of<T>(tx: &mpsc::Sender<T>) -> Self {
+        Self {
+            len: tx.max_capacity() - tx.capacity(),
+            capacity: tx.max_capacity(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceQueue {
+    pub device_id: String,
+    pub device_type: String,
+    #[serde(flatten)]
+    pub queue: QueueDepth,
+    /// Frames dropped on this connection
+    pub dropped: u64,
+}
+
+/// What `get_ws_metrics` returns
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSnapshot {
+    pub event_queue: QueueDepth,
+    pub devices: Vec<DeviceQueue>,
+    pub dropped_events: u64,
+    pub dropped_frames: u64,
+    pub backpressure_disconnects: u64,
+    pub rate_limited_frames: u64,
+    pub rate_limit_disconnects: u64,
+    pub oversized_frames: u64,
+}
+
+impl MetricsSnapshot {
+    pub fn new(metrics: &WsMetrics, event_queue: QueueDepth, devices: Vec<DeviceQueue>) -> Self {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        Self {
+            event_queue,
+            devices,
+            dropped_events: load(&metrics.dropped_events),
+            dropped_frames: load(&metrics.dropped_frames),
+            backpressure_disconnects: load(&metrics.backpressure_disconnects),
+            rate_limited_frames: load(&metrics.rate_limited_frames),
+            rate_limit_disconnects: load(&metrics.rate_limit_disconnects),
+            oversized_frames: load(&metrics.oversized_frames),
+        }
+    }
+}
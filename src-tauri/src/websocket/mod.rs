@@ -1,26 +1,60 @@
 use std::collections::HashMap;
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
 
 use futures_util::{SinkExt, StreamExt};
 use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
-use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::{mpsc, RwLock};
-use tokio_tungstenite::{accept_async, tungstenite::Message};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tokio_tungstenite::accept_async_with_config;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::{self, Message};
+use rusqlite::Connection;
+
+use crate::db::models::device::PairingCode;
+use crate::db::models::device_repo;
+use crate::db::models::ws_outbox_repo;
+use crate::db::pool::DbPool;
+use crate::error::{AppError, AppResult};
 
 pub mod event_bus;
+pub mod client;
+pub mod discovery;
+pub mod limits;
+pub mod pairing;
+pub mod presence;
+pub mod routing;
+pub mod standby;
+pub mod tls;
 #[cfg(any(desktop, target_os = "android"))]
 pub mod ws_routes;
+
+use limits::{DeviceQueue, DeviceSender, LimitsConfig, MetricsSnapshot, OverflowPolicy, QueueDepth, RateLimiter, WsMetrics};
+use pairing::PairingCodes;
+use presence::{DeviceInfo, HeartbeatConfig, PresenceEvent};
+use standby::{MirrorUpdate, MIRROR_MESSAGE, STANDBYS_MESSAGE, STANDBY_TYPE};
+use tls::ServerTls;
+
+/// Port the POS hub (and a standby taking over) listens on
+pub const HUB_PORT: u16 = 9001;
+
 /// ==============================
 /// Device Message (shared payload)
 /// ==============================
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DeviceMessage {
     pub message_type: String,
     pub device_id: Option<String>,
     pub device_type: String, // POS | KDS | QUEUE | PRINTER
     pub payload: serde_json::Value,
+    /// Set on messages published through the outbox; clients ack `seq`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<i64>,
 }
 
 /// ==============================
@@ -30,118 +64,608 @@ pub struct DeviceMessage {
 pub struct ConnectedDevice {
     pub device_id: String,
     pub device_type: String,
-    pub tx: mpsc::UnboundedSender<Message>,
+    pub tx: DeviceSender,
+    pub remote_addr: SocketAddr,
+    pub connected_at: String,
+    /// Epoch millis of the last frame received, shared with the connection task
+    pub last_seen: Arc<AtomicI64>,
+    /// Standby POS only: where it will serve the hub if it takes over
+    pub hub_url: Option<String>,
+}
+
+impl ConnectedDevice {
+    pub fn info(&self) -> DeviceInfo {
+        DeviceInfo {
+            device_id: self.device_id.clone(),
+            device_type: self.device_type.clone(),
+            remote_addr: self.remote_addr.to_string(),
+            connected_at: self.connected_at.clone(),
+            last_seen: presence::ms_to_rfc3339(self.last_seen.load(Ordering::Relaxed)),
+        }
+    }
 }
 
 /// Map of connected devices
 pub type DeviceMap = Arc<RwLock<HashMap<String, ConnectedDevice>>>;
 
+/// Outbox messages older than this are dropped
+const OUTBOX_KEEP_HOURS: i64 = 24;
+
+/// How often the outbox is pruned while the server runs (and once at startup)
+const OUTBOX_PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// How long a replay may wait on a device to make room in its queue
+const REPLAY_WAIT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Everything a connection task needs from the server
+#[derive(Clone)]
+struct ServerContext {
+    devices: DeviceMap,
+    event_tx: mpsc::Sender<DeviceMessage>,
+    pool: DbPool,
+    pairing: Arc<Mutex<PairingCodes>>,
+    tls: Arc<Mutex<Option<ServerTls>>>,
+    heartbeat: HeartbeatConfig,
+    limits: LimitsConfig,
+    metrics: Arc<WsMetrics>,
+    presence: broadcast::Sender<PresenceEvent>,
+}
+
+impl ServerContext {
+    fn tls(&self) -> Option<ServerTls> {
+        self.tls.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Send state built by `build` to every connected standby; skipped
+    /// (without touching the DB) when there is none
+    async fn mirror(&self, build: impl FnOnce(&Connection) -> AppResult<MirrorUpdate>) {
+        let devices = self.devices.read().await;
+        if !devices.values().any(|d| d.device_type == STANDBY_TYPE) {
+            return;
+        }
+
+        let update = self
+            .pool
+            .reader()
+            .map_err(AppError::from)
+            .and_then(|conn| build(&conn));
+
+        match update {
+            Ok(update) => send_mirror(&devices, &update),
+            Err(e) => error!("❌ Failed to mirror to the standby hub: {}", e),
+        }
+    }
+
+    /// Tell KDS/QUEUE devices where the standbys are
+    async fn announce_standbys(&self) {
+        let devices = self.devices.read().await;
+        let msg = DeviceMessage {
+            message_type: STANDBYS_MESSAGE.into(),
+            device_id: None,
+            device_type: "SERVER".into(),
+            payload: serde_json::json!({ "standby_hubs": standby_urls(&devices) }),
+            ..Default::default()
+        };
+        let Ok(text) = serde_json::to_string(&msg) else {
+            return;
+        };
+
+        for device in devices.values().filter(|d| d.device_type != STANDBY_TYPE) {
+            device.tx.send(Message::Text(text.clone().into()));
+        }
+    }
+
+    fn notify(&self, device: DeviceInfo, connected: bool, reason: Option<&str>) {
+        // No receivers (e.g. before the app subscribes) is fine
+        let _ = self.presence.send(PresenceEvent {
+            connected,
+            device,
+            reason: reason.map(str::to_string),
+        });
+    }
+}
+
 /// ==============================
 /// WebSocket Server
 /// ==============================
 pub struct WebSocketServer {
-    devices: DeviceMap,
-    event_tx: mpsc::UnboundedSender<DeviceMessage>,
+    ctx: ServerContext,
 }
 
 impl WebSocketServer {
-    pub fn new(event_tx: mpsc::UnboundedSender<DeviceMessage>) -> Self {
+    pub fn new(
+        event_tx: mpsc::Sender<DeviceMessage>,
+        pool: DbPool,
+        tls: Option<ServerTls>,
+        heartbeat: HeartbeatConfig,
+        limits: LimitsConfig,
+    ) -> Self {
+        let (presence, _) = broadcast::channel(64);
+
         Self {
-            devices: Arc::new(RwLock::new(HashMap::new())),
-            event_tx,
+            ctx: ServerContext {
+                devices: Arc::new(RwLock::new(HashMap::new())),
+                event_tx,
+                pool,
+                pairing: Arc::new(Mutex::new(PairingCodes::default())),
+                tls: Arc::new(Mutex::new(tls)),
+                heartbeat,
+                limits,
+                metrics: Arc::new(WsMetrics::default()),
+                presence,
+            },
         }
     }
 
     /// Start WebSocket server
+    ///
+    /// With TLS on, remote devices must use `wss://`; plain `ws://` is still
+    /// accepted over loopback for this POS's own windows, which can't trust a
+    /// self-signed certificate.
     pub async fn start(&self, addr: &str) -> Result<(), Box<dyn std::error::Error>> {
         let listener = TcpListener::bind(addr).await?;
-        info!("🚀 WebSocket server listening on {}", addr);
+        info!(
+            "🚀 WebSocket server listening on {} ({})",
+            addr,
+            if self.ctx.tls().is_some() { "wss" } else { "ws" }
+        );
+
+        // First tick is immediate
+        let mut prune = tokio::time::interval(OUTBOX_PRUNE_INTERVAL);
 
         loop {
-            let (stream, addr) = listener.accept().await?;
-            let devices = self.devices.clone();
-            let event_tx = self.event_tx.clone();
+            let (stream, addr) = tokio::select! {
+                accepted = listener.accept() => accepted?,
+                _ = prune.tick() => {
+                    prune_outbox(&self.ctx);
+                    continue;
+                }
+            };
+            let ctx = self.ctx.clone();
 
             tokio::spawn(async move {
-                if let Err(e) = handle_connection(stream, addr, devices, event_tx).await {
+                let result = match ctx.tls() {
+                    Some(tls) if tls::is_tls_hello(&stream).await => {
+                        match tls.acceptor.accept(stream).await {
+                            Ok(stream) => handle_connection(stream, addr, ctx).await,
+                            Err(e) => Err(e.into()),
+                        }
+                    }
+                    Some(_) if !addr.ip().is_loopback() => {
+                        warn!("⛔ Refused plain ws:// from {}, TLS is required", addr);
+                        Ok(())
+                    }
+                    _ => handle_connection(stream, addr, ctx).await,
+                };
+
+                if let Err(e) = result {
                     error!("❌ WebSocket error from {}: {}", addr, e);
                 }
             });
         }
     }
 
+    /// Fingerprint of the hub certificate when serving `wss://`
+    pub fn tls_fingerprint(&self) -> Option<String> {
+        self.ctx.tls().map(|t| t.fingerprint)
+    }
+
+    /// Serve `wss://` with `tls` from now on, e.g. the certificate a standby
+    /// mirrored from the primary it is taking over from
+    pub fn set_tls(&self, tls: Option<ServerTls>) {
+        *self.ctx.tls.lock().unwrap_or_else(|e| e.into_inner()) = tls;
+    }
+
     /// Get connected devices (read-only)
     pub fn get_devices(&self) -> DeviceMap {
-        self.devices.clone()
+        self.ctx.devices.clone()
+    }
+
+    /// Registered devices with their last-seen times
+    pub async fn connected_devices(&self) -> Vec<DeviceInfo> {
+        let mut devices: Vec<DeviceInfo> =
+            self.ctx.devices.read().await.values().map(ConnectedDevice::info).collect();
+        devices.sort_by(|a, b| (&a.device_type, &a.device_id).cmp(&(&b.device_type, &b.device_id)));
+        devices
+    }
+
+    /// Queue depths and what the limits have dropped so far
+    pub async fn metrics(&self) -> MetricsSnapshot {
+        let devices = self
+            .ctx
+            .devices
+            .read()
+            .await
+            .values()
+            .map(|d| DeviceQueue {
+                device_id: d.device_id.clone(),
+                device_type: d.device_type.clone(),
+                queue: d.tx.queue(),
+                dropped: d.tx.dropped(),
+            })
+            .collect();
+
+        MetricsSnapshot::new(&self.ctx.metrics, QueueDepth::of(&self.ctx.event_tx), devices)
+    }
+
+    /// Count a device message dropped on its way to a handler, for `metrics`
+    pub fn event_dropped(&self) {
+        self.ctx.metrics.dropped_events.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Connect/disconnect notifications, e.g. to forward as Tauri events
+    pub fn subscribe_presence(&self) -> broadcast::Receiver<PresenceEvent> {
+        self.ctx.presence.subscribe()
+    }
+
+    /// Issue a one-time code a KDS/QUEUE can pair with
+    pub fn create_pairing_code(&self, role: &str) -> AppResult<PairingCode> {
+        let conn = self.ctx.pool.reader()?;
+        let mut pairing = self
+            .ctx
+            .pairing
+            .lock()
+            .map_err(|_| AppError::Internal("pairing codes lock poisoned".into()))?;
+        let mut code = pairing.issue(&conn, role)?;
+        code.fingerprint = self.tls_fingerprint();
+        Ok(code)
+    }
+
+    /// Store a message for every `device_type` device and send it to those
+    /// connected now; the rest get it when they re-register
+    pub async fn publish(&self, device_type: &str, mut message: DeviceMessage) -> AppResult<i64> {
+        let seq = {
+            let conn = self.ctx.pool.writer()?;
+            let message_id = match message.message_id.clone() {
+                Some(id) => id,
+                None => device_repo::random_hex(&conn, 16)?,
+            };
+            message.message_id = Some(message_id.clone());
+            message.seq = None;
+
+            let stored = serde_json::to_string(&message)?;
+            ws_outbox_repo::enqueue(&conn, &message_id, device_type, None, &stored)?
+        };
+
+        message.seq = Some(seq);
+        broadcast_to_device_type(&self.ctx.devices, device_type, &message)
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to broadcast to {}: {}", device_type, e)))?;
+
+        self.ctx.mirror(|conn| MirrorUpdate::entry(conn, seq)).await;
+        Ok(seq)
+    }
+
+    /// Store a message for one device and send it if it is connected; it
+    /// gets it on re-register otherwise
+    pub async fn publish_to_device(&self, device_id: &str, mut message: DeviceMessage) -> AppResult<i64> {
+        let connected_type = self
+            .ctx
+            .devices
+            .read()
+            .await
+            .get(device_id)
+            .map(|d| d.device_type.clone());
+
+        let seq = {
+            let conn = self.ctx.pool.writer()?;
+            // Replay looks up the outbox by type, so it has to be the device's
+            let device_type = match connected_type {
+                Some(device_type) => device_type,
+                None => device_repo::get_paired_device(&conn, device_id)?.role,
+            };
+            let message_id = match message.message_id.clone() {
+                Some(id) => id,
+                None => device_repo::random_hex(&conn, 16)?,
+            };
+            message.message_id = Some(message_id.clone());
+            message.seq = None;
+
+            let stored = serde_json::to_string(&message)?;
+            ws_outbox_repo::enqueue(&conn, &message_id, &device_type, Some(device_id), &stored)?
+        };
+
+        message.seq = Some(seq);
+        if let Err(e) = send_to_device(&self.ctx.devices, device_id, &message).await {
+            // Not connected: it's in the outbox
+            info!("📭 {} kept for {}: {}", message.message_type, device_id, e);
+        }
+
+        self.ctx.mirror(|conn| MirrorUpdate::entry(conn, seq)).await;
+        Ok(seq)
     }
+
+    /// Copy the kitchen stations and mappings (just edited) to the standbys
+    pub async fn kitchen_changed(&self) {
+        self.ctx.mirror(MirrorUpdate::kitchen).await;
+    }
+
+    /// Copy the route table (just saved) to the standbys
+    pub async fn routes_changed(&self) {
+        self.ctx.mirror(MirrorUpdate::routes).await;
+    }
+
+    /// Copy a paired device's profile (e.g. just revoked) to the standbys
+    pub async fn device_changed(&self, device_id: &str) {
+        self.ctx.mirror(|conn| MirrorUpdate::device(conn, device_id)).await;
+    }
+
+    /// Drop a live connection, e.g. right after its token was revoked
+    pub async fn disconnect(&self, device_id: &str) {
+        let removed = self.ctx.devices.write().await.remove(device_id);
+        if let Some(device) = removed {
+            device.tx.close();
+            info!("🔌 Disconnected device {}", device_id);
+            self.ctx.notify(device.info(), false, Some("revoked"));
+        }
+    }
+}
+
+/// Who is on the other end once `register` succeeded
+struct Identity {
+    device_id: String,
+    device_type: String,
+    /// Set for a standby POS
+    hub_url: Option<String>,
 }
 
 /// ==============================
 /// Connection Handler
 /// ==============================
-async fn handle_connection(
-    stream: TcpStream,
+///
+/// Only `pair` and `register` are accepted until the device has registered.
+/// Remote devices register with the token they got from `pair`; the POS's
+/// own windows connect over loopback and are trusted as before.
+///
+/// Every connection is held to `ctx.limits`: frames over `max_frame_bytes`
+/// end it, frames over its rate are ignored (and it is dropped if it keeps
+/// that up), and a device that can't keep up with its queue is dropped.
+async fn handle_connection<S>(
+    stream: S,
     addr: SocketAddr,
-    devices: DeviceMap,
-    event_tx: mpsc::UnboundedSender<DeviceMessage>,
-) -> Result<(), Box<dyn std::error::Error>> {
+    ctx: ServerContext,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
     info!("📡 New WebSocket connection from {}", addr);
 
-    let ws_stream = accept_async(stream).await?;
+    let config = WebSocketConfig::default()
+        .max_message_size(Some(ctx.limits.max_frame_bytes))
+        .max_frame_size(Some(ctx.limits.max_frame_bytes));
+    let ws_stream = accept_async_with_config(stream, Some(config)).await?;
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
 
-    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
-    let mut device_id: Option<String> = None;
+    let (tx, mut rx) = DeviceSender::channel(&ctx.limits, ctx.metrics.clone());
+    let mut identity: Option<Identity> = None;
+    let mut limiter = RateLimiter::new(&ctx.limits);
+    let mut strikes = 0;
+
+    let connected_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let last_seen = Arc::new(AtomicI64::new(presence::now_ms()));
+    let mut heartbeat = tokio::time::interval(ctx.heartbeat.interval);
+    heartbeat.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    heartbeat.tick().await; // the first tick fires immediately
+    let mut reason = "closed";
 
     // Outgoing messages → WebSocket
-    let sender_task = tokio::spawn(async move {
+    let mut sender_task = tokio::spawn(async move {
         while let Some(msg) = rx.recv().await {
-            if ws_sender.send(msg).await.is_err() {
+            let closing = matches!(msg, Message::Close(_));
+            if ws_sender.send(msg).await.is_err() || closing {
                 break;
             }
         }
     });
 
-    // Incoming messages
-    while let Some(msg) = ws_receiver.next().await {
-        match msg {
-            Ok(Message::Text(text)) => {
-                match serde_json::from_str::<DeviceMessage>(&text) {
-                    Ok(device_msg) => {
-                        // 🔐 Registration
-                        if device_msg.message_type == "register" {
-                            let id = device_msg.device_id.clone().unwrap_or_else(|| {
-                                format!("device_{}", chrono::Utc::now().timestamp())
-                            });
+    // Incoming messages, with a ping on every heartbeat tick
+    loop {
+        let msg = tokio::select! {
+            msg = ws_receiver.next() => match msg {
+                Some(msg) => msg,
+                None => break,
+            },
+            _ = heartbeat.tick() => {
+                let silent = presence::now_ms() - last_seen.load(Ordering::Relaxed);
+                if silent > ctx.heartbeat.timeout.as_millis() as i64 {
+                    warn!("💤 Nothing from {} for {}s, dropping the connection", addr, silent / 1000);
+                    reason = "timeout";
+                    tx.close();
+                    break;
+                }
+                tx.send(Message::Ping(Default::default()));
+                continue;
+            }
+            kicked = tx.kicked() => {
+                reason = kicked;
+                if reason == "backpressure" {
+                    warn!("🐢 {} can't keep up with its queue, dropping the connection", addr);
+                    ctx.metrics.backpressure_disconnects.fetch_add(1, Ordering::Relaxed);
+                }
+                break;
+            }
+        };
 
-                            let device = ConnectedDevice {
-                                device_id: id.clone(),
-                                device_type: device_msg.device_type.clone(),
-                                tx: tx.clone(),
-                            };
+        // Any frame counts as a sign of life, pongs included
+        last_seen.store(presence::now_ms(), Ordering::Relaxed);
 
-                            devices.write().await.insert(id.clone(), device);
-                            device_id = Some(id.clone());
+        match msg {
+            Ok(Message::Text(text)) => {
+                let parsed = serde_json::from_str::<DeviceMessage>(&text);
+
+                // 🚦 Acks only answer what the hub sent; everything else counts
+                let is_ack = identity.is_some() && parsed.as_ref().is_ok_and(|m| m.message_type == "ack");
+                if !is_ack && !limiter.allow() {
+                    ctx.metrics.rate_limited_frames.fetch_add(1, Ordering::Relaxed);
+                    strikes += 1;
+                    if strikes == 1 {
+                        warn!("🚦 {} is over its rate, ignoring frames", addr);
+                    }
+                    if strikes >= ctx.limits.burst {
+                        warn!("🚦 {} stayed over its rate, dropping the connection", addr);
+                        ctx.metrics.rate_limit_disconnects.fetch_add(1, Ordering::Relaxed);
+                        reason = "rate_limited";
+                        tx.close();
+                        break;
+                    }
+                    continue;
+                }
+                strikes = 0;
 
-                            info!("✅ Registered device {} ({})", id, device_msg.device_type);
+                let mut device_msg = match parsed {
+                    Ok(m) => m,
+                    Err(e) => {
+                        warn!("⚠️ Invalid message from {}: {}", addr, e);
+                        continue;
+                    }
+                };
+
+                match device_msg.message_type.as_str() {
+                    // 🤝 Pairing
+                    "pair" => match pair(&ctx, &device_msg) {
+                        Ok(reply) => {
+                            tx.send(Message::Text(serde_json::to_string(&reply)?.into()));
+                            if let Some(id) = reply.device_id.as_deref() {
+                                ctx.mirror(|conn| MirrorUpdate::device(conn, id)).await;
+                            }
+                        }
+                        Err(e) => {
+                            warn!("⛔ Pairing from {} refused: {}", addr, e);
+                            reject(&tx, &e)?;
+                            break;
+                        }
+                    },
 
+                    // 🔐 Registration
+                    "register" => match authenticate(&ctx, &addr, &device_msg) {
+                        Ok(who) => {
                             // Acknowledge registration
                             let ack = DeviceMessage {
                                 message_type: "register_ack".into(),
-                                device_id: Some(id),
+                                device_id: Some(who.device_id.clone()),
                                 device_type: "SERVER".into(),
-                                payload: serde_json::json!({ "status": "connected" }),
+                                payload: serde_json::json!({
+                                    "status": "connected",
+                                    "device_type": who.device_type,
+                                    "standby_hubs": standby_urls(&*ctx.devices.read().await),
+                                }),
+                                ..Default::default()
                             };
 
-                            let _ = tx.send(Message::Text(serde_json::to_string(&ack)?.into()));
-                        } else {
-                            // 📤 Forward event to EventBus
-                            if let Err(e) = event_tx.send(device_msg) {
-                                error!("❌ Failed to forward event: {}", e);
+                            tx.send(Message::Text(serde_json::to_string(&ack)?.into()));
+
+                            // 🔁 Whatever it missed while away. Sent before the device is in
+                            // the map, so nothing published meanwhile gets ahead of it.
+                            let replayed = match who.hub_url {
+                                Some(_) => None,
+                                None => replay(&ctx, &who, &tx).await,
+                            };
+
+                            // Held until the device is in the map, so a message published
+                            // meanwhile is either caught up below or broadcast after it
+                            let mut devices = ctx.devices.write().await;
+
+                            if who.hub_url.is_some() {
+                                // A standby that misses a mirror update must start over
+                                tx.set_policy(OverflowPolicy::Disconnect);
+
+                                // 🪞 A standby starts from a full copy; live updates follow
+                                let snapshot = ctx
+                                    .pool
+                                    .reader()
+                                    .map_err(AppError::from)
+                                    .and_then(|conn| MirrorUpdate::snapshot(&conn, ctx.tls().as_ref()));
+                                match snapshot {
+                                    Ok(update) => {
+                                        info!(
+                                            "🪞 Mirroring to standby {}: {} device(s), {} message(s)",
+                                            who.device_id,
+                                            update.devices.len(),
+                                            update.outbox.len()
+                                        );
+                                        tx.send(Message::Text(serde_json::to_string(&mirror_message(&update))?.into()));
+                                    }
+                                    Err(e) => error!("❌ Snapshot for standby {} failed: {}", who.device_id, e),
+                                }
+                            } else {
+                                // Published while the replay was going out
+                                match missed_messages(&ctx, &who, replayed) {
+                                    Ok(missed) => {
+                                        for msg in missed {
+                                            tx.send_stored(Message::Text(serde_json::to_string(&msg)?.into()));
+                                        }
+                                    }
+                                    Err(e) => error!("❌ Replay to {} failed: {}", who.device_id, e),
+                                }
+
+                                // Its cursor may be new; a standby needs it to replay after a takeover
+                                if devices.values().any(|d| d.device_type == STANDBY_TYPE) {
+                                    match ctx
+                                        .pool
+                                        .reader()
+                                        .map_err(AppError::from)
+                                        .and_then(|conn| MirrorUpdate::cursor(&conn, &who.device_id))
+                                    {
+                                        Ok(update) => send_mirror(&devices, &update),
+                                        Err(e) => error!("❌ Failed to mirror cursor of {}: {}", who.device_id, e),
+                                    }
+                                }
+                            }
+
+                            let device = ConnectedDevice {
+                                device_id: who.device_id.clone(),
+                                device_type: who.device_type.clone(),
+                                tx: tx.clone(),
+                                remote_addr: addr,
+                                connected_at: connected_at.clone(),
+                                last_seen: last_seen.clone(),
+                                hub_url: who.hub_url.clone(),
+                            };
+                            let info = device.info();
+                            devices.insert(who.device_id.clone(), device);
+                            drop(devices);
+                            info!("✅ Registered device {} ({})", who.device_id, who.device_type);
+                            ctx.notify(info, true, None);
+                            if who.hub_url.is_some() {
+                                ctx.announce_standbys().await;
                             }
+
+                            identity = Some(who);
+                        }
+                        Err(e) => {
+                            warn!("⛔ Registration from {} refused: {}", addr, e);
+                            reject(&tx, &e)?;
+                            break;
+                        }
+                    },
+
+                    _ => {
+                        let Some(who) = &identity else {
+                            warn!("⛔ {} sent '{}' before registering", addr, device_msg.message_type);
+                            reject(&tx, &AppError::Unauthorized("register first".into()))?;
+                            break;
+                        };
+
+                        // ✅ Delivery ack for an outbox message
+                        if device_msg.message_type == "ack" {
+                            record_ack(&ctx, who, &device_msg).await;
+                            continue;
+                        }
+
+                        // Whatever the client claims, it speaks as the device it registered as
+                        device_msg.device_id = Some(who.device_id.clone());
+                        device_msg.device_type = who.device_type.clone();
+
+                        // 📤 Forward event to EventBus
+                        match ctx.event_tx.try_send(device_msg) {
+                            Ok(()) => {}
+                            Err(mpsc::error::TrySendError::Full(msg)) => {
+                                ctx.metrics.dropped_events.fetch_add(1, Ordering::Relaxed);
+                                warn!("⚠️ EventBus is backed up, dropped {} from {}", msg.message_type, who.device_id);
+                            }
+                            Err(e) => error!("❌ Failed to forward event: {}", e),
                         }
                     }
-                    Err(e) => warn!("⚠️ Invalid message from {}: {}", addr, e),
                 }
             }
 
@@ -150,8 +674,16 @@ async fn handle_connection(
                 break;
             }
 
+            Err(tungstenite::Error::Capacity(e)) => {
+                warn!("⛔ {} sent too much at once: {}", addr, e);
+                ctx.metrics.oversized_frames.fetch_add(1, Ordering::Relaxed);
+                reason = "oversized";
+                break;
+            }
+
             Err(e) => {
                 error!("❌ WebSocket receive error {}: {}", addr, e);
+                reason = "error";
                 break;
             }
 
@@ -159,16 +691,247 @@ async fn handle_connection(
         }
     }
 
-    // Cleanup
-    if let Some(id) = device_id {
-        devices.write().await.remove(&id);
-        info!("🗑️ Removed device {}", id);
+    // Cleanup, unless the id has since been taken over by a newer connection
+    if let Some(who) = identity {
+        let mut devices = ctx.devices.write().await;
+        if devices.get(&who.device_id).is_some_and(|d| d.tx.same_channel(&tx)) {
+            if let Some(device) = devices.remove(&who.device_id) {
+                drop(devices);
+                info!("🗑️ Removed device {} ({})", who.device_id, reason);
+                ctx.notify(device.info(), false, Some(reason));
+                if device.hub_url.is_some() {
+                    ctx.announce_standbys().await;
+                }
+            }
+        }
     }
 
+    // Let a queued rejection/close frame go out before the socket drops,
+    // unless the queue is what the device couldn't get through
+    drop(tx);
+    if reason != "backpressure" {
+        let _ = tokio::time::timeout(std::time::Duration::from_secs(1), &mut sender_task).await;
+    }
     sender_task.abort();
     Ok(())
 }
 
+fn prune_outbox(ctx: &ServerContext) {
+    let result = ctx
+        .pool
+        .writer()
+        .map_err(AppError::from)
+        .and_then(|conn| ws_outbox_repo::prune(&conn, OUTBOX_KEEP_HOURS));
+
+    match result {
+        Ok(0) => {}
+        Ok(n) => info!("🧹 Pruned {} old outbox message(s)", n),
+        Err(e) => warn!("⚠️ Outbox prune failed: {}", e),
+    }
+}
+
+async fn record_ack(ctx: &ServerContext, who: &Identity, msg: &DeviceMessage) {
+    let Some(seq) = msg.payload["seq"].as_i64() else {
+        warn!("⚠️ Ack without seq from {}", who.device_id);
+        return;
+    };
+
+    let result = ctx
+        .pool
+        .writer()
+        .map_err(AppError::from)
+        .and_then(|conn| ws_outbox_repo::ack(&conn, &who.device_id, seq));
+
+    if let Err(e) = result {
+        error!("❌ Failed to record ack {} from {}: {}", seq, who.device_id, e);
+        return;
+    }
+
+    ctx.mirror(|conn| MirrorUpdate::cursor(conn, &who.device_id)).await;
+}
+
+/// Queue what `who` hasn't acked yet, waiting for room as a replay can be
+/// longer than the device's queue. Returns the seq it got up to.
+async fn replay(ctx: &ServerContext, who: &Identity, tx: &DeviceSender) -> Option<i64> {
+    let missed = match missed_messages(ctx, who, None) {
+        Ok(missed) => missed,
+        Err(e) => {
+            error!("❌ Replay to {} failed: {}", who.device_id, e);
+            return None;
+        }
+    };
+    if missed.is_empty() {
+        return None;
+    }
+
+    info!("🔁 Replaying {} message(s) to {}", missed.len(), who.device_id);
+    let mut replayed = None;
+    for msg in missed {
+        let text = match serde_json::to_string(&msg) {
+            Ok(text) => text,
+            Err(e) => {
+                error!("❌ Replay to {} failed: {}", who.device_id, e);
+                break;
+            }
+        };
+        if !tx.send_within(Message::Text(text.into()), REPLAY_WAIT).await {
+            warn!("🐢 Replay to {} stalled", who.device_id);
+            break;
+        }
+        replayed = msg.seq;
+    }
+    replayed
+}
+
+/// Outbox messages for `who` after `after_seq`, or after its cursor (opened
+/// if new) when `None`
+fn missed_messages(
+    ctx: &ServerContext,
+    who: &Identity,
+    after_seq: Option<i64>,
+) -> AppResult<Vec<DeviceMessage>> {
+    let conn = ctx.pool.writer()?;
+    let after_seq = match after_seq {
+        Some(seq) => seq,
+        None => ws_outbox_repo::open_cursor(&conn, &who.device_id, &who.device_type)?,
+    };
+
+    ws_outbox_repo::pending_for(&conn, &who.device_id, &who.device_type, after_seq)?
+        .into_iter()
+        .map(|entry| {
+            let mut msg: DeviceMessage = serde_json::from_str(&entry.message)?;
+            msg.seq = Some(entry.seq);
+            Ok(msg)
+        })
+        .collect()
+}
+
+/// Redeem a pairing code and answer with the new device's credentials
+fn pair(ctx: &ServerContext, msg: &DeviceMessage) -> AppResult<DeviceMessage> {
+    let code = msg.payload["code"].as_str().unwrap_or_default();
+    let role = ctx
+        .pairing
+        .lock()
+        .map_err(|_| AppError::Internal("pairing codes lock poisoned".into()))?
+        .redeem(code)?;
+
+    let name = msg.payload["name"]
+        .as_str()
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .unwrap_or(role.as_str())
+        .to_string();
+
+    let conn = ctx.pool.writer()?;
+    let profile = device_repo::pair_device(&conn, &name, &role)?;
+    info!("🤝 Paired {} as {} ({})", profile.name, profile.id, profile.role);
+
+    Ok(DeviceMessage {
+        message_type: "pair_ack".into(),
+        device_id: Some(profile.id.clone()),
+        device_type: "SERVER".into(),
+        payload: serde_json::json!({
+            "device_id": profile.id,
+            "device_type": profile.role,
+            "token": profile.token,
+            "fingerprint": ctx.tls().map(|t| t.fingerprint),
+        }),
+        ..Default::default()
+    })
+}
+
+/// Work out who a `register` message really comes from
+fn authenticate(ctx: &ServerContext, addr: &SocketAddr, msg: &DeviceMessage) -> AppResult<Identity> {
+    let token = msg.payload["token"].as_str().filter(|t| !t.is_empty());
+
+    match (token, msg.device_id.as_deref()) {
+        (Some(token), Some(id)) => {
+            let conn = ctx.pool.reader()?;
+            let profile = device_repo::authenticate_device(&conn, id, token)?;
+
+            // A paired POS may stand by to take over the hub. Only over
+            // wss://: a restarted primary finds the standby that took over
+            // by the certificate it mirrored, and a ws:// hub has none.
+            if msg.payload["standby"].as_bool() == Some(true) {
+                if profile.role != "POS" {
+                    return Err(AppError::Unauthorized(format!("{} can't stand by as a hub", profile.role)));
+                }
+                if ctx.tls().is_none() {
+                    return Err(AppError::Unsupported("standby for a ws:// hub, turn on wss:// first".into()));
+                }
+                let host = match addr.ip() {
+                    std::net::IpAddr::V6(v6) => format!("[{}]", v6),
+                    ip => ip.to_string(),
+                };
+                return Ok(Identity {
+                    device_id: profile.id,
+                    device_type: STANDBY_TYPE.into(),
+                    hub_url: Some(format!("wss://{}:{}", host, HUB_PORT)),
+                });
+            }
+
+            Ok(Identity {
+                device_id: profile.id,
+                device_type: profile.role,
+                hub_url: None,
+            })
+        }
+        (None, _) if addr.ip().is_loopback() => {
+            let device_id = match msg.device_id.clone() {
+                Some(id) => id,
+                None => format!("device_{}", device_repo::random_hex(&*ctx.pool.reader()?, 4)?),
+            };
+            Ok(Identity {
+                device_id,
+                device_type: msg.device_type.clone(),
+                hub_url: None,
+            })
+        }
+        _ => Err(AppError::Unauthorized("device is not paired".into())),
+    }
+}
+
+/// Where connected standbys will serve the hub if they take over
+fn standby_urls(devices: &HashMap<String, ConnectedDevice>) -> Vec<String> {
+    let mut urls: Vec<String> = devices.values().filter_map(|d| d.hub_url.clone()).collect();
+    urls.sort();
+    urls
+}
+
+fn mirror_message(update: &MirrorUpdate) -> DeviceMessage {
+    DeviceMessage {
+        message_type: MIRROR_MESSAGE.into(),
+        device_id: None,
+        device_type: "SERVER".into(),
+        payload: serde_json::to_value(update).unwrap_or_default(),
+        ..Default::default()
+    }
+}
+
+fn send_mirror(devices: &HashMap<String, ConnectedDevice>, update: &MirrorUpdate) {
+    let Ok(text) = serde_json::to_string(&mirror_message(update)) else {
+        return;
+    };
+    for device in devices.values().filter(|d| d.device_type == STANDBY_TYPE) {
+        device.tx.send(Message::Text(text.clone().into()));
+    }
+}
+
+/// Tell the client why it is being dropped, then close
+fn reject(tx: &DeviceSender, reason: &AppError) -> Result<(), Box<dyn std::error::Error>> {
+    let msg = DeviceMessage {
+        message_type: "register_rejected".into(),
+        device_id: None,
+        device_type: "SERVER".into(),
+        payload: serde_json::json!({ "code": reason.code(), "reason": reason.to_string() }),
+        ..Default::default()
+    };
+
+    tx.send(Message::Text(serde_json::to_string(&msg)?.into()));
+    tx.close();
+    Ok(())
+}
+
 /// ==============================
 /// Helper: Broadcast by device type
 /// ==============================
@@ -183,7 +946,7 @@ pub async fn broadcast_to_device_type(
 
     for device in devices.values() {
         if device.device_type == device_type {
-            let _ = device.tx.send(Message::Text(text.clone().into()));
+            queue(&device.tx, message, text.clone());
         }
     }
 
@@ -203,11 +966,21 @@ pub async fn send_to_device(
 
     if let Some(device) = devices.get(device_id) {
         let text = serde_json::to_string(message)?;
-        device.tx.send(Message::Text(text.into()))?;
+        if !queue(&device.tx, message, text) {
+            return Err(format!("Queue full for device: {}", device_id).into());
+        }
         Ok(())
     } else {
         Err(format!("Device not found: {}", device_id).into())
     }
 }
 
+/// Outbox messages (with a `seq`) are never just dropped, see `send_stored`
+fn queue(tx: &DeviceSender, message: &DeviceMessage, text: String) -> bool {
+    match message.seq {
+        Some(_) => tx.send_stored(Message::Text(text.into())),
+        None => tx.send(Message::Text(text.into())),
+    }
+}
+
 
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use futures_util::{SinkExt, StreamExt};
+use rusqlite::Connection;
+use serde::Serialize;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::{tls, DeviceMessage};
+use crate::db::models::device::PairingCode;
+use crate::db::models::device_repo;
+use crate::error::{AppError, AppResult};
+
+/// How long a code shown on the POS stays valid
+pub const CODE_TTL: Duration = Duration::from_secs(120);
+
+/// Wrong codes tolerated before every pending code is thrown away, so the
+/// 6-digit space can't be walked from the LAN
+const MAX_FAILURES: u32 = 5;
+
+/// Roles a code can be issued for
+const PAIRABLE_ROLES: &[&str] = &["POS", "KDS", "QUEUE"];
+
+struct Pending {
+    role: String,
+    expires_at: Instant,
+}
+
+/// ==============================
+/// Pairing codes
+/// ==============================
+///
+/// Kept in memory only: a restart invalidates every code, which is fine for
+/// something that lives two minutes. Each code works once.
+#[derive(Default)]
+pub struct PairingCodes {
+    pending: HashMap<String, Pending>,
+    failures: u32,
+}
+
+impl PairingCodes {
+    pub fn issue(&mut self, conn: &Connection, role: &str) -> AppResult<PairingCode> {
+        let role = role.trim().to_uppercase();
+        if !PAIRABLE_ROLES.contains(&role.as_str()) {
+            return Err(AppError::InvalidInput(format!("can't pair a {} device", role)));
+        }
+
+        self.purge();
+
+        let code = loop {
+            let n = u64::from_str_radix(&device_repo::random_hex(conn, 4)?, 16)
+                .map_err(|e| AppError::Internal(e.to_string()))?;
+            let code = format!("{:06}", n % 1_000_000);
+            if !self.pending.contains_key(&code) {
+                break code;
+            }
+        };
+
+        self.pending.insert(
+            code.clone(),
+            Pending {
+                role: role.clone(),
+                expires_at: Instant::now() + CODE_TTL,
+            },
+        );
+
+        let expires_at = chrono::Utc::now() + chrono::Duration::from_std(CODE_TTL).unwrap_or_default();
+
+        Ok(PairingCode {
+            code,
+            role,
+            expires_at: expires_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            fingerprint: None,
+        })
+    }
+
+    /// Use up a code and return the role it was issued for
+    pub fn redeem(&mut self, code: &str) -> AppResult<String> {
+        self.purge();
+
+        if let Some(pending) = self.pending.remove(code.trim()) {
+            self.failures = 0;
+            return Ok(pending.role);
+        }
+
+        self.failures += 1;
+        if self.failures >= MAX_FAILURES {
+            log::warn!(
+                "⚠️ {} wrong pairing codes, discarding {} pending code(s)",
+                self.failures,
+                self.pending.len()
+            );
+            self.pending.clear();
+            self.failures = 0;
+        }
+
+        Err(AppError::Unauthorized("invalid or expired pairing code".into()))
+    }
+
+    fn purge(&mut self) {
+        let now = Instant::now();
+        self.pending.retain(|_, p| p.expires_at > now);
+    }
+}
+
+// ==============================
+// Client side
+// ==============================
+
+/// Credentials a device received from the hub
+#[derive(Debug, Serialize)]
+pub struct PairedHub {
+    pub device_id: String,
+    pub device_type: String,
+    #[serde(skip_serializing)]
+    pub token: String,
+    /// Hub certificate pinned for later `wss://` connections
+    pub fingerprint: Option<String>,
+}
+
+/// Redeem `code` at the hub behind `url` and pin its certificate.
+///
+/// The certificate is trusted on first use here; the POS shows the same
+/// fingerprint next to the code so the two can be compared.
+pub async fn pair_with_hub(url: &str, code: &str, name: &str, role: &str) -> AppResult<PairedHub> {
+    let (mut ws, seen) = tls::connect(url, None).await?;
+
+    let pair = DeviceMessage {
+        message_type: "pair".into(),
+        device_id: None,
+        device_type: role.to_string(),
+        payload: serde_json::json!({ "code": code, "name": name }),
+        ..Default::default()
+    };
+    ws.send(Message::Text(serde_json::to_string(&pair)?.into()))
+        .await
+        .map_err(|e| AppError::Network(e.to_string()))?;
+
+    let reply = tokio::time::timeout(CODE_TTL, async {
+        while let Some(msg) = ws.next().await {
+            if let Ok(Message::Text(text)) = msg {
+                if let Ok(reply) = serde_json::from_str::<DeviceMessage>(&text) {
+                    return Some(reply);
+                }
+            }
+        }
+        None
+    })
+    .await
+    .ok()
+    .flatten()
+    .ok_or_else(|| AppError::Network("hub closed the connection while pairing".into()))?;
+
+    let _ = ws.close(None).await;
+
+    match reply.message_type.as_str() {
+        "pair_ack" => {
+            let field = |key: &str| reply.payload[key].as_str().map(str::to_string);
+            let announced = field("fingerprint");
+
+            // The hub tells us which certificate it serves; what we saw must match
+            if let (Some(seen), Some(announced)) = (&seen, &announced) {
+                if !seen.eq_ignore_ascii_case(announced) {
+                    return Err(AppError::Unauthorized(format!(
+                        "hub certificate {} is not the one it announced ({})",
+                        seen, announced
+                    )));
+                }
+            }
+
+            Ok(PairedHub {
+                device_id: field("device_id")
+                    .ok_or_else(|| AppError::upstream(None, "pair_ack without device_id"))?,
+                device_type: field("device_type").unwrap_or_else(|| role.to_string()),
+                token: field("token")
+                    .ok_or_else(|| AppError::upstream(None, "pair_ack without token"))?,
+                fingerprint: seen,
+            })
+        }
+        _ => {
+            let reason = reply.payload["reason"].as_str().unwrap_or("pairing rejected");
+            Err(AppError::Unauthorized(
+                reason.strip_prefix("unauthorized: ").unwrap_or(reason).to_string(),
+            ))
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Emitted when a device registers (`PresenceEvent`)
+pub const EVENT_DEVICE_CONNECTED: &str = "ws:device-connected";
+
+/// Emitted when a registered device goes away (`PresenceEvent`)
+pub const EVENT_DEVICE_DISCONNECTED: &str = "ws:device-disconnected";
+
+/// ==============================
+/// Heartbeat configuration
+/// ==============================
+#[derive(Debug, Clone)]
+pub struct HeartbeatConfig {
+    /// Time between pings to each connection
+    pub interval: Duration,
+    /// A connection silent for this long (no frames, no pongs) is dropped
+    pub timeout: Duration,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl HeartbeatConfig {
+    /// Defaults, overridden by `HASHTOUCH_WS_PING_SECS` and
+    /// `HASHTOUCH_WS_TIMEOUT_SECS` when set
+    pub fn from_env() -> Self {
+        let secs = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|s| *s > 0)
+                .map(Duration::from_secs)
+        };
+
+        let defaults = Self::default();
+        let interval = secs("HASHTOUCH_WS_PING_SECS").unwrap_or(defaults.interval);
+        let timeout = secs("HASHTOUCH_WS_TIMEOUT_SECS").unwrap_or(defaults.timeout);
+
+        Self {
+            interval,
+            // At least two missed pings before giving up on a device
+            timeout: timeout.max(interval * 2),
+        }
+    }
+}
+
+/// A registered device as the POS sees it
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceInfo {
+    pub device_id: String,
+    pub device_type: String,
+    pub remote_addr: String,
+    pub connected_at: String,
+    /// Last frame of any kind (including pongs) from the device
+    pub last_seen: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PresenceEvent {
+    pub connected: bool,
+    #[serde(flatten)]
+    pub device: DeviceInfo,
+    /// Why a device went away: closed | timeout | error | revoked |
+    /// backpressure | rate_limited | oversized
+    pub reason: Option<String>,
+}
+
+impl PresenceEvent {
+    pub fn event_name(&self) -> &'static str {
+        if self.connected {
+            EVENT_DEVICE_CONNECTED
+        } else {
+            EVENT_DEVICE_DISCONNECTED
+        }
+    }
+}
+
+pub(crate) fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+pub(crate) fn ms_to_rfc3339(ms: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(ms)
+        .unwrap_or_default()
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
@@ -0,0 +1,493 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::DeviceMessage;
+use crate::db::models::app_state::AppState;
+use crate::error::{AppError, AppResult};
+
+/// ==============================
+/// Route table
+/// ==============================
+///
+/// Where the hub forwards messages it receives from devices, e.g.
+///
+/// ```json
+/// { "routes": [
+///   { "message_type": "order_ready", "to": [{ "device_type": "POS" }] },
+///   { "message_type": "new_order",
+///     "split": { "array": "items", "by": "station" },
+///     "when": [{ "field": "station", "equals": "grill" }],
+///     "to": [{ "device_id": "kds_1f2e3d4c5b6a7980" }] },
+///   { "message_type": "item_bumped", "from": ["KDS"],
+///     "to": [{ "device_id": "kds_0a1b2c3d4e5f6071" }] }
+/// ] }
+/// ```
+///
+/// Every route whose `message_type`, `from` and `when` match is applied,
+/// so one message can go to several places.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteTable {
+    pub routes: Vec<Route>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Route {
+    pub message_type: String,
+    /// Sending device types this route applies to (any when empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub from: Vec<String>,
+    /// Send one copy per distinct value instead of the whole message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split: Option<Split>,
+    /// Payload conditions, all of which must hold (checked per copy when split)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub when: Vec<PayloadFilter>,
+    pub to: Vec<RouteTarget>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteTarget {
+    /// Every device of a type (POS | KDS | QUEUE)
+    DeviceType(String),
+    /// One paired device
+    DeviceId(String),
+}
+
+/// Split `payload[array]` by each element's `by` field. Each copy keeps only
+/// the elements with one value, and gets that value as `payload[by]`.
+/// Elements without the field go in a copy where `payload[by]` is null.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Split {
+    pub array: String,
+    pub by: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PayloadFilter {
+    /// Dotted path into the payload, e.g. `order.channel`
+    pub field: String,
+    #[serde(flatten)]
+    pub op: FilterOp,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterOp {
+    Equals(Value),
+    NotEquals(Value),
+    In(Vec<Value>),
+    Exists(bool),
+}
+
+/// A message ready to publish, and where to
+#[derive(Debug, Clone)]
+pub struct Delivery {
+    pub target: RouteTarget,
+    pub message: DeviceMessage,
+}
+
+impl Default for RouteTable {
+    /// The routes the hub always had
+    fn default() -> Self {
+        let route = |message_type: &str, device_type: &str| Route {
+            message_type: message_type.into(),
+            from: Vec::new(),
+            split: None,
+            when: Vec::new(),
+            to: vec![RouteTarget::DeviceType(device_type.into())],
+        };
+
+        Self {
+            routes: vec![
+                // KDS → POS
+                route("order_ready", "POS"),
+                // POS → QUEUE
+                route("queue_call", "QUEUE"),
+                route("queue_served", "QUEUE"),
+            ],
+        }
+    }
+}
+
+impl RouteTable {
+    /// The table saved in `ws_routes`, or the defaults when there is none
+    pub fn from_app_state(state: &AppState) -> AppResult<Self> {
+        match state.ws_routes.as_deref().filter(|s| !s.trim().is_empty()) {
+            Some(json) => Self::parse(json),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn parse(json: &str) -> AppResult<Self> {
+        let table: Self = serde_json::from_str(json)
+            .map_err(|e| AppError::InvalidInput(format!("route table: {}", e)))?;
+        table.validate()?;
+        Ok(table)
+    }
+
+    pub fn validate(&self) -> AppResult<()> {
+        for (i, route) in self.routes.iter().enumerate() {
+            let invalid = |what: &str| AppError::InvalidInput(format!("route {}: {}", i + 1, what));
+
+            if route.message_type.trim().is_empty() {
+                return Err(invalid("message_type is empty"));
+            }
+            if route.to.is_empty() {
+                return Err(invalid("no targets"));
+            }
+            if route.to.iter().any(|t| t.key().trim().is_empty()) {
+                return Err(invalid("empty target"));
+            }
+            if let Some(split) = &route.split {
+                if split.array.trim().is_empty() || split.by.trim().is_empty() {
+                    return Err(invalid("split needs both array and by"));
+                }
+            }
+            if route.when.iter().any(|f| f.field.trim().is_empty()) {
+                return Err(invalid("filter without a field"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Every copy of `message` the table calls for, with its target
+    pub fn resolve(&self, message: &DeviceMessage) -> Vec<Delivery> {
+        let mut deliveries = Vec::new();
+
+        for (r, route) in self.routes.iter().enumerate() {
+            if route.message_type != message.message_type {
+                continue;
+            }
+            if !route.from.is_empty() && !route.from.iter().any(|f| f.eq_ignore_ascii_case(&message.device_type)) {
+                continue;
+            }
+
+            let copies = match &route.split {
+                Some(split) => split_payload(&message.payload, split)
+                    .into_iter()
+                    .map(|payload| DeviceMessage {
+                        payload,
+                        ..message.clone()
+                    })
+                    .collect(),
+                None => vec![message.clone()],
+            };
+
+            for (c, copy) in copies.into_iter().enumerate() {
+                if !route.when.iter().all(|f| f.matches(&copy.payload)) {
+                    continue;
+                }
+                for target in &route.to {
+                    // Outbox ids are unique: give every copy its own, stable
+                    // for the same message so a resend is still deduplicated
+                    let message_id = message
+                        .message_id
+                        .as_ref()
+                        .map(|id| format!("{}:{}.{}:{}", id, r, c, target.key()));
+                    deliveries.push(Delivery {
+                        target: target.clone(),
+                        message: DeviceMessage {
+                            message_id,
+                            ..copy.clone()
+                        },
+                    });
+                }
+            }
+        }
+
+        deliveries
+    }
+}
+
+impl RouteTarget {
+    fn key(&self) -> &str {
+        match self {
+            Self::DeviceType(device_type) => device_type,
+            Self::DeviceId(device_id) => device_id,
+        }
+    }
+}
+
+impl PayloadFilter {
+    fn matches(&self, payload: &Value) -> bool {
+        let value = lookup(payload, &self.field);
+
+        match &self.op {
+            FilterOp::Equals(expected) => value == Some(expected),
+            FilterOp::NotEquals(expected) => value != Some(expected),
+            FilterOp::In(allowed) => value.is_some_and(|v| allowed.contains(v)),
+            FilterOp::Exists(exists) => value.is_some_and(|v| !v.is_null()) == *exists,
+        }
+    }
+}
+
+fn lookup<'a>(payload: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(payload, |value, key| match value {
+            Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => value.get(key),
+        })
+}
+
+/// One payload per distinct `by` value of the `array` elements, in order of
+/// first appearance. A payload without the array is left whole.
+fn split_payload(payload: &Value, split: &Split) -> Vec<Value> {
+    let Some(items) = lookup(payload, &split.array).and_then(Value::as_array) else {
+        return vec![payload.clone()];
+    };
+
+    let mut order: Vec<String> = Vec::new();
+    let mut groups: BTreeMap<String, (Value, Vec<Value>)> = BTreeMap::new();
+    for item in items {
+        let key = item.get(&split.by).cloned().unwrap_or(Value::Null);
+        let group = groups.entry(key.to_string()).or_insert_with(|| {
+            order.push(key.to_string());
+            (key, Vec::new())
+        });
+        group.1.push(item.clone());
+    }
+
+    order
+        .into_iter()
+        .filter_map(|k| groups.remove(&k))
+        .map(|(key, items)| {
+            let mut copy = payload.clone();
+            if let Some(slot) = lookup_mut(&mut copy, &split.array) {
+                *slot = Value::Array(items);
+            }
+            if let Value::Object(map) = &mut copy {
+                map.insert(split.by.clone(), key);
+            }
+            copy
+        })
+        .collect()
+}
+
+fn lookup_mut<'a>(payload: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    path.split('.').try_fold(payload, |value, key| value.get_mut(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(message_type: &str, from: &str, payload: Value) -> DeviceMessage {
+        DeviceMessage {
+            message_type: message_type.into(),
+            device_id: Some("sender".into()),
+            device_type: from.into(),
+            payload,
+            message_id: None,
+            seq: None,
+        }
+    }
+
+    fn device_type(t: &str) -> RouteTarget {
+        RouteTarget::DeviceType(t.into())
+    }
+
+    fn device_id(id: &str) -> RouteTarget {
+        RouteTarget::DeviceId(id.into())
+    }
+
+    struct Case {
+        name: &'static str,
+        /// `None` for the default table
+        table: Option<Value>,
+        message: DeviceMessage,
+        /// (target, payload) per delivery, in order
+        expect: Vec<(RouteTarget, Value)>,
+    }
+
+    fn cases() -> Vec<Case> {
+        let order = json!({ "order_id": "o1", "items": [
+            { "name": "Burger", "station": "grill" },
+            { "name": "Cola", "station": "bar" },
+            { "name": "Steak", "station": "grill" },
+            { "name": "Napkins" },
+        ] });
+
+        vec![
+            Case {
+                name: "default table forwards order_ready to the POS",
+                table: None,
+                message: message("order_ready", "KDS", json!({ "order_id": "o1" })),
+                expect: vec![(device_type("POS"), json!({ "order_id": "o1" }))],
+            },
+            Case {
+                name: "no route for the message type: nothing is sent",
+                table: None,
+                message: message("item_bumped", "KDS", json!({})),
+                expect: vec![],
+            },
+            Case {
+                name: "from limits the sending device type",
+                table: Some(json!({ "routes": [
+                    { "message_type": "item_bumped", "from": ["kds"], "to": [{ "device_type": "POS" }] },
+                ] })),
+                message: message("item_bumped", "QUEUE", json!({})),
+                expect: vec![],
+            },
+            Case {
+                name: "every matching route applies, each to all its targets",
+                table: Some(json!({ "routes": [
+                    { "message_type": "order_ready", "to": [{ "device_type": "POS" }, { "device_type": "QUEUE" }] },
+                    { "message_type": "order_ready", "from": ["KDS"], "to": [{ "device_id": "expo" }] },
+                ] })),
+                message: message("order_ready", "KDS", json!({ "order_id": "o1" })),
+                expect: vec![
+                    (device_type("POS"), json!({ "order_id": "o1" })),
+                    (device_type("QUEUE"), json!({ "order_id": "o1" })),
+                    (device_id("expo"), json!({ "order_id": "o1" })),
+                ],
+            },
+            Case {
+                name: "split sends one copy per station, unassigned items under null",
+                table: Some(json!({ "routes": [
+                    { "message_type": "new_order", "split": { "array": "items", "by": "station" },
+                      "to": [{ "device_type": "KDS" }] },
+                ] })),
+                message: message("new_order", "POS", order.clone()),
+                expect: vec![
+                    (device_type("KDS"), json!({ "order_id": "o1", "station": "grill", "items": [
+                        { "name": "Burger", "station": "grill" },
+                        { "name": "Steak", "station": "grill" },
+                    ] })),
+                    (device_type("KDS"), json!({ "order_id": "o1", "station": "bar", "items": [
+                        { "name": "Cola", "station": "bar" },
+                    ] })),
+                    (device_type("KDS"), json!({ "order_id": "o1", "station": null, "items": [
+                        { "name": "Napkins" },
+                    ] })),
+                ],
+            },
+            Case {
+                name: "when is checked per split copy",
+                table: Some(json!({ "routes": [
+                    { "message_type": "new_order", "split": { "array": "items", "by": "station" },
+                      "when": [{ "field": "station", "equals": "bar" }],
+                      "to": [{ "device_id": "bar_kds" }] },
+                    { "message_type": "new_order", "split": { "array": "items", "by": "station" },
+                      "when": [{ "field": "station", "exists": false }],
+                      "to": [{ "device_type": "POS" }] },
+                ] })),
+                message: message("new_order", "POS", order.clone()),
+                expect: vec![
+                    (device_id("bar_kds"), json!({ "order_id": "o1", "station": "bar", "items": [
+                        { "name": "Cola", "station": "bar" },
+                    ] })),
+                    (device_type("POS"), json!({ "order_id": "o1", "station": null, "items": [
+                        { "name": "Napkins" },
+                    ] })),
+                ],
+            },
+            Case {
+                name: "when filters on a nested field, all conditions must hold",
+                table: Some(json!({ "routes": [
+                    { "message_type": "new_order",
+                      "when": [
+                        { "field": "order.channel", "in": ["talabat", "deliveroo"] },
+                        { "field": "order.priority", "not_equals": "low" },
+                      ],
+                      "to": [{ "device_type": "QUEUE" }] },
+                ] })),
+                message: message("new_order", "POS", json!({ "order": { "channel": "talabat", "priority": "low" } })),
+                expect: vec![],
+            },
+            Case {
+                name: "nested and array-index fields that all match",
+                table: Some(json!({ "routes": [
+                    { "message_type": "new_order",
+                      "when": [
+                        { "field": "order.channel", "in": ["talabat", "deliveroo"] },
+                        { "field": "items.0.name", "equals": "Burger" },
+                      ],
+                      "to": [{ "device_type": "QUEUE" }] },
+                ] })),
+                message: message("new_order", "POS", json!({ "order": { "channel": "talabat" }, "items": [{ "name": "Burger" }] })),
+                expect: vec![(
+                    device_type("QUEUE"),
+                    json!({ "order": { "channel": "talabat" }, "items": [{ "name": "Burger" }] }),
+                )],
+            },
+            Case {
+                name: "split without the array leaves the message whole",
+                table: Some(json!({ "routes": [
+                    { "message_type": "new_order", "split": { "array": "items", "by": "station" },
+                      "to": [{ "device_type": "KDS" }] },
+                ] })),
+                message: message("new_order", "POS", json!({ "order_id": "o2" })),
+                expect: vec![(device_type("KDS"), json!({ "order_id": "o2" }))],
+            },
+        ]
+    }
+
+    #[test]
+    fn routing_table() {
+        for case in cases() {
+            let table = match case.table {
+                Some(table) => RouteTable::parse(&table.to_string()).unwrap(),
+                None => RouteTable::default(),
+            };
+
+            let got: Vec<(RouteTarget, Value)> = table
+                .resolve(&case.message)
+                .into_iter()
+                .map(|d| {
+                    assert_eq!(d.message.message_type, case.message.message_type, "{}: message type", case.name);
+                    (d.target, d.message.payload)
+                })
+                .collect();
+            assert_eq!(got, case.expect, "{}", case.name);
+        }
+    }
+
+    #[test]
+    fn copies_get_their_own_stable_message_id() {
+        let table = RouteTable::parse(
+            &json!({ "routes": [
+                { "message_type": "new_order", "split": { "array": "items", "by": "station" },
+                  "to": [{ "device_type": "KDS" }, { "device_id": "expo" }] },
+            ] })
+            .to_string(),
+        )
+        .unwrap();
+        let mut msg = message("new_order", "POS", json!({ "items": [{ "station": "grill" }, { "station": "bar" }] }));
+
+        assert!(table.resolve(&msg).iter().all(|d| d.message.message_id.is_none()));
+
+        msg.message_id = Some("m1".into());
+        let ids: Vec<String> = table
+            .resolve(&msg)
+            .into_iter()
+            .filter_map(|d| d.message.message_id)
+            .collect();
+        assert_eq!(ids, ["m1:0.0:KDS", "m1:0.0:expo", "m1:0.1:KDS", "m1:0.1:expo"]);
+    }
+
+    #[test]
+    fn invalid_tables_are_rejected() {
+        let invalid = [
+            json!({ "routes": [{ "message_type": "", "to": [{ "device_type": "POS" }] }] }),
+            json!({ "routes": [{ "message_type": "order_ready", "to": [] }] }),
+            json!({ "routes": [{ "message_type": "order_ready", "to": [{ "device_id": " " }] }] }),
+            json!({ "routes": [{ "message_type": "new_order", "split": { "array": "items", "by": "" },
+                                 "to": [{ "device_type": "KDS" }] }] }),
+            json!({ "routes": [{ "message_type": "new_order", "when": [{ "field": "", "exists": true }],
+                                 "to": [{ "device_type": "KDS" }] }] }),
+            json!({ "routes": [{ "message_type": "new_order", "to": [{ "printer": "p1" }] }] }),
+        ];
+
+        for table in invalid {
+            assert!(
+                matches!(RouteTable::parse(&table.to_string()), Err(AppError::InvalidInput(_))),
+                "accepted {}",
+                table
+            );
+        }
+    }
+}
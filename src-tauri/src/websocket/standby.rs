@@ -0,0 +1,392 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use log::{error, info, warn};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use super::client::{ClientEvent, HubClient, HubConfig};
+use super::discovery;
+use super::presence::HeartbeatConfig;
+use super::routing::RouteTable;
+use super::tls::{self, ServerTls};
+use crate::db::models::app_state_repo;
+use crate::db::models::device::DeviceProfile;
+use crate::db::models::device_repo;
+use crate::db::models::kitchen_station::KitchenSetup;
+use crate::db::models::kitchen_station_repo;
+use crate::db::models::ws_outbox::{DeliveryCursor, OutboxEntry};
+use crate::db::models::ws_outbox_repo;
+use crate::db::pool::DbPool;
+use crate::error::{AppError, AppResult};
+
+/// Emitted when this POS starts or stops serving the hub (`HubRole`)
+pub const EVENT_HUB_ROLE: &str = "ws:hub-role";
+
+/// Device type a POS registers under while standing by
+pub const STANDBY_TYPE: &str = "STANDBY";
+
+/// Primary → standby: state to copy (`MirrorUpdate`)
+pub const MIRROR_MESSAGE: &str = "hub_mirror";
+
+/// Primary → KDS/QUEUE: where to look if the primary goes away
+pub const STANDBYS_MESSAGE: &str = "hub_standbys";
+
+/// What this POS does for the hub
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HubRole {
+    /// Not a POS, or not started yet
+    None,
+    /// Serving the hub
+    Primary,
+    /// Mirroring the primary, ready to take over
+    Standby,
+    /// Was a standby and took over when the primary went silent
+    Promoted,
+    /// Another POS serves this hub's certificate (a standby took over), so
+    /// this one stays off until it is set up as a standby
+    Superseded,
+}
+
+/// ==============================
+/// Takeover settings
+/// ==============================
+#[derive(Debug, Clone)]
+pub struct StandbyConfig {
+    /// How long the primary must be unreachable before the standby serves
+    pub takeover_after: Duration,
+}
+
+impl Default for StandbyConfig {
+    fn default() -> Self {
+        Self {
+            takeover_after: Duration::from_secs(45),
+        }
+    }
+}
+
+impl StandbyConfig {
+    /// Defaults, overridden by `HASHTOUCH_WS_TAKEOVER_SECS` when set. Never
+    /// shorter than the heartbeat timeout, or a slow primary would be
+    /// replaced before its devices even notice it's gone.
+    pub fn from_env(heartbeat: &HeartbeatConfig) -> Self {
+        let takeover_after = std::env::var("HASHTOUCH_WS_TAKEOVER_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|s| *s > 0)
+            .map(Duration::from_secs)
+            .unwrap_or(Self::default().takeover_after);
+
+        Self {
+            takeover_after: takeover_after.max(heartbeat.timeout),
+        }
+    }
+}
+
+// ==============================
+// Mirrored state
+// ==============================
+
+/// A paired device with its token, which `DeviceProfile` never serializes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirroredDevice {
+    pub id: String,
+    pub name: String,
+    pub role: String,
+    pub token: Option<String>,
+    pub paired_at: Option<String>,
+    pub revoked_at: Option<String>,
+    #[serde(default)]
+    pub station_id: Option<String>,
+}
+
+impl From<DeviceProfile> for MirroredDevice {
+    fn from(device: DeviceProfile) -> Self {
+        Self {
+            id: device.id,
+            name: device.name,
+            role: device.role,
+            token: device.token,
+            paired_at: device.paired_at,
+            revoked_at: device.revoked_at,
+            station_id: device.station_id,
+        }
+    }
+}
+
+impl From<MirroredDevice> for DeviceProfile {
+    fn from(device: MirroredDevice) -> Self {
+        Self {
+            id: device.id,
+            name: device.name,
+            role: device.role,
+            config: None,
+            sync_status: None,
+            token: device.token,
+            paired_at: device.paired_at,
+            revoked_at: device.revoked_at,
+            station_id: device.station_id,
+        }
+    }
+}
+
+/// The hub certificate and key, base64
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirroredTls {
+    pub cert: String,
+    pub key: String,
+}
+
+/// Payload of a `hub_mirror` message
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MirrorUpdate {
+    /// Everything the primary has; replaces what the standby held
+    #[serde(default)]
+    pub snapshot: bool,
+    #[serde(default)]
+    pub devices: Vec<MirroredDevice>,
+    #[serde(default)]
+    pub outbox: Vec<OutboxEntry>,
+    #[serde(default)]
+    pub cursors: Vec<DeliveryCursor>,
+    /// Only in snapshots from a `wss://` hub
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<MirroredTls>,
+    /// In snapshots, and whenever the primary's route table changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routes: Option<RouteTable>,
+    /// In snapshots, and whenever the primary's kitchen stations change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kitchen: Option<KitchenSetup>,
+}
+
+impl MirrorUpdate {
+    /// Full copy sent to a standby when it registers
+    pub(crate) fn snapshot(conn: &Connection, tls: Option<&ServerTls>) -> AppResult<Self> {
+        Ok(Self {
+            snapshot: true,
+            devices: device_repo::get_paired_devices(conn)?
+                .into_iter()
+                .map(MirroredDevice::from)
+                .collect(),
+            outbox: ws_outbox_repo::get_entries(conn)?,
+            cursors: ws_outbox_repo::get_cursors(conn)?,
+            tls: tls.map(|tls| {
+                let (cert, key) = tls.identity();
+                MirroredTls {
+                    cert: BASE64.encode(cert),
+                    key: BASE64.encode(key),
+                }
+            }),
+            routes: Some(Self::route_table(conn)?),
+            kitchen: Some(kitchen_station_repo::get_setup(conn)?),
+        })
+    }
+
+    pub(crate) fn kitchen(conn: &Connection) -> AppResult<Self> {
+        Ok(Self {
+            kitchen: Some(kitchen_station_repo::get_setup(conn)?),
+            ..Default::default()
+        })
+    }
+
+    pub(crate) fn routes(conn: &Connection) -> AppResult<Self> {
+        Ok(Self {
+            routes: Some(Self::route_table(conn)?),
+            ..Default::default()
+        })
+    }
+
+    /// What the primary routes by; its defaults are copied explicitly, so
+    /// the standby keeps them even if its own defaults differ
+    fn route_table(conn: &Connection) -> AppResult<RouteTable> {
+        let state = app_state_repo::get_app_state(conn)?;
+        Ok(RouteTable::from_app_state(&state).unwrap_or_default())
+    }
+
+    pub(crate) fn device(conn: &Connection, device_id: &str) -> AppResult<Self> {
+        Ok(Self {
+            devices: vec![device_repo::get_paired_device(conn, device_id)?.into()],
+            ..Default::default()
+        })
+    }
+
+    pub(crate) fn entry(conn: &Connection, seq: i64) -> AppResult<Self> {
+        Ok(Self {
+            outbox: vec![ws_outbox_repo::get_entry(conn, seq)?],
+            ..Default::default()
+        })
+    }
+
+    pub(crate) fn cursor(conn: &Connection, device_id: &str) -> AppResult<Self> {
+        Ok(Self {
+            cursors: vec![ws_outbox_repo::get_cursor(conn, device_id)?],
+            ..Default::default()
+        })
+    }
+}
+
+/// Store what the primary sent. The certificate goes where
+/// `tls::load_or_create` will find it, and whether to serve `wss://`
+/// follows the primary.
+pub fn apply(conn: &mut Connection, tls_dir: &Path, update: MirrorUpdate) -> AppResult<()> {
+    let tx = conn.transaction()?;
+    if update.snapshot {
+        ws_outbox_repo::clear(&tx)?;
+    }
+    // Stations before devices, which may point at them
+    if let Some(kitchen) = &update.kitchen {
+        kitchen_station_repo::replace_setup(&tx, kitchen)?;
+    }
+    for device in update.devices {
+        device_repo::upsert_paired_device(&tx, &device.into())?;
+    }
+    for entry in &update.outbox {
+        ws_outbox_repo::restore_entry(&tx, entry)?;
+    }
+    for cursor in &update.cursors {
+        ws_outbox_repo::restore_cursor(&tx, cursor)?;
+    }
+    if let Some(routes) = &update.routes {
+        app_state_repo::update_app_state(&tx, "ws_routes", &serde_json::to_string(routes)?)?;
+    }
+    tx.commit()?;
+
+    if !update.snapshot {
+        return Ok(());
+    }
+
+    match update.tls {
+        Some(mirrored) => {
+            let decode = |b64: &str| {
+                BASE64
+                    .decode(b64)
+                    .map_err(|e| AppError::InvalidInput(format!("mirrored certificate: {}", e)))
+            };
+            tls::store_identity(tls_dir, &decode(&mirrored.cert)?, &decode(&mirrored.key)?)?;
+            app_state_repo::update_app_state(conn, "ws_tls_enabled", "1")?;
+        }
+        None => app_state_repo::update_app_state(conn, "ws_tls_enabled", "0")?,
+    }
+    Ok(())
+}
+
+// ==============================
+// Standby loop
+// ==============================
+
+/// Follow the primary with `client`, mirroring its state into `pool`, and
+/// return once it has been unreachable for `takeover_after`: time for this
+/// POS to serve the hub. Fails if the primary refuses this standby.
+pub async fn follow(
+    client: &HubClient,
+    config: HubConfig,
+    pool: DbPool,
+    tls_dir: PathBuf,
+    settings: StandbyConfig,
+) -> AppResult<()> {
+    let mut events = client.subscribe();
+    client.start(config.clone());
+    info!(
+        "🪞 Standing by for hub {} (takeover after {}s)",
+        config.url,
+        settings.takeover_after.as_secs()
+    );
+
+    let mut last_contact = Instant::now();
+    let mut check = tokio::time::interval(Duration::from_secs(1));
+    check.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(ClientEvent::Mirror(update)) => {
+                    last_contact = Instant::now();
+                    let result = pool
+                        .writer()
+                        .map_err(AppError::from)
+                        .and_then(|mut conn| apply(&mut conn, &tls_dir, *update));
+                    if let Err(e) = result {
+                        error!("❌ Failed to mirror the hub: {}", e);
+                    }
+                }
+                Ok(ClientEvent::Status(status)) if status.state == "rejected" => {
+                    client.stop();
+                    return Err(AppError::Unauthorized(
+                        status.last_error.unwrap_or_else(|| "standby rejected".into()),
+                    ));
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(n)) => {
+                    // Updates were lost: reconnect for a fresh snapshot
+                    warn!("⚠️ Missed {} hub events, resyncing the mirror", n);
+                    client.stop();
+                    client.start(config.clone());
+                }
+                Err(RecvError::Closed) => {
+                    return Err(AppError::Internal("hub client went away".into()));
+                }
+            },
+
+            _ = check.tick() => {
+                if client.status().state == "connected" {
+                    last_contact = Instant::now();
+                    continue;
+                }
+                if last_contact.elapsed() < settings.takeover_after {
+                    continue;
+                }
+
+                // Unreachable from here isn't gone: a primary still answering
+                // on the LAN keeps the hub, or there would be two
+                if primary_still_advertised(&config).await {
+                    warn!("📡 Hub {} unreachable but still advertised, not taking over", config.url);
+                    last_contact = Instant::now();
+                    continue;
+                }
+
+                warn!(
+                    "🚨 Hub {} silent for {}s, taking over",
+                    config.url,
+                    last_contact.elapsed().as_secs()
+                );
+                client.stop();
+                return Ok(());
+            }
+        }
+    }
+}
+
+async fn primary_still_advertised(config: &HubConfig) -> bool {
+    let hubs = match discovery::discover(discovery::BROWSE_TIME).await {
+        Ok(hubs) => hubs,
+        Err(e) => {
+            warn!("⚠️ Hub discovery failed: {}", e);
+            return false;
+        }
+    };
+
+    hubs.iter().any(|hub| {
+        hub.url == config.url
+            || config
+                .fingerprint
+                .as_deref()
+                .zip(hub.fingerprint.as_deref())
+                .is_some_and(|(pin, fp)| pin.eq_ignore_ascii_case(fp))
+    })
+}
+
+/// Another hub on the LAN serving `fingerprint`: a standby that took over
+/// from this POS while it was down
+pub async fn find_successor(fingerprint: &str) -> Option<discovery::DiscoveredHub> {
+    let hubs = discovery::discover(discovery::BROWSE_TIME).await.ok()?;
+    hubs.into_iter().find(|hub| {
+        hub.fingerprint
+            .as_deref()
+            .is_some_and(|fp| fp.eq_ignore_ascii_case(fingerprint))
+    })
+}
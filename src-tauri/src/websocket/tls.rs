@@ -0,0 +1,449 @@
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use ring::digest::{digest, SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::crypto::{self, ring as ring_provider, CryptoProvider};
+use tokio_rustls::rustls::pki_types::{
+    CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime,
+};
+use tokio_rustls::rustls::{
+    CertificateError, ClientConfig, DigitallySignedStruct, ServerConfig, SignatureScheme,
+};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+use tokio_tungstenite::tungstenite::http::Uri;
+use tokio_tungstenite::WebSocketStream;
+
+use crate::error::{AppError, AppResult};
+
+const CERT_FILE: &str = "hub_cert.der";
+const KEY_FILE: &str = "hub_key.pk8";
+
+/// Common name on the hub's self-signed certificate
+const SUBJECT: &str = "Hash-touch POS hub";
+
+// ==============================
+// Server side
+// ==============================
+
+/// The POS hub's certificate, ready to terminate `wss://` connections
+#[derive(Clone)]
+pub struct ServerTls {
+    pub acceptor: TlsAcceptor,
+    /// SHA-256 of the certificate, shown on the POS and pinned by clients
+    pub fingerprint: String,
+    /// DER certificate and PKCS#8 key, copied to a standby hub so it can
+    /// take over with the certificate clients have pinned
+    identity: Arc<(Vec<u8>, Vec<u8>)>,
+}
+
+impl ServerTls {
+    pub(crate) fn identity(&self) -> (&[u8], &[u8]) {
+        (&self.identity.0, &self.identity.1)
+    }
+}
+
+/// Load the hub certificate from `dir`, generating a self-signed one on first use
+pub fn load_or_create(dir: &Path) -> AppResult<ServerTls> {
+    let cert_path = dir.join(CERT_FILE);
+    let key_path = dir.join(KEY_FILE);
+
+    let (cert, key) = if cert_path.exists() && key_path.exists() {
+        (fs::read(&cert_path).map_err(io_error)?, fs::read(&key_path).map_err(io_error)?)
+    } else {
+        log::info!("🔏 Generating a self-signed certificate for the WebSocket hub");
+        let (cert, key) = self_signed()?;
+        fs::create_dir_all(dir).map_err(io_error)?;
+        fs::write(&cert_path, &cert).map_err(io_error)?;
+        write_private(&key_path, &key)?;
+        (cert, key)
+    };
+
+    let fingerprint = fingerprint(&cert);
+
+    let config = ServerConfig::builder_with_provider(Arc::new(ring_provider::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(tls_error)?
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![CertificateDer::from(cert.clone())],
+            PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.clone())),
+        )
+        .map_err(tls_error)?;
+
+    log::info!("🔐 WebSocket hub certificate {}", fingerprint);
+
+    Ok(ServerTls {
+        acceptor: TlsAcceptor::from(Arc::new(config)),
+        fingerprint,
+        identity: Arc::new((cert, key)),
+    })
+}
+
+/// Install another hub's certificate and key in `dir`, for `load_or_create`
+/// to serve next (a standby taking over from the primary)
+pub fn store_identity(dir: &Path, cert: &[u8], key: &[u8]) -> AppResult<()> {
+    let cert_path = dir.join(CERT_FILE);
+    if fs::read(&cert_path).is_ok_and(|current| current == cert) {
+        return Ok(());
+    }
+
+    fs::create_dir_all(dir).map_err(io_error)?;
+    write_private(&dir.join(KEY_FILE), key)?;
+    fs::write(&cert_path, cert).map_err(io_error)?;
+    log::info!("🔐 Stored hub certificate {}", fingerprint(cert));
+    Ok(())
+}
+
+/// Whether the client opened with a TLS handshake rather than plain HTTP
+pub async fn is_tls_hello(stream: &TcpStream) -> bool {
+    let mut first = [0u8; 1];
+    // 0x16 = TLS handshake record
+    matches!(stream.peek(&mut first).await, Ok(1) if first[0] == 0x16)
+}
+
+/// Colon-separated SHA-256 of a DER certificate
+pub fn fingerprint(cert_der: &[u8]) -> String {
+    digest(&SHA256, cert_der)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn same_fingerprint(a: &str, b: &str) -> bool {
+    let normalize = |s: &str| {
+        s.chars()
+            .filter(char::is_ascii_hexdigit)
+            .map(|c| c.to_ascii_uppercase())
+            .collect::<String>()
+    };
+    normalize(a) == normalize(b)
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, data: &[u8]) -> AppResult<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .map_err(io_error)?;
+    file.write_all(data).map_err(io_error)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, data: &[u8]) -> AppResult<()> {
+    fs::write(path, data).map_err(io_error)
+}
+
+// ==============================
+// Client side (pinned)
+// ==============================
+
+/// Any stream a WebSocket client can run over, plain or TLS
+pub trait ClientIo: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> ClientIo for T {}
+
+pub type ClientStream = WebSocketStream<Box<dyn ClientIo>>;
+
+/// Open a WebSocket to the hub. For `wss://` the server certificate must
+/// match `pin`; with no pin any certificate is accepted and its fingerprint
+/// returned so the caller can pin it (trust on first use, during pairing).
+pub async fn connect(url: &str, pin: Option<&str>) -> AppResult<(ClientStream, Option<String>)> {
+    let uri: Uri = url
+        .parse()
+        .map_err(|e| AppError::InvalidInput(format!("bad WebSocket URL {}: {}", url, e)))?;
+    let secure = match uri.scheme_str() {
+        Some("wss") => true,
+        Some("ws") => false,
+        _ => return Err(AppError::InvalidInput(format!("not a ws:// or wss:// URL: {}", url))),
+    };
+    let host = uri
+        .host()
+        .ok_or_else(|| AppError::InvalidInput(format!("no host in {}", url)))?
+        .trim_matches(|c| c == '[' || c == ']')
+        .to_string();
+    let port = uri.port_u16().unwrap_or(if secure { 443 } else { 80 });
+
+    let tcp = TcpStream::connect((host.as_str(), port))
+        .await
+        .map_err(|e| AppError::Network(format!("{}: {}", url, e)))?;
+
+    let (io, seen): (Box<dyn ClientIo>, Option<String>) = if secure {
+        let verifier = Arc::new(PinnedCert::new(pin));
+        let seen = verifier.seen.clone();
+
+        let config = ClientConfig::builder_with_provider(verifier.provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?
+            .dangerous()
+            .with_custom_certificate_verifier(verifier)
+            .with_no_client_auth();
+
+        let server_name = ServerName::try_from(host)
+            .map_err(|e| AppError::InvalidInput(format!("bad host in {}: {}", url, e)))?;
+
+        let tls = TlsConnector::from(Arc::new(config))
+            .connect(server_name, tcp)
+            .await
+            .map_err(|e| AppError::Network(format!("TLS handshake with {} failed: {}", url, e)))?;
+
+        let seen = seen.lock().ok().and_then(|s| s.clone());
+        (Box::new(tls), seen)
+    } else {
+        (Box::new(tcp), None)
+    };
+
+    let (ws, _) = tokio_tungstenite::client_async(url, io)
+        .await
+        .map_err(|e| AppError::Network(format!("{}: {}", url, e)))?;
+
+    Ok((ws, seen))
+}
+
+/// Accepts exactly the pinned certificate (or, unpinned, whatever is presented)
+/// and records what it saw. No CA or hostname checks: the hub is self-signed.
+#[derive(Debug)]
+struct PinnedCert {
+    pin: Option<String>,
+    seen: Arc<Mutex<Option<String>>>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl PinnedCert {
+    fn new(pin: Option<&str>) -> Self {
+        Self {
+            pin: pin.map(str::to_string),
+            seen: Arc::new(Mutex::new(None)),
+            provider: Arc::new(ring_provider::default_provider()),
+        }
+    }
+}
+
+impl ServerCertVerifier for PinnedCert {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        let seen = fingerprint(end_entity);
+
+        if let Some(pin) = &self.pin {
+            if !same_fingerprint(pin, &seen) {
+                log::warn!("⛔ Hub certificate {} does not match the pinned {}", seen, pin);
+                return Err(CertificateError::ApplicationVerificationFailure.into());
+            }
+        }
+
+        if let Ok(mut slot) = self.seen.lock() {
+            *slot = Some(seen);
+        }
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        crypto::verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        crypto::verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn key_error<E>(_: E) -> AppError {
+    AppError::Internal("could not generate the hub key".into())
+}
+
+fn io_error(e: std::io::Error) -> AppError {
+    AppError::Internal(format!("hub certificate: {}", e))
+}
+
+fn tls_error(e: tokio_rustls::rustls::Error) -> AppError {
+    AppError::Internal(format!("TLS: {}", e))
+}
+
+// ==============================
+// Self-signed certificate
+// ==============================
+//
+// A minimal X.509 v3 certificate (ECDSA P-256, no extensions) in DER.
+// Clients pin the fingerprint, so only the key and the encoding matter.
+
+// 1.2.840.10045.4.3.2
+const OID_ECDSA_SHA256: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x04, 0x03, 0x02];
+// 1.2.840.10045.2.1
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01];
+// 1.2.840.10045.3.1.7
+const OID_P256: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07];
+// 2.5.4.3
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+
+/// Returns (certificate DER, PKCS#8 private key DER)
+fn self_signed() -> AppResult<(Vec<u8>, Vec<u8>)> {
+    let rng = SystemRandom::new();
+
+    let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).map_err(key_error)?;
+    let key_pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+        .map_err(key_error)?;
+
+    let mut serial = [0u8; 16];
+    rng.fill(&mut serial).map_err(key_error)?;
+    // Positive and without a leading zero byte
+    serial[0] = (serial[0] & 0x7F) | 0x40;
+
+    let algorithm = der(0x30, &der(0x06, OID_ECDSA_SHA256));
+    let name = der(
+        0x30,
+        &der(0x31, &der(0x30, &[der(0x06, OID_COMMON_NAME), der(0x0C, SUBJECT.as_bytes())].concat())),
+    );
+    let not_before = (chrono::Utc::now() - chrono::Duration::days(1)).format("%y%m%d%H%M%SZ").to_string();
+    // RFC 5280 §4.1.2.5: "no well-defined expiration date"
+    let validity = der(0x30, &[der(0x17, not_before.as_bytes()), der(0x18, b"99991231235959Z")].concat());
+    let public_key = der(
+        0x30,
+        &[
+            der(0x30, &[der(0x06, OID_EC_PUBLIC_KEY), der(0x06, OID_P256)].concat()),
+            bit_string(key_pair.public_key().as_ref()),
+        ]
+        .concat(),
+    );
+
+    let tbs = der(
+        0x30,
+        &[
+            der(0xA0, &der(0x02, &[0x02])), // v3
+            der(0x02, &serial),
+            algorithm.clone(),
+            name.clone(),
+            validity,
+            name,
+            public_key,
+        ]
+        .concat(),
+    );
+
+    let signature = key_pair.sign(&rng, &tbs).map_err(key_error)?;
+    let cert = der(0x30, &[tbs, algorithm, bit_string(signature.as_ref())].concat());
+
+    Ok((cert, pkcs8.as_ref().to_vec()))
+}
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let len = content.len();
+    let mut out = vec![tag];
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len.to_be_bytes().iter().copied().skip_while(|b| *b == 0).collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend(bytes);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+fn bit_string(content: &[u8]) -> Vec<u8> {
+    der(0x03, &[&[0u8][..], content].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    struct TempDir(std::path::PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("hash_touch_{}_{}", name, std::process::id())))
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Serve one `wss://` connection on a loopback port, echoing a message back
+    async fn echo_hub(tls: ServerTls) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("wss://127.0.0.1:{}", listener.local_addr().unwrap().port());
+
+        tokio::spawn(async move {
+            while let Ok((tcp, _)) = listener.accept().await {
+                let Ok(stream) = tls.acceptor.accept(tcp).await else { continue };
+                let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await else { continue };
+                if let Some(Ok(message)) = ws.next().await {
+                    let _ = ws.send(message).await;
+                }
+            }
+        });
+
+        url
+    }
+
+    #[test]
+    fn certificate_survives_a_restart() {
+        let dir = TempDir::new("tls_reload");
+        let first = load_or_create(&dir.0).unwrap();
+        let second = load_or_create(&dir.0).unwrap();
+
+        assert_eq!(first.fingerprint, second.fingerprint);
+        assert_eq!(first.fingerprint, fingerprint(&fs::read(dir.0.join(CERT_FILE)).unwrap()));
+    }
+
+    #[tokio::test]
+    async fn handshake_with_the_self_signed_certificate() {
+        let dir = TempDir::new("tls_handshake");
+        let tls = load_or_create(&dir.0).unwrap();
+        let pin = tls.fingerprint.clone();
+        let url = echo_hub(tls).await;
+
+        // Unpinned (pairing): the certificate is accepted and reported
+        let (mut ws, seen) = connect(&url, None).await.unwrap();
+        assert_eq!(seen.as_deref(), Some(pin.as_str()));
+        ws.send(Message::Text("hello".into())).await.unwrap();
+        assert_eq!(ws.next().await.unwrap().unwrap(), Message::Text("hello".into()));
+
+        // Pinned, in the lower-case form a user might type it
+        let (_, seen) = connect(&url, Some(&pin.to_lowercase())).await.unwrap();
+        assert_eq!(seen.as_deref(), Some(pin.as_str()));
+
+        // Any other certificate is refused
+        let wrong = "00".repeat(32);
+        assert!(matches!(connect(&url, Some(&wrong)).await, Err(AppError::Network(_))));
+    }
+}
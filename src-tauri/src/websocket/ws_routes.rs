@@ -1,64 +1,68 @@
-use crate::websocket::broadcast_to_device_type;
-use crate::websocket::event_bus::EventBus; 
+use crate::websocket::event_bus::EventBus;
+use crate::websocket::routing::{Delivery, RouteTarget};
 use crate::WsState;
 use std::sync::Arc;
+use tokio::sync::mpsc;
 
+/// Routed messages waiting to be published; past this new ones are dropped,
+/// as when the EventBus queue in front of the router is full
+const ROUTE_QUEUE: usize = 256;
+
+/// Forward device messages as `ws_state.routes` says. The table is read per
+/// message, so `set_ws_routes` applies without re-registering.
 pub async fn register_ws_routes(event_bus: Arc<EventBus>, ws_state: Arc<WsState>) {
     log::info!("🔧 Registering WebSocket routes...");
 
-    // KDS → POS: order_ready
-    {
-        let ws_state = ws_state.clone();
-        event_bus.subscribe("order_ready", move |msg| {
-            log::info!("📨 [Router] Received order_ready from KDS");
-            let devices = ws_state.server.get_devices();
+    // One task publishes everything routed, in the order it came in
+    let (tx, mut rx) = mpsc::channel::<Vec<Delivery>>(ROUTE_QUEUE);
+    let publisher = ws_state.clone();
+    tokio::spawn(async move {
+        while let Some(deliveries) = rx.recv().await {
+            for delivery in deliveries {
+                let message_type = delivery.message.message_type.clone();
+                let result = match &delivery.target {
+                    RouteTarget::DeviceType(device_type) => {
+                        publisher.server.publish(device_type, delivery.message).await
+                    }
+                    RouteTarget::DeviceId(device_id) => {
+                        publisher.server.publish_to_device(device_id, delivery.message).await
+                    }
+                };
 
-            let msg = msg.clone();
-            tokio::spawn(async move {
-                if let Err(e) = broadcast_to_device_type(&devices, "POS", &msg).await {
-                    log::error!("❌ order_ready → POS failed: {}", e);
-                } else {
-                    log::info!("✅ order_ready routed to POS");
+                match result {
+                    Ok(_) => log::info!("✅ {} routed to {:?}", message_type, delivery.target),
+                    Err(e) => log::error!("❌ {} → {:?} failed: {}", message_type, delivery.target, e),
                 }
-            });
-        }).await;
-    }
-
-    // POS → QUEUE: queue_call
-    {
-        let ws_state = ws_state.clone();
-        event_bus.subscribe("queue_call", move |msg| {
-            log::info!("📨 [Router] Received queue_call from POS");
-            let devices = ws_state.server.get_devices();
+            }
+        }
+    });
 
-            let msg = msg.clone();
-            tokio::spawn(async move {
-                if let Err(e) = broadcast_to_device_type(&devices, "QUEUE", &msg).await {
-                    log::error!("❌ queue_call → QUEUE failed: {}", e);
-                } else {
-                    log::info!("✅ queue_call routed to QUEUE");
-                }
-            });
-        }).await;
-    }
+    event_bus.subscribe("*", move |msg| {
+        let deliveries = ws_state
+            .routes
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .resolve(msg);
+        if deliveries.is_empty() {
+            return;
+        }
 
-    // POS → QUEUE: queue_served
-    {
-        let ws_state = ws_state.clone();
-        event_bus.subscribe("queue_served", move |msg| {
-            log::info!("📨 [Router] Received queue_served from POS");
-            let devices = ws_state.server.get_devices();
+        log::info!(
+            "📨 [Router] {} from {} → {} delivery(ies)",
+            msg.message_type,
+            msg.device_type,
+            deliveries.len()
+        );
 
-            let msg = msg.clone();
-            tokio::spawn(async move {
-                if let Err(e) = broadcast_to_device_type(&devices, "QUEUE", &msg).await {
-                    log::error!("❌ queue_served → QUEUE failed: {}", e);
-                } else {
-                    log::info!("✅ queue_served routed to QUEUE");
-                }
-            });
-        }).await;
-    }
+        match tx.try_send(deliveries) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                ws_state.server.event_dropped();
+                log::warn!("⚠️ Router is backed up, dropped {} from {}", msg.message_type, msg.device_type);
+            }
+            Err(e) => log::error!("❌ Failed to route {}: {}", msg.message_type, e),
+        }
+    }).await;
 
     log::info!("✅ WebSocket routes registered successfully");
-}
\ No newline at end of file
+}
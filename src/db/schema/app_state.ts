@@ -35,6 +35,12 @@ export const appStateSqlite = sqliteTable("app_state", {
   // WebSocket Settings
   wsServerMode: integer("ws_server_mode").default(0),
   wsServerUrl: text("ws_server_url").default(""),
+  wsDeviceId: text("ws_device_id"),
+  wsDeviceToken: text("ws_device_token"),
+  wsTlsEnabled: integer("ws_tls_enabled").default(0),
+  wsTlsFingerprint: text("ws_tls_fingerprint"),
+  wsHubStandby: integer("ws_hub_standby").default(0),
+  wsRoutes: text("ws_routes"),
 
   createdAt: integer("created_at", { mode: "timestamp" })
     .default(sql`CURRENT_TIMESTAMP`),
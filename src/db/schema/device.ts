@@ -12,4 +12,11 @@ export const deviceProfilesSqlite = sqliteTable("device_profiles", {
   createdAt: integer("created_at", { mode: "timestamp" }).default(sql`CURRENT_TIMESTAMP`),
   updatedAt: integer("updated_at", { mode: "timestamp" }).default(sql`CURRENT_TIMESTAMP`),
   lastSyncAt: integer("last_sync_at", { mode: "timestamp" }),
+
+  // Set on devices paired with this POS over WebSocket
+  token: text("token").unique(),
+  pairedAt: text("paired_at"),
+  revokedAt: text("revoked_at"),
+  // Kitchen station a paired KDS shows
+  stationId: text("station_id"),
 });
@@ -27,6 +27,9 @@ export const kdsTicketsSqlite = sqliteTable("kds_tickets", {
   // Queue Integration
   tokenNumber: integer("token_number"),
 
+  // Kitchen station whose items this ticket holds (whole order when null)
+  stationId: text("station_id"),
+
   // Timestamps
   createdAt: text("created_at")
     .notNull()
@@ -0,0 +1,23 @@
+import { sqliteTable, text, integer, index, primaryKey } from "drizzle-orm/sqlite-core";
+
+// POS hub: kitchen stations, and which products/categories each one makes
+
+export const kitchenStationsSqlite = sqliteTable("kitchen_stations", {
+  id: text("id").primaryKey(),
+  name: text("name").notNull(),
+  isExpo: integer("is_expo").notNull().default(0), // sees every station's items
+  sortOrder: integer("sort_order").notNull().default(0),
+  createdAt: text("created_at").notNull(),
+  updatedAt: text("updated_at"),
+});
+
+export const kitchenStationMappingsSqlite = sqliteTable("kitchen_station_mappings", {
+  targetType: text("target_type").notNull(), // PRODUCT | CATEGORY
+  targetId: text("target_id").notNull(),
+  stationId: text("station_id")
+    .notNull()
+    .references(() => kitchenStationsSqlite.id, { onDelete: "cascade" }),
+}, (table) => [
+  primaryKey({ columns: [table.targetType, table.targetId] }),
+  index("kitchen_station_mappings_station_idx").on(table.stationId),
+]);
@@ -0,0 +1,22 @@
+import { sqliteTable, text, integer, blob, index } from "drizzle-orm/sqlite-core";
+
+// Print queue, sent by the Rust spooler with retries and backup printers
+
+export const printJobsSqlite = sqliteTable("print_jobs", {
+  id: text("id").primaryKey(),
+  printerId: text("printer_id").notNull(),
+  kind: text("kind"), // template kind; NULL for raw ESC/POS from the webview
+  sourceId: text("source_id"),
+  data: blob("data").notNull(), // ESC/POS bytes
+  status: text("status").notNull().default("QUEUED"), // QUEUED | PRINTING | PRINTED | FAILED | DEAD | CANCELLED
+  attempts: integer("attempts").notNull().default(0),
+  lastError: text("last_error"),
+  nextAttemptAt: text("next_attempt_at"),
+  printedBy: text("printed_by"), // printer_id or its backup
+  reprintOf: text("reprint_of"),
+  createdAt: text("created_at").notNull(),
+  updatedAt: text("updated_at"),
+  printedAt: text("printed_at"),
+}, (table) => [
+  index("print_jobs_due_idx").on(table.status, table.nextAttemptAt),
+]);
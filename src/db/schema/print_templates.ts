@@ -0,0 +1,20 @@
+import { sqliteTable, text, integer, uniqueIndex } from "drizzle-orm/sqlite-core";
+
+// Receipt, kitchen chit, void slip and Z-report layouts, per location
+// (NULL location: the whole setup)
+
+export const printTemplatesSqlite = sqliteTable("print_templates", {
+  id: text("id").primaryKey(),
+  locationId: text("location_id"),
+  kind: text("kind").notNull(), // receipt | kitchen_chit | void_slip | z_report
+  header: text("header"),
+  footer: text("footer"),
+  showLogo: integer("show_logo").notNull().default(1),
+  fields: text("fields").notNull(), // JSON array of the fields shown
+  fontSize: text("font_size").notNull().default("normal"), // small | normal | large
+  paperWidth: text("paper_width").notNull().default("mm58"), // mm58 | mm80
+  createdAt: text("created_at").notNull(),
+  updatedAt: text("updated_at"),
+}, (table) => [
+  uniqueIndex("print_templates_location_kind_idx").on(table.locationId, table.kind),
+]);
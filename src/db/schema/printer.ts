@@ -3,10 +3,15 @@ import { sqliteTable, text, integer } from "drizzle-orm/sqlite-core";
 export const printers = sqliteTable("printers", {
   id: text("id").primaryKey().notNull(),
   name: text("name").notNull(),
-  printer_type: text("printer_type").notNull(), // "network", "usb", "bluetooth"
+  printer_type: text("printer_type").notNull(), // "network", "usb", "serial", "bluetooth"
   ip_address: text("ip_address"),
   port: integer("port"),
+  device_path: text("device_path"), // usb and serial printers
+  baud_rate: integer("baud_rate"), // serial printers
   is_active: integer("is_active").default(0).notNull(),
+  backup_printer_id: text("backup_printer_id"), // takes this printer's jobs while it can't print
+  status: text("status").default("unknown").notNull(), // online | paper_low | paper_out | cover_open | error | offline | unknown
+  status_checked_at: text("status_checked_at"),
   created_at: text("created_at"),
   updated_at: text("updated_at"),
 });
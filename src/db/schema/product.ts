@@ -1,4 +1,4 @@
-import { sqliteTable, text, integer, real } from "drizzle-orm/sqlite-core";
+import { sqliteTable, text, integer } from "drizzle-orm/sqlite-core";
 import { v4 as uuidv4 } from "uuid";
 
 
@@ -11,7 +11,7 @@ export const productSqlite = sqliteTable("products", {
 
   categoryId: text("category_id"),
 
-  price: real("price").notNull(),
+  price: integer("price").notNull(), // Minor units of the store currency
   active: integer("active").default(1),
   sortOrder: integer("sort_order").default(0),
   isSoldOut: integer("is_sold_out").default(0),
@@ -1,5 +1,5 @@
 
-import { sqliteTable, text, integer, real } from "drizzle-orm/sqlite-core";
+import { sqliteTable, text, integer } from "drizzle-orm/sqlite-core";
 
 export const productTagSqlite = sqliteTable(
   "product_tags",
@@ -10,7 +10,7 @@ export const productTagSqlite = sqliteTable(
     productId: text("product_id").notNull(),   // FK → products.id
 
     name: text("name").notNull(),
-    price: real("price").default(0),
+    price: integer("price").default(0), // Minor units
 
     active: integer("active").default(1),
     sortOrder: integer("sort_order").default(0),
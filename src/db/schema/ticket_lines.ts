@@ -0,0 +1,76 @@
+import { sqliteTable, text, integer, real, index } from "drizzle-orm/sqlite-core";
+import { ticketsSqlite } from "./tickets";
+
+// Normalized rows derived from tickets.ticket_data by Rust on save
+
+export const ticketItemsSqlite = sqliteTable("ticket_items", {
+  id: integer("id").primaryKey({ autoIncrement: true }),
+  ticketId: text("ticket_id")
+    .notNull()
+    .references(() => ticketsSqlite.id, { onDelete: "cascade" }),
+  sortOrder: integer("sort_order").notNull(),
+  productId: text("product_id"),
+  productName: text("product_name").notNull(),
+  categoryId: text("category_id"),
+  productGroupId: text("product_group_id"),
+  quantity: real("quantity").notNull().default(1),
+  unitPrice: integer("unit_price").notNull().default(0), // Money columns are minor units
+  netAmount: integer("net_amount").notNull().default(0),
+  taxAmount: integer("tax_amount").default(0),
+  chargeAmount: integer("charge_amount").default(0),
+  voided: integer("voided", { mode: "boolean" }).default(false),
+  refunded: integer("refunded", { mode: "boolean" }).default(false),
+  businessDate: text("business_date"),
+  orderTime: text("order_time"),
+}, (table) => [
+  index("ticket_items_ticket_idx").on(table.ticketId),
+  index("ticket_items_business_date_idx").on(table.businessDate, table.productId),
+]);
+
+export const ticketItemModifiersSqlite = sqliteTable("ticket_item_modifiers", {
+  id: integer("id").primaryKey({ autoIncrement: true }),
+  ticketItemId: integer("ticket_item_id")
+    .notNull()
+    .references(() => ticketItemsSqlite.id, { onDelete: "cascade" }),
+  ticketId: text("ticket_id").notNull(),
+  name: text("name").notNull(),
+  quantity: real("quantity").notNull().default(1),
+  price: integer("price").notNull().default(0),
+}, (table) => [
+  index("ticket_item_modifiers_item_idx").on(table.ticketItemId),
+  index("ticket_item_modifiers_ticket_idx").on(table.ticketId),
+]);
+
+export const ticketPaymentsSqlite = sqliteTable("ticket_payments", {
+  id: integer("id").primaryKey({ autoIncrement: true }),
+  ticketId: text("ticket_id")
+    .notNull()
+    .references(() => ticketsSqlite.id, { onDelete: "cascade" }),
+  paymentTypeId: text("payment_type_id"),
+  paymentType: text("payment_type").notNull(),
+  amount: integer("amount").notNull().default(0),
+  tipAmount: integer("tip_amount").default(0),
+  tenderedAmount: integer("tendered_amount"),
+  netAmount: integer("net_amount"),
+  currency: text("currency"),
+  businessDate: text("business_date"),
+  paymentDate: text("payment_date"),
+  paymentTime: text("payment_time"),
+}, (table) => [
+  index("ticket_payments_ticket_idx").on(table.ticketId),
+  index("ticket_payments_business_date_idx").on(table.businessDate, table.paymentTypeId),
+]);
+
+export const ticketChargesSqlite = sqliteTable("ticket_charges", {
+  id: integer("id").primaryKey({ autoIncrement: true }),
+  ticketId: text("ticket_id")
+    .notNull()
+    .references(() => ticketsSqlite.id, { onDelete: "cascade" }),
+  name: text("name").notNull(),
+  isTax: integer("is_tax", { mode: "boolean" }).notNull().default(false),
+  percentage: real("percentage"),
+  amount: integer("amount"),
+  transactionTypeId: text("transaction_type_id"),
+}, (table) => [
+  index("ticket_charges_ticket_idx").on(table.ticketId),
+]);
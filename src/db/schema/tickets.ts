@@ -1,4 +1,4 @@
-import { sqliteTable, text, integer } from "drizzle-orm/sqlite-core";
+import { sqliteTable, text, integer, index, uniqueIndex } from "drizzle-orm/sqlite-core";
 import { v4 as uuidv4 } from "uuid";
 
 export const ticketsSqlite = sqliteTable("tickets", {
@@ -8,9 +8,11 @@ export const ticketsSqlite = sqliteTable("tickets", {
   ticketData: text("ticket_data").notNull(), // Stringified TicketRequest
 
   // Sync status
-  syncStatus: text("sync_status").notNull().default("PENDING"), // PENDING, SYNCING, SYNCED, FAILED
+  syncStatus: text("sync_status").notNull().default("PENDING"), // PENDING, SYNCING, SYNCED, FAILED, DEAD
   syncError: text("sync_error"),
   syncAttempts: integer("sync_attempts").default(0),
+  nextAttemptAt: text("next_attempt_at"), // Backoff: not retried before this time
+  idempotencyKey: text("idempotency_key"), // Sent with every upload so retries are not double-counted
 
   // Order status (for KDS workflow)
   orderStatus: text("order_status").default("PENDING"), // PENDING, IN_PROGRESS, READY, COMPLETED
@@ -26,5 +28,8 @@ export const ticketsSqlite = sqliteTable("tickets", {
   createdAt: text("created_at"),
   updatedAt: text("updated_at"),
   syncedAt: text("synced_at"),
-});
+}, (table) => [
+  index("tickets_sync_due_idx").on(table.syncStatus, table.nextAttemptAt),
+  uniqueIndex("tickets_idempotency_key_unique").on(table.idempotencyKey),
+]);
 
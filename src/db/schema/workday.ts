@@ -1,6 +1,6 @@
 
 
-import { sqliteTable, text, integer, real } from "drizzle-orm/sqlite-core";
+import { sqliteTable, text, integer, index } from "drizzle-orm/sqlite-core";
 
 export const workdays = sqliteTable("workdays", {
   id: integer("id").primaryKey({ autoIncrement: true }),
@@ -10,8 +10,8 @@ export const workdays = sqliteTable("workdays", {
   startTime: text("start_time"),
   endTime: text("end_time"),
   locationId: text("location_id").notNull(),
-  totalSales: real("total_sales"),
-  totalTaxes: real("total_taxes"),
+  totalSales: integer("total_sales"), // Minor units
+  totalTaxes: integer("total_taxes"),
   totalTicketCount: integer("total_ticket_count"),
   workPeriodInformations: text("work_period_informations"),
   departmentTicketInformations: text("department_ticket_informations"),
@@ -20,8 +20,14 @@ export const workdays = sqliteTable("workdays", {
   externalProcessed: integer("external_processed", { mode: "boolean" }),
   workPeriodDay: text("work_period_day"),
   businessDate: text("business_date"),
-  syncStatus: text("sync_status").default("PENDING"), // PENDING, SYNCING, SYNCED, FAILED
+  syncStatus: text("sync_status").default("PENDING"), // PENDING, SYNCING, SYNCED, FAILED, DEAD
   syncError: text("sync_error"),
+  syncAttempts: integer("sync_attempts").default(0),
+  nextAttemptAt: text("next_attempt_at"), // Backoff: not retried before this time
+  lockedAt: text("locked_at"), // Set by close_workday; the row is read-only afterwards
+  zReport: text("z_report"), // JSON SalesReport built by close_workday
   createdAt: text("created_at").notNull(),
   updatedAt: text("updated_at"),
-});
+}, (table) => [
+  index("workdays_sync_due_idx").on(table.syncStatus, table.nextAttemptAt),
+]);
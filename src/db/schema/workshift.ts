@@ -1,10 +1,34 @@
 
 
 
-import { sqliteTable, text, integer } from "drizzle-orm/sqlite-core";
+import { sqliteTable, text, integer, index, uniqueIndex } from "drizzle-orm/sqlite-core";
+import { sql } from "drizzle-orm";
+import { workdays } from "./workday";
 
 export const workShiftDraft = sqliteTable("work_shift_draft", {
   id: integer("id").primaryKey(), 
   data: text("data").notNull(),
   updatedAt: text("updated_at"),
 });
+
+export const workShifts = sqliteTable("work_shifts", {
+  id: integer("id").primaryKey({ autoIncrement: true }),
+  workdayId: integer("workday_id").notNull().references(() => workdays.id), // Local workdays.id
+  locationId: text("location_id").notNull(),
+  status: text("status").notNull().default("OPEN"), // OPEN, CLOSED
+  openedBy: text("opened_by").notNull(),
+  openedAt: text("opened_at").notNull(),
+  openingFloat: integer("opening_float").notNull().default(0), // Minor units
+  closedBy: text("closed_by"),
+  closedAt: text("closed_at"),
+  expectedCash: integer("expected_cash"), // Opening float + cash taken during the shift
+  countedCash: integer("counted_cash"),
+  variance: integer("variance"), // counted - expected: positive is over, negative is short
+  cashUp: text("cash_up"), // JSON CashUpLine[] per payment method
+  xReport: text("x_report"), // JSON SalesReport for the shift
+  createdAt: text("created_at").notNull(),
+  updatedAt: text("updated_at"),
+}, (table) => [
+  index("work_shifts_workday_idx").on(table.workdayId),
+  uniqueIndex("work_shifts_open_location_unique").on(table.locationId).where(sql`${table.status} = 'OPEN'`),
+]);
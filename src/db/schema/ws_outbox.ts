@@ -0,0 +1,24 @@
+import { sqliteTable, text, integer, index, uniqueIndex } from "drizzle-orm/sqlite-core";
+
+// POS hub: messages for KDS/QUEUE/POS devices, replayed until acknowledged
+
+export const wsOutboxSqlite = sqliteTable("ws_outbox", {
+  seq: integer("seq").primaryKey({ autoIncrement: true }),
+  messageId: text("message_id").notNull(),
+  targetType: text("target_type").notNull(), // POS | KDS | QUEUE
+  targetDeviceId: text("target_device_id"),
+  message: text("message").notNull(), // DeviceMessage JSON
+  createdAt: text("created_at").notNull(),
+}, (table) => [
+  uniqueIndex("ws_outbox_message_id_unique").on(table.messageId),
+  index("ws_outbox_target_idx").on(table.targetType, table.seq),
+]);
+
+export const wsDeliveryCursorsSqlite = sqliteTable("ws_delivery_cursors", {
+  deviceId: text("device_id").primaryKey(),
+  deviceType: text("device_type").notNull(),
+  lastAckedSeq: integer("last_acked_seq").notNull().default(0),
+  lastAckedAt: text("last_acked_at"),
+  lastRegisteredAt: text("last_registered_at"),
+  createdAt: text("created_at").notNull(),
+});
@@ -56,7 +56,7 @@ export const ticketService = {
     const metadata = {
       locationId: ticketRequest.ticket.location_id,
       orderModeName: ticketRequest.ticket.ordermode_name,
-      ticketAmount: Math.round(ticketRequest.ticket.ticket_amount * 100), // Store in cents
+      ticketAmount: ticketRequest.ticket.ticket_amount, // Rust stores it in minor units
       itemsCount: ticketRequest.orders.length,
       queueNumber: ticketRequest.ticket.queue_number,
       ticketNumber: ticketRequest.ticket.ticket_number,
@@ -91,67 +91,6 @@ export const ticketService = {
     }
   },
 
-  /**
-   * Sync pending tickets to API
-   * Returns array of successfully synced ticket IDs
-   */
-  async syncPendingTickets(
-    domain: string,
-    token: string
-  ): Promise<{ synced: string[]; failed: string[] }> {
-    const online = await isOnline();
-
-    if (!online) {
-      throw new Error("Cannot sync tickets while offline");
-    }
-
-    const pendingTickets = await ticketLocal.getPending();
-
-    if (pendingTickets.length === 0) {
-      return { synced: [], failed: [] };
-    }
-
-    const synced: string[] = [];
-    const failed: string[] = [];
-
-    for (const dbTicket of pendingTickets) {
-      try {
-        console.log(`🔄 Processing ticket ${dbTicket.id}`);
-
-        // Update status to SYNCING
-        await ticketLocal.updateSyncStatus(dbTicket.id, "SYNCING");
-
-        // Parse and send ticket
-        const ticketRequest: TicketRequest = JSON.parse(dbTicket.ticket_data);
-        console.log(`📡 Sending ticket to API:`, {
-          ticketNumber: ticketRequest.ticket.ticket_number,
-          amount: ticketRequest.ticket.ticket_amount,
-          orderCount: ticketRequest.orders.length,
-        });
-
-        await post(domain, "sync-tickets", token, [ticketRequest]);
-
-        // Update status to SYNCED
-        await ticketLocal.updateSyncStatus(dbTicket.id, "SYNCED");
-        synced.push(dbTicket.id);
-        console.log(` Successfully synced ticket: ${dbTicket.id}`);
-      } catch (error) {
-        // Update status to FAILED
-        const errorMessage = error instanceof Error ? error.message : "Unknown error";
-        await ticketLocal.updateSyncStatus(dbTicket.id, "FAILED", errorMessage);
-        failed.push(dbTicket.id);
-        console.error(`Failed to sync ticket ${dbTicket.id}:`, error);
-        console.error(`📝 Error details:`, {
-          message: errorMessage,
-          type: error?.constructor?.name,
-        });
-      }
-    }
-
-    console.log(`Sync complete: ${synced.length} synced, ${failed.length} failed`);
-    return { synced, failed };
-  },
-
   /**
    * Legacy method for backward compatibility
    */
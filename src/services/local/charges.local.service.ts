@@ -1,5 +1,6 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { DbCharge, DbChargeMapping } from "@/types/charges";
+import type { DbCharge, DbChargeMapping, PricingResult } from "@/types/charges";
+import type { CartItem } from "@/types/cart";
 
 export const chargesLocal = {
   saveCharges(items: DbCharge[]) {
@@ -20,6 +21,13 @@ export const chargesLocal = {
     return invoke("get_charge_mappings");
   },
 
+  /**
+   * Line and ticket charges for the cart, computed in Rust
+   */
+  calculate(items: CartItem[]): Promise<PricingResult> {
+    return invoke("calculate_charges", { items });
+  },
+
   clearCache(): Promise<void> {
     return invoke("clear_charges_cache");
   },
@@ -19,6 +19,7 @@ export const kdsTicketLocal = {
         token_number: ticket.tokenNumber,
         created_at: ticket.createdAt,
         updated_at: ticket.updatedAt,
+        station_id: ticket.stationId ?? null,
       };
 
       await invoke("save_kds_ticket", { ticket: ticketForRust });
@@ -48,6 +49,7 @@ export const kdsTicketLocal = {
         tokenNumber: t.token_number,
         createdAt: t.created_at,
         updatedAt: t.updated_at,
+        stationId: t.station_id,
         
       }));
     } catch (error) {
@@ -76,6 +78,7 @@ export const kdsTicketLocal = {
         tokenNumber: t.token_number,
         createdAt: t.created_at,
         updatedAt: t.updated_at,
+        stationId: t.station_id,
 
       }));
     } catch (error) {
@@ -103,6 +106,7 @@ export const kdsTicketLocal = {
         tokenNumber: t.token_number,
         createdAt: t.created_at,
         updatedAt: t.updated_at,
+        stationId: t.station_id,
       }));
     } catch (error) {
       console.error(`Failed to get KDS tickets with status ${status}:`, error);
@@ -0,0 +1,50 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// Kitchen stations (POS hub): each KDS shows one station's items, an expo all of them
+
+export interface KitchenStation {
+  id: string;
+  name: string;
+  is_expo: boolean;
+  sort_order: number;
+  created_at: string;
+  updated_at?: string | null;
+}
+
+export type StationTarget = "PRODUCT" | "CATEGORY";
+
+export interface StationMapping {
+  target_type: StationTarget;
+  target_id: string;
+  station_id: string;
+}
+
+export interface KitchenSetup {
+  stations: KitchenStation[];
+  mappings: StationMapping[];
+}
+
+export const kitchenStationLocal = {
+  getSetup(): Promise<KitchenSetup> {
+    return invoke("get_kitchen_setup");
+  },
+
+  // Empty id creates a station
+  save(station: Pick<KitchenStation, "id" | "name" | "is_expo"> & { sort_order?: number }): Promise<KitchenStation> {
+    return invoke("save_kitchen_station", { station });
+  },
+
+  delete(stationId: string): Promise<void> {
+    return invoke("delete_kitchen_station", { stationId });
+  },
+
+  // A product mapping wins over its category's; null removes the mapping
+  setMapping(targetType: StationTarget, targetId: string, stationId: string | null): Promise<void> {
+    return invoke("set_station_mapping", { targetType, targetId, stationId });
+  },
+
+  // null: the KDS shows whole orders
+  setDeviceStation(deviceId: string, stationId: string | null): Promise<void> {
+    return invoke("set_device_station", { deviceId, stationId });
+  },
+};
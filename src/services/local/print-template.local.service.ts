@@ -0,0 +1,91 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { PrintJob } from "./printer.local.service";
+
+// Print templates: how receipts, kitchen chits, void slips and Z-reports are
+// laid out, per location. Rendering happens in Rust.
+
+export type TemplateKind = "receipt" | "kitchen_chit" | "void_slip" | "z_report";
+
+export type TemplateField =
+  | "ticket_number"
+  | "queue_number"
+  | "order_mode"
+  | "date_time"
+  | "modifiers"
+  | "notes"
+  | "station"
+  | "prices"
+  | "charges"
+  | "payments"
+  | "change"
+  | "ticket_barcode"
+  | "order_modes"
+  | "categories"
+  | "voids"
+  | "refunds"
+  | "tips";
+
+// What each kind can show; save_print_template rejects anything else
+export const TEMPLATE_FIELDS: Record<TemplateKind, TemplateField[]> = {
+  receipt: ["ticket_number", "queue_number", "order_mode", "date_time", "modifiers", "charges", "payments", "change", "ticket_barcode"],
+  kitchen_chit: ["ticket_number", "queue_number", "order_mode", "date_time", "station", "modifiers", "notes"],
+  void_slip: ["ticket_number", "order_mode", "date_time", "modifiers", "prices"],
+  z_report: ["date_time", "payments", "order_modes", "categories", "charges", "voids", "refunds", "tips"],
+};
+
+export type FontSize = "small" | "normal" | "large";
+
+export type PaperWidth = "mm58" | "mm80";
+
+export interface PrintTemplate {
+  id: string; // empty creates one
+  location_id?: string | null; // null: every location without its own
+  kind: TemplateKind;
+  header?: string | null;
+  footer?: string | null;
+  show_logo: boolean; // app_state.logo_url
+  fields: TemplateField[];
+  font_size: FontSize;
+  paper_width: PaperWidth;
+  created_at?: string;
+  updated_at?: string | null;
+}
+
+export type PreviewFormat = "text" | "png";
+
+export const printTemplateLocal = {
+  getAll(): Promise<PrintTemplate[]> {
+    return invoke("get_print_templates");
+  },
+
+  // The template the location prints with, falling back to the setup-wide one and then the defaults
+  resolve(kind: TemplateKind, locationId: string | null = null): Promise<PrintTemplate> {
+    return invoke("get_print_template", { kind, locationId });
+  },
+
+  save(template: PrintTemplate): Promise<PrintTemplate> {
+    return invoke("save_print_template", { template });
+  },
+
+  delete(id: string): Promise<void> {
+    return invoke("delete_print_template", { id });
+  },
+
+  // Plain text, or a base64 PNG. Without sourceId the preview uses sample data.
+  // A PNG of a document with Arabic text fails with UNSUPPORTED; ask for text.
+  preview(template: PrintTemplate, format: PreviewFormat, sourceId?: string): Promise<string> {
+    return invoke("preview_print_template", { template, format, sourceId: sourceId ?? null });
+  },
+
+  // sourceId: a ticket id for receipts and void slips, a KDS ticket id for
+  // kitchen chits, a workday id for Z-reports. Without printerId it goes to
+  // every active network printer. Returns the queued jobs.
+  print(kind: TemplateKind, sourceId: string, printerId?: string): Promise<PrintJob[]> {
+    return invoke("print_document", { kind, sourceId, printerId: printerId ?? null });
+  },
+
+  // Base64 ESC/POS for the builtin printer bridge
+  render(kind: TemplateKind, sourceId: string): Promise<string> {
+    return invoke("render_document", { kind, sourceId });
+  },
+};
@@ -1,40 +1,68 @@
 import { invoke } from "@tauri-apps/api/core";
+import { errorMessage } from "@/types/error";
+import type { TemplateKind } from "./print-template.local.service";
 
 export interface Printer {
   id: string;
   name: string;
-  printer_type: string; // "network", "usb", "bluetooth", "builtin"
+  printer_type: string; // "network", "usb", "serial", "bluetooth", "builtin"
   ip_address?: string;
   port?: number;
+  device_path?: string | null; // usb and serial: "/dev/usb/lp0", "/dev/ttyUSB0", "COM3"
+  baud_rate?: number | null; // serial, 9600 when unset
   is_active: boolean;
+  backup_printer_id?: string | null; // takes this printer's jobs while it can't print
+  status?: PrinterHealth; // last health check, reset to "unknown" on save
+  status_checked_at?: string | null;
   created_at?: string;
   updated_at?: string;
 }
 
-export interface ReceiptItem {
-  name: string;
-  quantity: number;
-  price: number;
-  total: number;
+/** A USB printer or serial port attached to this machine (desktop) */
+export interface PrinterDevice {
+  printer_type: "usb" | "serial";
+  device_path: string;
+  description?: string | null; // maker and model, when known
 }
 
-export interface ReceiptCharge {
+// Printer types the Rust spooler prints to; builtin goes through the Android bridge
+export const DIRECT_PRINTER_TYPES = ["network", "usb", "serial"];
+
+export type PrinterHealth = "online" | "paper_low" | "paper_out" | "cover_open" | "error" | "offline" | "unknown";
+
+/** Payload of the `printer:status` event, sent when a printer's health changes */
+export interface PrinterStatusChange {
+  printer_id: string;
   name: string;
-  amount: number;
+  status: PrinterHealth;
+  previous: PrinterHealth;
+  flags?: {
+    offline: boolean;
+    cover_open: boolean;
+    paper_near_end: boolean;
+    paper_out: boolean;
+    error: boolean;
+  } | null; // null when unreachable or it doesn't answer status queries
+  error?: string | null;
 }
 
-export interface ReceiptData {
-  ticket_number: string;
-  location_name: string;
-  order_mode: string;
-  items: ReceiptItem[];
-  subtotal: number;
-  charges: ReceiptCharge[];
-  total: number;
-  payment_method: string;
-  tendered: number;
-  change: number;
-  timestamp: string;
+export type PrintJobStatus = "QUEUED" | "PRINTING" | "PRINTED" | "FAILED" | "DEAD" | "CANCELLED";
+
+/** A queued print, sent by the Rust spooler; also the payload of the `print:job` event */
+export interface PrintJob {
+  id: string;
+  printer_id: string;
+  kind?: TemplateKind | null; // null for raw ESC/POS from the webview
+  source_id?: string | null;
+  status: PrintJobStatus;
+  attempts: number;
+  last_error?: string | null;
+  next_attempt_at?: string | null;
+  printed_by?: string | null; // printer_id or its backup
+  reprint_of?: string | null;
+  created_at: string;
+  updated_at?: string | null;
+  printed_at?: string | null;
 }
 
 // Built-in Printer Detection Result (from Android)
@@ -107,159 +135,6 @@ declare global {
   }
 }
 
-const PAPER_WIDTH = 32; // 58mm printer (use 48 for 80mm)
-
-const ESC_POS = {
-  INIT: [0x1b, 0x40],
-  ALIGN_LEFT: [0x1b, 0x61, 0x00],
-  ALIGN_CENTER: [0x1b, 0x61, 0x01],
-  BOLD_ON: [0x1b, 0x45, 0x01],
-  BOLD_OFF: [0x1b, 0x45, 0x00],
-  SIZE_NORMAL: [0x1d, 0x21, 0x00],
-  SIZE_DOUBLE: [0x1d, 0x21, 0x11],
-  FEED: [0x0a],
-  CUT: [0x1d, 0x56, 0x00],
-};
-
-const encoder = new TextEncoder();
-
-function line(text = ""): number[] {
-  return [...encoder.encode(text), ...ESC_POS.FEED];
-}
-
-function center(text: string): number[] {
-  return [
-    ...ESC_POS.ALIGN_CENTER,
-    ...encoder.encode(text),
-    ...ESC_POS.FEED,
-    ...ESC_POS.ALIGN_LEFT,
-  ];
-}
-
-function hr(): number[] {
-  return line("-".repeat(PAPER_WIDTH));
-}
-
-function padRight(text: string, width: number): string {
-  return text.length > width ? text.substring(0, width) : text.padEnd(width);
-}
-
-function padLeft(text: string, width: number): string {
-  return text.length > width ? text.substring(0, width) : text.padStart(width);
-}
-
-function formatItemRow(
-  name: string,
-  qty: number,
-  price: number,
-  total: number
-): number[] {
-  const nameW = 16;
-  const qtyW = 4;
-  const priceW = 6;
-  const totalW = PAPER_WIDTH - (nameW + qtyW + priceW);
-
-  const row =
-    padRight(name, nameW) +
-    padLeft(`x${qty}`, qtyW) +
-    padLeft(price.toFixed(2), priceW) +
-    padLeft(total.toFixed(2), totalW);
-
-  return line(row);
-}
-
-function formatMoneyRow(label: string, value: number): number[] {
-  const left = padRight(label, PAPER_WIDTH - 10);
-  const right = padLeft(value.toFixed(2), 10);
-  return line(left + right);
-}
-function buildReceiptEscPos(receipt: ReceiptData): Uint8Array {
-  const cmd: number[] = [];
-
-  cmd.push(...ESC_POS.INIT);
-
-  // -------- HEADER --------
-  cmd.push(...ESC_POS.SIZE_DOUBLE);
-  cmd.push(...ESC_POS.BOLD_ON);
-  cmd.push(...center(receipt.location_name));
-  cmd.push(...ESC_POS.SIZE_NORMAL);
-  cmd.push(...ESC_POS.BOLD_OFF);
-
-  cmd.push(...center(`Ticket: ${receipt.ticket_number}`));
-  cmd.push(...line(`Mode: ${receipt.order_mode}`));
-  cmd.push(...line(receipt.timestamp));
-
-  cmd.push(...hr());
-
-  // -------- ITEMS HEADER --------
-  cmd.push(...line("Item            Qty Price Total"));
-  cmd.push(...hr());
-
-  // -------- ITEMS --------
-  receipt.items.forEach((item) => {
-    cmd.push(
-      ...formatItemRow(
-        item.name,
-        item.quantity,
-        item.price,
-        item.total
-      )
-    );
-  });
-
-  cmd.push(...hr());
-
-  // -------- TOTALS --------
-  cmd.push(...formatMoneyRow("Subtotal", receipt.subtotal));
-
-  receipt.charges.forEach((charge) => {
-    cmd.push(...formatMoneyRow(charge.name, charge.amount));
-  });
-
-  cmd.push(...hr());
-
-  // -------- GRAND TOTAL --------
-  cmd.push(...ESC_POS.BOLD_ON);
-  cmd.push(...ESC_POS.SIZE_DOUBLE);
-  cmd.push(...center(`TOTAL  ${receipt.total.toFixed(2)}`));
-  cmd.push(...ESC_POS.SIZE_NORMAL);
-  cmd.push(...ESC_POS.BOLD_OFF);
-
-  cmd.push(...hr());
-
-  // -------- PAYMENT --------
-  cmd.push(...line(`Payment: ${receipt.payment_method}`));
-  cmd.push(...formatMoneyRow("Tendered", receipt.tendered));
-  cmd.push(...formatMoneyRow("Change", receipt.change));
-
-  cmd.push(...ESC_POS.FEED);
-  cmd.push(...ESC_POS.FEED);
-
-  // -------- FOOTER --------
-  cmd.push(...center("Thank You!"));
-  cmd.push(...center("Please Come Again"));
-
-  cmd.push(...ESC_POS.FEED);
-  cmd.push(...ESC_POS.FEED);
-
-  // -------- CUT --------
-  cmd.push(...ESC_POS.CUT);
-
-  return new Uint8Array(cmd);
-}
-
-
-/**
- * Convert Uint8Array to base64
- */
-function uint8ArrayToBase64(bytes: Uint8Array): string {
-  let binary = "";
-  for (let i = 0; i < bytes.length; i++) {
-    binary += String.fromCharCode(bytes[i]);
-  }
-  return btoa(binary);
-}
-
 export const printerService = {
   /**
    * Check if builtin printer is available (Android only)
@@ -360,7 +235,7 @@ export const printerService = {
   /**
    * Print receipt via builtin printer (Android only)
    */
-  async printReceiptBuiltin(receiptData: ReceiptData): Promise<void> {
+  async printReceiptBuiltin(ticketId: string): Promise<void> {
     if (!window.BuiltinPrinter) {
       throw new Error("Built-in printer not available");
     }
@@ -381,8 +256,8 @@ export const printerService = {
 
     // SERIAL printers DO NOT need permission
 
-    const escPosCommands = buildReceiptEscPos(receiptData);
-    const base64Data = uint8ArrayToBase64(escPosCommands);
+    // Laid out by Rust, same as network printers get
+    const base64Data: string = await invoke("render_receipt", { ticketId });
 
     const resultJson = window.BuiltinPrinter.printEscPos(base64Data);
     const result: BuiltinPrinterResult = JSON.parse(resultJson);
@@ -486,38 +361,30 @@ export const printerService = {
     return invoke("test_printer", { printer });
   },
 
-  async printReceipt(printerId: string, receiptData: ReceiptData): Promise<void> {
+  async printReceipt(printerId: string, ticketId: string): Promise<void> {
     // Check if this is a builtin printer
     const printer = await this.getPrinter(printerId);
     if (printer?.printer_type === "builtin") {
-      return this.printReceiptBuiltin(receiptData);
+      return this.printReceiptBuiltin(ticketId);
     }
 
-    // Build ESC/POS commands in TypeScript
-    const escPosCommands = buildReceiptEscPos(receiptData);
-    const base64Data = uint8ArrayToBase64(escPosCommands);
-
-    // Send raw data to Rust for network printing
-    return invoke("print_raw", { printerId, data: base64Data });
+    // Queued; failures come as `print:job` events
+    await invoke<PrintJob[]>("print_receipt", { ticketId, printerId });
   },
 
-  async printReceiptToAllActive(receiptData: ReceiptData): Promise<void> {
+  async printReceiptToAllActive(ticketId: string): Promise<void> {
     const activePrinters = await this.getActivePrinters();
     const errors: string[] = [];
     let printedToAny = false;
 
-    // Build ESC/POS commands once in TypeScript
-    const escPosCommands = buildReceiptEscPos(receiptData);
-    const base64Data = uint8ArrayToBase64(escPosCommands);
-
     // Print to builtin printer if available and active (from database)
     const builtinPrinter = activePrinters.find((p) => p.printer_type === "builtin");
     if (builtinPrinter) {
       try {
-        await this.printReceiptBuiltin(receiptData);
+        await this.printReceiptBuiltin(ticketId);
         printedToAny = true;
       } catch (e) {
-        errors.push(`Builtin: ${e instanceof Error ? e.message : String(e)}`);
+        errors.push(`Builtin: ${errorMessage(e)}`);
       }
     } else {
       // Try to auto-detect and print to builtin printer even without database entry
@@ -525,35 +392,36 @@ export const printerService = {
       const isBuiltinAvailable = this.isBuiltinPrinterAvailable();
       if (isBuiltinAvailable) {
         try {
-          await this.printReceiptBuiltin(receiptData);
+          await this.printReceiptBuiltin(ticketId);
           printedToAny = true;
           // Also auto-setup the printer for future use
           this.autoSetupBuiltinPrinter().catch(() => {
             // Ignore auto-setup errors
           });
         } catch (e) {
-          errors.push(`Builtin: ${e instanceof Error ? e.message : String(e)}`);
+          errors.push(`Builtin: ${errorMessage(e)}`);
         }
       }
     }
 
-    // Print to network printers via Tauri (raw data, formatted by TS)
-    const networkPrinters = activePrinters.filter((p) => p.printer_type === "network");
-    if (networkPrinters.length > 0) {
+    // Queue for network, USB and serial printers via Tauri (rendered in Rust
+    // from the ticket, retried by the spooler; failures come as `print:job` events)
+    const directPrinters = activePrinters.filter((p) => DIRECT_PRINTER_TYPES.includes(p.printer_type));
+    if (directPrinters.length > 0) {
       try {
-        await invoke("print_raw_to_all_active", { data: base64Data });
+        await invoke("print_receipt", { ticketId });
         printedToAny = true;
       } catch (e) {
-        errors.push(`Network: ${e instanceof Error ? e.message : String(e)}`);
+        errors.push(`Network: ${errorMessage(e)}`);
       }
     }
 
     // If no printers printed successfully and no builtin available, try network fallback
-    if (!printedToAny && networkPrinters.length === 0) {
+    if (!printedToAny && directPrinters.length === 0) {
       try {
-        await invoke("print_raw_to_all_active", { data: base64Data });
+        await invoke("print_receipt", { ticketId });
       } catch (e) {
-        errors.push(`Fallback: ${e instanceof Error ? e.message : String(e)}`);
+        errors.push(`Fallback: ${errorMessage(e)}`);
       }
     }
 
@@ -562,6 +430,29 @@ export const printerService = {
     }
   },
 
+  // USB printers and serial ports to offer when adding a printer (desktop)
+  async listPrinterDevices(): Promise<PrinterDevice[]> {
+    return invoke("list_printer_devices");
+  },
+
+  // Checks now instead of waiting for the next health check (every minute)
+  async checkPrinterStatus(id: string): Promise<PrinterHealth> {
+    return invoke("check_printer_status", { id });
+  },
+
+  async getPrintJobs(status?: PrintJobStatus, limit?: number): Promise<PrintJob[]> {
+    return invoke("get_print_jobs", { status: status ?? null, limit: limit ?? null });
+  },
+
+  // A new job with the same data, on printerId or the original printer
+  async reprintJob(jobId: string, printerId?: string): Promise<PrintJob> {
+    return invoke("reprint_print_job", { jobId, printerId: printerId ?? null });
+  },
+
+  async cancelPrintJob(jobId: string): Promise<PrintJob> {
+    return invoke("cancel_print_job", { jobId });
+  },
+
   /**
    * Auto-setup builtin printer if detected (call on app startup for Android)
    */
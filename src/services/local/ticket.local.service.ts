@@ -4,7 +4,7 @@ import type { TicketRequest } from "@/types/ticket";
 export interface DbTicket {
   id: string;
   ticket_data: string; // JSON stringified TicketRequest
-  sync_status: "PENDING" | "SYNCING" | "SYNCED" | "FAILED";
+  sync_status: "PENDING" | "SYNCING" | "SYNCED" | "FAILED" | "DEAD";
   sync_error?: string | null;
   sync_attempts: number;
   order_status?: "IN_PROGRESS" | "READY" | "COMPLETED" | null;
@@ -17,6 +17,7 @@ export interface DbTicket {
   created_at?: string | null;
   updated_at?: string | null;
   synced_at?: string | null;
+  idempotency_key?: string | null; // Generated by Rust on first save
 }
 
 export interface SyncStats {
@@ -25,6 +26,97 @@ export interface SyncStats {
   synced: number;
 }
 
+/** Result of one pass of the Rust sync worker */
+export interface SyncReport {
+  synced: number;
+  failed: number;
+  dead: number;
+  /** Already on the server from an earlier attempt, marked SYNCED without re-posting */
+  reconciled: number;
+  halted?: string | null;
+}
+
+/** Payload of the `sync:progress` event */
+export interface SyncProgress {
+  kind: "ticket" | "workday";
+  id: string;
+  status: "SYNCING" | "SYNCED" | "FAILED" | "DEAD";
+  attempts: number;
+  error?: string | null;
+  next_attempt_at?: string | null;
+}
+
+/** Normalized rows Rust derives from `ticket_data` on save */
+export interface TicketItemModifier {
+  name: string;
+  quantity: number;
+  price: number;
+}
+
+export interface TicketItem {
+  id: number | null;
+  ticket_id: string;
+  sort_order: number;
+  product_id?: string | null;
+  product_name: string;
+  category_id?: string | null;
+  product_group_id?: string | null;
+  quantity: number;
+  unit_price: number;
+  net_amount: number;
+  tax_amount: number;
+  charge_amount: number;
+  voided: boolean;
+  refunded: boolean;
+  business_date?: string | null;
+  order_time?: string | null;
+  modifiers: TicketItemModifier[];
+}
+
+export interface TicketPayment {
+  ticket_id: string;
+  payment_type_id?: string | null;
+  payment_type: string;
+  amount: number;
+  tip_amount: number;
+  tendered_amount?: number | null;
+  net_amount?: number | null;
+  currency?: string | null;
+  business_date?: string | null;
+  payment_date?: string | null;
+  payment_time?: string | null;
+}
+
+export interface TicketCharge {
+  ticket_id: string;
+  name: string;
+  is_tax: boolean;
+  percentage?: number | null;
+  amount?: number | null;
+  transaction_type_id?: string | null;
+}
+
+export interface TicketDetail {
+  items: TicketItem[];
+  payments: TicketPayment[];
+  charges: TicketCharge[];
+}
+
+export interface ProductSales {
+  product_id?: string | null;
+  product_name: string;
+  quantity: number;
+  net_amount: number;
+}
+
+export interface PaymentTotal {
+  payment_type_id?: string | null;
+  payment_type: string;
+  count: number;
+  amount: number;
+  tip_amount: number;
+}
+
 export const ticketLocal = {
   async save(ticketRequest: TicketRequest, metadata?: {
     locationId?: string;
@@ -108,6 +200,36 @@ export const ticketLocal = {
     return invoke("clear_all_tickets");
   },
 
+  getDetail(ticketId: string): Promise<TicketDetail> {
+    return invoke("get_ticket_detail", { ticketId });
+  },
+
+  /**
+   * Units and revenue per product for a business date (all locations when locationId is omitted)
+   */
+  getProductSales(businessDate: string, locationId?: string): Promise<ProductSales[]> {
+    return invoke("get_product_sales", { businessDate, locationId: locationId ?? null });
+  },
+
+  getPaymentTotals(businessDate: string, locationId?: string): Promise<PaymentTotal[]> {
+    return invoke("get_payment_totals", { businessDate, locationId: locationId ?? null });
+  },
+
+  /**
+   * Run a pass of the background sync worker now (tickets and workdays)
+   */
+  syncNow(): Promise<SyncReport> {
+    return invoke("sync_now");
+  },
+
+  /**
+   * Give dead-lettered tickets and workdays a fresh set of attempts
+   */
+  async requeueDeadLetters(): Promise<{ tickets: number; workdays: number }> {
+    const [tickets, workdays]: [number, number] = await invoke("requeue_dead_letters");
+    return { tickets, workdays };
+  },
+
   /**
    * Get the next queue number for a location and business date
    * Queue numbers reset daily per location
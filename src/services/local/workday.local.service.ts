@@ -1,5 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { WorkdayPayload, LocalWorkday } from "@/types/workday";
+import type { WorkdayPayload, LocalWorkday, SalesReport } from "@/types/workday";
 
 export const workdayLocal = {
   /**
@@ -83,6 +83,14 @@ export const workdayLocal = {
     });
   },
 
+  /**
+   * Close a workday: Rust builds the Z-report from local tickets, stores the
+   * totals, locks the workday and queues it for upload
+   */
+  close(id: number, endUser: string): Promise<SalesReport> {
+    return invoke("close_workday", { id, endUser });
+  },
+
   /**
    * Update sync status of a workday
    */
@@ -1,6 +1,7 @@
 
 import { invoke } from "@tauri-apps/api/core";
-import type { WorkShiftState } from "@/types/workshift";
+import type { CountedAmount, WorkShift, WorkShiftState } from "@/types/workshift";
+import type { SalesReport } from "@/types/workday";
 
 export const workShiftLocal = {
   async getDraft(): Promise<WorkShiftState | null> {
@@ -17,4 +18,33 @@ export const workShiftLocal = {
   async clear() {
     return invoke("clear_work_shift_draft");
   },
+
+  /**
+   * Open a shift inside a local workday with the till's opening float
+   */
+  start(workdayId: number, openedBy: string, openingFloat: number): Promise<WorkShift> {
+    return invoke("start_shift", { workdayId, openedBy, openingFloat });
+  },
+
+  /**
+   * Close a shift with the counted takings; Rust stores the cash-up
+   */
+  end(id: number, closedBy: string, counted?: CountedAmount[]): Promise<WorkShift> {
+    return invoke("end_shift", { id, closedBy, counted: counted ?? null });
+  },
+
+  getOpen(locationId: string): Promise<WorkShift | null> {
+    return invoke("get_open_shift", { locationId });
+  },
+
+  getForWorkday(workdayId: number): Promise<WorkShift[]> {
+    return invoke("get_workday_shifts", { workdayId });
+  },
+
+  /**
+   * X-report for a workday (the active one by default). Nothing is saved.
+   */
+  xReport(workdayId?: number): Promise<SalesReport> {
+    return invoke("get_x_report", { workdayId: workdayId ?? null });
+  },
 };
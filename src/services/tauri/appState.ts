@@ -44,7 +44,8 @@ export const appStateApi = {
   },
 
   // WebSocket settings
-  getWsSettings(): Promise<[boolean, string]> {
+  // [server mode, server URL, serve wss://]
+  getWsSettings(): Promise<[boolean, string, boolean]> {
     return invoke("get_ws_settings");
   },
 
@@ -52,9 +53,23 @@ export const appStateApi = {
     return invoke("set_ws_server_mode", { enabled });
   },
 
+  setWsTlsEnabled(enabled: boolean): Promise<void> {
+    return invoke("set_ws_tls_enabled", { enabled });
+  },
+
+  // Stand by for the hub at the saved server URL instead of serving one.
+  // Only for a wss:// hub; fails with INVALID_INPUT before pairing with one.
+  setWsHubStandby(enabled: boolean): Promise<void> {
+    return invoke("set_ws_hub_standby", { enabled });
+  },
+
   setWsServerUrl(url: string): Promise<void> {
     return invoke("set_ws_server_url", { url });
   },
+
+  setWsCredentials(deviceId: string, token: string): Promise<void> {
+    return invoke("set_ws_credentials", { deviceId, token });
+  },
   
   getIpAddress(): Promise<string> {
     return invoke('get_local_ip');
@@ -0,0 +1,172 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { DeviceClient, DeviceMessage, MessageHandler } from "./WebSocketClient";
+
+// Payload of `ws:client-status` (mirrors ClientStatus in Rust)
+export interface HubClientStatus {
+  state: "idle" | "connecting" | "connected" | "reconnecting" | "rejected";
+  url: string | null;
+  device_id: string | null;
+  attempt: number;
+  last_error: string | null;
+  retry_in_ms: number | null;
+  connected_at: string | null;
+  last_seq: number;
+}
+
+export type StatusHandler = (status: HubClientStatus) => void;
+
+// Highest hub seq handled by this window; kept across reloads so the
+// messages that arrived meanwhile can be fetched from Rust
+const LAST_SEQ_KEY = "hub-client:last-seq";
+
+// Messages held for a type nobody listens to yet (e.g. right after a reload)
+const MAX_PENDING = 200;
+
+/**
+ * KDS/QUEUE connection to the POS hub, held by Rust.
+ *
+ * Rust keeps the socket, reconnects and acks; this class only listens to the
+ * `ws:message` / `ws:client-status` events, so a reload doesn't drop the
+ * connection or lose messages.
+ */
+export class HubClient implements DeviceClient {
+  private status: HubClientStatus | null = null;
+  private messageHandlers: Map<string, MessageHandler[]> = new Map();
+  private statusHandlers: StatusHandler[] = [];
+  private pending: DeviceMessage[] = [];
+  private unlisten: UnlistenFn[] = [];
+  private lastSeq = Number(sessionStorage.getItem(LAST_SEQ_KEY) ?? 0);
+
+  // Start (or join) the Rust connection and catch up on missed messages
+  async start(role: string, fallbackDeviceId?: string): Promise<HubClientStatus> {
+    this.unlisten = await Promise.all([
+      listen<DeviceMessage>("ws:message", (e) => this.handleMessage(e.payload)),
+      listen<HubClientStatus>("ws:client-status", (e) => this.setStatus(e.payload)),
+    ]);
+
+    const status = await invoke<HubClientStatus>("start_hub_client", {
+      role,
+      fallbackDeviceId,
+    });
+    this.setStatus(status);
+
+    const missed = await invoke<DeviceMessage[]>("get_hub_messages", {
+      afterSeq: this.lastSeq,
+    });
+    missed.forEach((m) => this.handleMessage(m));
+
+    return status;
+  }
+
+  // Resolves once registered with the hub; rejects if the hub refuses us
+  waitUntilConnected(timeoutMs = 10000): Promise<void> {
+    return new Promise((resolve, reject) => {
+      const check = (status: HubClientStatus) => {
+        if (status.state === "connected") done(resolve);
+        if (status.state === "rejected") {
+          done(() => reject(new Error(status.last_error || "Registration rejected")));
+        }
+      };
+      const timer = setTimeout(
+        () => done(() => reject(new Error(this.status?.last_error || "Hub connection timeout"))),
+        timeoutMs
+      );
+      const done = (settle: () => void) => {
+        clearTimeout(timer);
+        this.offStatus(check);
+        settle();
+      };
+
+      this.onStatus(check);
+      if (this.status) check(this.status);
+    });
+  }
+
+  getStatus(): HubClientStatus | null {
+    return this.status;
+  }
+
+  onStatus(handler: StatusHandler): void {
+    this.statusHandlers.push(handler);
+  }
+
+  offStatus(handler: StatusHandler): void {
+    this.statusHandlers = this.statusHandlers.filter((h) => h !== handler);
+  }
+
+  send(message: DeviceMessage): void {
+    invoke("send_to_hub", { message }).catch((err) =>
+      console.warn("⚠️ Hub not connected, message skipped:", err)
+    );
+  }
+
+  on(messageType: string, handler: MessageHandler): void {
+    if (!this.messageHandlers.has(messageType)) {
+      this.messageHandlers.set(messageType, []);
+    }
+    this.messageHandlers.get(messageType)!.push(handler);
+
+    // Hand over anything that arrived before this handler was attached
+    const waiting = this.pending.filter(
+      (m) => messageType === "*" || m.message_type === messageType
+    );
+    this.pending = this.pending.filter((m) => !waiting.includes(m));
+    waiting.forEach((m) => handler(m));
+  }
+
+  off(messageType: string, handler: MessageHandler): void {
+    const handlers = this.messageHandlers.get(messageType);
+    if (!handlers) return;
+
+    const index = handlers.indexOf(handler);
+    if (index >= 0) {
+      handlers.splice(index, 1);
+    }
+  }
+
+  getDeviceId(): string {
+    return this.status?.device_id ?? "";
+  }
+
+  isConnected(): boolean {
+    return this.status?.state === "connected";
+  }
+
+  // Stop listening in this window; the Rust connection keeps running
+  detach(): void {
+    this.unlisten.forEach((u) => u());
+    this.unlisten = [];
+  }
+
+  // Close the connection for good (until started again)
+  disconnect(): void {
+    this.detach();
+    invoke("stop_hub_client").catch(console.error);
+  }
+
+  private setStatus(status: HubClientStatus): void {
+    this.status = status;
+    this.statusHandlers.forEach((h) => h(status));
+  }
+
+  private handleMessage(message: DeviceMessage): void {
+    if (message.seq != null) {
+      if (message.seq <= this.lastSeq) return;
+      this.lastSeq = message.seq;
+      sessionStorage.setItem(LAST_SEQ_KEY, String(message.seq));
+    }
+
+    const handlers = [
+      ...(this.messageHandlers.get(message.message_type) ?? []),
+      ...(this.messageHandlers.get("*") ?? []),
+    ];
+
+    if (handlers.length === 0) {
+      this.pending = [...this.pending, message].slice(-MAX_PENDING);
+      return;
+    }
+
+    handlers.forEach((h) => h(message));
+  }
+}
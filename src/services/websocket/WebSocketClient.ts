@@ -3,18 +3,45 @@ export interface DeviceMessage {
   device_id?: string;
   device_type: string; // "POS", "KDS", "QUEUE", "PRINTER"
   payload: any;
+  message_id?: string; // set on hub messages kept in the outbox
+  seq?: number; // acked back so the hub can replay what we miss
 }
 
 export type MessageHandler = (message: DeviceMessage) => void;
 
-export class WebSocketClient {
+// What contexts and websocketService use, whether the socket lives in the
+// webview (WebSocketClient) or in Rust (HubClient)
+export interface DeviceClient {
+  send(message: DeviceMessage): void;
+  on(messageType: string, handler: MessageHandler): void;
+  off(messageType: string, handler: MessageHandler): void;
+  getDeviceId(): string;
+  isConnected(): boolean;
+  disconnect(): void;
+}
+
+// 🔐 What the POS needs to accept this device
+export interface WsCredentials {
+  token?: string; // issued by the POS when the device was paired
+  pairingCode?: string; // one-time code shown on the POS, used if there is no token yet
+  name?: string; // shown in the POS's paired device list
+}
+
+export type PairedHandler = (deviceId: string, token: string) => void | Promise<void>;
+
+export class WebSocketClient implements DeviceClient {
   private ws: WebSocket | null = null;
   private url: string;
   private deviceId: string;
   private deviceType: string;
+  private credentials: WsCredentials;
+  private onPaired?: PairedHandler;
 
   private messageHandlers: Map<string, MessageHandler[]> = new Map();
 
+  // Highest outbox seq handled; replays after a reconnect may repeat some
+  private lastSeq = 0;
+
   private reconnectAttempts = 0;
   private maxReconnectAttempts = 5;
   private reconnectDelay = 3000;
@@ -23,11 +50,20 @@ export class WebSocketClient {
   // 🔐 Registration ACK handling
   private registerAckPromise: Promise<void> | null = null;
   private resolveRegisterAck: (() => void) | null = null;
-
-  constructor(url: string, deviceId: string, deviceType: string) {
+  private rejectRegisterAck: ((err: Error) => void) | null = null;
+
+  constructor(
+    url: string,
+    deviceId: string,
+    deviceType: string,
+    credentials: WsCredentials = {},
+    onPaired?: PairedHandler
+  ) {
     this.url = url;
     this.deviceId = deviceId;
     this.deviceType = deviceType;
+    this.credentials = credentials;
+    this.onPaired = onPaired;
   }
 
   connect(): Promise<void> {
@@ -36,9 +72,11 @@ export class WebSocketClient {
         console.log(`[WebSocketClient] Connecting to ${this.url}`);
 
         // 🔐 Prepare register ACK promise
-        this.registerAckPromise = new Promise((res) => {
+        this.registerAckPromise = new Promise((res, rej) => {
           this.resolveRegisterAck = res;
+          this.rejectRegisterAck = rej;
         });
+        this.registerAckPromise.catch(() => {});
 
         this.ws = new WebSocket(this.url);
         this.isIntentionallyClosed = false;
@@ -65,13 +103,20 @@ export class WebSocketClient {
           console.log("✅ WebSocket connected");
           this.reconnectAttempts = 0;
 
-          // 🔐 Register device with server
-          this.send({
-            message_type: "register",
-            device_id: this.deviceId,
-            device_type: this.deviceType,
-            payload: {},
-          });
+          // 🔐 Pair first if the POS hasn't issued us a token yet
+          if (!this.credentials.token && this.credentials.pairingCode) {
+            this.send({
+              message_type: "pair",
+              device_id: this.deviceId,
+              device_type: this.deviceType,
+              payload: {
+                code: this.credentials.pairingCode,
+                name: this.credentials.name,
+              },
+            });
+          } else {
+            this.register();
+          }
 
           resolve();
         };
@@ -82,12 +127,41 @@ export class WebSocketClient {
             const message: DeviceMessage = JSON.parse(event.data);
             console.log("📨 WS message:", message.message_type);
 
+            // 🤝 Paired: keep the credentials, then register with them
+            if (message.message_type === "pair_ack") {
+              const { device_id, token } = message.payload;
+              console.log("🤝 Device paired as", device_id);
+              this.deviceId = device_id;
+              this.credentials = { ...this.credentials, token, pairingCode: undefined };
+              Promise.resolve(this.onPaired?.(device_id, token)).catch(console.error);
+              this.register();
+            }
+
             // ✅ Register ACK
             if (message.message_type === "register_ack") {
               console.log("✅ Device successfully registered");
               this.resolveRegisterAck?.();
             }
 
+            // ⛔ Unknown, revoked or bad code: retrying won't help
+            if (message.message_type === "register_rejected") {
+              console.error("⛔ Registration rejected:", message.payload?.reason);
+              this.isIntentionallyClosed = true;
+              this.rejectRegisterAck?.(
+                new Error(message.payload?.reason || "Registration rejected")
+              );
+            }
+
+            if (message.seq != null) {
+              const duplicate = message.seq <= this.lastSeq;
+              if (!duplicate) {
+                this.lastSeq = message.seq;
+                this.handleMessage(message);
+              }
+              this.ack(message.seq);
+              return;
+            }
+
             this.handleMessage(message);
           } catch (err) {
             console.error("❌ Invalid WS message:", err);
@@ -152,6 +226,28 @@ export class WebSocketClient {
     }
   }
 
+  private register(): void {
+    this.send({
+      message_type: "register",
+      device_id: this.deviceId,
+      device_type: this.deviceType,
+      payload: { token: this.credentials.token },
+    });
+  }
+
+  private ack(seq: number): void {
+    this.send({
+      message_type: "ack",
+      device_id: this.deviceId,
+      device_type: this.deviceType,
+      payload: { seq },
+    });
+  }
+
+  getDeviceId(): string {
+    return this.deviceId;
+  }
+
   send(message: DeviceMessage): void {
     if (this.ws && this.ws.readyState === WebSocket.OPEN) {
       this.ws.send(JSON.stringify(message));
@@ -1,14 +1,140 @@
 import { invoke } from "@tauri-apps/api/core";
-import { WebSocketClient, type DeviceMessage } from "./WebSocketClient";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { DeviceClient, DeviceMessage } from "./WebSocketClient";
+
+export interface PairingCode {
+  code: string;
+  role: string;
+  expires_at: string;
+  fingerprint: string | null; // hub certificate, when serving wss://
+}
+
+// What a KDS/QUEUE got back from pairing (credentials are kept in Rust)
+export interface PairedHub {
+  device_id: string;
+  device_type: string;
+  fingerprint: string | null;
+}
+
+// A KDS/QUEUE/POS device paired with this POS
+export interface PairedDevice {
+  id: string;
+  name: string;
+  role: string;
+  paired_at: string | null;
+  revoked_at: string | null;
+  station_id: string | null;
+}
+
+// Outbox delivery per device (POS side)
+export interface DeliveryStatus {
+  device_id: string;
+  device_type: string;
+  last_acked_seq: number;
+  last_acked_at: string | null;
+  last_registered_at: string | null;
+  pending: number;
+  connected: boolean;
+}
+
+// A device currently registered with this POS
+export interface ConnectedDevice {
+  device_id: string;
+  device_type: string;
+  remote_addr: string;
+  connected_at: string;
+  last_seen: string; // last frame or heartbeat pong
+}
+
+// Payload of `ws:device-connected` / `ws:device-disconnected`
+export interface PresenceEvent extends ConnectedDevice {
+  connected: boolean;
+  reason:
+    | "closed"
+    | "timeout"
+    | "error"
+    | "revoked"
+    | "backpressure" // couldn't keep up with what the hub sent
+    | "rate_limited" // kept sending faster than allowed
+    | "oversized" // sent a frame over the size limit
+    | null;
+}
+
+// Frames waiting in a queue, out of how many it holds
+export interface QueueDepth {
+  len: number;
+  capacity: number;
+}
+
+export interface DeviceQueue extends QueueDepth {
+  device_id: string;
+  device_type: string;
+  dropped: number; // frames dropped on this connection
+}
+
+// The hub's queues and what its connection limits have dropped
+export interface WsMetrics {
+  event_queue: QueueDepth;
+  devices: DeviceQueue[];
+  dropped_events: number;
+  dropped_frames: number;
+  backpressure_disconnects: number;
+  rate_limited_frames: number;
+  rate_limit_disconnects: number;
+  oversized_frames: number;
+}
+
+// What app_state starts with before anyone picks a hub
+export const DEFAULT_HUB_URL = "ws://localhost:9001";
+
+// A POS hub found on the LAN over mDNS (`_hashtouch._tcp`)
+export interface DiscoveredHub {
+  name: string;
+  url: string;
+  addresses: string[];
+  port: number;
+  location_id: string | null;
+  brand_id: string | null;
+  tls: boolean;
+  fingerprint: string | null;
+}
+
+// This POS's part in the hub (`ws:hub-role`)
+// standby: mirroring another POS, ready to take over
+// promoted: was the standby and took over
+// superseded: a standby took over while this POS was down; it doesn't serve
+export type HubRole = "none" | "primary" | "standby" | "promoted" | "superseded";
+
+// Hub route table (`ws_routes`): where the POS forwards device messages
+export type RouteTarget = { device_type: string } | { device_id: string };
+
+export type PayloadFilter = { field: string } & (
+  | { equals: unknown }
+  | { not_equals: unknown }
+  | { in: unknown[] }
+  | { exists: boolean }
+);
+
+export interface Route {
+  message_type: string;
+  from?: string[];
+  split?: { array: string; by: string };
+  when?: PayloadFilter[];
+  to: RouteTarget[];
+}
+
+export interface RouteTable {
+  routes: Route[];
+}
 
 class WebSocketService {
-  private client: WebSocketClient | null = null;
+  private client: DeviceClient | null = null;
 
-  setClient(client: WebSocketClient) {
+  setClient(client: DeviceClient | null) {
     this.client = client;
   }
 
-  getClient(): WebSocketClient | null {
+  getClient(): DeviceClient | null {
     return this.client;
   }
 
@@ -68,6 +194,75 @@ class WebSocketService {
       throw error;
     }
   }
+
+  // 🤝 Pairing (POS side)
+  async createPairingCode(role: string): Promise<PairingCode> {
+    return invoke<PairingCode>("create_pairing_code", { role });
+  }
+
+  async getPairedDevices(): Promise<PairedDevice[]> {
+    return invoke<PairedDevice[]>("get_paired_devices");
+  }
+
+  // 🤝 Pairing (KDS/QUEUE side), done in Rust so a wss:// certificate can be pinned
+  async pairWithHub(url: string, code: string, name: string, role: string): Promise<PairedHub> {
+    return invoke<PairedHub>("pair_with_hub", { url, code, name, role });
+  }
+
+  async getDeliveryStatus(): Promise<DeliveryStatus[]> {
+    return invoke<DeliveryStatus[]>("get_delivery_status");
+  }
+
+  async getConnectedDevices(): Promise<ConnectedDevice[]> {
+    return invoke<ConnectedDevice[]>("get_connected_devices");
+  }
+
+  async getMetrics(): Promise<WsMetrics> {
+    return invoke<WsMetrics>("get_ws_metrics");
+  }
+
+  // Called whenever a device registers or goes away (closed, heartbeat timeout, revoked)
+  async onPresence(handler: (event: PresenceEvent) => void): Promise<UnlistenFn> {
+    const unlisten = await Promise.all([
+      listen<PresenceEvent>("ws:device-connected", (e) => handler(e.payload)),
+      listen<PresenceEvent>("ws:device-disconnected", (e) => handler(e.payload)),
+    ]);
+    return () => unlisten.forEach((u) => u());
+  }
+
+  // 🧭 Discovery (KDS/QUEUE side): hubs for the selected location
+  async discoverHubs(): Promise<DiscoveredHub[]> {
+    return invoke<DiscoveredHub[]>("discover_hubs");
+  }
+
+  // Picks and saves the hub to use (the pinned one, or the first for the location)
+  async autoSelectHub(): Promise<DiscoveredHub | null> {
+    return invoke<DiscoveredHub | null>("auto_select_hub");
+  }
+
+  // 🪞 Failover (POS side)
+  async getHubRole(): Promise<HubRole> {
+    return invoke<HubRole>("get_hub_role");
+  }
+
+  async onHubRole(handler: (role: HubRole) => void): Promise<UnlistenFn> {
+    return listen<HubRole>("ws:hub-role", (e) => handler(e.payload));
+  }
+
+  // 🔀 Routing (POS side)
+  async getRoutes(): Promise<RouteTable> {
+    return invoke<RouteTable>("get_ws_routes");
+  }
+
+  // Applied right away; null goes back to the default routes
+  async setRoutes(routes: RouteTable | null): Promise<RouteTable> {
+    return invoke<RouteTable>("set_ws_routes", { routes });
+  }
+
+  async revokeDevice(deviceId: string): Promise<void> {
+    await invoke("revoke_device", { deviceId });
+    console.log("🚫 Device revoked:", deviceId);
+  }
 }
 
 export const websocketService = new WebSocketService();
@@ -22,6 +22,12 @@ export interface AppState {
 
   ws_server_mode?: number | null;
   ws_server_url?: string | null;
+  ws_device_id?: string | null;
+  ws_device_token?: string | null;
+  ws_tls_enabled?: number | null;
+  ws_tls_fingerprint?: string | null;
+  ws_hub_standby?: number | null;
+  ws_routes?: string | null;
   setup_code?:string|null;
   logo_url?: string | null;
 }
@@ -37,7 +37,7 @@ export interface DbCharge {
   id: string;
   code?: string;
   name: string;
-  percentage?: string;
+  percentage?: string | number; // Saved as the API's "10.00", read back as a number
   is_tax: number;
   transaction_type_id?: string;
   parent_charge_id?: string;
@@ -66,3 +66,36 @@ export interface DbChargeMapping {
   updated_by?: string;
   deleted_by?: string;
 }
+
+// Mirrors `PricingResult` in src-tauri/src/pricing/mod.rs
+export interface AppliedCharge {
+  id: string;
+  name: string;
+  code: string | null;
+  percentage: number;
+  is_tax: boolean;
+  sort_order: number;
+  transaction_type_id: string | null;
+  parent_charge_id: string | null;
+  applicable_amount: number;
+  amount: number;
+  applied: boolean;
+}
+
+export interface PricedLine {
+  id: string;
+  amount: number;
+  charges: { charge_id: string; applicable_amount: number; amount: number }[];
+  total_charges: number;
+  total: number;
+}
+
+export interface PricingResult {
+  currency_code: string;
+  lines: PricedLine[];
+  charges: AppliedCharge[];
+  subtotal: number;
+  total_taxes: number;
+  total_charges: number; // non-tax charges
+  total: number;
+}
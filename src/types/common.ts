@@ -7,7 +7,7 @@ export type OrderStatus = "PLACED" | "PAID" | "READY" | "COMPLETED" | "CANCELLED
 // export type QueueStatus = "WAITING" | "CALLED" | "SERVED";
 export type KdsStatus = "PENDING" | "IN_PROGRESS" | "READY";
 export type PaymentMethod =  "CASH"  | "CREDIT_CARD"  | "E_WALLET"  | "BANK_TRANSFER"  | "CRM_POINTS"  | "TABSQUARE"  | "QUICK_DINE"  | "MALL_VOUCHER"  | "STRIPE";
-export type PrinterTypes=  "network"| "usb" ;
+export type PrinterTypes=  "network"| "usb" | "serial" ;
 
 export interface DeviceProfile {
   id: string;
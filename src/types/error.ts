@@ -0,0 +1,39 @@
+// Mirrors `AppError` in src-tauri/src/error.rs
+export type AppErrorCode =
+  | "NOT_FOUND"
+  | "DB_BUSY"
+  | "DB_ERROR"
+  | "NETWORK"
+  | "UPSTREAM"
+  | "INVALID_INPUT"
+  | "CONFLICT"
+  | "UNAUTHORIZED"
+  | "SERIALIZATION"
+  | "PRINTER_UNAVAILABLE"
+  | "UNSUPPORTED"
+  | "INTERNAL";
+
+export interface AppError {
+  code: AppErrorCode;
+  message: string;
+  retryable: boolean;
+}
+
+export function isAppError(e: unknown): e is AppError {
+  return (
+    typeof e === "object" &&
+    e !== null &&
+    typeof (e as AppError).code === "string" &&
+    typeof (e as AppError).message === "string"
+  );
+}
+
+export function errorMessage(e: unknown): string {
+  if (isAppError(e)) return e.message;
+  if (e instanceof Error) return e.message;
+  return String(e);
+}
+
+export function isRetryable(e: unknown): boolean {
+  return isAppError(e) && e.retryable;
+}
@@ -12,6 +12,7 @@ export interface KDSTicketData {
   tokenNumber?: number;
   createdAt: string;
   updatedAt: string;
+  stationId?: string | null; // kitchen station; whole order when unset
 }
 
 export interface KDSTicketItem {
@@ -22,4 +23,7 @@ export interface KDSTicketItem {
   modifiers?: Array<{ name: string; qty: number; price: number }>;
   completed?: boolean;
   notes?: string;
+  // Set on expo tickets: the station making the item
+  station_id?: string | null;
+  station_name?: string | null;
 }
@@ -46,8 +46,47 @@ export interface LocalWorkday {
     externalProcessed?: boolean;
     workPeriodDay?: string;
     businessDate?: string;
-    syncStatus: "PENDING" | "SYNCING" | "SYNCED" | "FAILED";
+    syncStatus: "PENDING" | "SYNCING" | "SYNCED" | "FAILED" | "DEAD";
     syncError?: string;
     createdAt: string;
     updatedAt?: string;
+    lockedAt?: string;
+    zReport?: string; // JSON SalesReport
+}
+
+// Mirrors `SalesReport` in src-tauri/src/db/models/report.rs
+export interface SalesReport {
+    location_id: string;
+    from: string;
+    to: string;
+    generated_at: string;
+    ticket_count: number;
+    gross_sales: number;
+    total_taxes: number;
+    total_charges: number;
+    total_payments: number;
+    total_tips: number;
+    by_payment_method: {
+        payment_type_id?: string | null;
+        payment_type: string;
+        count: number;
+        amount: number;
+        tip_amount: number;
+    }[];
+    by_order_mode: { order_mode_name?: string | null; ticket_count: number; amount: number }[];
+    by_category: {
+        category_id?: string | null;
+        category_name?: string | null;
+        quantity: number;
+        net_amount: number;
+    }[];
+    charges: {
+        name: string;
+        is_tax: boolean;
+        percentage?: number | null;
+        ticket_count: number;
+        amount: number;
+    }[];
+    voids: { lines: number; quantity: number; amount: number };
+    refunds: { lines: number; quantity: number; amount: number };
 }
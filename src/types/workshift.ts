@@ -9,4 +9,40 @@ export interface WorkShiftState {
     openTillAmount?: number;
     workdayId?: string; 
     localWorkdayId?: number; 
+    shiftId?: number; // Local work_shifts.id
+}
+
+// Mirrors `WorkShift` in src-tauri/src/db/models/work_shift.rs
+export interface WorkShift {
+    id: number;
+    workday_id: number;
+    location_id: string;
+    status: "OPEN" | "CLOSED";
+    opened_by: string;
+    opened_at: string;
+    opening_float: number;
+    closed_by?: string | null;
+    closed_at?: string | null;
+    expected_cash?: number | null;
+    counted_cash?: number | null;
+    variance?: number | null; // counted - expected
+    cash_up?: string | null; // JSON CashUpLine[]
+    x_report?: string | null; // JSON SalesReport
+    created_at: string;
+    updated_at?: string | null;
+}
+
+export interface CashUpLine {
+    payment_type_id?: string | null;
+    payment_type: string;
+    is_cash: boolean;
+    expected: number;
+    counted?: number | null;
+    variance?: number | null;
+}
+
+export interface CountedAmount {
+    payment_type_id?: string | null;
+    payment_type: string;
+    amount: number;
 }
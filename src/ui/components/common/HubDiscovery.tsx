@@ -0,0 +1,72 @@
+import { useState } from "react";
+import { Search, Lock } from "lucide-react";
+import {
+  websocketService,
+  type DiscoveredHub,
+} from "@/services/websocket/websocket.service";
+
+interface HubDiscoveryProps {
+  onSelect: (url: string) => void;
+}
+
+// 🧭 Lists POS hubs advertised on the LAN for this location
+export default function HubDiscovery({ onSelect }: HubDiscoveryProps) {
+  const [hubs, setHubs] = useState<DiscoveredHub[] | null>(null);
+  const [searching, setSearching] = useState(false);
+
+  const handleSearch = async () => {
+    setSearching(true);
+    try {
+      const found = await websocketService.discoverHubs();
+      setHubs(found);
+
+      // Only one POS here: use it right away
+      if (found.length === 1) onSelect(found[0].url);
+    } catch (error) {
+      console.error("Hub discovery failed:", error);
+      setHubs([]);
+    } finally {
+      setSearching(false);
+    }
+  };
+
+  return (
+    <div className="space-y-2">
+      <button
+        type="button"
+        onClick={handleSearch}
+        disabled={searching}
+        className="w-full px-4 py-2 rounded-lg border text-sm font-medium flex items-center justify-center gap-2 hover:bg-gray-100 disabled:opacity-60"
+      >
+        <Search className="w-4 h-4" />
+        {searching ? "Searching…" : "Find POS on this network"}
+      </button>
+
+      {hubs && hubs.length === 0 && (
+        <p className="text-xs text-gray-500">
+          No POS found for this location. Enter its address instead.
+        </p>
+      )}
+
+      {hubs && hubs.length > 1 && (
+        <ul className="divide-y rounded-lg border">
+          {hubs.map((hub) => (
+            <li key={hub.name}>
+              <button
+                type="button"
+                onClick={() => onSelect(hub.url)}
+                className="w-full px-4 py-2 text-left hover:bg-gray-50"
+              >
+                <span className="font-medium text-sm">{hub.name}</span>
+                <span className="block text-xs text-gray-500 font-mono">
+                  {hub.tls && <Lock className="w-3 h-3 inline mr-1" />}
+                  {hub.url}
+                </span>
+              </button>
+            </li>
+          ))}
+        </ul>
+      )}
+    </div>
+  );
+}
@@ -5,6 +5,7 @@ import { appStateApi } from "@/services/tauri/appState";
 import { useAppState } from "@/ui/hooks/useAppState";
 import { useKdsWebSocket } from "@/ui/context/web-socket/KdsWebSocketContext";
 import SplashScreen from "@/ui/components/common/SplashScreen";
+import HubDiscovery from "@/ui/components/common/HubDiscovery";
 
 export default function KdsConnectionPage() {
   const navigate = useNavigate();
@@ -12,6 +13,7 @@ export default function KdsConnectionPage() {
 
   const [serverUrl, setServerUrl] = useState("");
   const [inputUrl, setInputUrl] = useState("");
+  const [pairingCode, setPairingCode] = useState("");
   const [showSplash, setShowSplash] = useState(false);
 
   const {
@@ -76,7 +78,10 @@ export default function KdsConnectionPage() {
     }
 
     setShowSplash(true);
-    await connect(inputUrl, appState.device_role);
+
+    // The context pairs first when a code was entered
+    await connect(inputUrl, appState.device_role, pairingCode.trim() || undefined);
+    setPairingCode("");
   };
 
   /* =========================
@@ -172,6 +177,29 @@ export default function KdsConnectionPage() {
               )}
             </div>
 
+            {!isConnected && <HubDiscovery onSelect={setInputUrl} />}
+
+            {/* PAIRING CODE */}
+            {!isConnected && (
+              <div>
+                <label className="block text-sm font-medium text-gray-700 mb-2">
+                  Pairing Code
+                </label>
+                <input
+                  type="text"
+                  inputMode="numeric"
+                  maxLength={6}
+                  value={pairingCode}
+                  onChange={(e) => setPairingCode(e.target.value.replace(/\D/g, ""))}
+                  placeholder="123456"
+                  className="w-full px-4 py-3 rounded-lg border font-mono text-sm tracking-widest"
+                />
+                <p className="text-xs text-gray-500 mt-1">
+                  Shown on the POS under Device Communication. Leave empty if this device is already paired.
+                </p>
+              </div>
+            )}
+
             {/* ACTION BUTTON */}
             {isConnected ? (
               <button
@@ -139,6 +139,9 @@ const TicketCard = ({ ticket, theme, onMarkAsDone, onToggleItem }: TicketCardPro
                     }}
                   >
                     {item.quantity}- {item.name}
+                    {item.station && (
+                      <span className="ml-2 text-xs uppercase opacity-80">[{item.station}]</span>
+                    )}
                   </p>
                   {item.modifiers && item.modifiers.length > 0 ? (
                     <div className="mt-1 space-y-0.5">
@@ -24,6 +24,7 @@ function transformKdsTicketToTicket(kdsTicket: KDSTicketData): Ticket {
       status: (item.completed ? 'completed' : 'pending') as 'pending' | 'completed',
       notes: item.notes || '',
       modifiers: item.modifiers || [],
+      station: item.station_name ?? null,
     }));
   } catch (error) {
     console.error('[Tickets] Failed to parse items:', error);
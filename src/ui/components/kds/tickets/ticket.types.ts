@@ -25,6 +25,7 @@ export interface TicketItem {
   status: 'pending' | 'completed';
   notes: string;
   modifiers?: TicketItemModifier[];
+  station?: string | null; // expo: the station making it
 }
 
 export interface ThemeSettings {
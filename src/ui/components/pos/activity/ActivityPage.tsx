@@ -4,7 +4,6 @@ import { Button } from "@/ui/shadcn/components/ui/button";
 import { Loader2, ArrowLeft } from "lucide-react";
 import { useTranslation } from "react-i18next";
 import { ticketLocal, type DbTicket } from "@/services/local/ticket.local.service";
-import { ticketService } from "@/services/data/ticket.service";
 import { useAppState } from "@/ui/hooks/useAppState";
 import { isOnline } from "@/ui/utils/networkDetection";
 import { useNotification } from "@/ui/context/NotificationContext";
@@ -65,10 +64,7 @@ export default function ActivityPage() {
 
     setSyncing(true);
     try {
-      await ticketService.syncPendingTickets(
-        appState.tenant_domain,
-        appState.access_token
-      );
+      await ticketLocal.syncNow();
       await loadData();
     } finally {
       setSyncing(false);
@@ -76,11 +72,10 @@ export default function ActivityPage() {
   };
 
   /* -------------------- TOTALS -------------------- */
-  const totalAmount =
-    filteredTickets.reduce(
-      (sum, t) => sum + (t.ticket_amount ?? 0),
-      0
-    ) / 100;
+  const totalAmount = filteredTickets.reduce(
+    (sum, t) => sum + (t.ticket_amount ?? 0),
+    0
+  );
 
   /* -------------------- LOADING -------------------- */
   if (loading) {
@@ -164,7 +159,7 @@ export default function ActivityPage() {
                 </div>
 
                 <p className="text-primary font-semibold">
-                  S$ {((ticket.ticket_amount ?? 0) / 100).toFixed(2)}
+                  S$ {(ticket.ticket_amount ?? 0).toFixed(2)}
                 </p>
               </div>
 
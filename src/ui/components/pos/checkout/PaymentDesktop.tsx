@@ -14,7 +14,7 @@ import { ticketLocal } from "@/services/local/ticket.local.service";
 import { buildUpdatedPayments, calculateSurcharge, isTerminalApproved } from "@/services/local/payment-method.local.service";
 import { kdsTicketLocal } from "@/services/local/kds-ticket.local.service";
 import { queueTokenLocal } from "@/services/local/queue-token.local.service";
-import { printerService, type ReceiptData } from "@/services/local/printer.local.service";
+import { printerService } from "@/services/local/printer.local.service";
 import { websocketService } from "@services/websocket/websocket.service";
 import { useSetup } from "@/ui/context/SetupContext";
 
@@ -46,7 +46,7 @@ export default function PaymentDesktop() {
   const { paymentMethods } = usePaymentMethods();
   const { transactionTypes } = useTransactionTypes();
   const { showNotification } = useNotification();
-  const [savedReceiptData, setSavedReceiptData] = useState<ReceiptData | null>(null);
+  const [savedTicketId, setSavedTicketId] = useState<string | null>(null);
   const [savedTicketRequest, setSavedTicketRequest] = useState<TicketRequest | null>(null);
 
   const subtotal = Math.round(items.reduce((s, i) => s + i.price * i.quantity, 0) * 100) / 100;
@@ -439,7 +439,7 @@ export default function PaymentDesktop() {
             modifiers: item.modifiers || [],
             completed: false,
           }))),
-          totalAmount: Math.round(finalTotal * 100),
+          totalAmount: finalTotal,
           tokenNumber: queueNumber,
           createdAt,
           updatedAt: createdAt,
@@ -475,10 +475,12 @@ export default function PaymentDesktop() {
           order_mode: appState.selected_order_mode_name,
           location_id: appState.selected_location_id,
           location: appState.selected_location_name,
-          total_amount: Math.round(finalTotal * 100),
+          total_amount: finalTotal,
           token_number: queueNumber,
           items: items.map(item => ({
             id: item.id,
+            product_id: item.product_id,
+            category_id: item.category_id ?? null,
             name: item.name,
             quantity: item.quantity,
             price: item.price,
@@ -497,43 +499,8 @@ export default function PaymentDesktop() {
       const hasCashPayment = paymentsToProcess.some(p =>
         p.paymentMethodName.toLowerCase().includes("cash")
       );
-      // Include surcharge in receipt charges if applicable
-      const receiptCharges = charges.filter(c => c.applied).map(c => ({
-        name: c.name,
-        amount: c.amount,
-      }));
-
-      if (orderSurcharge.hasSurcharge) {
-        receiptCharges.push({
-          name: orderSurcharge.surchargeAmount > 0 ? "Payment Surcharge" : "Payment Discount",
-          amount: orderSurcharge.surchargeAmount,
-        });
-      }
-
-
-
-      const receiptDataToSave: ReceiptData = {
-        ticket_number: String(ticketRequest.ticket.ticket_number),
-        location_name: appState.selected_location_name,
-        order_mode: appState.selected_order_mode_name,
-        items: items.map(item => ({
-          name: item.name,
-          quantity: item.quantity,
-          price: item.price,
-          total: item.price * item.quantity,
-        })),
-        subtotal,
-        charges: receiptCharges,
-        total: finalTotal,
-        payment_method: paymentsToProcess
-          .map(p => p.paymentMethodName)
-          .join(", "),
-        tendered: totalTendered,
-        change: Math.abs(finalRemainingBalance),
-        timestamp: new Date().toLocaleString(),
-      };
-
-      setSavedReceiptData(receiptDataToSave);
+      // Receipts are laid out in Rust from the saved ticket
+      setSavedTicketId(result.ticketId ?? null);
 
 
 
@@ -565,12 +532,12 @@ export default function PaymentDesktop() {
 
   const handlePrintReceipt = async () => {
     try {
-      if (!savedReceiptData) {
+      if (!savedTicketId) {
         showNotification.error("No receipt data available");
         return;
       }
 
-      await printerService.printReceiptToAllActive(savedReceiptData);
+      await printerService.printReceiptToAllActive(savedTicketId);
       showNotification.success("Receipt printed successfully");
     } catch (error) {
       const errorMessage = error instanceof Error ? error.message : "Unknown print error";
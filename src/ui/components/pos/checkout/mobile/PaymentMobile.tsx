@@ -22,7 +22,7 @@ import { websocketService } from "@/services/websocket/websocket.service";
 import { useNotification } from "@/ui/context/NotificationContext";
 import { useSetup } from "@/ui/context/SetupContext";
 import { transactionTypeLocal } from "@/services/local/transaction-type.local.service";
-import { printerService, type ReceiptData } from "@/services/local/printer.local.service";
+import { printerService } from "@/services/local/printer.local.service";
 import { useTranslation } from "react-i18next";
 import PaymentEntriesModal from "../PaymentEntriesModal";
 import type { TicketRequest } from "@/types/ticket";
@@ -59,7 +59,7 @@ export default function PaymentMobile() {
   const [loading, setLoading] = useState(false);
   const [payments, setPayments] = useState<PaymentEntry[]>([]);
   const [showPaymentModal, setShowPaymentModal] = useState(false);
-  const [savedReceiptData, setSavedReceiptData] = useState<ReceiptData | null>(null);
+  const [savedTicketId, setSavedTicketId] = useState<string | null>(null);
   const [savedTicketRequest, setSavedTicketRequest] = useState<TicketRequest | null>(null);
 
   const [pendingMethod, setPendingMethod] = useState<string | null>(null);
@@ -449,7 +449,7 @@ export default function PaymentMobile() {
           orderModeName: appState.selected_order_mode_name,
           status: "IN_PROGRESS",
           items: JSON.stringify(items.map((item) => ({ id: item.id, name: item.name, quantity: item.quantity, price: item.price, notes: item.notes || "", modifiers: item.modifiers || [], completed: false }))),
-          totalAmount: Math.round(finalTotal * 100),
+          totalAmount: finalTotal,
           tokenNumber: queueNumber,
           createdAt,
           updatedAt: createdAt,
@@ -481,9 +481,9 @@ export default function PaymentMobile() {
           order_mode: appState.selected_order_mode_name,
           location_id: appState.selected_location_id,
           location: appState.selected_location_name,
-          total_amount: Math.round(finalTotal * 100),
+          total_amount: finalTotal,
           token_number: queueNumber,
-          items: items.map((item) => ({ id: item.id, name: item.name, quantity: item.quantity, price: item.price, notes: item.notes || "", modifiers: item.modifiers || [], completed: false })),
+          items: items.map((item) => ({ id: item.id, product_id: item.product_id, category_id: item.category_id ?? null, name: item.name, quantity: item.quantity, price: item.price, notes: item.notes || "", modifiers: item.modifiers || [], completed: false })),
           created_at: createdAt,
         });
       } catch (err) {
@@ -492,39 +492,8 @@ export default function PaymentMobile() {
 
       const hasCashPayment = paymentsToProcess.some((p) => p.paymentMethodName.toLowerCase().includes("cash"));
 
-      // Include surcharge in receipt charges if applicable
-      const receiptCharges = charges.filter((c) => c.applied).map((charge) => ({
-        name: charge.name,
-        amount: charge.amount,
-      }));
-
-      if (orderSurcharge.hasSurcharge) {
-        receiptCharges.push({
-          name: orderSurcharge.surchargeAmount > 0 ? "Payment Surcharge" : "Payment Discount",
-          amount: orderSurcharge.surchargeAmount,
-        });
-      }
-
-      // Save receipt data BEFORE clearing the cart
-      const receiptDataToSave: ReceiptData = {
-        ticket_number: String(ticketRequest.ticket.ticket_number),
-        location_name: appState.selected_location_name,
-        order_mode: appState.selected_order_mode_name,
-        items: items.map((item) => ({
-          name: item.name,
-          quantity: item.quantity,
-          price: item.price,
-          total: item.price * item.quantity,
-        })),
-        subtotal,
-        charges: receiptCharges,
-        total: finalTotal,
-        payment_method: paymentMethodName,
-        tendered: totalTendered,
-        change: Math.abs(finalRemainingBalance),
-        timestamp: new Date().toLocaleString(),
-      };
-      setSavedReceiptData(receiptDataToSave);
+      // Receipts are laid out in Rust from the saved ticket
+      setSavedTicketId(result.ticketId ?? null);
 
       if (hasCashPayment) {
         setShowDrawer(true);
@@ -553,11 +522,11 @@ export default function PaymentMobile() {
 
   const handlePrintReceipt = async () => {
     try {
-      if (!savedReceiptData) {
+      if (!savedTicketId) {
         showNotification.error(t("No receipt data available"));
         return;
       }
-      await printerService.printReceiptToAllActive(savedReceiptData);
+      await printerService.printReceiptToAllActive(savedTicketId);
       showNotification.success(t("Receipt printed"));
     } catch (error) {
       const errorMessage = error instanceof Error ? error.message : "Unknown print error";
@@ -4,6 +4,7 @@ import { Button } from "@/ui/shadcn/components/ui/button"
 import { Plus, ArrowLeft, Bug, RefreshCw } from "lucide-react"
 import { type Printer, printerService, type BuiltinPrinterDetection } from "@services/local/printer.local.service"
 import { generateUUID } from "@/utils/uuid"
+import { errorMessage } from "@/types/error"
 import PrinterStats from "./PrinterStats"
 import PrinterList from "./PrinterList"
 import PrinterFormModal from "./PrinterFormModal"
@@ -81,7 +82,7 @@ export default function PrinterSettingsPage() {
       await loadPrinters()
       alert("Built-in printer added manually!")
     } catch (e) {
-      alert("Failed to add: " + (e instanceof Error ? e.message : String(e)))
+      alert("Failed to add: " + errorMessage(e))
     }
   }
 
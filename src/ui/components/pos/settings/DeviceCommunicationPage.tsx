@@ -1,10 +1,22 @@
 import { useNavigate } from "react-router-dom";
 import { useTranslation } from "react-i18next";
 import { Card } from "@/ui/shadcn/components/ui/card";
-import { Server, Copy, Check } from "lucide-react";
-import { useEffect, useState } from "react";
+import { Server, Copy, Check, KeyRound, AlertTriangle, RefreshCw, Route } from "lucide-react";
+import { useCallback, useEffect, useState } from "react";
 import { appStateApi } from "@/services/tauri/appState";
+import {
+  websocketService,
+  type ConnectedDevice,
+  type DeliveryStatus,
+  type HubRole,
+  type PairedDevice,
+  type PairingCode,
+  type PresenceEvent,
+  type RouteTable,
+  type WsMetrics,
+} from "@/services/websocket/websocket.service";
 import { useAppState } from "@/ui/hooks/useAppState";
+import KitchenStationsCard from "./KitchenStationsCard";
 
 export default function DeviceCommunicationPage() {
   const { t } = useTranslation();
@@ -15,7 +27,194 @@ export default function DeviceCommunicationPage() {
 
   const [copied, setCopied] = useState(false);
 
+  const [pairRole, setPairRole] = useState<"KDS" | "QUEUE" | "POS">("KDS");
+  const [pairingCode, setPairingCode] = useState<PairingCode | null>(null);
+  const [pairedDevices, setPairedDevices] = useState<PairedDevice[]>([]);
+  const [delivery, setDelivery] = useState<Record<string, DeliveryStatus>>({});
+  const [connected, setConnected] = useState<Record<string, ConnectedDevice>>({});
+  const [metrics, setMetrics] = useState<WsMetrics | null>(null);
+  const [offlineAlerts, setOfflineAlerts] = useState<PresenceEvent[]>([]);
+  const [tlsEnabled, setTlsEnabled] = useState(false);
+  const [hubRole, setHubRole] = useState<HubRole>("none");
+  const [standby, setStandby] = useState(false);
+  const [primaryUrl, setPrimaryUrl] = useState("");
+  const [primaryCode, setPrimaryCode] = useState("");
+  const [standbyPaired, setStandbyPaired] = useState(false);
+  const [routesJson, setRoutesJson] = useState("");
+  const [routesError, setRoutesError] = useState<string | null>(null);
+
   const isPOS = appState?.device_role === "POS";
+  const serving = hubRole === "primary" || hubRole === "promoted";
+
+  const loadPairedDevices = useCallback(async () => {
+    try {
+      setPairedDevices(await websocketService.getPairedDevices());
+      const statuses = await websocketService.getDeliveryStatus();
+      setDelivery(Object.fromEntries(statuses.map((s) => [s.device_id, s])));
+      const online = await websocketService.getConnectedDevices();
+      setConnected(Object.fromEntries(online.map((d) => [d.device_id, d])));
+      setMetrics(await websocketService.getMetrics());
+    } catch (error) {
+      console.error("Failed to load paired devices:", error);
+    }
+  }, []);
+
+  useEffect(() => {
+    if (isPOS) loadPairedDevices();
+  }, [isPOS, loadPairedDevices]);
+
+  // Live presence: warn when a display drops, clear the warning when it's back
+  useEffect(() => {
+    if (!isPOS) return;
+    let unlisten: (() => void) | undefined;
+    let cancelled = false;
+
+    websocketService
+      .onPresence((event) => {
+        setOfflineAlerts((alerts) => {
+          const rest = alerts.filter((a) => a.device_id !== event.device_id);
+          return event.connected || event.reason === "revoked" ? rest : [...rest, event];
+        });
+        loadPairedDevices();
+      })
+      .then((u) => {
+        if (cancelled) u();
+        else unlisten = u;
+      })
+      .catch(console.error);
+
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, [isPOS, loadPairedDevices]);
+
+  const queueOf = (deviceId: string) => metrics?.devices.find((d) => d.device_id === deviceId);
+
+  const deviceName = (deviceId: string) =>
+    pairedDevices.find((d) => d.id === deviceId)?.name ?? deviceId;
+
+  useEffect(() => {
+    appStateApi
+      .getWsSettings()
+      .then(([, , tls]) => setTlsEnabled(tls))
+      .catch(console.error);
+  }, []);
+
+  useEffect(() => {
+    if (!isPOS) return;
+    setStandby(appState?.ws_hub_standby === 1);
+    setStandbyPaired(appState?.ws_hub_standby === 1 && !!appState?.ws_device_id);
+
+    websocketService.getHubRole().then(setHubRole).catch(console.error);
+
+    let unlisten: (() => void) | undefined;
+    let cancelled = false;
+    websocketService
+      .onHubRole(setHubRole)
+      .then((u) => {
+        if (cancelled) u();
+        else unlisten = u;
+      })
+      .catch(console.error);
+
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, [isPOS, appState?.ws_hub_standby, appState?.ws_device_id]);
+
+  const showRoutes = (routes: RouteTable) => setRoutesJson(JSON.stringify(routes, null, 2));
+
+  useEffect(() => {
+    if (isPOS) websocketService.getRoutes().then(showRoutes).catch(console.error);
+  }, [isPOS]);
+
+  // null resets to the default routes
+  const handleSaveRoutes = async (routes: RouteTable | null) => {
+    try {
+      showRoutes(await websocketService.setRoutes(routes));
+      setRoutesError(null);
+    } catch (error) {
+      setRoutesError((error as { message?: string })?.message ?? String(error));
+    }
+  };
+
+  const handleSaveRoutesJson = () => {
+    let routes: RouteTable;
+    try {
+      routes = JSON.parse(routesJson);
+    } catch (error) {
+      setRoutesError((error as Error).message);
+      return;
+    }
+    handleSaveRoutes(routes);
+  };
+
+  const handleToggleStandby = async () => {
+    try {
+      await appStateApi.setWsHubStandby(!standby);
+      setStandby(!standby);
+    } catch (error) {
+      console.error("Failed to save standby setting:", error);
+      alert("Failed to save standby setting");
+    }
+  };
+
+  // Pair with the primary POS as its standby (code issued there for "POS")
+  const handlePairWithPrimary = async () => {
+    try {
+      await websocketService.pairWithHub(primaryUrl.trim(), primaryCode.trim(), "Standby POS", "POS");
+      setStandbyPaired(true);
+      setPrimaryCode("");
+    } catch (error) {
+      console.error("Failed to pair with the primary POS:", error);
+      alert((error as { message?: string })?.message ?? "Failed to pair with the primary POS");
+    }
+  };
+
+  const handleToggleTls = async () => {
+    try {
+      await appStateApi.setWsTlsEnabled(!tlsEnabled);
+      setTlsEnabled(!tlsEnabled);
+    } catch (error) {
+      console.error("Failed to save TLS setting:", error);
+      alert("Failed to save TLS setting");
+    }
+  };
+
+  const scheme = tlsEnabled ? "wss" : "ws";
+
+  // Hide the code once it expires; refresh the list in case it was used
+  useEffect(() => {
+    if (!pairingCode) return;
+    const ms = new Date(pairingCode.expires_at).getTime() - Date.now();
+    const timer = setTimeout(() => {
+      setPairingCode(null);
+      loadPairedDevices();
+    }, Math.max(ms, 0));
+    return () => clearTimeout(timer);
+  }, [pairingCode, loadPairedDevices]);
+
+  const handleCreatePairingCode = async () => {
+    try {
+      setPairingCode(await websocketService.createPairingCode(pairRole));
+    } catch (error) {
+      console.error("Failed to create pairing code:", error);
+      alert("Failed to create pairing code");
+    }
+  };
+
+  const handleRevoke = async (device: PairedDevice) => {
+    if (!confirm(`${t("Revoke")} ${device.name}?`)) return;
+    try {
+      await websocketService.revokeDevice(device.id);
+      await loadPairedDevices();
+    } catch (error) {
+      console.error("Failed to revoke device:", error);
+      alert("Failed to revoke device");
+    }
+  };
 
   useEffect(() => {
 
@@ -39,7 +238,7 @@ export default function DeviceCommunicationPage() {
 
   const handleCopyIp = async () => {
     if (!localIp) return;
-    const wsUrl = `ws://${localIp}:9001`;
+    const wsUrl = `${scheme}://${localIp}:9001`;
     try {
       await navigator.clipboard.writeText(wsUrl);
       setCopied(true);
@@ -80,9 +279,19 @@ export default function DeviceCommunicationPage() {
             </p>
 
           </div>
-          {isPOS && (
+          {isPOS && serving && (
             <span className="px-3 py-1 rounded-full bg-green-100 dark:bg-green-900/30 text-green-600 dark:text-green-400 text-sm font-medium">
-              {t("Server Running")}
+              {hubRole === "promoted" ? t("Took over as hub") : t("Server Running")}
+            </span>
+          )}
+          {isPOS && hubRole === "standby" && (
+            <span className="px-3 py-1 rounded-full bg-secondary text-sm font-medium">
+              {t("Standby")}
+            </span>
+          )}
+          {isPOS && hubRole === "superseded" && (
+            <span className="px-3 py-1 rounded-full bg-amber-100 dark:bg-amber-900/30 text-amber-800 dark:text-amber-300 text-sm font-medium">
+              {t("Not serving")}
             </span>
           )}
         </div>
@@ -92,7 +301,7 @@ export default function DeviceCommunicationPage() {
             <p className="text-sm font-medium mb-2">{t("Other devices should connect to:")}</p>
             <div className="flex gap-2 items-center">
               <code className="flex-1 bg-background px-3 py-2 rounded text-sm font-mono">
-                ws://{localIp}:9001
+                {scheme}://{localIp}:9001
               </code>
               <button
                 onClick={handleCopyIp}
@@ -114,8 +323,267 @@ export default function DeviceCommunicationPage() {
             </div>
           </div>
         )}
+
+        {isPOS && (
+          <label className="mt-4 flex items-center gap-3 cursor-pointer">
+            <input
+              type="checkbox"
+              checked={tlsEnabled}
+              onChange={handleToggleTls}
+              className="w-4 h-4"
+            />
+            <span className="text-sm">
+              {t("Encrypt connections (wss://)")}
+              <span className="block text-xs text-muted-foreground">
+                {t("Applies after restarting the app. Devices must be paired again to pin the certificate.")}
+              </span>
+            </span>
+          </label>
+        )}
       </Card>
 
+      {/* Failover */}
+      {isPOS && (
+        <Card className="p-6 space-y-4">
+          <div className="flex items-center gap-4">
+            <RefreshCw className="w-8 h-8 text-primary" />
+            <div className="flex-1">
+              <h2 className="text-xl font-semibold">{t("Hub Failover")}</h2>
+              <p className="text-sm text-muted-foreground mt-1">
+                {t("A second POS can mirror this hub and take over if this one goes down")}
+              </p>
+            </div>
+          </div>
+
+          {hubRole === "superseded" && (
+            <div className="flex items-center gap-3 p-4 rounded-lg bg-amber-100 dark:bg-amber-900/30 text-amber-800 dark:text-amber-300">
+              <AlertTriangle className="w-5 h-5" />
+              <p className="flex-1 text-sm">
+                {t("Another POS took over as hub while this one was down. Pair this POS as its standby.")}
+              </p>
+            </div>
+          )}
+
+          <label className="flex items-center gap-3 cursor-pointer">
+            <input
+              type="checkbox"
+              checked={standby}
+              onChange={handleToggleStandby}
+              className="w-4 h-4"
+            />
+            <span className="text-sm">
+              {t("Stand by for another POS")}
+              <span className="block text-xs text-muted-foreground">
+                {t("Applies after restarting the app. To add a standby, pair it here as POS.")}
+              </span>
+            </span>
+          </label>
+
+          {standby &&
+            (standbyPaired ? (
+              <p className="text-sm text-muted-foreground">
+                {t("Paired with the primary POS")}
+                {appState?.ws_server_url && (
+                  <span className="font-mono"> · {appState.ws_server_url}</span>
+                )}
+              </p>
+            ) : (
+              <div className="flex gap-2 items-center">
+                <input
+                  value={primaryUrl}
+                  onChange={(e) => setPrimaryUrl(e.target.value)}
+                  placeholder="wss://192.168.1.10:9001"
+                  className="flex-1 px-3 py-2 rounded-lg border bg-background text-sm font-mono"
+                />
+                <input
+                  value={primaryCode}
+                  onChange={(e) => setPrimaryCode(e.target.value)}
+                  placeholder={t("Pairing code")}
+                  className="w-32 px-3 py-2 rounded-lg border bg-background text-sm font-mono"
+                />
+                <button
+                  onClick={handlePairWithPrimary}
+                  disabled={!primaryUrl.trim() || !primaryCode.trim()}
+                  className="px-4 py-2 rounded-lg bg-primary hover:bg-primary/90 text-primary-foreground font-medium transition-colors disabled:opacity-60"
+                >
+                  {t("Pair")}
+                </button>
+              </div>
+            ))}
+        </Card>
+      )}
+
+      {/* Offline warnings */}
+      {isPOS &&
+        offlineAlerts.map((alert) => (
+          <div
+            key={alert.device_id}
+            className="flex items-center gap-3 p-4 rounded-lg bg-amber-100 dark:bg-amber-900/30 text-amber-800 dark:text-amber-300"
+          >
+            <AlertTriangle className="w-5 h-5" />
+            <p className="flex-1 text-sm">
+              {deviceName(alert.device_id)} ({alert.device_type}) {t("offline")}
+              {alert.reason === "timeout" && ` · ${t("no heartbeat")}`}
+              {alert.reason === "backpressure" && ` · ${t("too slow to keep up")}`}
+              {alert.reason === "rate_limited" && ` · ${t("sending too fast")}`}
+              {alert.reason === "oversized" && ` · ${t("message too large")}`}
+              {" · "}
+              {t("Last seen")} {new Date(alert.last_seen).toLocaleTimeString()}
+            </p>
+            <button
+              onClick={() =>
+                setOfflineAlerts((alerts) => alerts.filter((a) => a.device_id !== alert.device_id))
+              }
+              className="text-sm underline"
+            >
+              {t("Dismiss")}
+            </button>
+          </div>
+        ))}
+
+      {/* Pairing */}
+      {isPOS && (
+        <Card className="p-6 space-y-4">
+          <div className="flex items-center gap-4">
+            <KeyRound className="w-8 h-8 text-primary" />
+            <div className="flex-1">
+              <h2 className="text-xl font-semibold">{t("Paired Devices")}</h2>
+              <p className="text-sm text-muted-foreground mt-1">
+                {t("Kitchen and queue displays must be paired before they can connect")}
+              </p>
+            </div>
+          </div>
+
+          <div className="flex gap-2 items-center">
+            <select
+              value={pairRole}
+              onChange={(e) => setPairRole(e.target.value as "KDS" | "QUEUE" | "POS")}
+              className="px-3 py-2 rounded-lg border bg-background text-sm"
+            >
+              <option value="KDS">KDS</option>
+              <option value="QUEUE">QUEUE</option>
+              <option value="POS">POS ({t("standby")})</option>
+            </select>
+            <button
+              onClick={handleCreatePairingCode}
+              className="px-4 py-2 rounded-lg bg-primary hover:bg-primary/90 text-primary-foreground font-medium transition-colors"
+            >
+              {t("Pair device")}
+            </button>
+          </div>
+
+          {pairingCode && (
+            <div className="p-4 bg-secondary rounded-lg">
+              <p className="text-sm font-medium mb-2">
+                {t("Enter this code on the")} {pairingCode.role}:
+              </p>
+              <code className="block text-4xl font-mono tracking-[0.5em]">
+                {pairingCode.code}
+              </code>
+              <p className="text-xs text-muted-foreground mt-2">
+                {t("Valid until")} {new Date(pairingCode.expires_at).toLocaleTimeString()}
+              </p>
+              {pairingCode.fingerprint && (
+                <p className="text-xs text-muted-foreground mt-2 break-all font-mono">
+                  {t("Certificate")}: {pairingCode.fingerprint}
+                </p>
+              )}
+            </div>
+          )}
+
+          {pairedDevices.length > 0 && (
+            <ul className="divide-y rounded-lg border">
+              {pairedDevices.map((device) => (
+                <li key={device.id} className="flex items-center gap-4 px-4 py-3">
+                  <div className="flex-1">
+                    <p className="font-medium">{device.name}</p>
+                    <p className="text-xs text-muted-foreground font-mono">
+                      {device.role} · {device.id}
+                    </p>
+                    {(delivery[device.id] || connected[device.id]) && (
+                      <p className="text-xs text-muted-foreground">
+                        {connected[device.id]
+                          ? `${t("Online")} · ${connected[device.id].remote_addr} · ${t("Last seen")} ${new Date(connected[device.id].last_seen).toLocaleTimeString()}`
+                          : t("Offline")}
+                        {(delivery[device.id]?.pending ?? 0) > 0 &&
+                          ` · ${delivery[device.id].pending} ${t("messages pending")}`}
+                        {(queueOf(device.id)?.len ?? 0) > 0 &&
+                          ` · ${queueOf(device.id)?.len} ${t("frames queued")}`}
+                      </p>
+                    )}
+                  </div>
+                  {device.revoked_at ? (
+                    <span className="text-sm text-muted-foreground">{t("Revoked")}</span>
+                  ) : (
+                    <button
+                      onClick={() => handleRevoke(device)}
+                      className="px-3 py-1 rounded-lg bg-red-600 hover:bg-red-700 text-white text-sm"
+                    >
+                      {t("Revoke")}
+                    </button>
+                  )}
+                </li>
+              ))}
+            </ul>
+          )}
+
+          {metrics && (
+            <p className="text-xs text-muted-foreground">
+              {t("Incoming queue")} {metrics.event_queue.len}/{metrics.event_queue.capacity}
+              {" · "}
+              {t("Dropped")}: {metrics.dropped_events} {t("events")}, {metrics.dropped_frames}{" "}
+              {t("frames")}, {metrics.rate_limited_frames} {t("over rate")}
+              {" · "}
+              {t("Disconnected")}:{" "}
+              {metrics.backpressure_disconnects + metrics.rate_limit_disconnects + metrics.oversized_frames}
+            </p>
+          )}
+        </Card>
+      )}
+
+      {/* Kitchen stations */}
+      {isPOS && <KitchenStationsCard devices={pairedDevices} onDevicesChanged={loadPairedDevices} />}
+
+      {/* Routing */}
+      {isPOS && (
+        <Card className="p-6 space-y-4">
+          <div className="flex items-center gap-4">
+            <Route className="w-8 h-8 text-primary" />
+            <div className="flex-1">
+              <h2 className="text-xl font-semibold">{t("Message Routes")}</h2>
+              <p className="text-sm text-muted-foreground mt-1">
+                {t("Where this hub forwards messages from devices, by type or device id")}
+              </p>
+            </div>
+          </div>
+
+          <textarea
+            value={routesJson}
+            onChange={(e) => setRoutesJson(e.target.value)}
+            rows={12}
+            spellCheck={false}
+            className="w-full px-3 py-2 rounded-lg border bg-background text-xs font-mono"
+          />
+
+          {routesError && <p className="text-sm text-red-600">{routesError}</p>}
+
+          <div className="flex gap-2">
+            <button
+              onClick={handleSaveRoutesJson}
+              className="px-4 py-2 rounded-lg bg-primary hover:bg-primary/90 text-primary-foreground font-medium transition-colors"
+            >
+              {t("Save")}
+            </button>
+            <button
+              onClick={() => handleSaveRoutes(null)}
+              className="px-4 py-2 rounded-lg border text-sm font-medium hover:bg-gray-100"
+            >
+              {t("Reset to defaults")}
+            </button>
+          </div>
+        </Card>
+      )}
+
     </div>
   );
 }
@@ -0,0 +1,190 @@
+import { useTranslation } from "react-i18next";
+import { useCallback, useEffect, useState } from "react";
+import { ChefHat, Trash2 } from "lucide-react";
+import { Card } from "@/ui/shadcn/components/ui/card";
+import {
+  kitchenStationLocal,
+  type KitchenSetup,
+} from "@/services/local/kitchen-station.local.service";
+import { categoryLocal, type DbCategory } from "@/services/local/category.local.service";
+import type { PairedDevice } from "@/services/websocket/websocket.service";
+
+interface KitchenStationsCardProps {
+  devices: PairedDevice[];
+  onDevicesChanged: () => void;
+}
+
+// 🍳 Stations, what each one makes, and which KDS shows which station
+export default function KitchenStationsCard({ devices, onDevicesChanged }: KitchenStationsCardProps) {
+  const { t } = useTranslation();
+  const [setup, setSetup] = useState<KitchenSetup>({ stations: [], mappings: [] });
+  const [categories, setCategories] = useState<DbCategory[]>([]);
+  const [newName, setNewName] = useState("");
+  const [newExpo, setNewExpo] = useState(false);
+
+  const load = useCallback(async () => {
+    try {
+      setSetup(await kitchenStationLocal.getSetup());
+    } catch (error) {
+      console.error("Failed to load kitchen stations:", error);
+    }
+  }, []);
+
+  useEffect(() => {
+    load();
+    categoryLocal.getAll().then(setCategories).catch(console.error);
+  }, [load]);
+
+  const run = async (action: () => Promise<unknown>, failure: string) => {
+    try {
+      await action();
+      await load();
+    } catch (error) {
+      console.error(failure, error);
+      alert((error as { message?: string })?.message ?? failure);
+    }
+  };
+
+  const handleAdd = () =>
+    run(async () => {
+      await kitchenStationLocal.save({ id: "", name: newName.trim(), is_expo: newExpo });
+      setNewName("");
+      setNewExpo(false);
+    }, "Failed to add station");
+
+  const handleDelete = (stationId: string) =>
+    run(async () => {
+      await kitchenStationLocal.delete(stationId);
+      onDevicesChanged();
+    }, "Failed to delete station");
+
+  const categoryStation = (categoryId: string) =>
+    setup.mappings.find((m) => m.target_type === "CATEGORY" && m.target_id === categoryId)
+      ?.station_id ?? "";
+
+  const prepStations = setup.stations.filter((s) => !s.is_expo);
+  const screens = devices.filter((d) => d.role === "KDS" && !d.revoked_at);
+
+  return (
+    <Card className="p-6 space-y-4">
+      <div className="flex items-center gap-4">
+        <ChefHat className="w-8 h-8 text-primary" />
+        <div className="flex-1">
+          <h2 className="text-xl font-semibold">{t("Kitchen Stations")}</h2>
+          <p className="text-sm text-muted-foreground mt-1">
+            {t("Each kitchen screen gets only its station's items; an expo screen sees them all")}
+          </p>
+        </div>
+      </div>
+
+      {/* Stations */}
+      {setup.stations.length > 0 && (
+        <ul className="divide-y rounded-lg border">
+          {setup.stations.map((station) => (
+            <li key={station.id} className="flex items-center gap-4 px-4 py-2">
+              <span className="flex-1 font-medium">{station.name}</span>
+              {station.is_expo && (
+                <span className="text-xs text-muted-foreground">{t("Expo")}</span>
+              )}
+              <button
+                onClick={() => handleDelete(station.id)}
+                className="p-1 rounded hover:bg-gray-100"
+                aria-label={t("Delete")}
+              >
+                <Trash2 className="w-4 h-4 text-red-600" />
+              </button>
+            </li>
+          ))}
+        </ul>
+      )}
+
+      <div className="flex gap-2 items-center">
+        <input
+          value={newName}
+          onChange={(e) => setNewName(e.target.value)}
+          placeholder={t("Grill, Fry, Bar…")}
+          className="flex-1 px-3 py-2 rounded-lg border bg-background text-sm"
+        />
+        <label className="flex items-center gap-2 text-sm">
+          <input
+            type="checkbox"
+            checked={newExpo}
+            onChange={(e) => setNewExpo(e.target.checked)}
+            className="w-4 h-4"
+          />
+          {t("Expo")}
+        </label>
+        <button
+          onClick={handleAdd}
+          disabled={!newName.trim()}
+          className="px-4 py-2 rounded-lg bg-primary hover:bg-primary/90 text-primary-foreground font-medium transition-colors disabled:opacity-60"
+        >
+          {t("Add")}
+        </button>
+      </div>
+
+      {/* Category → station */}
+      {prepStations.length > 0 && categories.length > 0 && (
+        <div className="space-y-2">
+          <h3 className="text-sm font-semibold">{t("Categories")}</h3>
+          <ul className="divide-y rounded-lg border">
+            {categories.map((category) => (
+              <li key={category.id} className="flex items-center gap-4 px-4 py-2">
+                <span className="flex-1 text-sm">{category.name}</span>
+                <select
+                  value={categoryStation(category.id)}
+                  onChange={(e) =>
+                    run(
+                      () =>
+                        kitchenStationLocal.setMapping("CATEGORY", category.id, e.target.value || null),
+                      "Failed to map category"
+                    )
+                  }
+                  className="px-3 py-1 rounded-lg border bg-background text-sm"
+                >
+                  <option value="">{t("No station")}</option>
+                  {prepStations.map((s) => (
+                    <option key={s.id} value={s.id}>
+                      {s.name}
+                    </option>
+                  ))}
+                </select>
+              </li>
+            ))}
+          </ul>
+        </div>
+      )}
+
+      {/* KDS → station */}
+      {setup.stations.length > 0 && screens.length > 0 && (
+        <div className="space-y-2">
+          <h3 className="text-sm font-semibold">{t("Screens")}</h3>
+          <ul className="divide-y rounded-lg border">
+            {screens.map((device) => (
+              <li key={device.id} className="flex items-center gap-4 px-4 py-2">
+                <span className="flex-1 text-sm">{device.name}</span>
+                <select
+                  value={device.station_id ?? ""}
+                  onChange={(e) =>
+                    run(async () => {
+                      await kitchenStationLocal.setDeviceStation(device.id, e.target.value || null);
+                      onDevicesChanged();
+                    }, "Failed to set the screen's station")
+                  }
+                  className="px-3 py-1 rounded-lg border bg-background text-sm"
+                >
+                  <option value="">{t("Whole orders")}</option>
+                  {setup.stations.map((s) => (
+                    <option key={s.id} value={s.id}>
+                      {s.name}
+                    </option>
+                  ))}
+                </select>
+              </li>
+            ))}
+          </ul>
+        </div>
+      )}
+    </Card>
+  );
+}
@@ -7,6 +7,7 @@ import { appStateApi } from "@/services/tauri/appState";
 import { useAppState } from "@/ui/hooks/useAppState";
 import { useQueueWebSocket } from "@/ui/context/web-socket/QueueWebSocketContext";
 import SplashScreen from "@/ui/components/common/SplashScreen";
+import HubDiscovery from "@/ui/components/common/HubDiscovery";
 
 export default function QueueConnectionPage() {
     const navigate = useNavigate();
@@ -14,6 +15,7 @@ export default function QueueConnectionPage() {
 
     const [serverUrl, setServerUrl] = useState("");
     const [inputUrl, setInputUrl] = useState("");
+    const [pairingCode, setPairingCode] = useState("");
     const [showSplash, setShowSplash] = useState(false);
 
     const {
@@ -62,7 +64,10 @@ export default function QueueConnectionPage() {
         setServerUrl(inputUrl);
 
         setShowSplash(true);
-        await connect(inputUrl|| appState.device_role);
+
+        // The context pairs first when a code was entered
+        await connect(inputUrl, pairingCode.trim() || undefined);
+        setPairingCode("");
     };
 
     /* ---------------- Disconnect ---------------- */
@@ -138,6 +143,20 @@ export default function QueueConnectionPage() {
                             className="w-full px-4 py-3 rounded-lg border font-mono text-sm"
                         />
 
+                        {!isConnected && <HubDiscovery onSelect={setInputUrl} />}
+
+                        {/* Pairing code from the POS, first connection only */}
+                        {!isConnected && (
+                            <input
+                                inputMode="numeric"
+                                maxLength={6}
+                                value={pairingCode}
+                                onChange={(e) => setPairingCode(e.target.value.replace(/\D/g, ""))}
+                                placeholder="Pairing code (leave empty if already paired)"
+                                className="w-full px-4 py-3 rounded-lg border font-mono text-sm tracking-widest"
+                            />
+                        )}
+
                         {isConnected ? (
                             <button
                                 onClick={handleDisconnect}
@@ -1,14 +1,12 @@
 
 import { createContext, useContext, useEffect, useState, type ReactNode, } from "react";
 
-import type { WorkShiftState } from "@/types/workshift";
+import type { CountedAmount, WorkShiftState } from "@/types/workshift";
 import { workShiftLocal } from "@/services/local/workshift.local.service";
 import { workdayDataService } from "@/services/data/workday.data.service";
 import { workdayLocal } from "@/services/local/workday.local.service";
-import { ticketLocal } from "@/services/local/ticket.local.service";
 import { useAppState } from "./AppStateContext";
 import type { WorkdayPayload } from "@/types/workday";
-import type { TicketRequest } from "@/types/ticket";
 
 
 interface WorkShiftContextType {
@@ -16,7 +14,7 @@ interface WorkShiftContextType {
     isHydrated: boolean;
 
     startShift: (user: string, amount: number) => Promise<void>;
-    endShift: (user: string) => Promise<void>;
+    endShift: (user: string, counted?: CountedAmount[]) => Promise<void>;
     clear: () => Promise<void>;
 }
 
@@ -27,70 +25,6 @@ export const WorkShiftProvider = ({ children }: { children: ReactNode }) => {
     const [isHydrated, setIsHydrated] = useState(false);
     const { state: appState } = useAppState();
 
-    /**
-     * Calculate totals from all tickets for the current shift
-     */
-    const calculateShiftTotals = async (businessDate: string, locationId: string) => {
-        try {
-            console.log("📊 Calculating shift totals for:", { businessDate, locationId });
-
-            const allTickets = await ticketLocal.getAll();
-
-            // Filter tickets for this business date and location
-            const relevantTickets = allTickets.filter(dbTicket => {
-                if (dbTicket.location_id !== locationId) return false;
-
-                try {
-                    const ticketRequest: TicketRequest = JSON.parse(dbTicket.ticket_data);
-                    return ticketRequest.ticket.business_date === businessDate;
-                } catch {
-                    return false;
-                }
-            });
-
-            console.log(`📋 Found ${relevantTickets.length} tickets for this shift`);
-
-            let totalSales = 0;
-            let totalTaxes = 0;
-
-            relevantTickets.forEach(dbTicket => {
-                try {
-                    const ticketRequest: TicketRequest = JSON.parse(dbTicket.ticket_data);
-
-                    // Add ticket amount (convert from cents to dollars)
-                    const ticketAmount = (dbTicket.ticket_amount || 0) / 100;
-                    totalSales += ticketAmount;
-
-                    // Calculate total taxes from orders
-                    ticketRequest.orders.forEach(order => {
-                        const taxAmount = parseFloat(order.tax_amount || '0');
-                        totalTaxes += taxAmount;
-                    });
-                } catch (error) {
-                    console.error("Failed to parse ticket:", error);
-                }
-            });
-
-            const totals = {
-                totalSales: Math.round(totalSales * 100) / 100, // Round to 2 decimals
-                totalTaxes: Math.round(totalTaxes * 100) / 100,
-                totalTicketCount: relevantTickets.length,
-            };
-
-            console.log("💰 Calculated totals:", totals);
-
-            return totals;
-        } catch (error) {
-            console.error("❌ Failed to calculate shift totals:", error);
-            return {
-                totalSales: 0,
-                totalTaxes: 0,
-                totalTicketCount: 0,
-            };
-        }
-    };
-
-
     useEffect(() => {
         const hydrate = async () => {
             try {
@@ -203,6 +137,8 @@ export const WorkShiftProvider = ({ children }: { children: ReactNode }) => {
 
                 console.log("✅ Shift state updated with IDs");
 
+                await openShiftRecord(localId, user, amount);
+
             } catch (error) {
                 console.error("❌ Failed to sync workday start:", error);
 
@@ -233,6 +169,8 @@ export const WorkShiftProvider = ({ children }: { children: ReactNode }) => {
                         ...prev,
                         localWorkdayId: localId,
                     } : prev);
+
+                    await openShiftRecord(localId, user, amount);
                 }
             }
         } else {
@@ -240,23 +178,30 @@ export const WorkShiftProvider = ({ children }: { children: ReactNode }) => {
         }
     };
 
-    const endShift = async (user: string) => {
+    const openShiftRecord = async (localWorkdayId: number, user: string, amount: number) => {
+        try {
+            const record = await workShiftLocal.start(localWorkdayId, user, amount);
+            console.log("💵 Shift opened:", record);
+            setShift(prev => prev ? { ...prev, shiftId: record.id } : prev);
+        } catch (error) {
+            console.error("❌ Failed to open shift:", error);
+        }
+    };
+
+    const endShift = async (user: string, counted?: CountedAmount[]) => {
         const endTime = new Date().toISOString();
 
         // Capture current shift data BEFORE updating state
-        let currentWorkdayId = shift?.workdayId;
         let currentLocalWorkdayId = shift?.localWorkdayId;
 
         console.log("🔚 Ending shift:", {
-            workdayId: currentWorkdayId,
+            workdayId: shift?.workdayId,
             localWorkdayId: currentLocalWorkdayId,
-            hasTenantDomain: !!appState?.tenant_domain,
-            hasAccessToken: !!appState?.access_token,
         });
 
-        // If no workdayId exists, create one retroactively
-        if (!currentWorkdayId && appState?.tenant_domain && appState?.access_token && appState?.selected_location_id && shift) {
-            console.log("⚠️ No workday ID found - creating workday retroactively");
+        // No local workday (shift started without credentials): create one now so the day can be closed
+        if (!currentLocalWorkdayId && appState?.selected_location_id && shift) {
+            console.log("⚠️ No local workday found - creating workday retroactively");
 
             try {
                 const businessDate = shift.startTime ? new Date(shift.startTime).toISOString().split("T")[0] : new Date().toISOString().split("T")[0];
@@ -274,24 +219,12 @@ export const WorkShiftProvider = ({ children }: { children: ReactNode }) => {
                     external_processed: false,
                 };
 
-                const response = await workdayDataService.syncWorkday(
-                    appState.tenant_domain,
-                    appState.access_token,
-                    workdayPayload
-                );
-
-                console.log("✅ Workday created retroactively:", response);
-
-                // Save to local DB
-                const localId = await workdayLocal.save(workdayPayload, {
-                    workdayId: response.wid,
-                    syncStatus: "SYNCED",
+                currentLocalWorkdayId = await workdayLocal.save(workdayPayload, {
+                    workdayId: shift.workdayId,
+                    syncStatus: "PENDING",
                 });
 
-                currentWorkdayId = response.wid;
-                currentLocalWorkdayId = localId;
-
-                console.log("💾 Saved retroactive workday:", { workdayId: response.wid, localId });
+                console.log("💾 Saved retroactive workday:", { localId: currentLocalWorkdayId });
             } catch (error) {
                 console.error("❌ Failed to create retroactive workday:", error);
             }
@@ -305,67 +238,33 @@ export const WorkShiftProvider = ({ children }: { children: ReactNode }) => {
                     isOpen: false,
                     endTime,
                     endedBy: user,
-                    workdayId: currentWorkdayId,
                     localWorkdayId: currentLocalWorkdayId,
                 }
                 : prev
         );
 
-        // Sync with API if we have the required data
-        if (currentWorkdayId && appState?.tenant_domain && appState?.access_token && appState?.selected_location_id) {
-            try {
-                console.log("📡 Calling update-workday API...");
-
-                // Calculate actual totals from tickets
-                const businessDate = shift?.startTime ? new Date(shift.startTime).toISOString().split("T")[0] : new Date().toISOString().split("T")[0];
-                const totals = await calculateShiftTotals(businessDate, appState.selected_location_id);
-
-                const response = await workdayDataService.endWorkday(
-                    appState.tenant_domain,
-                    appState.access_token,
-                    currentWorkdayId,
-                    appState.selected_location_id,
-                    user,
-                    totals
-                );
-
-                console.log("✅ Workday ended on server:", response);
-
-                // Update local DB
-                if (currentLocalWorkdayId) {
-                    await workdayLocal.updateSyncStatus(currentLocalWorkdayId, "SYNCED");
-                }
+        if (!currentLocalWorkdayId) {
+            console.log("⚠️ Skipping workday close - no workday for this shift");
+            return;
+        }
 
+        if (shift?.shiftId) {
+            try {
+                const closed = await workShiftLocal.end(shift.shiftId, user, counted);
+                console.log("💵 Shift cash-up:", closed);
             } catch (error) {
-                console.error("❌ Failed to sync workday end:", error);
-
-                // Mark as pending sync
-                if (currentLocalWorkdayId) {
-                    await workdayLocal.updateSyncStatus(currentLocalWorkdayId, "PENDING");
-                }
-            }
-        } else {
-            console.log("⚠️ Skipping API call - missing required data:", {
-                hasWorkdayId: !!currentWorkdayId,
-                hasTenantDomain: !!appState?.tenant_domain,
-                hasAccessToken: !!appState?.access_token,
-                hasLocationId: !!appState?.selected_location_id,
-            });
-
-            // Update local workday without server sync
-            if (currentLocalWorkdayId && appState?.selected_location_id) {
-                try {
-                    await workdayLocal.update(currentLocalWorkdayId, {
-                        end_user: user,
-                        end_time: endTime,
-                        location_id: appState.selected_location_id,
-                    });
-                    console.log("✅ Updated local workday");
-                } catch (error) {
-                    console.error("❌ Failed to update local workday:", error);
-                }
+                console.error("❌ Failed to end shift:", error);
             }
         }
+
+        try {
+            // Rust builds the Z-report from local tickets, locks the workday
+            // and wakes the sync worker to upload it
+            const report = await workdayLocal.close(currentLocalWorkdayId, user);
+            console.log("🔒 Workday closed:", report);
+        } catch (error) {
+            console.error("❌ Failed to close workday:", error);
+        }
     };
 
     const clear = async () => {
@@ -1,6 +1,6 @@
 import { createContext, useContext, useState, useEffect, useRef, useCallback, type ReactNode } from "react";
-import { WebSocketClient } from "@/services/websocket/WebSocketClient";
-import { websocketService } from "@/services/websocket/websocket.service";
+import { HubClient } from "@/services/websocket/HubClient";
+import { websocketService, DEFAULT_HUB_URL } from "@/services/websocket/websocket.service";
 import { deviceService } from "@/services/local/device.local.service";
 import { useAppState } from "@/ui/hooks/useAppState";
 import { kdsTicketLocal } from "@/services/local/kds-ticket.local.service";
@@ -11,8 +11,8 @@ interface KdsWebSocketContextType {
   isConnected: boolean;
   isConnecting: boolean;
   error: string | null;
-  client: WebSocketClient | null;
-  connect: (wsUrl: string, deviceRole: string) => Promise<void>;
+  client: HubClient | null;
+  connect: (wsUrl: string, deviceRole: string, pairingCode?: string) => Promise<void>;
   disconnect: () => void;
 }
 
@@ -23,12 +23,12 @@ export const KdsWebSocketProvider = ({ children }: { children: ReactNode }) => {
   const [isConnected, setIsConnected] = useState(false);
   const [isConnecting, setIsConnecting] = useState(false);
   const [error, setError] = useState<string | null>(null);
-  const [client, setClient] = useState<WebSocketClient | null>(null);
-  const clientRef = useRef<WebSocketClient | null>(null);
+  const [client, setClient] = useState<HubClient | null>(null);
+  const clientRef = useRef<HubClient | null>(null);
   const hasAttemptedAutoConnect = useRef(false);
   const { playSound } = useNotificationSound();
 
-  const connect = useCallback(async (wsUrl: string, deviceRole: string) => {
+  const connect = useCallback(async (wsUrl: string, deviceRole: string, pairingCode?: string) => {
     // Skip for POS devices (they run as server)
     if (deviceRole === "POS") {
       console.log("[KdsWebSocketContext] POS device - skipping client connection");
@@ -55,32 +55,40 @@ export const KdsWebSocketProvider = ({ children }: { children: ReactNode }) => {
     try {
       console.log(`[KdsWebSocketContext] Connecting ${deviceRole} to ${wsUrl}`);
 
-      // Disconnect existing connection if any
-      if (clientRef.current) {
-        clientRef.current.disconnect();
-        clientRef.current = null;
-      }
+      // Stop listening through the previous client; Rust swaps the connection
+      clientRef.current?.detach();
+      clientRef.current = null;
 
-      // Create WebSocket client with unique ID (device.id + role) to support multiple apps on same device
-      const uniqueDeviceId = `${device.id}_${deviceRole}`;
-      const newClient = new WebSocketClient(wsUrl, uniqueDeviceId, deviceRole);
+      // Pairing is done from Rust, which saves the credentials (and pins a wss:// certificate)
+      if (pairingCode) {
+        const hub = await websocketService.pairWithHub(wsUrl, pairingCode, device.name, deviceRole);
+        console.log("🤝 Paired with hub as", hub.device_id, hub.fingerprint ?? "");
+      }
 
-      // Connect
-      await newClient.connect();
-      await newClient.waitForRegisterAck();
+      // Unpaired devices fall back to a unique ID (device.id + role), which only a
+      // POS on the same machine accepts
+      const newClient = new HubClient();
+      newClient.onStatus((status) => setIsConnected(status.state === "connected"));
+      await newClient.start(deviceRole, `${device.id}_${deviceRole}`);
 
-      // Store client in service and state
+      // Store client in service and state; Rust keeps retrying even if the wait below fails
       websocketService.setClient(newClient);
       clientRef.current = newClient;
       setClient(newClient);
 
+      await newClient.waitUntilConnected();
+
       console.log("✅ [KdsWebSocketContext] WebSocket connected successfully");
 
       setIsConnected(true);
       setIsConnecting(false);
       setError(null);
     } catch (error) {
-      const errorMessage = error instanceof Error ? error.message : "Unknown error";
+      // Rust command errors arrive as { code, message }
+      const errorMessage =
+        error instanceof Error
+          ? error.message
+          : (error as { message?: string })?.message ?? "Unknown error";
       console.error("❌ [KdsWebSocketContext] Connection failed:", errorMessage);
 
       setIsConnected(false);
@@ -94,7 +102,7 @@ export const KdsWebSocketProvider = ({ children }: { children: ReactNode }) => {
       console.log("[KdsWebSocketContext] Disconnecting WebSocket");
       clientRef.current.disconnect();
       clientRef.current = null;
-      websocketService.setClient(null as any);
+      websocketService.setClient(null);
       setClient(null);
 
       setIsConnected(false);
@@ -123,8 +131,14 @@ export const KdsWebSocketProvider = ({ children }: { children: ReactNode }) => {
       hasAttemptedAutoConnect.current = true;
 
       // Schedule connection after current render to avoid cascading updates
-      setTimeout(() => {
-        connect(state.ws_server_url, state.device_role);
+      setTimeout(async () => {
+        // Still on the default URL: look for the POS on the LAN first
+        let url = state.ws_server_url;
+        if (url === DEFAULT_HUB_URL) {
+          const hub = await websocketService.autoSelectHub().catch(() => null);
+          if (hub) url = hub.url;
+        }
+        connect(url, state.device_role);
       }, 0);
     }
   }, [loading, state, isConnected, isConnecting, connect]);
@@ -132,7 +146,8 @@ export const KdsWebSocketProvider = ({ children }: { children: ReactNode }) => {
   // Global WebSocket listener for incoming orders (KDS only)
   useEffect(() => {
     // Only listen for orders if we're a KDS device and connected
-    if (!state || state.device_role === "POS" || !isConnected || !client) return;
+    // Messages that arrive while the listener is detached are held by the client
+    if (!state || state.device_role === "POS" || !client) return;
 
     const handleNewOrder = async (message: any) => {
       console.log("[KdsWebSocketContext] 🆕 Received new_order globally:", message);
@@ -143,8 +158,9 @@ export const KdsWebSocketProvider = ({ children }: { children: ReactNode }) => {
       playSound();
 
       try {
+        // A station's screen gets its own ticket id (ticket_id:station_id)
         await kdsTicketLocal.saveTicket({
-          id: orderData.ticket_id || `kds-${Date.now()}`,
+          id: orderData.kds_ticket_id || orderData.ticket_id || `kds-${Date.now()}`,
           ticketNumber: String(orderData.ticket_number),
           orderId: orderData.ticket_id || "",
           locationId: orderData.location_id || "",
@@ -155,6 +171,7 @@ export const KdsWebSocketProvider = ({ children }: { children: ReactNode }) => {
           tokenNumber: orderData.token_number || 0,
           createdAt: orderData.created_at || new Date().toISOString(),
           updatedAt: new Date().toISOString(),
+          stationId: orderData.station_id ?? null,
         });
 
         console.log("[KdsWebSocketContext] ✅ Ticket saved to local database");
@@ -183,7 +200,7 @@ export const KdsWebSocketProvider = ({ children }: { children: ReactNode }) => {
       console.log("[KdsWebSocketContext] 🔌 Removing global new_order listener");
       client.off("new_order", handleNewOrder);
     };
-  }, [isConnected, client, state,playSound]);
+  }, [client, state, playSound]);
 
   return (
     <KdsWebSocketContext.Provider
@@ -11,7 +11,8 @@ import {
 } from "react";
 import { useLocation } from "react-router-dom";
 
-import { WebSocketClient } from "@/services/websocket/WebSocketClient";
+import { HubClient } from "@/services/websocket/HubClient";
+import { websocketService, DEFAULT_HUB_URL } from "@/services/websocket/websocket.service";
 import { deviceService } from "@/services/local/device.local.service";
 import { queueTokenLocal } from "@/services/local/queue-token.local.service";
 import { useAppState } from "@/ui/hooks/useAppState";
@@ -22,8 +23,8 @@ interface QueueWebSocketContextType {
   isConnected: boolean;
   isConnecting: boolean;
   error: string | null;
-  client: WebSocketClient | null;
-  connect: (wsUrl: string) => Promise<void>;
+  client: HubClient | null;
+  connect: (wsUrl: string, pairingCode?: string) => Promise<void>;
   disconnect: () => void;
 }
 
@@ -37,15 +38,15 @@ export const QueueWebSocketProvider = ({ children }: { children: ReactNode }) =>
   const [isConnected, setIsConnected] = useState(false);
   const [isConnecting, setIsConnecting] = useState(false);
   const [error, setError] = useState<string | null>(null);
-  const [client, setClient] = useState<WebSocketClient | null>(null);
+  const [client, setClient] = useState<HubClient | null>(null);
 
-  const clientRef = useRef<WebSocketClient | null>(null);
+  const clientRef = useRef<HubClient | null>(null);
   const hasAutoConnected = useRef(false);
 
   /* =========================
      MANUAL CONNECT
   ========================= */
-  const connect = useCallback(async (wsUrl: string) => {
+  const connect = useCallback(async (wsUrl: string, pairingCode?: string) => {
     if (state?.device_role !== "QUEUE") return;
 
     const device = await deviceService.getDevice();
@@ -58,20 +59,31 @@ export const QueueWebSocketProvider = ({ children }: { children: ReactNode }) =>
     setError(null);
 
     try {
-      clientRef.current?.disconnect();
+      clientRef.current?.detach();
 
-      // Use unique ID (device.id + role) to support multiple apps on same device
-      const uniqueDeviceId = `${device.id}_QUEUE`;
-      const wsClient = new WebSocketClient(wsUrl, uniqueDeviceId, "QUEUE");
+      // Pairing is done from Rust, which saves the credentials (and pins a wss:// certificate)
+      if (pairingCode) {
+        await websocketService.pairWithHub(wsUrl, pairingCode, device.name, "QUEUE");
+      }
 
-      await wsClient.connect();
-      await wsClient.waitForRegisterAck();
+      // Unpaired devices fall back to a unique ID (device.id + role), which only a
+      // POS on the same machine accepts
+      const hubClient = new HubClient();
+      hubClient.onStatus((status) => setIsConnected(status.state === "connected"));
+      await hubClient.start("QUEUE", `${device.id}_QUEUE`);
 
-      clientRef.current = wsClient;
-      setClient(wsClient);
+      clientRef.current = hubClient;
+      setClient(hubClient);
+
+      await hubClient.waitUntilConnected();
       setIsConnected(true);
     } catch (err) {
-      setError(err instanceof Error ? err.message : "Connection failed");
+      // Rust command errors arrive as { code, message }
+      setError(
+        err instanceof Error
+          ? err.message
+          : (err as { message?: string })?.message ?? "Connection failed"
+      );
       setIsConnected(false);
     } finally {
       setIsConnecting(false);
@@ -107,7 +119,17 @@ export const QueueWebSocketProvider = ({ children }: { children: ReactNode }) =>
 
     if (state.ws_server_url && !isConnected && !isConnecting) {
       hasAutoConnected.current = true;
-      connect(state.ws_server_url);
+
+      // Still on the default URL: look for the POS on the LAN first
+      const url = state.ws_server_url;
+      if (url === DEFAULT_HUB_URL) {
+        websocketService
+          .autoSelectHub()
+          .catch(() => null)
+          .then((hub) => connect(hub?.url ?? url));
+      } else {
+        connect(url);
+      }
     }
   }, [loading, state, location.pathname, isConnected, isConnecting, connect]);
 
@@ -115,7 +137,8 @@ export const QueueWebSocketProvider = ({ children }: { children: ReactNode }) =>
      WS MESSAGE HANDLERS (QUEUE)
   ========================= */
   useEffect(() => {
-    if (!client || !isConnected) return;
+    // Messages that arrive while the listener is detached are held by the client
+    if (!client) return;
 
     // POS → Queue : new order
     const onNewTicket = async (msg: any) => {
@@ -179,7 +202,7 @@ export const QueueWebSocketProvider = ({ children }: { children: ReactNode }) =>
       client.off("queue_call", onQueueCall);
       client.off("queue_served", onQueueServed);
     };
-  }, [client, isConnected]);
+  }, [client]);
 
   return (
     <QueueWebSocketContext.Provider
@@ -121,8 +121,10 @@ export function buildTicketRequest(params: BuildTicketParams): TicketRequest {
       created_by: userName,
     },
     extra_data: {
+      product_id: item.product_id,
       category_id: item.category_id,
       product_group_id: item.product_group_id,
+      modifiers: item.modifiers ?? [],
     },
     business_date: date,
     order_date: date,
@@ -212,6 +214,16 @@ export function buildTicketRequest(params: BuildTicketParams): TicketRequest {
         subtotal: subtotal.toFixed(2),
         total_tax: totalTax.toFixed(2),
         total_charges: totalCharges.toFixed(2),
+        // Read by Rust into ticket_charges
+        charges: charges
+          .filter((c) => c.applied)
+          .map((c) => ({
+            name: c.name,
+            is_tax: c.is_tax,
+            percentage: c.percentage,
+            amount: c.amount,
+            transaction_type_id: c.transaction_type_id,
+          })),
       },
       business_date: date,
       ticket_created_time: timestamp,